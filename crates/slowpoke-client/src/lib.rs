@@ -0,0 +1,363 @@
+//! A client for `slowpoke::server`, so a program (or a test) can drive a
+//! turtle in a slowpoke window that's running somewhere else:
+//!
+//! ```ignore
+//! let mut client = Client::connect("127.0.0.1:7007")?;
+//! client.forward(100.0)?;
+//! client.right(90.0)?;
+//! let [x, y] = client.position()?;
+//! ```
+//!
+//! Each client gets a turtle of its own. The methods here cover the common
+//! commands; any other can be sent with `draw()`, `screen()` or `data()`,
+//! using the words it has in a recording, e.g.
+//! `client.draw(&[json!("circle"), json!(50), json!(360), json!(20)])`.
+
+use std::{
+    fmt::{Debug, Display},
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, path::Path};
+
+pub use serde_json::{json, Value};
+
+/// What went wrong with a request
+#[derive(Debug)]
+pub enum ClientError {
+    Io(io::Error),  // talking to the server failed
+    Server(String), // the server couldn't run the command, and said why
+    Reply(String),  // the server's answer wasn't the one expected
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Server(message) => write!(f, "the server said: {message}"),
+            Self::Reply(reply) => write!(f, "unexpected reply {reply}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// The server's answer to a request, e.g. `position` with `[100, 0]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    pub name: String,
+    pub values: Vec<Value>,
+}
+
+impl Reply {
+    fn number(&self, index: usize) -> Result<f32, ClientError> {
+        #[allow(clippy::cast_possible_truncation)]
+        self.values
+            .get(index)
+            .and_then(Value::as_f64)
+            .map(|n| n as f32)
+            .ok_or_else(|| ClientError::Reply(format!("{self:?}")))
+    }
+
+    fn expect(self, name: &str) -> Result<Self, ClientError> {
+        if self.name == name {
+            Ok(self)
+        } else {
+            Err(ClientError::Reply(format!("{self:?}")))
+        }
+    }
+}
+
+/// A connection to a slowpoke server, and the turtle it drives
+pub struct Client {
+    reader: Box<dyn BufRead + Send>,
+    writer: Box<dyn Write + Send>,
+    next_id: u64,
+}
+
+impl Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("next_id", &self.next_id)
+            .finish_non_exhaustive()
+    }
+}
+
+type Result<T, E = ClientError> = std::result::Result<T, E>;
+
+impl Client {
+    /// Connect to a server listening on TCP
+    ///
+    /// # Errors
+    /// Returns an error when the server can't be reached
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        Ok(Self::new(
+            Box::new(BufReader::new(stream.try_clone()?)),
+            Box::new(stream),
+        ))
+    }
+
+    /// Connect to a server listening on a Unix socket
+    ///
+    /// # Errors
+    /// Returns an error when the server can't be reached
+    #[cfg(unix)]
+    pub fn connect_unix<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let stream = UnixStream::connect(path)?;
+        Ok(Self::new(
+            Box::new(BufReader::new(stream.try_clone()?)),
+            Box::new(stream),
+        ))
+    }
+
+    fn new(reader: Box<dyn BufRead + Send>, writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            reader,
+            writer,
+            next_id: 1,
+        }
+    }
+
+    /// Send a command of a kind (`draw`, `screen` or `data`) and wait for
+    /// the answer
+    ///
+    /// # Errors
+    /// Returns an error when the connection fails, or the server can't run
+    /// the command
+    pub fn request(&mut self, kind: &str, words: &[Value]) -> Result<Reply> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&json!({ kind: words, "id": id }))?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let reply: Value =
+            serde_json::from_str(&line).map_err(|_| ClientError::Reply(line.clone()))?;
+        let Value::Object(mut reply) = reply else {
+            return Err(ClientError::Reply(line));
+        };
+        if reply.remove("id") != Some(json!(id)) {
+            return Err(ClientError::Reply(line));
+        }
+        match reply.into_iter().next() {
+            Some((name, Value::String(message))) if name == "error" => {
+                Err(ClientError::Server(message))
+            }
+            Some((name, Value::Array(values))) => Ok(Reply { name, values }),
+            _ => Err(ClientError::Reply(line)),
+        }
+    }
+
+    fn send(&mut self, request: &Value) -> io::Result<()> {
+        writeln!(self.writer, "{request}")?;
+        self.writer.flush()
+    }
+
+    /// Send a drawing command, e.g. `&[json!("forward"), json!(100)]`
+    ///
+    /// # Errors
+    /// See `request()`
+    pub fn draw(&mut self, words: &[Value]) -> Result<Reply> {
+        self.request("draw", words)
+    }
+
+    /// Send a command for the screen, e.g. `&[json!("bgcolor"), ...]`
+    ///
+    /// # Errors
+    /// See `request()`
+    pub fn screen(&mut self, words: &[Value]) -> Result<Reply> {
+        self.request("screen", words)
+    }
+
+    /// Ask something, e.g. `&[json!("towards"), json!(0), json!(0)]`
+    ///
+    /// # Errors
+    /// See `request()`
+    pub fn data(&mut self, words: &[Value]) -> Result<Reply> {
+        self.request("data", words)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn forward(&mut self, distance: f32) -> Result<()> {
+        self.draw(&[json!("forward"), json!(distance)]).map(drop)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn backward(&mut self, distance: f32) -> Result<()> {
+        self.forward(-distance)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn right(&mut self, angle: f32) -> Result<()> {
+        self.draw(&[json!("right"), json!(angle)]).map(drop)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn left(&mut self, angle: f32) -> Result<()> {
+        self.draw(&[json!("left"), json!(angle)]).map(drop)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn goto(&mut self, x: f32, y: f32) -> Result<()> {
+        self.draw(&[json!("goto"), json!(x), json!(y)]).map(drop)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn setheading(&mut self, heading: f32) -> Result<()> {
+        self.draw(&[json!("setheading"), json!(heading)]).map(drop)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn penup(&mut self) -> Result<()> {
+        self.draw(&[json!("penup")]).map(drop)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn pendown(&mut self) -> Result<()> {
+        self.draw(&[json!("pendown")]).map(drop)
+    }
+
+    /// The pen's color, as red, green and blue from 0 to 1
+    ///
+    /// # Errors
+    /// See `request()`
+    pub fn pencolor(&mut self, r: f32, g: f32, b: f32) -> Result<()> {
+        self.draw(&[json!("pencolor"), json!(r), json!(g), json!(b)])
+            .map(drop)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn write(&mut self, text: &str) -> Result<()> {
+        self.draw(&[json!("write"), json!(text)]).map(drop)
+    }
+
+    /// How fast the turtle draws, from 1 to 10, or 0 for no animation
+    ///
+    /// # Errors
+    /// See `request()`
+    pub fn speed(&mut self, speed: u8) -> Result<()> {
+        self.screen(&[json!("speed"), json!(speed)]).map(drop)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn title(&mut self, title: &str) -> Result<()> {
+        self.screen(&[json!("title"), json!(title)]).map(drop)
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn position(&mut self) -> Result<[f32; 2]> {
+        let reply = self.data(&[json!("position")])?.expect("position")?;
+        Ok([reply.number(0)?, reply.number(1)?])
+    }
+
+    /// # Errors
+    /// See `request()`
+    pub fn heading(&mut self) -> Result<f32> {
+        self.data(&[json!("heading")])?.expect("heading")?.number(0)
+    }
+
+    /// Close the window. The server doesn't answer; it goes away.
+    ///
+    /// # Errors
+    /// Returns an error when the request can't be sent
+    pub fn bye(mut self) -> io::Result<()> {
+        self.send(&json!({ "screen": ["bye"] }))?;
+        let mut rest = String::new();
+        while self.reader.read_line(&mut rest).unwrap_or(0) > 0 {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, thread};
+
+    use slowpoke::{headless::Headless, server::Server, SlowpokeLib};
+
+    use super::*;
+
+    #[test]
+    fn clients_drive_their_own_turtles() {
+        let (send, receive) = mpsc::channel();
+        let window = thread::spawn(move || {
+            SlowpokeLib::<Headless>::default().run(move |turtle| {
+                let server = Server::tcp("127.0.0.1:0").expect("can't listen");
+                let _ = send.send(server.local_addr().expect("no address"));
+                let e = server.run(turtle);
+                panic!("the server stopped: {e}");
+            });
+        });
+        let address = receive.recv().expect("the server didn't start");
+
+        let mut first = Client::connect(address).unwrap();
+        let mut second = Client::connect(address).unwrap();
+        first.speed(0).unwrap();
+        first.forward(100.).unwrap();
+        first.left(90.).unwrap();
+        first.forward(20.).unwrap();
+        second.speed(0).unwrap();
+        second.goto(-30., 40.).unwrap();
+
+        let [x, y] = first.position().unwrap();
+        assert_eq!([x.round(), y.round()], [100., 20.]);
+        assert_eq!(first.heading().unwrap().round(), 90.);
+        let [x, y] = second.position().unwrap();
+        assert_eq!([x.round(), y.round()], [-30., 40.]);
+        assert_eq!(second.heading().unwrap(), 0.);
+
+        let reply = second
+            .data(&[json!("shape"), Value::Null])
+            .expect("no shape");
+        assert_eq!(reply.name, "name");
+        assert!(matches!(
+            first.draw(&[json!("fly"), json!(10)]),
+            Err(ClientError::Server(message)) if message == "unknown drawing command fly"
+        ));
+        assert!(matches!(
+            first.draw(&[json!("forward")]),
+            Err(ClientError::Server(_))
+        ));
+
+        // ids that don't exist are refused, and the window carries on
+        assert!(matches!(
+            first.screen(&[json!("clearstamp"), json!(5)]),
+            Err(ClientError::Server(message)) if message == "there's no stamp 5"
+        ));
+        assert!(matches!(
+            second.data(&[json!("overlaps"), json!(99)]),
+            Err(ClientError::Server(message)) if message == "there's no turtle 99"
+        ));
+        let stamp = first.data(&[json!("stamp")]).unwrap();
+        assert_eq!(stamp.name, "stamp");
+        first
+            .screen(&[json!("clearstamp"), stamp.values[0].clone()])
+            .unwrap();
+        let [x, y] = second.position().unwrap();
+        assert_eq!([x.round(), y.round()], [-30., 40.]);
+
+        first.bye().unwrap();
+        window.join().unwrap();
+    }
+}
@@ -5,15 +5,15 @@ use std::{
 };
 
 use eframe::CreationContext;
-use egui::{epaint::PathStroke, vec2, Painter, Pos2, Rect, Shape, Stroke, Vec2};
+use egui::{vec2, Mesh, Painter, Pos2, Rect, Shape, Stroke, Vec2};
 use slowpoke::{
-    GetPolyPath, Handler, LineSegment, PolygonPath, PopupID, SlowpokeLib, TurtleColor, TurtleDraw,
-    TurtleGui, TurtleID, TurtleTask, TurtleUI, TurtleUserInterface,
+    EventResult, Handler, LineCap, LineJoin, LineSegment, PopupID, SlowpokeLib, TurtleColor,
+    TurtleDraw, TurtleEvent, TurtleGui, TurtleID, TurtleTask, TurtleUI, TurtleUserInterface,
 };
 
 pub type Slowpoke = SlowpokeLib<EguiFramework>;
 pub type Turtle = slowpoke::Turtle;
-pub use slowpoke::TurtleShapeName; // TODO XXX Fix this -- we shouldn't need to do this?
+pub use slowpoke::{FillStyle, TurtleShapeName}; // TODO XXX Fix this -- we shouldn't need to do this?
 
 #[derive(Debug)]
 pub struct EguiFramework {
@@ -70,12 +70,39 @@ impl EguiUI {
 
         for op in ops {
             match op {
-                TurtleDraw::DrawLines(color, width, line_segments) => {
+                TurtleDraw::DrawLines(color, width, style, line_segments) => {
                     let color: EguiColor = color.into();
                     let stroke = Stroke::new(*width, color);
-
-                    let line_list = get_path(&win_center, line_segments);
-                    painter.line(line_list, stroke);
+                    let radius = width / 2.;
+
+                    // egui has no caps or joins of its own, so they're drawn
+                    // as extra circles, or by lengthening the line
+                    let dashes = style.dashes(*width, line_segments);
+                    for run in dashes.chunk_by(|a, b| a.end == b.start) {
+                        let mut line_list = get_path(&win_center, run);
+                        let last = line_list.len() - 1;
+                        if style.cap == LineCap::Square {
+                            // a line with no length has no direction to extend in
+                            let start = line_list[0] - line_list[1];
+                            if start != Vec2::ZERO {
+                                line_list[0] += start.normalized() * radius;
+                            }
+                            let end = line_list[last] - line_list[last - 1];
+                            if end != Vec2::ZERO {
+                                line_list[last] += end.normalized() * radius;
+                            }
+                        }
+                        if style.cap == LineCap::Round {
+                            painter.circle_filled(line_list[0], radius, color);
+                            painter.circle_filled(line_list[last], radius, color);
+                        }
+                        if style.join == LineJoin::Round {
+                            for point in &line_list[1..last] {
+                                painter.circle_filled(*point, radius, color);
+                            }
+                        }
+                        painter.line(line_list, stroke);
+                    }
                 }
                 TurtleDraw::DrawDot(center, radius, color) => {
                     let center = Pos2 {
@@ -86,29 +113,46 @@ impl EguiUI {
                     painter.circle_filled(center, *radius, color);
                 }
                 TurtleDraw::DrawText(_, _) => {}
-                TurtleDraw::FillPolygon(fill_color, line_color, _, line_segments) => {
-                    let fill_color: EguiColor = fill_color.into();
+                TurtleDraw::FillPolygon(
+                    fill_color,
+                    line_color,
+                    _,
+                    fill_style,
+                    fill_rule,
+                    line_segments,
+                    triangles,
+                ) => {
                     let _line_color: EguiColor = line_color.into();
-                    let path: PolygonPath = line_segments.as_slice().into();
-                    let path = path.get_path();
-                    for triangle in path.chunks(3) {
-                        let p0: Pos2 = Pos2 {
-                            x: triangle[0].0.x,
-                            y: triangle[0].0.y,
-                        } + win_center;
-                        let p1: Pos2 = Pos2 {
-                            x: triangle[1].0.x,
-                            y: triangle[1].0.y,
-                        } + win_center;
-                        let p2: Pos2 = Pos2 {
-                            x: triangle[2].0.x,
-                            y: triangle[2].0.y,
-                        } + win_center;
-                        painter.add(Shape::convex_polygon(
-                            vec![p0, p1, p2],
-                            fill_color,
-                            PathStroke::default(),
-                        ));
+
+                    // vertex colors let egui draw the gradients itself
+                    let mut mesh = Mesh::default();
+                    for triangle in
+                        fill_style.shaded_triangles(*fill_color, line_segments, triangles)
+                    {
+                        for (point, color) in triangle {
+                            let color: EguiColor = color.into();
+                            let pos = Pos2 {
+                                x: point.x,
+                                y: point.y,
+                            } + win_center;
+                            mesh.colored_vertex(pos, *color);
+                        }
+                        let index = mesh.vertices.len() as u32;
+                        mesh.add_triangle(index - 3, index - 2, index - 1);
+                    }
+                    painter.add(Shape::mesh(mesh));
+
+                    if let Some((color, lines)) =
+                        fill_style.hatch_lines(*fill_color, *fill_rule, line_segments)
+                    {
+                        let color: EguiColor = color.into();
+                        for line in &lines {
+                            let (start, end) = points_to_pos(line);
+                            painter.line_segment(
+                                [start + win_center, end + win_center],
+                                Stroke::new(1., color),
+                            );
+                        }
                     }
                 }
             }
@@ -117,7 +161,9 @@ impl EguiUI {
 }
 
 impl TurtleUserInterface for EguiFramework {
-    fn start(mut flags: slowpoke::TurtleFlags) {
+    type Options = ();
+
+    fn start(mut flags: slowpoke::TurtleFlags, (): ()) {
         let func = flags.start_func.take();
         let title = flags.title.clone();
         let mut tt = TurtleTask::new(&mut flags);
@@ -149,7 +195,15 @@ impl TurtleUserInterface for EguiFramework {
 
 impl eframe::App for EguiFramework {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.tt.tick(&mut self.handler);
+        if ctx.input(|i| i.viewport().close_requested())
+            && self.tt.handle_event(None, None, &TurtleEvent::WindowClose) == EventResult::Continue
+        {
+            // keep the window open until the `onclose` handlers have finished
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+        }
+        if self.tt.tick(&mut self.handler) == EventResult::ShutDown {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
         // let frame = Frame {
         // fill: Color32::WHITE,
         // ..Frame::default()
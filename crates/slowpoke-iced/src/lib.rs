@@ -3,13 +3,13 @@
 
 use iced::Pixels;
 use slowpoke::{
-    EventResult, Handler, LineSegment, PopupData, PopupID, SlowpokeLib, TurtleColor, TurtleDraw,
-    TurtleEvent, TurtleFlags, TurtleGui, TurtleID, TurtleTask, TurtleThread, TurtleUI,
-    TurtleUserInterface,
+    EventResult, FillRule, Handler, LineCap, LineJoin, LineSegment, LineStyle, PopupData, PopupID,
+    SlowpokeLib, TurtleColor, TurtleDraw, TurtleEvent, TurtleFlags, TurtleGui, TurtleID,
+    TurtleTask, TurtleThread, TurtleUI, TurtleUserInterface,
 };
 
 use std::collections::HashMap;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
 
 use iced::widget::text;
 
@@ -18,19 +18,22 @@ use iced::{
     multi_window::Application,
     widget::{
         button,
-        canvas::{self, fill::Rule, stroke, Cache, Fill, Frame, LineJoin, Path, Stroke},
+        canvas::{
+            self, fill::Rule, gradient, stroke, Cache, Fill, Frame, Gradient, LineDash, Path,
+            Stroke,
+        },
         column, container, horizontal_space, row, text_input, vertical_space, Canvas, TextInput,
     },
     window::{self, Id as WindowID},
-    Element, Length, Point, Rectangle, Renderer, Settings, Size, Subscription, Theme,
+    Element, Length, Point, Rectangle, Renderer, Settings, Size, Subscription, Theme, Vector,
 };
 
 use iced::keyboard::{Event::KeyPressed, Event::KeyReleased, Key};
-use iced::window::Event::Resized;
+use iced::window::Event::{CloseRequested, Resized};
 
 pub type Slowpoke = SlowpokeLib<IcedGuiFramework>;
 pub type Turtle = slowpoke::Turtle;
-pub use slowpoke::TurtleShapeName; // TODO XXX Fix this -- we shouldn't need to do this?
+pub use slowpoke::{FillStyle, TurtleShapeName}; // TODO XXX Fix this -- we shouldn't need to do this?
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -42,11 +45,45 @@ pub enum Message {
     Cancel(WindowID),
 }
 
+// finished ops are cached in layers of about this many, so a new stroke only
+// redraws the last layer
+const OPS_PER_LAYER: usize = 32;
+
+#[derive(Debug)]
+struct Layer {
+    cache: Cache,
+    ops: Range<usize>,
+}
+
 #[derive(Default, Debug)]
-struct IcedUI;
+struct IcedUI {
+    layers: Vec<Layer>,
+}
 
 impl IcedUI {
-    fn draw(&self, frame: &mut Frame, ops: &[TurtleDraw]) {
+    // keep the cached layers in step with the turtle's finished ops
+    fn update_layers(&mut self, rewound: Option<usize>, finished: usize) {
+        if let Some(pos) = rewound {
+            self.layers.retain(|layer| layer.ops.end <= pos);
+        }
+
+        let start = self.layers.last().map_or(0, |layer| layer.ops.end);
+        if finished <= start {
+            return;
+        }
+        match self.layers.last_mut() {
+            Some(layer) if layer.ops.len() < OPS_PER_LAYER => {
+                layer.cache.clear();
+                layer.ops.end = finished;
+            }
+            _ => self.layers.push(Layer {
+                cache: Cache::default(),
+                ops: start..finished,
+            }),
+        }
+    }
+
+    fn draw(frame: &mut Frame, ops: &[TurtleDraw]) {
         fn segs_to_path(segments: &[LineSegment]) -> Option<Path> {
             let mut iter = segments.iter();
             let mut cur = iter.next()?;
@@ -79,18 +116,40 @@ impl IcedUI {
             }))
         }
 
+        fn line_cap(cap: LineCap) -> stroke::LineCap {
+            match cap {
+                LineCap::Butt => stroke::LineCap::Butt,
+                LineCap::Round => stroke::LineCap::Round,
+                LineCap::Square => stroke::LineCap::Square,
+            }
+        }
+
+        fn line_join(join: LineJoin) -> stroke::LineJoin {
+            match join {
+                LineJoin::Miter => stroke::LineJoin::Miter,
+                LineJoin::Round => stroke::LineJoin::Round,
+                LineJoin::Bevel => stroke::LineJoin::Bevel,
+            }
+        }
+
         for op in ops {
             match op {
-                TurtleDraw::DrawLines(color, width, segments) => {
+                TurtleDraw::DrawLines(color, width, style, segments) => {
                     let color: IcedColor = color.into();
+                    let LineStyle { cap, join, .. } = style;
+                    let dashes = style.dash_pattern(*width).unwrap_or_default();
                     if let Some(path) = segs_to_path(segments) {
                         frame.stroke(
                             &path,
                             Stroke {
                                 style: stroke::Style::Solid(*color),
                                 width: *width,
-                                line_join: LineJoin::Round,
-                                ..Stroke::default()
+                                line_cap: line_cap(*cap),
+                                line_join: line_join(*join),
+                                line_dash: LineDash {
+                                    segments: &dashes,
+                                    offset: 0,
+                                },
                             },
                         );
                     }
@@ -107,23 +166,87 @@ impl IcedUI {
                         },
                     );
                 }
-                TurtleDraw::FillPolygon(fillcolor, pencolor, pen_width, segments) => {
+                TurtleDraw::FillPolygon(
+                    fillcolor,
+                    pencolor,
+                    pen_width,
+                    fill_style,
+                    fill_rule,
+                    segments,
+                    triangles,
+                ) => {
                     let stroke_color: IcedColor = pencolor.into();
                     let fill_color: IcedColor = fillcolor.into();
                     if let Some(path) = segs_to_path(segments) {
-                        frame.fill(
-                            &path,
-                            Fill {
-                                style: stroke::Style::Solid(*fill_color),
-                                rule: Rule::EvenOdd,
-                            },
-                        );
+                        let style = if let Some((start, from, end, to)) =
+                            fill_style.linear_gradient(*fillcolor, segments)
+                        {
+                            let from: IcedColor = from.into();
+                            let to: IcedColor = to.into();
+                            let linear = gradient::Linear::new(
+                                Point::new(start.x, start.y),
+                                Point::new(end.x, end.y),
+                            )
+                            .add_stop(0., *from)
+                            .add_stop(1., *to);
+                            stroke::Style::Gradient(Gradient::Linear(linear))
+                        } else {
+                            stroke::Style::Solid(*fill_color)
+                        };
+
+                        if let FillStyle::RadialGradient(..) = fill_style {
+                            // iced can't draw radial gradients, so fill lots of
+                            // small triangles instead
+                            for triangle in
+                                fill_style.shaded_triangles(*fillcolor, segments, triangles)
+                            {
+                                // the color at the triangle's center
+                                let corners = triangle.map(|(_, color)| <[f32; 4]>::from(color));
+                                let color: [f32; 4] = std::array::from_fn(|i| {
+                                    corners.iter().map(|c| c[i]).sum::<f32>() / 3.
+                                });
+                                let color: IcedColor = TurtleColor::from(color).into();
+                                let triangle = Path::new(|b| {
+                                    b.move_to(Point::new(triangle[0].0.x, triangle[0].0.y));
+                                    b.line_to(Point::new(triangle[1].0.x, triangle[1].0.y));
+                                    b.line_to(Point::new(triangle[2].0.x, triangle[2].0.y));
+                                    b.close();
+                                });
+                                frame.fill(&triangle, *color);
+                            }
+                        } else {
+                            frame.fill(
+                                &path,
+                                Fill {
+                                    style,
+                                    rule: match fill_rule {
+                                        FillRule::EvenOdd => Rule::EvenOdd,
+                                        FillRule::NonZero => Rule::NonZero,
+                                    },
+                                },
+                            );
+                        }
+
+                        if let Some((color, lines)) =
+                            fill_style.hatch_lines(*fillcolor, *fill_rule, segments)
+                        {
+                            let color: IcedColor = color.into();
+                            for line in lines {
+                                frame.stroke(
+                                    &Path::line(
+                                        Point::new(line.start.x, line.start.y),
+                                        Point::new(line.end.x, line.end.y),
+                                    ),
+                                    Stroke::default().with_color(*color),
+                                );
+                            }
+                        }
                         frame.stroke(
                             &path,
                             Stroke {
                                 style: stroke::Style::Solid(*stroke_color),
                                 width: *pen_width,
-                                line_join: LineJoin::Round,
+                                line_join: stroke::LineJoin::Round,
                                 ..Stroke::default()
                             },
                         );
@@ -167,10 +290,8 @@ impl TurtleUI for IcedGuiInternal {
 
 #[derive(Debug)]
 pub struct IcedGuiFramework {
-    cache: Cache,
     tt: TurtleTask,
     handler: Handler<IcedUI, IcedGuiInternal>,
-    clear_cache: bool,
     winsize: (f32, f32),   // width, height
     mouse_pos: (f32, f32), // x, y
     mouse_down: bool,
@@ -186,7 +307,9 @@ struct IcedGuiInternal {
 }
 
 impl TurtleUserInterface for IcedGuiFramework {
-    fn start(flags: TurtleFlags) {
+    type Options = ();
+
+    fn start(flags: TurtleFlags, (): ()) {
         let (xsize, ysize) = (flags.size[0], flags.size[1]);
 
         Self::run(Settings {
@@ -194,6 +317,7 @@ impl TurtleUserInterface for IcedGuiFramework {
             flags,
             window: window::Settings {
                 size: Size::new(xsize, ysize),
+                exit_on_close_request: false, // give `onclose` handlers a chance to run
                 ..Default::default()
             },
             id: None,
@@ -235,9 +359,7 @@ impl Application for IcedGuiFramework {
         tt.run_turtle(func.unwrap());
 
         let framework = Self {
-            cache: Cache::default(),
             tt,
-            clear_cache: true,
             handler: new_handler(title),
             winsize: (0., 0.),
             mouse_pos: (0., 0.),
@@ -263,14 +385,10 @@ impl Application for IcedGuiFramework {
     fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
         match message {
             Message::Tick => {
-                if self.clear_cache {
-                    self.cache.clear();
-                    self.clear_cache = false;
-                }
-                self.tt.tick(&mut self.handler);
-                if self.update_turtles() {
-                    self.clear_cache = true;
+                if self.tt.tick(&mut self.handler) == EventResult::ShutDown {
+                    self.shut_down();
                 }
+                self.update_turtles();
             }
             Message::AckError(win_id) => {
                 let popid = self.handler.screen.winid_to_popupid.get(&win_id).unwrap();
@@ -286,16 +404,11 @@ impl Application for IcedGuiFramework {
                 match &turtle_event {
                     TurtleEvent::WindowResize(x, y) => {
                         self.winsize = (*x as f32, *y as f32);
-                        if self.handler.screen.resize_request.is_none() {
-                            self.tt.handle_event(None, None, &turtle_event);
-                        } else {
-                            let (turtle, thread) = self
-                                .handler
-                                .screen
-                                .resize_request
-                                .expect("missing resize data");
+                        if let Some((turtle, thread)) = self.handler.screen.resize_request {
                             self.tt
                                 .handle_event(Some(turtle), Some(thread), &turtle_event);
+                        } else {
+                            self.tt.handle_event(None, None, &turtle_event);
                         }
                     }
                     TurtleEvent::MousePosition(x, y) => {
@@ -317,7 +430,7 @@ impl Application for IcedGuiFramework {
                             &TurtleEvent::MousePress(self.mouse_pos.0, self.mouse_pos.1),
                         ) == EventResult::ShutDown
                         {
-                            std::process::exit(0);
+                            self.shut_down();
                         }
                     }
                     TurtleEvent::MouseRelease(_x, _y) => {
@@ -332,6 +445,12 @@ impl Application for IcedGuiFramework {
                     TurtleEvent::KeyPress(_) | TurtleEvent::KeyRelease(_) => {
                         self.tt.handle_event(None, None, &turtle_event);
                     }
+                    TurtleEvent::WindowClose => {
+                        if self.tt.handle_event(None, None, &turtle_event) == EventResult::ShutDown
+                        {
+                            self.shut_down();
+                        }
+                    }
                     TurtleEvent::_Timer => todo!(),
                 }
             }
@@ -461,43 +580,71 @@ impl<Message> canvas::Program<Message> for IcedGuiFramework {
         bounds: Rectangle,
         _cursor: mouse::Cursor,
     ) -> Vec<<Renderer as canvas::Renderer>::Geometry> {
-        let geometry = self.cache.draw(renderer, bounds.size(), |frame| {
-            let center = frame.center();
-            let ic: IcedColor = self.handler.screen.bgcolor.into();
-            frame.fill_rectangle(
-                [0., 0.].into(),
-                bounds.size(),
-                Fill {
-                    style: stroke::Style::Solid(ic.into()),
-                    rule: Rule::NonZero,
-                },
-            );
-            frame.translate([center.x, center.y].into());
-            for turtle in self.handler.turtle.values() {
-                let ui = turtle.ui.borrow();
-                ui.draw(frame, &turtle.ops);
+        let center = Vector::new(bounds.width / 2., bounds.height / 2.);
+        let mut geometry = Vec::new();
+
+        let mut background = Frame::new(renderer, bounds.size());
+        let ic: IcedColor = self.handler.screen.bgcolor.into();
+        background.fill_rectangle(
+            [0., 0.].into(),
+            bounds.size(),
+            Fill {
+                style: stroke::Style::Solid(ic.into()),
+                rule: Rule::NonZero,
+            },
+        );
+        geometry.push(background.into_geometry());
+
+        // finished drawings come from the caches; whatever is still moving,
+        // including the turtles themselves, is drawn every frame on top
+        let mut animated = Frame::new(renderer, bounds.size());
+        animated.translate(center);
+        for turtle in self.handler.turtle.values() {
+            let ui = turtle.ui.borrow();
+            for layer in &ui.layers {
+                let ops = turtle.ops.get(layer.ops.clone()).unwrap_or_default();
+                geometry.push(layer.cache.draw(renderer, bounds.size(), |frame| {
+                    frame.translate(center);
+                    IcedUI::draw(frame, ops);
+                }));
             }
-        });
-        vec![geometry]
+            let finished = ui.layers.last().map_or(0, |layer| layer.ops.end);
+            IcedUI::draw(
+                &mut animated,
+                turtle.ops.get(finished..).unwrap_or_default(),
+            );
+        }
+        geometry.push(animated.into_geometry());
+
+        geometry
     }
 }
 
 impl IcedGuiFramework {
-    // returns true if the cache should be cleared
-    fn update_turtles(&mut self) -> bool {
-        let mut done = true;
+    // iced exits its event loop once the last window has been closed
+    fn shut_down(&mut self) {
+        let screen = &mut self.handler.screen;
+        for (winid, popid) in &screen.winid_to_popupid {
+            if self.handler.popups.contains_key(popid) {
+                screen.wcmds.push(window::close(*winid));
+            }
+        }
+        screen.wcmds.push(window::close(WindowID::MAIN));
+    }
 
+    fn update_turtles(&mut self) {
         for (tid, turtle) in &mut self.handler.turtle {
             let (pct, prog) = self.tt.progress(*tid);
-            if turtle.has_new_cmd {
-                done = false;
-                if prog.is_done(pct) {
-                    turtle.has_new_cmd = false;
-                }
+            if turtle.has_new_cmd && prog.is_done(pct) {
+                turtle.has_new_cmd = false;
             }
-        }
 
-        !done
+            let rewound = turtle.take_rewound();
+            turtle
+                .ui
+                .borrow_mut()
+                .update_layers(rewound, turtle.finished_ops());
+        }
     }
 
     fn to_turtle_pos(&self, x: f32, y: f32) -> (f32, f32) {
@@ -544,6 +691,7 @@ impl From<Event> for TurtleEvent {
                 #[allow(clippy::cast_possible_wrap)]
                 TurtleEvent::WindowResize(width as isize, height as isize)
             }
+            iced::Event::Window(window::Id::MAIN, CloseRequested) => TurtleEvent::WindowClose,
             iced::Event::Mouse(mouse_event) => convert_mouse_event(mouse_event),
             iced::Event::Touch(_) | iced::Event::Window(..) | iced::Event::Keyboard(_) => {
                 TurtleEvent::Unhandled
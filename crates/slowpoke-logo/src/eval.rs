@@ -0,0 +1,675 @@
+use std::{collections::HashMap, io::Write, thread, time::Duration};
+
+use slowpoke::{Turtle, TurtleColor};
+
+use crate::{
+    lexer::{Op, Pos},
+    parser::{Expr, ExprKind},
+    Logo, LogoError, Value,
+};
+
+// each procedure call takes a few of our own stack frames, see `Logo::run`
+const MAX_DEPTH: usize = 10_000;
+
+// the standard Logo colors, for `setpc 4` and the like
+const PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],       // black
+    [0, 0, 255],     // blue
+    [0, 255, 0],     // green
+    [0, 255, 255],   // cyan
+    [255, 0, 0],     // red
+    [255, 0, 255],   // magenta
+    [255, 255, 0],   // yellow
+    [255, 255, 255], // white
+    [155, 96, 59],   // brown
+    [197, 136, 18],  // tan
+    [100, 162, 64],  // forest
+    [120, 187, 187], // aqua
+    [255, 149, 119], // salmon
+    [144, 113, 208], // purple
+    [255, 163, 0],   // orange
+    [183, 183, 183], // grey
+];
+
+// Why evaluation stopped early
+pub(crate) enum Unwind {
+    Stop,
+    Output(Value, Pos),
+    Error(LogoError),
+}
+
+impl From<LogoError> for Unwind {
+    fn from(error: LogoError) -> Self {
+        Self::Error(error)
+    }
+}
+
+type Flow<T> = Result<T, Unwind>;
+
+fn error<T>(pos: Pos, message: impl Into<String>) -> Flow<T> {
+    Err(Unwind::Error(LogoError::new(pos, message)))
+}
+
+fn block(expr: &Expr) -> &[Expr] {
+    match &expr.kind {
+        ExprKind::Block(body) => body,
+        _ => unreachable!("the parser only gives blocks to inputs which take them"),
+    }
+}
+
+pub(crate) struct Eval<'a> {
+    logo: &'a mut Logo,
+    turtle: &'a mut Turtle,
+    frames: Vec<HashMap<String, Option<Value>>>, // the variables of each procedure being run
+    repcounts: Vec<usize>,
+}
+
+impl<'a> Eval<'a> {
+    pub(crate) fn new(logo: &'a mut Logo, turtle: &'a mut Turtle) -> Self {
+        Self {
+            logo,
+            turtle,
+            frames: Vec::new(),
+            repcounts: Vec::new(),
+        }
+    }
+
+    pub(crate) fn run(&mut self, body: &[Expr]) -> Result<(), LogoError> {
+        match self.run_block(body) {
+            Ok(()) | Err(Unwind::Stop) => Ok(()),
+            Err(Unwind::Output(_, pos)) => Err(LogoError::new(
+                pos,
+                "output can only be used inside a procedure",
+            )),
+            Err(Unwind::Error(e)) => Err(e),
+        }
+    }
+
+    fn run_block(&mut self, body: &[Expr]) -> Flow<()> {
+        for instruction in body {
+            if let Some(value) = self.eval(instruction)? {
+                return error(
+                    instruction.pos,
+                    format!("you don't say what to do with {value}"),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // Logo uses dynamic scope: a procedure sees the variables of whatever
+    // called it
+    fn var(&self, name: &str) -> Option<&Value> {
+        for frame in self.frames.iter().rev() {
+            if let Some(value) = frame.get(name) {
+                return value.as_ref();
+            }
+        }
+        self.logo.globals.get(name)
+    }
+
+    fn set_var(&mut self, name: &str, value: Value) {
+        for frame in self.frames.iter_mut().rev() {
+            if let Some(slot) = frame.get_mut(name) {
+                *slot = Some(value);
+                return;
+            }
+        }
+        self.logo.globals.insert(name.to_string(), value);
+    }
+
+    fn eval(&mut self, expr: &Expr) -> Flow<Option<Value>> {
+        let value = match &expr.kind {
+            ExprKind::Value(value) => value.clone(),
+            ExprKind::Var(name) => match self.var(name) {
+                Some(value) => value.clone(),
+                None => return error(expr.pos, format!("{name} has no value")),
+            },
+            ExprKind::Negate(inner) => Value::Number(-self.number(inner, "minus")?),
+            ExprKind::Binary(op, left, right) => self.binary(*op, left, right, expr.pos)?,
+            ExprKind::Call(name, inputs) => return self.call(name, inputs, expr.pos),
+            ExprKind::Primitive(name, inputs) => return self.primitive(name, inputs, expr.pos),
+            ExprKind::Block(_) => unreachable!("blocks are only run by primitives"),
+        };
+        Ok(Some(value))
+    }
+
+    // evaluate an input to `who`, which needs a value
+    fn value(&mut self, expr: &Expr, who: &str) -> Flow<Value> {
+        match self.eval(expr)? {
+            Some(value) => Ok(value),
+            None => {
+                let name = match &expr.kind {
+                    ExprKind::Call(name, _) => name.as_str(),
+                    ExprKind::Primitive(name, _) => name,
+                    _ => unreachable!("only procedures can output nothing"),
+                };
+                error(expr.pos, format!("{name} didn't output to {who}"))
+            }
+        }
+    }
+
+    fn number(&mut self, expr: &Expr, who: &str) -> Flow<f64> {
+        let value = self.value(expr, who)?;
+        match value.as_number() {
+            Some(n) => Ok(n),
+            None => error(expr.pos, format!("{who} doesn't like {value} as input")),
+        }
+    }
+
+    fn bool(&mut self, expr: &Expr, who: &str) -> Flow<bool> {
+        let value = self.value(expr, who)?;
+        match value.as_bool() {
+            Some(b) => Ok(b),
+            None => error(expr.pos, format!("{who} doesn't like {value} as input")),
+        }
+    }
+
+    fn word(&mut self, expr: &Expr, who: &str) -> Flow<String> {
+        match self.value(expr, who)? {
+            Value::List(_) => error(expr.pos, format!("{who} needs a word")),
+            value => Ok(value.to_string().to_lowercase()),
+        }
+    }
+
+    fn count(&mut self, expr: &Expr, who: &str) -> Flow<usize> {
+        let n = self.number(expr, who)?;
+        if n < 0. || n.fract() != 0. {
+            return error(expr.pos, format!("{who} doesn't like {n} as input"));
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(n as usize)
+    }
+
+    // a palette number, a name such as "red or "#ff8000, or [red green blue]
+    // from 0 to 255
+    fn color(&mut self, expr: &Expr, who: &str) -> Flow<TurtleColor> {
+        let rgb = |c: [f64; 3]| {
+            #[allow(clippy::cast_possible_truncation)]
+            let [r, g, b] = c.map(|c| (c.clamp(0., 255.) / 255.) as f32);
+            TurtleColor::Color(r, g, b)
+        };
+        let value = self.value(expr, who)?;
+        let color = match &value {
+            Value::List(items) => match items
+                .iter()
+                .map(Value::as_number)
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(c) if c.len() == 3 => Some(rgb([c[0], c[1], c[2]])),
+                _ => None,
+            },
+            value if value.as_number().is_some() => {
+                let n = value.as_number().expect("missing number");
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                PALETTE
+                    .get(n as usize)
+                    .filter(|_| n >= 0. && n.fract() == 0.)
+                    .map(|c| rgb(c.map(f64::from)))
+            }
+            Value::Word(name) => {
+                let name = name.to_lowercase();
+                let hex = name.strip_prefix('#').is_some_and(|hex| {
+                    hex.len() >= 6 && hex.chars().all(|c| c.is_ascii_hexdigit())
+                });
+                if name.starts_with('#') && !hex {
+                    None
+                } else {
+                    Some(TurtleColor::from(name.as_str()))
+                        .filter(|c| matches!(c, TurtleColor::Color(..)))
+                }
+            }
+            Value::Number(_) => unreachable!("numbers are handled above"),
+        };
+        match color {
+            Some(color) => Ok(color),
+            None => error(expr.pos, format!("{who} doesn't like {value} as input")),
+        }
+    }
+
+    fn binary(&mut self, op: Op, left: &Expr, right: &Expr, pos: Pos) -> Flow<Value> {
+        let symbol = match op {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::Eq => "=",
+            Op::Ne => "<>",
+            Op::Lt => "<",
+            Op::Gt => ">",
+            Op::Le => "<=",
+            Op::Ge => ">=",
+        };
+        if let Op::Eq | Op::Ne = op {
+            let equal = self
+                .value(left, symbol)?
+                .equals(&self.value(right, symbol)?);
+            return Ok(Value::bool(equal == (op == Op::Eq)));
+        }
+
+        let a = self.number(left, symbol)?;
+        let b = self.number(right, symbol)?;
+        Ok(match op {
+            Op::Add => Value::Number(a + b),
+            Op::Sub => Value::Number(a - b),
+            Op::Mul => Value::Number(a * b),
+            Op::Div if b == 0. => return error(pos, "can't divide by zero"),
+            Op::Div => Value::Number(a / b),
+            Op::Lt => Value::bool(a < b),
+            Op::Gt => Value::bool(a > b),
+            Op::Le => Value::bool(a <= b),
+            Op::Ge => Value::bool(a >= b),
+            Op::Eq | Op::Ne => unreachable!("compared above"),
+        })
+    }
+
+    fn call(&mut self, name: &str, inputs: &[Expr], pos: Pos) -> Flow<Option<Value>> {
+        let Some(procedure) = self.logo.procedures.get(name).cloned() else {
+            return error(pos, format!("I don't know how to {name}"));
+        };
+        if self.frames.len() >= MAX_DEPTH {
+            return error(pos, "too many procedures are running inside each other");
+        }
+
+        let mut frame = HashMap::new();
+        for (param, input) in procedure.params.iter().zip(inputs) {
+            frame.insert(param.clone(), Some(self.value(input, name)?));
+        }
+        self.frames.push(frame);
+        let result = self.run_block(&procedure.body);
+        self.frames.pop();
+
+        match result {
+            Ok(()) | Err(Unwind::Stop) => Ok(None),
+            Err(Unwind::Output(value, _)) => Ok(Some(value)),
+            Err(e) => Err(e),
+        }
+    }
+
+    // the first item of a word or list, or everything but it
+    fn split(&mut self, expr: &Expr, who: &str, first: bool, from_end: bool) -> Flow<Value> {
+        let value = self.value(expr, who)?;
+        let empty = || error(expr.pos, format!("{who} doesn't like {value} as input"));
+        match &value {
+            Value::List(items) if items.is_empty() => empty(),
+            Value::List(items) => Ok(match (first, from_end) {
+                (true, false) => items[0].clone(),
+                (true, true) => items[items.len() - 1].clone(),
+                (false, false) => Value::List(items[1..].to_vec()),
+                (false, true) => Value::List(items[..items.len() - 1].to_vec()),
+            }),
+            word => {
+                let chars: Vec<char> = word.to_string().chars().collect();
+                if chars.is_empty() {
+                    return empty();
+                }
+                let last = chars.len() - 1;
+                Ok(Value::Word(match (first, from_end) {
+                    (true, false) => chars[0].to_string(),
+                    (true, true) => chars[last].to_string(),
+                    (false, false) => chars[1..].iter().collect(),
+                    (false, true) => chars[..last].iter().collect(),
+                }))
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn primitive(&mut self, name: &str, inputs: &[Expr], pos: Pos) -> Flow<Option<Value>> {
+        let value = match name {
+            "forward" => {
+                let n = self.number(&inputs[0], name)?;
+                self.turtle.forward(n);
+                return Ok(None);
+            }
+            "back" => {
+                let n = self.number(&inputs[0], name)?;
+                self.turtle.backward(n);
+                return Ok(None);
+            }
+            "right" => {
+                let n = self.number(&inputs[0], name)?;
+                self.turtle.right(n);
+                return Ok(None);
+            }
+            "left" => {
+                let n = self.number(&inputs[0], name)?;
+                self.turtle.left(n);
+                return Ok(None);
+            }
+            "home" => {
+                self.turtle.home();
+                self.turtle.setheading(0);
+                return Ok(None);
+            }
+            "setxy" => {
+                let x = self.number(&inputs[0], name)?;
+                let y = self.number(&inputs[1], name)?;
+                self.turtle.goto(x, y);
+                return Ok(None);
+            }
+            "setpos" => {
+                let [x, y] = self.point(&inputs[0], name)?;
+                self.turtle.goto(x, y);
+                return Ok(None);
+            }
+            "setx" => {
+                let x = self.number(&inputs[0], name)?;
+                self.turtle.setx(x);
+                return Ok(None);
+            }
+            "sety" => {
+                #[allow(clippy::cast_possible_truncation)]
+                let y = self.number(&inputs[0], name)? as f32;
+                self.turtle.sety(y);
+                return Ok(None);
+            }
+            "setheading" => {
+                let n = self.number(&inputs[0], name)?;
+                self.turtle.setheading(n);
+                return Ok(None);
+            }
+            "circle" => {
+                let n = self.number(&inputs[0], name)?;
+                self.turtle.circle(n);
+                return Ok(None);
+            }
+            "penup" => {
+                self.turtle.penup();
+                return Ok(None);
+            }
+            "pendown" => {
+                self.turtle.pendown();
+                return Ok(None);
+            }
+            "setpencolor" => {
+                let color = self.color(&inputs[0], name)?;
+                self.turtle.pencolor(color);
+                return Ok(None);
+            }
+            "setfillcolor" => {
+                let color = self.color(&inputs[0], name)?;
+                self.turtle.fillcolor(color);
+                return Ok(None);
+            }
+            "setbackground" => {
+                let color = self.color(&inputs[0], name)?;
+                self.turtle.bgcolor(color);
+                return Ok(None);
+            }
+            "setpensize" => {
+                let n = self.number(&inputs[0], name)?;
+                self.turtle.pensize(n);
+                return Ok(None);
+            }
+            "beginfill" => {
+                self.turtle.begin_fill();
+                return Ok(None);
+            }
+            "endfill" => {
+                self.turtle.end_fill();
+                return Ok(None);
+            }
+            "filled" => {
+                let color = self.color(&inputs[0], name)?;
+                self.turtle.fillcolor(color);
+                self.turtle.begin_fill();
+                let result = self.run_block(block(&inputs[1]));
+                self.turtle.end_fill();
+                result?;
+                return Ok(None);
+            }
+            "clearscreen" => {
+                self.turtle.clear();
+                self.turtle.teleport(0, 0);
+                self.turtle.setheading(0);
+                return Ok(None);
+            }
+            "clean" => {
+                self.turtle.clear();
+                return Ok(None);
+            }
+            "hideturtle" => {
+                self.turtle.hideturtle();
+                return Ok(None);
+            }
+            "showturtle" => {
+                self.turtle.showturtle();
+                return Ok(None);
+            }
+            "label" => {
+                let text = self.value(&inputs[0], name)?.to_print();
+                self.turtle.write(&text);
+                return Ok(None);
+            }
+            "stamp" => {
+                self.turtle.stamp();
+                return Ok(None);
+            }
+            "undo" => {
+                self.turtle.undo();
+                return Ok(None);
+            }
+            "setspeed" => {
+                #[allow(clippy::cast_possible_truncation)]
+                let speed = self.number(&inputs[0], name)?.round() as i32;
+                self.turtle.speed(speed);
+                return Ok(None);
+            }
+            "wait" => {
+                // in 60ths of a second
+                let n = self.number(&inputs[0], name)?;
+                thread::sleep(Duration::from_secs_f64(n.max(0.) / 60.));
+                return Ok(None);
+            }
+            "print" => {
+                println!("{}", self.value(&inputs[0], name)?.to_print());
+                return Ok(None);
+            }
+            "show" => {
+                println!("{}", self.value(&inputs[0], name)?);
+                return Ok(None);
+            }
+            "type" => {
+                print!("{}", self.value(&inputs[0], name)?.to_print());
+                let _ = std::io::stdout().flush();
+                return Ok(None);
+            }
+            "make" => {
+                let var = self.word(&inputs[0], name)?;
+                let value = self.value(&inputs[1], name)?;
+                self.set_var(&var, value);
+                return Ok(None);
+            }
+            "local" => {
+                let names = match self.value(&inputs[0], name)? {
+                    Value::List(items) => items,
+                    word => vec![word],
+                };
+                if let Some(frame) = self.frames.last_mut() {
+                    for var in names {
+                        frame.insert(var.to_string().to_lowercase(), None);
+                    }
+                }
+                return Ok(None);
+            }
+            "thing" => {
+                let var = self.word(&inputs[0], name)?;
+                match self.var(&var) {
+                    Some(value) => value.clone(),
+                    None => return error(inputs[0].pos, format!("{var} has no value")),
+                }
+            }
+            "repeat" => {
+                let times = self.count(&inputs[0], name)?;
+                self.repcounts.push(0);
+                let mut result = Ok(());
+                for i in 1..=times {
+                    *self.repcounts.last_mut().expect("missing repcount") = i;
+                    result = self.run_block(block(&inputs[1]));
+                    if result.is_err() {
+                        break;
+                    }
+                }
+                self.repcounts.pop();
+                result?;
+                return Ok(None);
+            }
+            #[allow(clippy::cast_precision_loss)]
+            "repcount" => Value::Number(self.repcounts.last().map_or(-1., |&n| n as f64)),
+            "if" => {
+                if self.bool(&inputs[0], name)? {
+                    self.run_block(block(&inputs[1]))?;
+                }
+                return Ok(None);
+            }
+            "ifelse" => {
+                let body = if self.bool(&inputs[0], name)? {
+                    &inputs[1]
+                } else {
+                    &inputs[2]
+                };
+                self.run_block(block(body))?;
+                return Ok(None);
+            }
+            "stop" => return Err(Unwind::Stop),
+            "output" => {
+                let value = self.value(&inputs[0], name)?;
+                return Err(Unwind::Output(value, pos));
+            }
+            "sum" | "difference" | "product" | "quotient" | "remainder" | "power" => {
+                let a = self.number(&inputs[0], name)?;
+                let b = self.number(&inputs[1], name)?;
+                if b == 0. && (name == "quotient" || name == "remainder") {
+                    return error(inputs[1].pos, format!("{name} doesn't like 0 as input"));
+                }
+                Value::Number(match name {
+                    "sum" => a + b,
+                    "difference" => a - b,
+                    "product" => a * b,
+                    "quotient" => a / b,
+                    "remainder" => a % b,
+                    _ => a.powf(b),
+                })
+            }
+            "minus" | "abs" | "int" | "round" | "sqrt" | "sin" | "cos" | "tan" | "arctan" => {
+                let n = self.number(&inputs[0], name)?;
+                if name == "sqrt" && n < 0. {
+                    return error(inputs[0].pos, format!("sqrt doesn't like {n} as input"));
+                }
+                Value::Number(match name {
+                    "minus" => -n,
+                    "abs" => n.abs(),
+                    "int" => n.trunc(),
+                    "round" => n.round(),
+                    "sqrt" => n.sqrt(),
+                    "sin" => n.to_radians().sin(),
+                    "cos" => n.to_radians().cos(),
+                    "tan" => n.to_radians().tan(),
+                    _ => n.atan().to_degrees(),
+                })
+            }
+            "random" => {
+                let n = self.count(&inputs[0], name)?;
+                if n == 0 {
+                    return error(inputs[0].pos, "random doesn't like 0 as input");
+                }
+                #[allow(clippy::cast_precision_loss)]
+                Value::Number((self.logo.random() % n as u64) as f64)
+            }
+            "and" => {
+                let a = self.bool(&inputs[0], name)?;
+                let b = self.bool(&inputs[1], name)?;
+                Value::bool(a && b)
+            }
+            "or" => {
+                let a = self.bool(&inputs[0], name)?;
+                let b = self.bool(&inputs[1], name)?;
+                Value::bool(a || b)
+            }
+            "not" => Value::bool(!self.bool(&inputs[0], name)?),
+            "xcor" => Value::Number(self.turtle.xcor().into()),
+            "ycor" => Value::Number(self.turtle.ycor().into()),
+            "heading" => Value::Number(self.turtle.heading().into()),
+            "pos" => {
+                let pos = self.turtle.pos();
+                Value::List(vec![
+                    Value::Number(pos.x.into()),
+                    Value::Number(pos.y.into()),
+                ])
+            }
+            "towards" => {
+                let [x, y] = self.point(&inputs[0], name)?;
+                Value::Number(self.turtle.towards(x, y).into())
+            }
+            "pendownp" => Value::bool(self.turtle.isdown()),
+            "first" => self.split(&inputs[0], name, true, false)?,
+            "last" => self.split(&inputs[0], name, true, true)?,
+            "butfirst" => self.split(&inputs[0], name, false, false)?,
+            "butlast" => self.split(&inputs[0], name, false, true)?,
+            "item" => {
+                let index = self.count(&inputs[0], name)?;
+                let item = match self.value(&inputs[1], name)? {
+                    Value::List(items) => index.checked_sub(1).and_then(|i| items.get(i).cloned()),
+                    word => index
+                        .checked_sub(1)
+                        .and_then(|i| word.to_string().chars().nth(i))
+                        .map(|c| Value::Word(c.to_string())),
+                };
+                match item {
+                    Some(item) => item,
+                    None => {
+                        return error(inputs[0].pos, format!("item doesn't like {index} as input"));
+                    }
+                }
+            }
+            #[allow(clippy::cast_precision_loss)]
+            "count" => Value::Number(match self.value(&inputs[0], name)? {
+                Value::List(items) => items.len(),
+                word => word.to_string().chars().count(),
+            } as f64),
+            "emptyp" => Value::bool(match self.value(&inputs[0], name)? {
+                Value::List(items) => items.is_empty(),
+                word => word.to_string().is_empty(),
+            }),
+            "list" => Value::List(vec![
+                self.value(&inputs[0], name)?,
+                self.value(&inputs[1], name)?,
+            ]),
+            "fput" | "lput" => {
+                let item = self.value(&inputs[0], name)?;
+                let Value::List(mut items) = self.value(&inputs[1], name)? else {
+                    return error(inputs[1].pos, format!("{name} needs a list"));
+                };
+                if name == "fput" {
+                    items.insert(0, item);
+                } else {
+                    items.push(item);
+                }
+                Value::List(items)
+            }
+            "word" => {
+                let a = self.value(&inputs[0], name)?;
+                let b = self.value(&inputs[1], name)?;
+                if matches!(a, Value::List(_)) || matches!(b, Value::List(_)) {
+                    return error(pos, "word needs words");
+                }
+                Value::Word(format!("{a}{b}"))
+            }
+            _ => unreachable!("no primitive {name}"),
+        };
+        Ok(Some(value))
+    }
+
+    // [x y]
+    fn point(&mut self, expr: &Expr, who: &str) -> Flow<[f64; 2]> {
+        let value = self.value(expr, who)?;
+        if let Value::List(items) = &value
+            && let [x, y] = items.as_slice()
+            && let (Some(x), Some(y)) = (x.as_number(), y.as_number())
+        {
+            return Ok([x, y]);
+        }
+        error(expr.pos, format!("{who} doesn't like {value} as input"))
+    }
+}
@@ -0,0 +1,242 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::LogoError;
+
+/// Where something is in the program, counting from 1
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Pos {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind {
+    Number(f64),
+    Word(String),   // a procedure name, as typed
+    Quoted(String), // "word
+    Var(String),    // :name
+    Open,           // [
+    Close,          // ]
+    OpenParen,
+    CloseParen,
+    Op(Op),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub pos: Pos,
+    // a minus sign with a space before it and none after, as in `fd -10`,
+    // starts a negative number rather than subtracting
+    pub unary: bool,
+}
+
+impl Token {
+    pub(crate) fn is_word(&self, word: &str) -> bool {
+        matches!(&self.kind, TokenKind::Word(w) if w.eq_ignore_ascii_case(word))
+    }
+}
+
+// characters which end a word, besides spaces
+const DELIMITERS: &str = "[]()+-*/=<>;";
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    pos: Pos,
+    spaced: bool, // there was a space (or nothing) before the next character
+}
+
+impl Lexer<'_> {
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.pos.line += 1;
+            self.pos.column = 1;
+        } else {
+            self.pos.column += 1;
+        }
+        Some(c)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    // the rest of a word, up to a space or one of `stop`
+    fn word(&mut self, first: Option<char>, stop: &str) -> String {
+        let mut word: String = first.into_iter().collect();
+        while let Some(c) = self.peek()
+            && !c.is_whitespace()
+            && !stop.contains(c)
+        {
+            word.push(c);
+            self.next();
+        }
+        word
+    }
+
+    fn op(&mut self, c: char) -> Op {
+        match (c, self.peek()) {
+            ('<', Some('=')) => {
+                self.next();
+                Op::Le
+            }
+            ('>', Some('=')) => {
+                self.next();
+                Op::Ge
+            }
+            ('<', Some('>')) => {
+                self.next();
+                Op::Ne
+            }
+            ('+', _) => Op::Add,
+            ('-', _) => Op::Sub,
+            ('*', _) => Op::Mul,
+            ('/', _) => Op::Div,
+            ('=', _) => Op::Eq,
+            ('<', _) => Op::Lt,
+            _ => Op::Gt,
+        }
+    }
+}
+
+pub(crate) fn tokenize(source: &str) -> Result<Vec<Token>, LogoError> {
+    let mut lexer = Lexer {
+        chars: source.chars().peekable(),
+        pos: Pos { line: 1, column: 1 },
+        spaced: true,
+    };
+    let mut tokens = Vec::new();
+
+    while let Some(c) = lexer.peek() {
+        if c.is_whitespace() {
+            lexer.next();
+            lexer.spaced = true;
+            continue;
+        }
+        if c == ';' {
+            while lexer.peek().is_some_and(|c| c != '\n') {
+                lexer.next();
+            }
+            continue;
+        }
+
+        let pos = lexer.pos;
+        let spaced = lexer.spaced;
+        lexer.next();
+        let kind = match c {
+            '[' => TokenKind::Open,
+            ']' => TokenKind::Close,
+            '(' => TokenKind::OpenParen,
+            ')' => TokenKind::CloseParen,
+            '"' => TokenKind::Quoted(lexer.word(None, "[]()")),
+            ':' => {
+                let name = lexer.word(None, DELIMITERS);
+                if name.is_empty() {
+                    return Err(LogoError::new(pos, "a variable needs a name after the :"));
+                }
+                TokenKind::Var(name)
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let number = lexer.word(Some(c), DELIMITERS);
+                TokenKind::Number(
+                    number
+                        .parse()
+                        .map_err(|_| LogoError::new(pos, format!("{number} isn't a number")))?,
+                )
+            }
+            c if DELIMITERS.contains(c) => TokenKind::Op(lexer.op(c)),
+            c => TokenKind::Word(lexer.word(Some(c), DELIMITERS)),
+        };
+
+        // opening brackets count as space for the minus sign after them
+        lexer.spaced = matches!(kind, TokenKind::Open | TokenKind::OpenParen);
+        let unary = kind == TokenKind::Op(Op::Sub)
+            && spaced
+            && lexer.peek().is_some_and(|c| !c.is_whitespace());
+        tokens.push(Token { kind, pos, unary });
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn words_numbers_and_operators() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("REPEAT 4 [fd :size*2] ; a comment\nsetpc \"red"),
+            vec![
+                Word("REPEAT".into()),
+                Number(4.),
+                Open,
+                Word("fd".into()),
+                Var("size".into()),
+                Op(super::Op::Mul),
+                Number(2.),
+                Close,
+                Word("setpc".into()),
+                Quoted("red".into()),
+            ]
+        );
+        assert_eq!(
+            kinds("1<=2<>3"),
+            vec![
+                Number(1.),
+                Op(super::Op::Le),
+                Number(2.),
+                Op(super::Op::Ne),
+                Number(3.)
+            ]
+        );
+    }
+
+    #[test]
+    fn minus_signs() {
+        let unary: Vec<bool> = ["fd -10", "fd :x - 10", "fd :x-10", "fd [-10]", "fd (-10)"]
+            .iter()
+            .map(|source| {
+                tokenize(source)
+                    .unwrap()
+                    .iter()
+                    .find(|t| t.kind == TokenKind::Op(Op::Sub))
+                    .unwrap()
+                    .unary
+            })
+            .collect();
+        assert_eq!(unary, [true, false, false, true, true]);
+    }
+
+    #[test]
+    fn positions() {
+        let tokens = tokenize("fd 10\n  rt 3x").unwrap_err();
+        assert_eq!((tokens.line, tokens.column), (2, 6));
+        let tokens = tokenize("fd 10\n  rt 90").unwrap();
+        assert_eq!(tokens[2].pos, Pos { line: 2, column: 3 });
+    }
+}
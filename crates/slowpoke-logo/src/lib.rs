@@ -0,0 +1,216 @@
+//! A Logo interpreter which drives a slowpoke `Turtle`, so it runs under any
+//! frontend:
+//!
+//! ```ignore
+//! Slowpoke::default().run(|turtle| {
+//!     if let Err(e) = slowpoke_logo::run(turtle, "repeat 4 [fd 100 rt 90]") {
+//!         eprintln!("{e}");
+//!     }
+//! });
+//! ```
+//!
+//! The language is the classic core of Logo:
+//!
+//! - procedures: `to square :size repeat 4 [fd :size rt 90] end`, which can
+//!   call themselves, and `output`/`op` or `stop` to leave them early
+//! - variables: `make "x 10`, `:x`, `local "y` and `thing "x`
+//! - arithmetic and comparisons: `+ - * / = <> < > <= >=`, as well as `sum`,
+//!   `remainder`, `sqrt`, `sin` (in degrees), `random` and so on
+//! - control: `repeat`, `repcount`, `if`, `ifelse`, `and`, `or` and `not`
+//! - the turtle: `fd`, `bk`, `rt`, `lt`, `setxy`, `setheading`, `home`,
+//!   `circle`, `pu`, `pd`, `setpc`, `setfc`, `setbg`, `setpensize`,
+//!   `beginfill`/`endfill` (or `filled color [ ... ]`), `label`, `stamp`,
+//!   `undo`, `cs`, `ht`, `st`, `setspeed`, and `xcor`, `ycor`, `heading`,
+//!   `pos` and `towards` to ask where it is
+//! - words and lists: `first`, `butfirst`, `item`, `count`, `fput`, `word`...
+//!
+//! Names aren't case sensitive. Colors are a number from the standard Logo
+//! palette, a name such as `"red`, or a list of red, green and blue from 0 to
+//! 255. The turtle is put in logo mode, so it starts facing north and turns
+//! clockwise.
+
+mod eval;
+mod lexer;
+mod parser;
+mod primitives;
+mod value;
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    fmt::Display,
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+};
+
+use slowpoke::Turtle;
+
+pub use lexer::Pos;
+pub use primitives::names as primitive_names;
+pub use value::Value;
+
+use eval::Eval;
+use parser::Procedure;
+
+/// Something wrong with a program, and where
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogoError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl LogoError {
+    pub(crate) fn new<S: Into<String>>(pos: Pos, message: S) -> Self {
+        Self {
+            line: pos.line,
+            column: pos.column,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for LogoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for LogoError {}
+
+/// A Logo workspace. Procedures and global variables are kept from one
+/// program to the next.
+#[derive(Debug)]
+pub struct Logo {
+    procedures: HashMap<String, Arc<Procedure>>,
+    globals: HashMap<String, Value>,
+    random: u64,
+    started: bool, // the turtle has been put in logo mode
+}
+
+impl Default for Logo {
+    fn default() -> Self {
+        Self {
+            procedures: HashMap::new(),
+            globals: HashMap::new(),
+            // xorshift needs a seed other than zero
+            random: RandomState::new().build_hasher().finish() | 1,
+            started: false,
+        }
+    }
+}
+
+impl Logo {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run a program on `turtle`. Any procedures it defines are kept for later
+    /// programs, even if it fails part way through.
+    ///
+    /// # Errors
+    /// Returns the first error in the program, or the one that stopped it
+    ///
+    /// # Panics
+    /// Panics if the thread that runs the program can't be started
+    pub fn run(&mut self, turtle: &mut Turtle, source: &str) -> Result<(), LogoError> {
+        let tokens = lexer::tokenize(source)?;
+        let known = self
+            .procedures
+            .iter()
+            .map(|(name, procedure)| (name.clone(), procedure.params.len()))
+            .collect();
+        let program = parser::parse(&tokens, known)?;
+        for procedure in program.procedures {
+            self.procedures
+                .insert(procedure.name.clone(), Arc::new(procedure));
+        }
+
+        if !self.started {
+            turtle.mode("logo");
+            self.started = true;
+        }
+
+        // deeply recursive procedures need more stack than a thread usually gets
+        turtle.with_deep_stack(|turtle| Eval::new(self, turtle).run(&program.body))
+    }
+
+    /// The names of the procedures defined so far
+    pub fn procedures(&self) -> impl Iterator<Item = &str> {
+        self.procedures.keys().map(String::as_str)
+    }
+
+    // xorshift64
+    fn random(&mut self) -> u64 {
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 7;
+        self.random ^= self.random << 17;
+        self.random
+    }
+}
+
+/// Run a program on `turtle`, in a new workspace
+///
+/// # Errors
+/// Returns the first error in the program, or the one that stopped it
+pub fn run(turtle: &mut Turtle, source: &str) -> Result<(), LogoError> {
+    Logo::new().run(turtle, source)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use slowpoke::{headless::Headless, SlowpokeLib};
+
+    use super::*;
+
+    // where the turtle ended up: x, y and heading
+    fn run_logo(source: &'static str) -> (Result<(), LogoError>, [f32; 3]) {
+        let (send, receive) = mpsc::channel();
+        SlowpokeLib::<Headless>::default().run(move |turtle| {
+            turtle.speed(0);
+            let result = run(turtle, source);
+            let _ = send.send((result, [turtle.xcor(), turtle.ycor(), turtle.heading()]));
+            turtle.bye();
+        });
+        receive.recv().expect("the program didn't finish")
+    }
+
+    #[test]
+    fn drives_the_turtle() {
+        let (result, [x, y, heading]) = run_logo("fd 3 * 4 + 2 rt 90 fd -10 / 2 lt 45");
+        assert_eq!(result, Ok(()));
+        assert_eq!([x.round(), y.round(), heading.round()], [-5., 14., 45.]);
+    }
+
+    #[test]
+    fn procedures_recurse() {
+        let (result, [x, y, _]) = run_logo(
+            "to spiral :n\n  if :n = 0 [stop]\n  fd 1\n  spiral :n - 1\nend\n\
+             to double :n\n  op :n * 2\nend\n\
+             make \"steps double 150\nspiral :steps\n\
+             repeat 3 [setx xcor + repcount]",
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!([x.round(), y.round()], [6., 300.]);
+    }
+
+    #[test]
+    fn runtime_errors_say_where() {
+        let (result, _) = run_logo("make \"size 10\nfd :size\nfd :sise");
+        let error = result.unwrap_err();
+        assert_eq!((error.line, error.column), (3, 4));
+        assert_eq!(error.message, "sise has no value");
+
+        let (result, _) = run_logo("repeat 2 [\n  setpc \"nocolor\n]");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "line 2, column 9: setpencolor doesn't like nocolor as input"
+        );
+    }
+}
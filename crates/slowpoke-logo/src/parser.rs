@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+
+use crate::{
+    lexer::{Op, Pos, Token, TokenKind},
+    primitives::{self, Input},
+    LogoError, Value,
+};
+
+#[derive(Debug, Clone)]
+pub(crate) struct Expr {
+    pub kind: ExprKind,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum ExprKind {
+    Value(Value), // numbers, quoted words and lists
+    Var(String),
+    Primitive(&'static str, Vec<Expr>),
+    Call(String, Vec<Expr>), // a procedure defined with TO
+    Block(Vec<Expr>),
+    Negate(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug)]
+pub(crate) struct Procedure {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Vec<Expr>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Program {
+    pub procedures: Vec<Procedure>,
+    pub body: Vec<Expr>,
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    next: usize,
+    arity: HashMap<String, usize>, // of each procedure defined with TO
+}
+
+/// Parse a program. Calls are split up by how many inputs each procedure
+/// takes, so `known` has the procedures defined by earlier programs; the ones
+/// this program defines are found before parsing starts.
+pub(crate) fn parse(tokens: &[Token], known: HashMap<String, usize>) -> Result<Program, LogoError> {
+    let mut parser = Parser {
+        tokens,
+        next: 0,
+        arity: known,
+    };
+    parser.find_procedures()?;
+
+    let mut program = Program::default();
+    while let Some(token) = parser.peek() {
+        if token.is_word("to") {
+            program.procedures.push(parser.procedure()?);
+        } else {
+            program.body.push(parser.expr()?);
+        }
+    }
+    Ok(program)
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Option<&'a Token> {
+        self.next += 1;
+        self.tokens.get(self.next - 1)
+    }
+
+    // just after the last token, for complaining about things that are missing
+    fn end_pos(&self) -> Pos {
+        self.tokens.last().map_or(Pos { line: 1, column: 1 }, |t| {
+            let mut pos = t.pos;
+            pos.column += 1;
+            pos
+        })
+    }
+
+    fn find_procedures(&mut self) -> Result<(), LogoError> {
+        for (i, token) in self.tokens.iter().enumerate() {
+            if !token.is_word("to") {
+                continue;
+            }
+            let Some(Token {
+                kind: TokenKind::Word(name),
+                pos,
+                ..
+            }) = self.tokens.get(i + 1)
+            else {
+                return Err(LogoError::new(token.pos, "TO needs a name"));
+            };
+            if primitives::lookup(name).is_some() || name.eq_ignore_ascii_case("to") {
+                return Err(LogoError::new(*pos, format!("{name} is a primitive")));
+            }
+            let params = self.tokens[i + 2..]
+                .iter()
+                .take_while(|t| matches!(t.kind, TokenKind::Var(_)))
+                .count();
+            self.arity.insert(name.to_lowercase(), params);
+        }
+        Ok(())
+    }
+
+    // TO name :input ... END
+    fn procedure(&mut self) -> Result<Procedure, LogoError> {
+        let start = self.advance().expect("missing TO").pos;
+        let Some(TokenKind::Word(name)) = self.advance().map(|t| &t.kind) else {
+            unreachable!("checked by find_procedures");
+        };
+        let name = name.to_lowercase();
+
+        let mut params = Vec::new();
+        while let Some(TokenKind::Var(param)) = self.peek().map(|t| &t.kind) {
+            params.push(param.to_lowercase());
+            self.next += 1;
+        }
+
+        let mut body = Vec::new();
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(LogoError::new(
+                        start,
+                        format!("TO {name} is missing its END"),
+                    ));
+                }
+                Some(t) if t.is_word("end") => {
+                    self.next += 1;
+                    break;
+                }
+                Some(t) if t.is_word("to") => {
+                    return Err(LogoError::new(t.pos, "TO can't be used inside a procedure"));
+                }
+                Some(_) => body.push(self.expr()?),
+            }
+        }
+
+        Ok(Procedure { name, params, body })
+    }
+
+    // [ instructions ]
+    fn block(&mut self) -> Result<Expr, LogoError> {
+        let pos = match self.advance() {
+            Some(Token {
+                kind: TokenKind::Open,
+                pos,
+                ..
+            }) => *pos,
+            Some(t) => return Err(LogoError::new(t.pos, "expected [")),
+            None => return Err(LogoError::new(self.end_pos(), "expected [")),
+        };
+
+        let mut body = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(LogoError::new(pos, "this [ is missing its ]")),
+                Some(t) if t.kind == TokenKind::Close => {
+                    self.next += 1;
+                    break;
+                }
+                Some(t) if t.is_word("to") => {
+                    return Err(LogoError::new(t.pos, "TO can't be used inside brackets"));
+                }
+                Some(_) => body.push(self.expr()?),
+            }
+        }
+        Ok(Expr {
+            kind: ExprKind::Block(body),
+            pos,
+        })
+    }
+
+    // a list outside of a procedure's inputs is data, and isn't run
+    fn list(&mut self, pos: Pos) -> Result<Value, LogoError> {
+        let mut items = Vec::new();
+        loop {
+            let Some(token) = self.advance() else {
+                return Err(LogoError::new(pos, "this [ is missing its ]"));
+            };
+            let item = match &token.kind {
+                TokenKind::Close => break,
+                TokenKind::Open => self.list(token.pos)?,
+                TokenKind::Number(n) => Value::Number(*n),
+                TokenKind::Word(w) | TokenKind::Quoted(w) => Value::Word(w.clone()),
+                TokenKind::Var(v) => Value::Word(format!(":{v}")),
+                TokenKind::Op(Op::Sub) if token.unary => match self.advance() {
+                    Some(Token {
+                        kind: TokenKind::Number(n),
+                        ..
+                    }) => Value::Number(-n),
+                    _ => return Err(LogoError::new(token.pos, "lists can only hold words")),
+                },
+                _ => return Err(LogoError::new(token.pos, "lists can only hold words")),
+            };
+            items.push(item);
+        }
+        Ok(Value::List(items))
+    }
+
+    pub(crate) fn expr(&mut self) -> Result<Expr, LogoError> {
+        let mut left = self.additive()?;
+        while let Some(op @ (Op::Eq | Op::Ne | Op::Lt | Op::Gt | Op::Le | Op::Ge)) = self.op() {
+            let pos = self.advance().expect("missing operator").pos;
+            let right = self.additive()?;
+            left = Expr {
+                kind: ExprKind::Binary(op, Box::new(left), Box::new(right)),
+                pos,
+            };
+        }
+        Ok(left)
+    }
+
+    // the next token, if it's an operator that carries on an expression
+    fn op(&self) -> Option<Op> {
+        match self.peek() {
+            Some(Token {
+                kind: TokenKind::Op(op),
+                unary: false,
+                ..
+            }) => Some(*op),
+            _ => None,
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, LogoError> {
+        let mut left = self.term()?;
+        while let Some(op @ (Op::Add | Op::Sub)) = self.op() {
+            let pos = self.advance().expect("missing operator").pos;
+            let right = self.term()?;
+            left = Expr {
+                kind: ExprKind::Binary(op, Box::new(left), Box::new(right)),
+                pos,
+            };
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, LogoError> {
+        let mut left = self.unary()?;
+        while let Some(op @ (Op::Mul | Op::Div)) = self.op() {
+            let pos = self.advance().expect("missing operator").pos;
+            let right = self.unary()?;
+            left = Expr {
+                kind: ExprKind::Binary(op, Box::new(left), Box::new(right)),
+                pos,
+            };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, LogoError> {
+        if let Some(Token {
+            kind: TokenKind::Op(Op::Sub),
+            pos,
+            ..
+        }) = self.peek()
+        {
+            let pos = *pos;
+            self.next += 1;
+            let value = self.unary()?;
+            return Ok(Expr {
+                kind: ExprKind::Negate(Box::new(value)),
+                pos,
+            });
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, LogoError> {
+        let Some(token) = self.advance() else {
+            return Err(LogoError::new(self.end_pos(), "the program ends too soon"));
+        };
+        let pos = token.pos;
+        let kind = match &token.kind {
+            TokenKind::Number(n) => ExprKind::Value(Value::Number(*n)),
+            TokenKind::Quoted(w) => ExprKind::Value(Value::Word(w.clone())),
+            TokenKind::Var(v) => ExprKind::Var(v.to_lowercase()),
+            TokenKind::Open => ExprKind::Value(self.list(pos)?),
+            TokenKind::OpenParen => {
+                let inner = self.expr()?;
+                match self.advance() {
+                    Some(Token {
+                        kind: TokenKind::CloseParen,
+                        ..
+                    }) => return Ok(inner),
+                    _ => return Err(LogoError::new(pos, "this ( is missing its )")),
+                }
+            }
+            TokenKind::Word(name) => {
+                let name = name.clone();
+                return self.call(&name, pos);
+            }
+            TokenKind::Close => return Err(LogoError::new(pos, "unexpected ]")),
+            TokenKind::CloseParen => return Err(LogoError::new(pos, "unexpected )")),
+            TokenKind::Op(_) => return Err(LogoError::new(pos, "unexpected operator")),
+        };
+        Ok(Expr { kind, pos })
+    }
+
+    fn call(&mut self, name: &str, pos: Pos) -> Result<Expr, LogoError> {
+        let kind = if let Some(primitive) = primitives::lookup(name) {
+            ExprKind::Primitive(
+                primitive.name,
+                self.inputs(primitive.name, primitive.inputs, pos)?,
+            )
+        } else if let Some(&arity) = self.arity.get(&name.to_lowercase()) {
+            let inputs = self.inputs(name, &vec![Input::Value; arity], pos)?;
+            ExprKind::Call(name.to_lowercase(), inputs)
+        } else if name.eq_ignore_ascii_case("end") {
+            return Err(LogoError::new(pos, "END without TO"));
+        } else {
+            return Err(LogoError::new(pos, format!("I don't know how to {name}")));
+        };
+        Ok(Expr { kind, pos })
+    }
+
+    fn inputs(&mut self, name: &str, inputs: &[Input], pos: Pos) -> Result<Vec<Expr>, LogoError> {
+        inputs
+            .iter()
+            .map(|input| {
+                let ends = self.peek().is_none_or(|t| {
+                    t.kind == TokenKind::Close
+                        || t.kind == TokenKind::CloseParen
+                        || t.is_word("end")
+                });
+                if ends {
+                    return Err(LogoError::new(pos, format!("not enough inputs to {name}")));
+                }
+                match input {
+                    Input::Value => self.expr(),
+                    Input::Block => self.block(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lexer::tokenize;
+
+    fn parse_str(source: &str) -> Result<Program, LogoError> {
+        parse(&tokenize(source)?, HashMap::new())
+    }
+
+    #[test]
+    fn calls_take_as_many_inputs_as_they_need() {
+        let program = parse_str(
+            "to square :size\n  repeat 4 [fd :size rt 90]\nend\nsquare 10 square -10 * 2",
+        )
+        .unwrap();
+        assert_eq!(program.procedures.len(), 1);
+        assert_eq!(program.procedures[0].params, ["size"]);
+        assert_eq!(program.body.len(), 2);
+        let ExprKind::Call(_, inputs) = &program.body[1].kind else {
+            panic!("not a call");
+        };
+        assert!(matches!(inputs[0].kind, ExprKind::Binary(Op::Mul, ..)));
+    }
+
+    #[test]
+    fn procedures_can_be_used_before_they_are_defined() {
+        assert!(parse_str("tree 5\nto tree :n\nif :n > 0 [tree :n - 1]\nend").is_ok());
+    }
+
+    #[test]
+    fn errors_say_where() {
+        let error = parse_str("fd 10\nrt").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(error.message, "not enough inputs to right");
+
+        let error = parse_str("repeat 4 [fd 10\nrt 90").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 10));
+
+        let error = parse_str("fd 10 jump 5").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 7));
+        assert_eq!(error.message, "I don't know how to jump");
+    }
+}
@@ -0,0 +1,135 @@
+// The procedures Logo knows without being taught, and what they take
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Input {
+    Value,
+    Block, // an instruction list in brackets, run by the primitive
+}
+
+#[derive(Debug)]
+pub(crate) struct Primitive {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub inputs: &'static [Input],
+}
+
+const V: Input = Input::Value;
+const B: Input = Input::Block;
+
+macro_rules! primitives {
+    ($($name:literal $([$($alias:literal),*])? ($($input:ident),*),)*) => {
+        &[$(Primitive {
+            name: $name,
+            aliases: &[$($($alias),*)?],
+            inputs: &[$($input),*],
+        },)*]
+    };
+}
+
+const PRIMITIVES: &[Primitive] = primitives! {
+    // moving
+    "forward" ["fd"] (V),
+    "back" ["bk", "backward"] (V),
+    "right" ["rt"] (V),
+    "left" ["lt"] (V),
+    "home" (),
+    "setxy" (V, V),
+    "setpos" (V),
+    "setx" (V),
+    "sety" (V),
+    "setheading" ["seth"] (V),
+    "circle" (V),
+
+    // drawing
+    "penup" ["pu"] (),
+    "pendown" ["pd"] (),
+    "setpencolor" ["setpc"] (V),
+    "setfillcolor" ["setfc"] (V),
+    "setbackground" ["setbg"] (V),
+    "setpensize" ["setwidth", "setpw"] (V),
+    "beginfill" (),
+    "endfill" (),
+    "filled" (V, B),
+    "clearscreen" ["cs"] (),
+    "clean" (),
+    "hideturtle" ["ht"] (),
+    "showturtle" ["st"] (),
+    "label" (V),
+    "stamp" (),
+    "undo" (),
+    "setspeed" (V),
+    "wait" (V),
+
+    // printing
+    "print" ["pr"] (V),
+    "show" (V),
+    "type" (V),
+
+    // variables
+    "make" (V, V),
+    "local" (V),
+    "thing" (V),
+
+    // control
+    "repeat" (V, B),
+    "repcount" ["#"] (),
+    "if" (V, B),
+    "ifelse" (V, B, B),
+    "stop" (),
+    "output" ["op"] (V),
+
+    // arithmetic
+    "sum" (V, V),
+    "difference" (V, V),
+    "product" (V, V),
+    "quotient" (V, V),
+    "remainder" (V, V),
+    "power" (V, V),
+    "minus" (V),
+    "abs" (V),
+    "int" (V),
+    "round" (V),
+    "sqrt" (V),
+    "sin" (V),
+    "cos" (V),
+    "tan" (V),
+    "arctan" (V),
+    "random" (V),
+    "and" (V, V),
+    "or" (V, V),
+    "not" (V),
+
+    // asking the turtle
+    "xcor" (),
+    "ycor" (),
+    "heading" (),
+    "pos" (),
+    "towards" (V),
+    "pendownp" ["pendown?"] (),
+
+    // words and lists
+    "first" (V),
+    "last" (V),
+    "butfirst" ["bf"] (V),
+    "butlast" ["bl"] (V),
+    "item" (V, V),
+    "count" (V),
+    "emptyp" ["empty?"] (V),
+    "list" (V, V),
+    "fput" (V, V),
+    "lput" (V, V),
+    "word" (V, V),
+};
+
+pub(crate) fn lookup(name: &str) -> Option<&'static Primitive> {
+    PRIMITIVES.iter().find(|p| {
+        p.name.eq_ignore_ascii_case(name) || p.aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+    })
+}
+
+/// The names of all the primitives, including their short forms
+pub fn names() -> impl Iterator<Item = &'static str> {
+    PRIMITIVES
+        .iter()
+        .flat_map(|p| std::iter::once(p.name).chain(p.aliases.iter().copied()))
+}
@@ -0,0 +1,81 @@
+use std::fmt::Display;
+
+/// Everything in Logo is a number, a word or a list. True and false are the
+/// words `true` and `false`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Word(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    pub(crate) fn bool(value: bool) -> Self {
+        Self::Word(if value { "true" } else { "false" }.into())
+    }
+
+    // words which look like numbers can be used as numbers
+    pub(crate) fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            Self::Word(w) => w.parse().ok(),
+            Self::List(_) => None,
+        }
+    }
+
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Word(w) if w.eq_ignore_ascii_case("true") => Some(true),
+            Self::Word(w) if w.eq_ignore_ascii_case("false") => Some(false),
+            _ => None,
+        }
+    }
+
+    // Logo compares words without caring about case, and numbers by value
+    pub(crate) fn equals(&self, other: &Self) -> bool {
+        if let (Some(a), Some(b)) = (self.as_number(), other.as_number()) {
+            return a == b;
+        }
+        match (self, other) {
+            (Self::Word(a), Self::Word(b)) => a.eq_ignore_ascii_case(b),
+            (Self::List(a), Self::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.equals(b))
+            }
+            _ => false,
+        }
+    }
+
+    /// How `print` shows a value: lists without their outer brackets
+    #[must_use]
+    pub fn to_print(&self) -> String {
+        match self {
+            Self::List(items) => items
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            value => value.to_string(),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // whole numbers are shown without a decimal point
+            Self::Number(n) if n.fract() == 0. && n.abs() < 1e15 => write!(f, "{n:.0}"),
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Word(w) => write!(f, "{w}"),
+            Self::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
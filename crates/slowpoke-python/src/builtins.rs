@@ -0,0 +1,933 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::hash_map::{DefaultHasher, RandomState},
+    hash::{BuildHasher, Hash, Hasher},
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    eval::{error, int, iterate, less_than, number, text, type_error, Args, Eval, Flow},
+    format,
+    lexer::Pos,
+    parser::BinOp,
+    turtles::{screen_method, turtle_method, SCREEN_METHODS, TURTLE_METHODS},
+    value::{Module, Value},
+};
+
+const BUILTINS: &[&str] = &[
+    "abs",
+    "bool",
+    "divmod",
+    "enumerate",
+    "float",
+    "int",
+    "len",
+    "list",
+    "max",
+    "min",
+    "pow",
+    "print",
+    "range",
+    "repr",
+    "reversed",
+    "round",
+    "sorted",
+    "str",
+    "sum",
+    "tuple",
+    "zip",
+];
+
+// the functions in each module, qualified by the module's name
+const FUNCTIONS: &[&str] = &[
+    "math.acos",
+    "math.asin",
+    "math.atan",
+    "math.atan2",
+    "math.ceil",
+    "math.cos",
+    "math.degrees",
+    "math.exp",
+    "math.fabs",
+    "math.floor",
+    "math.hypot",
+    "math.log",
+    "math.log10",
+    "math.pow",
+    "math.radians",
+    "math.sin",
+    "math.sqrt",
+    "math.tan",
+    "math.trunc",
+    "random.choice",
+    "random.randint",
+    "random.random",
+    "random.randrange",
+    "random.seed",
+    "random.shuffle",
+    "random.uniform",
+    "time.sleep",
+    "time.time",
+    "turtle.Pen",
+    "turtle.RawTurtle",
+    "turtle.Screen",
+    "turtle.Turtle",
+    "turtle.Vec2D",
+];
+
+const MATH_CONSTANTS: &[(&str, f64)] = &[
+    ("e", std::f64::consts::E),
+    ("inf", f64::INFINITY),
+    ("nan", f64::NAN),
+    ("pi", std::f64::consts::PI),
+    ("tau", std::f64::consts::TAU),
+];
+
+const LIST_METHODS: &[&str] = &[
+    "append", "clear", "copy", "count", "extend", "index", "insert", "pop", "remove", "reverse",
+    "sort",
+];
+
+const STR_METHODS: &[&str] = &[
+    "count",
+    "endswith",
+    "find",
+    "format",
+    "join",
+    "lower",
+    "lstrip",
+    "replace",
+    "rstrip",
+    "split",
+    "startswith",
+    "strip",
+    "upper",
+];
+
+fn find(names: &[&'static str], name: &str) -> Option<&'static str> {
+    names.iter().copied().find(|n| *n == name)
+}
+
+pub(crate) fn list_method(name: &str) -> Option<&'static str> {
+    find(LIST_METHODS, name)
+}
+
+pub(crate) fn str_method(name: &str) -> Option<&'static str> {
+    find(STR_METHODS, name)
+}
+
+fn domain_error<T>(pos: Pos) -> Flow<T> {
+    error(pos, "ValueError: math domain error")
+}
+
+// sort the way Python does, failing if two of the items can't be compared
+fn sort(items: &mut [Value], reverse: bool, pos: Pos) -> Flow<()> {
+    let mut failure = None;
+    items.sort_by(|a, b| {
+        let ordering = less_than(a, b, pos).and_then(|less| {
+            Ok(if less {
+                Ordering::Less
+            } else if less_than(b, a, pos)? {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            })
+        });
+        ordering.unwrap_or_else(|e| {
+            failure.get_or_insert(e);
+            Ordering::Equal
+        })
+    });
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    if reverse {
+        items.reverse();
+    }
+    Ok(())
+}
+
+// min() and max() take either one iterable or several values
+fn extreme(name: &'static str, args: Args, pick: Ordering) -> Flow<Value> {
+    let pos = args.pos;
+    args.check_keywords(name, &["default"])?;
+    let default = args.keyword("default").cloned();
+    let items = match args.values.as_slice() {
+        [] => return type_error(pos, format!("{name} expected at least 1 argument, got 0")),
+        [iterable] => iterate(iterable, pos)?,
+        values => values.to_vec(),
+    };
+    let mut items = items.into_iter();
+    let Some(mut best) = items.next() else {
+        return match default {
+            Some(default) => Ok(default),
+            None => error(
+                pos,
+                format!("ValueError: {name}() iterable argument is empty"),
+            ),
+        };
+    };
+    for item in items {
+        let better = match pick {
+            Ordering::Less => less_than(&item, &best, pos)?,
+            _ => less_than(&best, &item, pos)?,
+        };
+        if better {
+            best = item;
+        }
+    }
+    Ok(best)
+}
+
+fn to_int(value: &Value, base: Option<i64>, pos: Pos) -> Flow<Value> {
+    let Value::Str(s) = value else {
+        if base.is_some() {
+            return type_error(pos, "int() can't convert non-string with explicit base");
+        }
+        return match value {
+            Value::Int(_) | Value::Bool(_) => Ok(Value::Int(value.as_int().unwrap_or(0))),
+            Value::Float(n) if n.is_nan() => {
+                error(pos, "ValueError: cannot convert float NaN to integer")
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            Value::Float(n) if n.abs() < 9.2e18 => Ok(Value::Int(n.trunc() as i64)),
+            Value::Float(_) => error(pos, "OverflowError: cannot convert float to integer"),
+            _ => type_error(
+                pos,
+                format!(
+                    "int() argument must be a string or a number, not '{}'",
+                    value.type_name()
+                ),
+            ),
+        };
+    };
+    let base = base.unwrap_or(10);
+    let digits = s.trim().replace('_', "");
+    let (negative, digits) = match digits.strip_prefix('-') {
+        Some(digits) => (true, digits.to_string()),
+        None => (false, digits.trim_start_matches('+').to_string()),
+    };
+    let parsed = u32::try_from(base)
+        .ok()
+        .filter(|base| (2..=36).contains(base))
+        .and_then(|base| i64::from_str_radix(&digits, base).ok());
+    match parsed {
+        Some(n) => Ok(Value::Int(if negative { -n } else { n })),
+        None => error(
+            pos,
+            format!(
+                "ValueError: invalid literal for int() with base {base}: {}",
+                value.repr()
+            ),
+        ),
+    }
+}
+
+fn to_float(value: &Value, pos: Pos) -> Flow<Value> {
+    if let Value::Str(s) = value {
+        let trimmed = s.trim().to_lowercase();
+        let parsed = match trimmed.trim_start_matches(['+', '-']) {
+            "inf" | "infinity" | "nan" => trimmed.parse().ok(),
+            digits
+                if digits
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_') =>
+            {
+                trimmed.replace('_', "").parse().ok()
+            }
+            _ => None,
+        };
+        return match parsed {
+            Some(n) => Ok(Value::Float(n)),
+            None => error(
+                pos,
+                format!(
+                    "ValueError: could not convert string to float: {}",
+                    value.repr()
+                ),
+            ),
+        };
+    }
+    match value.as_float() {
+        Some(n) => Ok(Value::Float(n)),
+        None => type_error(
+            pos,
+            format!(
+                "float() argument must be a string or a real number, not '{}'",
+                value.type_name()
+            ),
+        ),
+    }
+}
+
+// round half to even, as Python does
+fn round(value: &Value, digits: Option<i64>, pos: Pos) -> Flow<Value> {
+    match (value, digits) {
+        (Value::Int(_) | Value::Bool(_), _) => Ok(Value::Int(value.as_int().unwrap_or(0))),
+        (Value::Float(n), None) => {
+            let rounded = n.round_ties_even();
+            if !rounded.is_finite() || rounded.abs() >= 9.2e18 {
+                return error(pos, "OverflowError: cannot convert float to integer");
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            Ok(Value::Int(rounded as i64))
+        }
+        (Value::Float(n), Some(digits)) => {
+            let scale = 10f64.powi(i32::try_from(digits.clamp(-308, 308)).unwrap_or(0));
+            let rounded = (n * scale).round_ties_even() / scale;
+            Ok(Value::Float(if rounded.is_finite() { rounded } else { *n }))
+        }
+        _ => type_error(
+            pos,
+            format!("type {} doesn't define __round__ method", value.type_name()),
+        ),
+    }
+}
+
+impl Eval<'_> {
+    // xorshift64
+    fn next_random(&mut self) -> u64 {
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 7;
+        self.random ^= self.random << 17;
+        self.random
+    }
+
+    // a float in [0, 1)
+    fn random_float(&mut self) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let n = (self.next_random() >> 11) as f64;
+        n / (1u64 << 53) as f64
+    }
+
+    // an int in [0, n)
+    fn random_below(&mut self, n: u64) -> u64 {
+        self.next_random() % n
+    }
+
+    pub(crate) fn builtin(&self, name: &str) -> Option<Value> {
+        find(BUILTINS, name).map(Value::Builtin)
+    }
+
+    pub(crate) fn module_attr(&self, module: Module, name: &str) -> Option<Value> {
+        if module == Module::Math
+            && let Some((_, n)) = MATH_CONSTANTS.iter().find(|(c, _)| *c == name)
+        {
+            return Some(Value::Float(*n));
+        }
+        if let Some(function) = FUNCTIONS.iter().copied().find(|f| {
+            f.strip_prefix(module.name())
+                .and_then(|f| f.strip_prefix('.'))
+                == Some(name)
+        }) {
+            return Some(Value::Builtin(function));
+        }
+        if module != Module::Turtle {
+            return None;
+        }
+        // the module's functions work the turtle and screen it made for you
+        if let Some(method) = turtle_method(name) {
+            return Some(Value::Method(Box::new(Value::Turtle(0)), method));
+        }
+        screen_method(name).map(|method| Value::Method(Box::new(Value::Screen), method))
+    }
+
+    // the names `from module import *` brings in
+    pub(crate) fn module_names(&self, module: Module) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = FUNCTIONS
+            .iter()
+            .filter_map(|f| f.strip_prefix(module.name())?.strip_prefix('.'))
+            .collect();
+        match module {
+            Module::Math => names.extend(MATH_CONSTANTS.iter().map(|(c, _)| *c)),
+            Module::Turtle => {
+                names.extend(TURTLE_METHODS);
+                names.extend(SCREEN_METHODS);
+            }
+            Module::Random | Module::Time => {}
+        }
+        names
+    }
+
+    pub(crate) fn call_builtin(&mut self, name: &'static str, args: Args) -> Flow<Value> {
+        let pos = args.pos;
+        Ok(match name {
+            "print" => {
+                args.check_keywords("print", &["sep", "end"])?;
+                let separator = match args.keyword("sep") {
+                    None | Some(Value::None) => " ".into(),
+                    Some(sep) => text(sep, "print", pos)?,
+                };
+                let end = match args.keyword("end") {
+                    None | Some(Value::None) => "\n".into(),
+                    Some(end) => text(end, "print", pos)?,
+                };
+                let line: Vec<String> = args.values.iter().map(ToString::to_string).collect();
+                print!("{}{end}", line.join(&separator));
+                Value::None
+            }
+            "range" => {
+                args.check_keywords("range", &[])?;
+                let mut bounds = Vec::new();
+                for value in &args.values {
+                    bounds.push(int(value, "range", pos)?);
+                }
+                match bounds[..] {
+                    [stop] => Value::Range(0, stop, 1),
+                    [start, stop] => Value::Range(start, stop, 1),
+                    [_, _, 0] => return error(pos, "ValueError: range() arg 3 must not be zero"),
+                    [start, stop, step] => Value::Range(start, stop, step),
+                    _ => {
+                        return type_error(
+                            pos,
+                            format!("range expected 1 to 3 arguments, got {}", bounds.len()),
+                        );
+                    }
+                }
+            }
+            "len" => {
+                let args = args.bind("len", ["obj"], 1)?;
+                let len = match args.value(0) {
+                    Value::Str(s) => s.chars().count(),
+                    Value::Tuple(items) => items.len(),
+                    Value::List(items) => items.borrow().len(),
+                    Value::Vec2D(..) => 2,
+                    range @ Value::Range(..) => {
+                        usize::try_from(crate::value::range_len(range)).unwrap_or(0)
+                    }
+                    value => {
+                        return type_error(
+                            pos,
+                            format!("object of type '{}' has no len()", value.type_name()),
+                        );
+                    }
+                };
+                Value::Int(i64::try_from(len).unwrap_or(i64::MAX))
+            }
+            "int" => {
+                let args = args.bind("int", ["x", "base"], 0)?;
+                let base = args.get(1).map(|b| int(b, "int", pos)).transpose()?;
+                match args.get(0) {
+                    Some(value) => to_int(value, base, pos)?,
+                    None => Value::Int(0),
+                }
+            }
+            "float" => {
+                let args = args.bind("float", ["x"], 0)?;
+                match args.get(0) {
+                    Some(value) => to_float(value, pos)?,
+                    None => Value::Float(0.),
+                }
+            }
+            "str" => {
+                let args = args.bind("str", ["object"], 0)?;
+                Value::str(args.get(0).map(ToString::to_string).unwrap_or_default())
+            }
+            "repr" => Value::str(args.bind("repr", ["obj"], 1)?.value(0).repr()),
+            "bool" => Value::Bool(args.bind("bool", ["x"], 0)?.flag(0)),
+            "abs" => match args.bind("abs", ["x"], 1)?.value(0) {
+                Value::Int(n) => match n.checked_abs() {
+                    Some(n) => Value::Int(n),
+                    None => return error(pos, "OverflowError: integer is too big"),
+                },
+                Value::Bool(b) => Value::Int(i64::from(*b)),
+                Value::Float(n) => Value::Float(n.abs()),
+                Value::Vec2D(x, y) => Value::Float(x.hypot(*y)),
+                value => {
+                    return type_error(
+                        pos,
+                        format!("bad operand type for abs(): '{}'", value.type_name()),
+                    );
+                }
+            },
+            "min" => extreme("min", args, Ordering::Less)?,
+            "max" => extreme("max", args, Ordering::Greater)?,
+            "round" => {
+                let args = args.bind("round", ["number", "ndigits"], 1)?;
+                let digits = args.get(1).map(|d| int(d, "round", pos)).transpose()?;
+                round(args.value(0), digits, pos)?
+            }
+            "sum" => {
+                let args = args.bind("sum", ["iterable", "start"], 1)?;
+                let mut total = args.get(1).cloned().unwrap_or(Value::Int(0));
+                if let Value::Str(_) = total {
+                    return type_error(pos, "sum() can't sum strings [use ''.join(seq) instead]");
+                }
+                for item in iterate(args.value(0), pos)? {
+                    total = self.binary(BinOp::Add, &total, &item, pos)?;
+                }
+                total
+            }
+            "pow" => {
+                let args = args.bind("pow", ["base", "exp"], 2)?;
+                self.binary(BinOp::Pow, args.value(0), args.value(1), pos)?
+            }
+            "divmod" => {
+                let args = args.bind("divmod", ["x", "y"], 2)?;
+                let (x, y) = (args.value(0), args.value(1));
+                Value::tuple(vec![
+                    self.binary(BinOp::FloorDiv, x, y, pos)?,
+                    self.binary(BinOp::Mod, x, y, pos)?,
+                ])
+            }
+            "list" => {
+                let args = args.bind("list", ["iterable"], 0)?;
+                match args.get(0) {
+                    Some(value) => Value::list(iterate(value, pos)?),
+                    None => Value::list(Vec::new()),
+                }
+            }
+            "tuple" => {
+                let args = args.bind("tuple", ["iterable"], 0)?;
+                match args.get(0) {
+                    Some(value) => Value::tuple(iterate(value, pos)?),
+                    None => Value::tuple(Vec::new()),
+                }
+            }
+            "enumerate" => {
+                let args = args.bind("enumerate", ["iterable", "start"], 1)?;
+                let start = args.get(1).map(|s| int(s, "enumerate", pos)).transpose()?;
+                let items = iterate(args.value(0), pos)?;
+                Value::list(
+                    (start.unwrap_or(0)..)
+                        .zip(items)
+                        .map(|(i, item)| Value::tuple(vec![Value::Int(i), item]))
+                        .collect(),
+                )
+            }
+            "zip" => {
+                args.check_keywords("zip", &[])?;
+                let mut columns = Vec::new();
+                for value in &args.values {
+                    columns.push(iterate(value, pos)?);
+                }
+                let len = columns.iter().map(Vec::len).min().unwrap_or(0);
+                Value::list(
+                    (0..len)
+                        .map(|i| Value::tuple(columns.iter().map(|c| c[i].clone()).collect()))
+                        .collect(),
+                )
+            }
+            "reversed" => {
+                let mut items = iterate(args.bind("reversed", ["sequence"], 1)?.value(0), pos)?;
+                items.reverse();
+                Value::list(items)
+            }
+            "sorted" => {
+                let args = args.bind("sorted", ["iterable", "reverse"], 1)?;
+                let mut items = iterate(args.value(0), pos)?;
+                sort(&mut items, args.flag(1), pos)?;
+                Value::list(items)
+            }
+            _ if name.starts_with("math.") => self.call_math(&name[5..], args)?,
+            _ if name.starts_with("random.") => self.call_random(&name[7..], args)?,
+            "time.sleep" => {
+                let seconds = args.bind("sleep", ["secs"], 1)?.number(0)?;
+                if seconds < 0. || !seconds.is_finite() {
+                    return error(pos, "ValueError: sleep length must be non-negative");
+                }
+                std::thread::sleep(Duration::from_secs_f64(seconds));
+                Value::None
+            }
+            "time.time" => {
+                args.bind("time", [], 0)?;
+                let now = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default();
+                Value::Float(now.as_secs_f64())
+            }
+            "turtle.Turtle" | "turtle.Pen" | "turtle.RawTurtle" => self.new_turtle(args)?,
+            "turtle.Screen" => {
+                args.bind("Screen", [], 0)?;
+                Value::Screen
+            }
+            "turtle.Vec2D" => {
+                let args = args.bind("Vec2D", ["x", "y"], 2)?;
+                Value::Vec2D(args.number(0)?, args.number(1)?)
+            }
+            _ => unreachable!("no builtin {name}"),
+        })
+    }
+
+    fn call_math(&mut self, name: &'static str, args: Args) -> Flow<Value> {
+        let pos = args.pos;
+        let one = |args: Args| args.bind(name, ["x"], 1)?.number(0);
+        Ok(Value::Float(match name {
+            "sqrt" => match one(args)? {
+                x if x < 0. => return domain_error(pos),
+                x => x.sqrt(),
+            },
+            "sin" => one(args)?.sin(),
+            "cos" => one(args)?.cos(),
+            "tan" => one(args)?.tan(),
+            "asin" | "acos" => match one(args)? {
+                x if !(-1. ..=1.).contains(&x) => return domain_error(pos),
+                x if name == "asin" => x.asin(),
+                x => x.acos(),
+            },
+            "atan" => one(args)?.atan(),
+            "atan2" => {
+                let args = args.bind("atan2", ["y", "x"], 2)?;
+                args.number(0)?.atan2(args.number(1)?)
+            }
+            "degrees" => one(args)?.to_degrees(),
+            "radians" => one(args)?.to_radians(),
+            "fabs" => one(args)?.abs(),
+            "exp" => one(args)?.exp(),
+            "floor" | "ceil" | "trunc" => {
+                let value = args.bind(name, ["x"], 1)?.value(0).clone();
+                if let Value::Int(_) | Value::Bool(_) = value {
+                    return Ok(Value::Int(value.as_int().unwrap_or(0)));
+                }
+                let x = number(&value, name, pos)?;
+                let x = match name {
+                    "floor" => x.floor(),
+                    "ceil" => x.ceil(),
+                    _ => x.trunc(),
+                };
+                return to_int(&Value::Float(x), None, pos);
+            }
+            "hypot" => {
+                args.check_keywords("hypot", &[])?;
+                let mut total = 0f64;
+                for value in &args.values {
+                    total = total.hypot(number(value, "hypot", pos)?);
+                }
+                total
+            }
+            "pow" => {
+                let args = args.bind("pow", ["x", "y"], 2)?;
+                let (x, y) = (args.number(0)?, args.number(1)?);
+                if x < 0. && y.fract() != 0. {
+                    return domain_error(pos);
+                }
+                x.powf(y)
+            }
+            "log" => {
+                let args = args.bind("log", ["x", "base"], 1)?;
+                let x = args.number(0)?;
+                let base = args.opt_number(1)?;
+                if x <= 0. || base.is_some_and(|b| b <= 0. || b == 1.) {
+                    return domain_error(pos);
+                }
+                base.map_or_else(|| x.ln(), |base| x.ln() / base.ln())
+            }
+            "log10" => match one(args)? {
+                x if x <= 0. => return domain_error(pos),
+                x => x.log10(),
+            },
+            _ => unreachable!("no function math.{name}"),
+        }))
+    }
+
+    fn call_random(&mut self, name: &'static str, args: Args) -> Flow<Value> {
+        let pos = args.pos;
+        Ok(match name {
+            "random" => {
+                args.bind("random", [], 0)?;
+                Value::Float(self.random_float())
+            }
+            "uniform" => {
+                let args = args.bind("uniform", ["a", "b"], 2)?;
+                let (a, b) = (args.number(0)?, args.number(1)?);
+                Value::Float(a + (b - a) * self.random_float())
+            }
+            "randint" | "randrange" => {
+                let (start, stop, step) = if name == "randint" {
+                    let args = args.bind("randint", ["a", "b"], 2)?;
+                    (args.int(0)?, args.int(1)?.saturating_add(1), 1)
+                } else {
+                    let args = args.bind("randrange", ["start", "stop", "step"], 1)?;
+                    let step = args.get(2).map(|s| int(s, name, pos)).transpose()?;
+                    match args.get(1) {
+                        Some(stop) => (args.int(0)?, int(stop, name, pos)?, step.unwrap_or(1)),
+                        None => (0, args.int(0)?, 1),
+                    }
+                };
+                if step == 0 {
+                    return error(pos, "ValueError: zero step for randrange()");
+                }
+                let range = Value::Range(start, stop, step);
+                let len = crate::value::range_len(&range);
+                if len == 0 {
+                    return error(
+                        pos,
+                        format!("ValueError: empty range in {name}({start}, {stop})"),
+                    );
+                }
+                let i = self.random_below(len.unsigned_abs());
+                Value::Int(start + i64::try_from(i).unwrap_or(0) * step)
+            }
+            "choice" => {
+                let items = iterate(args.bind("choice", ["seq"], 1)?.value(0), pos)?;
+                if items.is_empty() {
+                    return error(pos, "IndexError: Cannot choose from an empty sequence");
+                }
+                let i = self.random_below(items.len() as u64);
+                items[usize::try_from(i).unwrap_or(0)].clone()
+            }
+            "shuffle" => {
+                let args = args.bind("shuffle", ["x"], 1)?;
+                let Value::List(items) = args.value(0) else {
+                    return type_error(
+                        pos,
+                        format!(
+                            "shuffle() needs a list, not '{}'",
+                            args.value(0).type_name()
+                        ),
+                    );
+                };
+                let len = items.borrow().len();
+                for i in (1..len).rev() {
+                    let j = self.random_below(i as u64 + 1);
+                    items.borrow_mut().swap(i, usize::try_from(j).unwrap_or(0));
+                }
+                Value::None
+            }
+            "seed" => {
+                let args = args.bind("seed", ["a"], 0)?;
+                let mut hasher = match args.get(0) {
+                    Some(_) => DefaultHasher::new(),
+                    None => RandomState::new().build_hasher(),
+                };
+                if let Some(seed) = args.get(0) {
+                    seed.repr().hash(&mut hasher);
+                }
+                // xorshift needs a seed other than zero
+                self.random = hasher.finish() | 1;
+                Value::None
+            }
+            _ => unreachable!("no function random.{name}"),
+        })
+    }
+
+    pub(crate) fn list_call(
+        &mut self,
+        items: &Rc<RefCell<Vec<Value>>>,
+        name: &'static str,
+        args: Args,
+    ) -> Flow<Value> {
+        let pos = args.pos;
+        let find_item = |items: &[Value], item: &Value| items.iter().position(|i| i.equals(item));
+        Ok(match name {
+            "append" => {
+                let value = args.bind("append", ["object"], 1)?.value(0).clone();
+                items.borrow_mut().push(value);
+                Value::None
+            }
+            "extend" => {
+                let more = iterate(args.bind("extend", ["iterable"], 1)?.value(0), pos)?;
+                items.borrow_mut().extend(more);
+                Value::None
+            }
+            "insert" => {
+                let args = args.bind("insert", ["index", "object"], 2)?;
+                let mut items = items.borrow_mut();
+                let len = i64::try_from(items.len()).unwrap_or(i64::MAX);
+                let i = args.int(0)?;
+                let i = if i < 0 { (i + len).max(0) } else { i.min(len) };
+                items.insert(usize::try_from(i).unwrap_or(0), args.value(1).clone());
+                Value::None
+            }
+            "pop" => {
+                let args = args.bind("pop", ["index"], 0)?;
+                let i = args.get(0).map(|i| int(i, "pop", pos)).transpose()?;
+                let mut items = items.borrow_mut();
+                if items.is_empty() {
+                    return error(pos, "IndexError: pop from empty list");
+                }
+                let len = i64::try_from(items.len()).unwrap_or(i64::MAX);
+                let i = i.unwrap_or(-1);
+                let i = if i < 0 { i + len } else { i };
+                match usize::try_from(i).ok().filter(|&i| i < items.len()) {
+                    Some(i) => items.remove(i),
+                    None => return error(pos, "IndexError: pop index out of range"),
+                }
+            }
+            "remove" => {
+                let args = args.bind("remove", ["value"], 1)?;
+                let mut items = items.borrow_mut();
+                match find_item(&items, args.value(0)) {
+                    Some(i) => items.remove(i),
+                    None => return error(pos, "ValueError: list.remove(x): x not in list"),
+                };
+                Value::None
+            }
+            "index" => {
+                let args = args.bind("index", ["value"], 1)?;
+                match find_item(&items.borrow(), args.value(0)) {
+                    Some(i) => Value::Int(i64::try_from(i).unwrap_or(i64::MAX)),
+                    None => {
+                        return error(
+                            pos,
+                            format!("ValueError: {} is not in list", args.value(0).repr()),
+                        );
+                    }
+                }
+            }
+            "count" => {
+                let args = args.bind("count", ["value"], 1)?;
+                let count = items
+                    .borrow()
+                    .iter()
+                    .filter(|i| i.equals(args.value(0)))
+                    .count();
+                Value::Int(i64::try_from(count).unwrap_or(i64::MAX))
+            }
+            "reverse" => {
+                args.bind("reverse", [], 0)?;
+                items.borrow_mut().reverse();
+                Value::None
+            }
+            "sort" => {
+                let args = args.bind("sort", ["reverse"], 0)?;
+                // sort a copy, so the list can't change while it's being sorted
+                let mut sorted = items.borrow().clone();
+                sort(&mut sorted, args.flag(0), pos)?;
+                *items.borrow_mut() = sorted;
+                Value::None
+            }
+            "clear" => {
+                args.bind("clear", [], 0)?;
+                items.borrow_mut().clear();
+                Value::None
+            }
+            "copy" => {
+                args.bind("copy", [], 0)?;
+                Value::list(items.borrow().clone())
+            }
+            _ => unreachable!("no list method {name}"),
+        })
+    }
+
+    pub(crate) fn str_call(&mut self, s: &Rc<str>, name: &'static str, args: Args) -> Flow<Value> {
+        let pos = args.pos;
+        let strip = |args: Args, name: &'static str| -> Flow<Option<Vec<char>>> {
+            let args = args.bind(name, ["chars"], 0)?;
+            Ok(args
+                .get(0)
+                .map(|chars| text(chars, name, pos))
+                .transpose()?
+                .map(|chars| chars.chars().collect()))
+        };
+        Ok(match name {
+            "upper" => {
+                args.bind("upper", [], 0)?;
+                Value::str(s.to_uppercase())
+            }
+            "lower" => {
+                args.bind("lower", [], 0)?;
+                Value::str(s.to_lowercase())
+            }
+            "strip" | "lstrip" | "rstrip" => {
+                let chars = strip(args, name)?;
+                let matches = |c: char| match &chars {
+                    Some(chars) => chars.contains(&c),
+                    None => c.is_whitespace(),
+                };
+                Value::str(match name {
+                    "strip" => s.trim_matches(matches),
+                    "lstrip" => s.trim_start_matches(matches),
+                    _ => s.trim_end_matches(matches),
+                })
+            }
+            "split" => {
+                let args = args.bind("split", ["sep", "maxsplit"], 0)?;
+                let separator = args.get(0).map(|sep| text(sep, "split", pos)).transpose()?;
+                let limit = args.get(1).map(|m| int(m, "split", pos)).transpose()?;
+                let limit = limit
+                    .and_then(|m| usize::try_from(m).ok())
+                    .map_or(usize::MAX, |m| m + 1);
+                let parts: Vec<Value> = match separator.as_deref() {
+                    Some("") => return error(pos, "ValueError: empty separator"),
+                    Some(separator) => s.splitn(limit, separator).map(Value::str).collect(),
+                    None => {
+                        let mut parts = Vec::new();
+                        let mut rest = s.trim_start();
+                        while !rest.is_empty() {
+                            if parts.len() + 1 == limit {
+                                parts.push(Value::str(rest.trim_end()));
+                                break;
+                            }
+                            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                            parts.push(Value::str(&rest[..end]));
+                            rest = rest[end..].trim_start();
+                        }
+                        parts
+                    }
+                };
+                Value::list(parts)
+            }
+            "join" => {
+                let args = args.bind("join", ["iterable"], 1)?;
+                let mut parts = Vec::new();
+                for (i, item) in iterate(args.value(0), pos)?.iter().enumerate() {
+                    let Value::Str(part) = item else {
+                        return type_error(
+                            pos,
+                            format!(
+                                "sequence item {i}: expected str instance, {} found",
+                                item.type_name()
+                            ),
+                        );
+                    };
+                    parts.push(part.to_string());
+                }
+                Value::str(parts.join(s))
+            }
+            "format" => match format::format_method(s, &args.values, &args.keywords) {
+                Ok(text) => Value::str(text),
+                Err(e) => return error(pos, format!("ValueError: {e}")),
+            },
+            "startswith" | "endswith" => {
+                let args = args.bind(name, ["prefix"], 1)?;
+                let affixes = match args.value(0) {
+                    Value::Tuple(items) => items.to_vec(),
+                    value => vec![value.clone()],
+                };
+                let mut found = false;
+                for affix in &affixes {
+                    let affix = text(affix, name, pos)?;
+                    found |= if name == "startswith" {
+                        s.starts_with(affix.as_ref())
+                    } else {
+                        s.ends_with(affix.as_ref())
+                    };
+                }
+                Value::Bool(found)
+            }
+            "replace" => {
+                let args = args.bind("replace", ["old", "new", "count"], 2)?;
+                let (old, new) = (args.text(0)?, args.text(1)?);
+                match args.get(2).map(|c| int(c, "replace", pos)).transpose()? {
+                    Some(count) if count >= 0 => Value::str(s.replacen(
+                        old.as_ref(),
+                        &new,
+                        usize::try_from(count).unwrap_or(usize::MAX),
+                    )),
+                    _ => Value::str(s.replace(old.as_ref(), &new)),
+                }
+            }
+            "find" => {
+                let sub = args.bind("find", ["sub"], 1)?.text(0)?;
+                match s.find(sub.as_ref()) {
+                    // Python counts characters, not bytes
+                    Some(at) => Value::Int(i64::try_from(s[..at].chars().count()).unwrap_or(-1)),
+                    None => Value::Int(-1),
+                }
+            }
+            "count" => {
+                let sub = args.bind("count", ["sub"], 1)?.text(0)?;
+                let count = if sub.is_empty() {
+                    s.chars().count() + 1
+                } else {
+                    s.matches(sub.as_ref()).count()
+                };
+                Value::Int(i64::try_from(count).unwrap_or(i64::MAX))
+            }
+            _ => unreachable!("no str method {name}"),
+        })
+    }
+}
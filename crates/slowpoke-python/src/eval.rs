@@ -0,0 +1,1142 @@
+use std::{cmp::Ordering, collections::HashMap, rc::Rc};
+
+use slowpoke::Turtle;
+
+use crate::{
+    builtins::{list_method, str_method},
+    format,
+    lexer::Pos,
+    parser::{Arg, BinOp, CmpOp, Expr, ExprKind, FPart, Function, Stmt, StmtKind},
+    turtles::{screen_method, turtle_method, UNSUPPORTED},
+    value::{range_len, Closure, Module, Value},
+    PythonError,
+};
+
+// Python's own limit on how deeply functions can call each other
+const MAX_DEPTH: usize = 1000;
+
+// Why running stopped early
+pub(crate) enum Unwind {
+    Return(Value),
+    Break(Pos),
+    Continue(Pos),
+    Error(PythonError),
+}
+
+impl From<PythonError> for Unwind {
+    fn from(error: PythonError) -> Self {
+        Self::Error(error)
+    }
+}
+
+pub(crate) type Flow<T> = Result<T, Unwind>;
+
+pub(crate) fn error<T>(pos: Pos, message: impl Into<String>) -> Flow<T> {
+    Err(Unwind::Error(PythonError::new(pos, message)))
+}
+
+pub(crate) fn type_error<T>(pos: Pos, message: impl Into<String>) -> Flow<T> {
+    error(pos, format!("TypeError: {}", message.into()))
+}
+
+pub(crate) fn number(value: &Value, who: &str, pos: Pos) -> Flow<f64> {
+    match value.as_float() {
+        Some(n) => Ok(n),
+        None => type_error(
+            pos,
+            format!("{who}() needs a number, not '{}'", value.type_name()),
+        ),
+    }
+}
+
+pub(crate) fn int(value: &Value, who: &str, pos: Pos) -> Flow<i64> {
+    match value.as_int() {
+        Some(n) => Ok(n),
+        None => type_error(
+            pos,
+            format!(
+                "{who}(): '{}' object cannot be interpreted as an integer",
+                value.type_name()
+            ),
+        ),
+    }
+}
+
+pub(crate) fn text(value: &Value, who: &str, pos: Pos) -> Flow<Rc<str>> {
+    match value {
+        Value::Str(s) => Ok(s.clone()),
+        _ => type_error(
+            pos,
+            format!("{who}() needs a string, not '{}'", value.type_name()),
+        ),
+    }
+}
+
+pub(crate) fn iterate(value: &Value, pos: Pos) -> Flow<Vec<Value>> {
+    match value.items() {
+        Some(items) => Ok(items),
+        None => type_error(
+            pos,
+            format!("'{}' object is not iterable", value.type_name()),
+        ),
+    }
+}
+
+// a position within a sequence, which counts from the end if it's negative
+fn position(index: i64, len: usize) -> Option<usize> {
+    let len = i64::try_from(len).ok()?;
+    let index = if index < 0 { index + len } else { index };
+    (0..len)
+        .contains(&index)
+        .then(|| usize::try_from(index).ok())?
+}
+
+// the arguments a function was called with
+pub(crate) struct Args {
+    pub values: Vec<Value>,
+    pub keywords: Vec<(String, Value)>,
+    pub pos: Pos,
+}
+
+// the arguments matched to the parameters of a builtin
+pub(crate) struct Bound<const N: usize> {
+    pub name: &'static str,
+    pub slots: [Option<Value>; N],
+    pub pos: Pos,
+}
+
+impl Args {
+    // match the arguments to `params`, the first `required` of which must be
+    // given
+    pub(crate) fn bind<const N: usize>(
+        self,
+        name: &'static str,
+        params: [&str; N],
+        required: usize,
+    ) -> Flow<Bound<N>> {
+        let pos = self.pos;
+        if self.values.len() > N {
+            return type_error(
+                pos,
+                format!(
+                    "{name}() takes at most {N} argument{} ({} given)",
+                    if N == 1 { "" } else { "s" },
+                    self.values.len()
+                ),
+            );
+        }
+        let mut slots: [Option<Value>; N] = std::array::from_fn(|_| None);
+        for (slot, value) in slots.iter_mut().zip(self.values) {
+            *slot = Some(value);
+        }
+        for (keyword, value) in self.keywords {
+            match params.iter().position(|p| *p == keyword) {
+                Some(i) if slots[i].is_some() => {
+                    return type_error(
+                        pos,
+                        format!("{name}() got multiple values for argument '{keyword}'"),
+                    );
+                }
+                Some(i) => slots[i] = Some(value),
+                None => {
+                    return type_error(
+                        pos,
+                        format!("{name}() got an unexpected keyword argument '{keyword}'"),
+                    );
+                }
+            }
+        }
+        if let Some(i) = (0..required).find(|&i| slots[i].is_none()) {
+            return type_error(
+                pos,
+                format!("{name}() missing required argument '{}'", params[i]),
+            );
+        }
+        Ok(Bound { name, slots, pos })
+    }
+
+    // for builtins which take any number of arguments, and only the keywords
+    // in `allowed`
+    pub(crate) fn check_keywords(&self, name: &str, allowed: &[&str]) -> Flow<()> {
+        if let Some((keyword, _)) = self
+            .keywords
+            .iter()
+            .find(|(k, _)| !allowed.contains(&k.as_str()))
+        {
+            return type_error(
+                self.pos,
+                format!("{name}() got an unexpected keyword argument '{keyword}'"),
+            );
+        }
+        Ok(())
+    }
+
+    pub(crate) fn keyword(&self, name: &str) -> Option<&Value> {
+        self.keywords
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v)
+    }
+}
+
+impl<const N: usize> Bound<N> {
+    // an argument, which counts as missing if it's None
+    pub(crate) fn get(&self, i: usize) -> Option<&Value> {
+        self.slots[i].as_ref().filter(|v| !matches!(v, Value::None))
+    }
+
+    // a required argument
+    pub(crate) fn value(&self, i: usize) -> &Value {
+        self.slots[i]
+            .as_ref()
+            .expect("required arguments are checked by bind")
+    }
+
+    pub(crate) fn number(&self, i: usize) -> Flow<f64> {
+        number(self.value(i), self.name, self.pos)
+    }
+
+    pub(crate) fn opt_number(&self, i: usize) -> Flow<Option<f64>> {
+        self.get(i)
+            .map(|v| number(v, self.name, self.pos))
+            .transpose()
+    }
+
+    pub(crate) fn int(&self, i: usize) -> Flow<i64> {
+        int(self.value(i), self.name, self.pos)
+    }
+
+    pub(crate) fn text(&self, i: usize) -> Flow<Rc<str>> {
+        text(self.value(i), self.name, self.pos)
+    }
+
+    pub(crate) fn flag(&self, i: usize) -> bool {
+        self.get(i).is_some_and(Value::truthy)
+    }
+}
+
+struct Frame {
+    function: Rc<Function>,
+    locals: HashMap<String, Value>,
+}
+
+pub(crate) struct Eval<'a> {
+    pub turtle: &'a mut Turtle,
+    pub turtles: Vec<Turtle>, // made by Turtle() or clone(), numbered from 1
+    pub colormode: f64,       // 1.0 or 255
+    pub random: u64,
+    globals: HashMap<String, Value>,
+    frames: Vec<Frame>,
+}
+
+impl<'a> Eval<'a> {
+    pub(crate) fn new(turtle: &'a mut Turtle, random: u64) -> Self {
+        let mut globals = HashMap::new();
+        globals.insert("__name__".to_string(), Value::str("__main__"));
+        Self {
+            turtle,
+            turtles: Vec::new(),
+            colormode: 1.,
+            random,
+            globals,
+            frames: Vec::new(),
+        }
+    }
+
+    pub(crate) fn run(&mut self, body: &[Stmt]) -> Result<(), PythonError> {
+        match self.run_block(body) {
+            Ok(()) => Ok(()),
+            Err(Unwind::Error(e)) => Err(e),
+            Err(Unwind::Return(_)) => unreachable!("return is checked outside functions"),
+            Err(Unwind::Break(pos)) => {
+                Err(PythonError::new(pos, "SyntaxError: 'break' outside loop"))
+            }
+            Err(Unwind::Continue(pos)) => Err(PythonError::new(
+                pos,
+                "SyntaxError: 'continue' not properly in loop",
+            )),
+        }
+    }
+
+    pub(crate) fn turtle(&mut self, index: usize) -> &mut Turtle {
+        if index == 0 {
+            self.turtle
+        } else {
+            &mut self.turtles[index - 1]
+        }
+    }
+
+    fn run_block(&mut self, body: &[Stmt]) -> Flow<()> {
+        for stmt in body {
+            self.exec(stmt)?;
+        }
+        Ok(())
+    }
+
+    // run a loop body, and say whether to carry on looping
+    fn run_loop_body(&mut self, body: &[Stmt]) -> Flow<bool> {
+        match self.run_block(body) {
+            Ok(()) | Err(Unwind::Continue(_)) => Ok(true),
+            Err(Unwind::Break(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn exec(&mut self, stmt: &Stmt) -> Flow<()> {
+        match &stmt.kind {
+            StmtKind::Expr(expr) => {
+                self.eval(expr)?;
+            }
+            StmtKind::Assign(targets, value) => {
+                let value = self.eval(value)?;
+                for target in targets {
+                    self.assign(target, value.clone())?;
+                }
+            }
+            StmtKind::AugAssign(target, op, value) => {
+                let current = self.eval(target)?;
+                let value = self.eval(value)?;
+                // += changes a list in place
+                if let (BinOp::Add, Value::List(items)) = (op, &current) {
+                    let more = iterate(&value, stmt.pos)?;
+                    items.borrow_mut().extend(more);
+                }
+                let result = self.binary(*op, &current, &value, stmt.pos)?;
+                self.assign(target, result)?;
+            }
+            StmtKind::If(condition, then, otherwise) => {
+                if self.eval(condition)?.truthy() {
+                    self.run_block(then)?;
+                } else {
+                    self.run_block(otherwise)?;
+                }
+            }
+            StmtKind::While(condition, body) => {
+                while self.eval(condition)?.truthy() {
+                    if !self.run_loop_body(body)? {
+                        break;
+                    }
+                }
+            }
+            StmtKind::For(target, iterable, body) => {
+                let iterable = self.eval(iterable)?;
+                // ranges aren't made into lists, so range(10 ** 9) is fine
+                if let Value::Range(start, _, step) = iterable {
+                    for i in 0..range_len(&iterable) {
+                        self.assign(target, Value::Int(start + i * step))?;
+                        if !self.run_loop_body(body)? {
+                            break;
+                        }
+                    }
+                } else {
+                    for item in iterate(&iterable, iterable_pos(stmt, iterable_expr(stmt)))? {
+                        self.assign(target, item)?;
+                        if !self.run_loop_body(body)? {
+                            break;
+                        }
+                    }
+                }
+            }
+            StmtKind::Def(function) => {
+                let mut defaults = Vec::new();
+                for (_, default) in &function.params {
+                    defaults.push(match default {
+                        Some(expr) => Some(self.eval(expr)?),
+                        None => None,
+                    });
+                }
+                let closure = Closure {
+                    function: function.clone(),
+                    defaults,
+                };
+                self.assign_name(&function.name, Value::Function(Rc::new(closure)));
+            }
+            StmtKind::Return(value) => {
+                if self.frames.is_empty() {
+                    return error(stmt.pos, "SyntaxError: 'return' outside function");
+                }
+                let value = match value {
+                    Some(expr) => self.eval(expr)?,
+                    None => Value::None,
+                };
+                return Err(Unwind::Return(value));
+            }
+            StmtKind::Pass | StmtKind::Global(_) => {}
+            StmtKind::Break => return Err(Unwind::Break(stmt.pos)),
+            StmtKind::Continue => return Err(Unwind::Continue(stmt.pos)),
+            StmtKind::Import(name, alias) => {
+                let Some(module) = Module::named(name) else {
+                    return error(
+                        stmt.pos,
+                        format!("ModuleNotFoundError: No module named '{name}'"),
+                    );
+                };
+                self.assign_name(alias.as_deref().unwrap_or(name), Value::Module(module));
+            }
+            StmtKind::ImportFrom(name, names) => {
+                let Some(module) = Module::named(name) else {
+                    return error(
+                        stmt.pos,
+                        format!("ModuleNotFoundError: No module named '{name}'"),
+                    );
+                };
+                match names {
+                    None => {
+                        for name in self.module_names(module) {
+                            let value = self.module_attr(module, name).expect("missing name");
+                            self.assign_name(name, value);
+                        }
+                    }
+                    Some(names) => {
+                        for name in names {
+                            let Some(value) = self.module_attr(module, name) else {
+                                return error(
+                                    stmt.pos,
+                                    format!(
+                                        "ImportError: cannot import name '{name}' from '{}'",
+                                        module.name()
+                                    ),
+                                );
+                            };
+                            self.assign_name(name, value);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str, pos: Pos) -> Flow<Value> {
+        if let Some(frame) = self.frames.last()
+            && frame.function.locals.contains(name)
+        {
+            return match frame.locals.get(name) {
+                Some(value) => Ok(value.clone()),
+                None => error(
+                    pos,
+                    format!(
+                        "UnboundLocalError: cannot access local variable '{name}' \
+                         where it is not associated with a value"
+                    ),
+                ),
+            };
+        }
+        if let Some(value) = self.globals.get(name) {
+            return Ok(value.clone());
+        }
+        match self.builtin(name) {
+            Some(value) => Ok(value),
+            None => error(pos, format!("NameError: name '{name}' is not defined")),
+        }
+    }
+
+    fn assign_name(&mut self, name: &str, value: Value) {
+        if let Some(frame) = self.frames.last_mut()
+            && frame.function.locals.contains(name)
+        {
+            frame.locals.insert(name.to_string(), value);
+        } else {
+            self.globals.insert(name.to_string(), value);
+        }
+    }
+
+    fn assign(&mut self, target: &Expr, value: Value) -> Flow<()> {
+        match &target.kind {
+            ExprKind::Name(name) => self.assign_name(name, value),
+            ExprKind::Index(container, index) => {
+                let container = self.eval(container)?;
+                let index = self.eval(index)?;
+                let Value::List(items) = &container else {
+                    return type_error(
+                        target.pos,
+                        format!(
+                            "'{}' object does not support item assignment",
+                            container.type_name()
+                        ),
+                    );
+                };
+                let i = int(&index, "list index", target.pos)?;
+                let mut items = items.borrow_mut();
+                let Some(i) = position(i, items.len()) else {
+                    return error(target.pos, "IndexError: list assignment index out of range");
+                };
+                items[i] = value;
+            }
+            ExprKind::Attr(..) => {
+                return error(
+                    target.pos,
+                    "AttributeError: setting attributes isn't supported",
+                );
+            }
+            ExprKind::Tuple(targets) | ExprKind::List(targets) => {
+                let Some(items) = value.items() else {
+                    return type_error(
+                        target.pos,
+                        format!("cannot unpack non-iterable {} object", value.type_name()),
+                    );
+                };
+                match items.len().cmp(&targets.len()) {
+                    Ordering::Greater => {
+                        return error(
+                            target.pos,
+                            format!(
+                                "ValueError: too many values to unpack (expected {})",
+                                targets.len()
+                            ),
+                        );
+                    }
+                    Ordering::Less => {
+                        return error(
+                            target.pos,
+                            format!(
+                                "ValueError: not enough values to unpack (expected {}, got {})",
+                                targets.len(),
+                                items.len()
+                            ),
+                        );
+                    }
+                    Ordering::Equal => {}
+                }
+                for (target, item) in targets.iter().zip(items) {
+                    self.assign(target, item)?;
+                }
+            }
+            _ => unreachable!("the parser checks what can be assigned to"),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn eval(&mut self, expr: &Expr) -> Flow<Value> {
+        let pos = expr.pos;
+        Ok(match &expr.kind {
+            ExprKind::None => Value::None,
+            ExprKind::Bool(b) => Value::Bool(*b),
+            ExprKind::Int(n) => Value::Int(*n),
+            ExprKind::Float(n) => Value::Float(*n),
+            ExprKind::Str(s) => Value::Str(s.clone()),
+            ExprKind::FString(parts) => {
+                let mut text = String::new();
+                for part in parts {
+                    match part {
+                        FPart::Text(t) => text.push_str(t),
+                        FPart::Value(expr, conversion, spec) => {
+                            let value = self.eval(expr)?;
+                            let value = format::convert(&value, *conversion);
+                            match format::format(&value, spec) {
+                                Ok(formatted) => text.push_str(&formatted),
+                                Err(e) => return error(pos, format!("ValueError: {e}")),
+                            }
+                        }
+                    }
+                }
+                Value::str(text)
+            }
+            ExprKind::Name(name) => self.lookup(name, pos)?,
+            ExprKind::Attr(object, name) => {
+                let object = self.eval(object)?;
+                self.attr(&object, name, pos)?
+            }
+            ExprKind::Call(callee, args) => {
+                let callee = self.eval(callee)?;
+                let args = self.args(args, pos)?;
+                self.call(&callee, args)?
+            }
+            ExprKind::Index(container, index) => {
+                let container = self.eval(container)?;
+                let index = self.eval(index)?;
+                index_value(&container, &index, pos)?
+            }
+            ExprKind::Slice(container, parts) => {
+                let container = self.eval(container)?;
+                let mut bounds = [None, None, None];
+                for (bound, part) in bounds.iter_mut().zip(parts) {
+                    if let Some(part) = part {
+                        let value = self.eval(part)?;
+                        if !matches!(value, Value::None) {
+                            *bound = Some(int(&value, "slice", pos)?);
+                        }
+                    }
+                }
+                slice(&container, bounds, pos)?
+            }
+            ExprKind::Tuple(items) => Value::tuple(self.eval_all(items)?),
+            ExprKind::List(items) => Value::list(self.eval_all(items)?),
+            ExprKind::Negate(inner) => match self.eval(inner)? {
+                Value::Int(n) => match n.checked_neg() {
+                    Some(n) => Value::Int(n),
+                    None => return error(pos, "OverflowError: integer is too big"),
+                },
+                Value::Bool(b) => Value::Int(-i64::from(b)),
+                Value::Float(n) => Value::Float(-n),
+                Value::Vec2D(x, y) => Value::Vec2D(-x, -y),
+                value => {
+                    return type_error(
+                        pos,
+                        format!("bad operand type for unary -: '{}'", value.type_name()),
+                    );
+                }
+            },
+            ExprKind::Plus(inner) => match self.eval(inner)? {
+                Value::Bool(b) => Value::Int(i64::from(b)),
+                value @ (Value::Int(_) | Value::Float(_) | Value::Vec2D(..)) => value,
+                value => {
+                    return type_error(
+                        pos,
+                        format!("bad operand type for unary +: '{}'", value.type_name()),
+                    );
+                }
+            },
+            ExprKind::Not(inner) => Value::Bool(!self.eval(inner)?.truthy()),
+            ExprKind::Binary(op, left, right) => {
+                let left = self.eval(left)?;
+                let right = self.eval(right)?;
+                self.binary(*op, &left, &right, pos)?
+            }
+            ExprKind::Compare(first, rest) => {
+                let mut left = self.eval(first)?;
+                for (op, right) in rest {
+                    let right = self.eval(right)?;
+                    if !compare(*op, &left, &right, pos)? {
+                        return Ok(Value::Bool(false));
+                    }
+                    left = right;
+                }
+                Value::Bool(true)
+            }
+            ExprKind::And(left, right) => {
+                let left = self.eval(left)?;
+                if left.truthy() {
+                    self.eval(right)?
+                } else {
+                    left
+                }
+            }
+            ExprKind::Or(left, right) => {
+                let left = self.eval(left)?;
+                if left.truthy() {
+                    left
+                } else {
+                    self.eval(right)?
+                }
+            }
+            ExprKind::IfElse(condition, then, otherwise) => {
+                if self.eval(condition)?.truthy() {
+                    self.eval(then)?
+                } else {
+                    self.eval(otherwise)?
+                }
+            }
+        })
+    }
+
+    fn eval_all(&mut self, exprs: &[Expr]) -> Flow<Vec<Value>> {
+        exprs.iter().map(|expr| self.eval(expr)).collect()
+    }
+
+    fn args(&mut self, args: &[Arg], pos: Pos) -> Flow<Args> {
+        let mut values = Vec::new();
+        let mut keywords: Vec<(String, Value)> = Vec::new();
+        for arg in args {
+            let value = self.eval(&arg.value)?;
+            match &arg.keyword {
+                Some(keyword) if keywords.iter().any(|(k, _)| k == keyword) => {
+                    return error(
+                        arg.value.pos,
+                        format!("SyntaxError: keyword argument repeated: {keyword}"),
+                    );
+                }
+                Some(keyword) => keywords.push((keyword.clone(), value)),
+                None => values.push(value),
+            }
+        }
+        Ok(Args {
+            values,
+            keywords,
+            pos,
+        })
+    }
+
+    fn attr(&self, object: &Value, name: &str, pos: Pos) -> Flow<Value> {
+        let found = match object {
+            Value::Module(module) => {
+                if let Some(value) = self.module_attr(*module, name) {
+                    return Ok(value);
+                }
+                None
+            }
+            Value::Turtle(_) if name == "screen" => return Ok(Value::Screen),
+            Value::Turtle(_) => turtle_method(name),
+            Value::Screen => screen_method(name),
+            Value::List(_) => list_method(name),
+            Value::Str(_) => str_method(name),
+            _ => None,
+        };
+        if let Some(method) = found {
+            return Ok(Value::Method(Box::new(object.clone()), method));
+        }
+        if matches!(
+            object,
+            Value::Turtle(_) | Value::Screen | Value::Module(Module::Turtle)
+        ) && UNSUPPORTED.contains(&name)
+        {
+            return error(
+                pos,
+                format!("NotImplementedError: {name}() isn't supported"),
+            );
+        }
+        if let Value::Module(module) = object {
+            return error(
+                pos,
+                format!(
+                    "AttributeError: module '{}' has no attribute '{name}'",
+                    module.name()
+                ),
+            );
+        }
+        error(
+            pos,
+            format!(
+                "AttributeError: '{}' object has no attribute '{name}'",
+                object.type_name()
+            ),
+        )
+    }
+
+    pub(crate) fn call(&mut self, callee: &Value, args: Args) -> Flow<Value> {
+        match callee {
+            Value::Function(closure) => self.call_function(closure, args),
+            Value::Builtin(name) => self.call_builtin(name, args),
+            Value::Method(receiver, name) => match receiver.as_ref() {
+                Value::Turtle(index) => self.turtle_call(*index, name, args),
+                Value::Screen => self.screen_call(name, args),
+                Value::List(items) => self.list_call(items, name, args),
+                Value::Str(text) => self.str_call(text, name, args),
+                _ => unreachable!("only turtles, the screen, lists and strings have methods"),
+            },
+            value => type_error(
+                args.pos,
+                format!("'{}' object is not callable", value.type_name()),
+            ),
+        }
+    }
+
+    fn call_function(&mut self, closure: &Closure, args: Args) -> Flow<Value> {
+        let function = &closure.function;
+        let name = &function.name;
+        let pos = args.pos;
+        if self.frames.len() >= MAX_DEPTH {
+            return error(pos, "RecursionError: maximum recursion depth exceeded");
+        }
+        let params = &function.params;
+        if args.values.len() > params.len() {
+            return type_error(
+                pos,
+                format!(
+                    "{name}() takes {} positional argument{} but {} {} given",
+                    params.len(),
+                    if params.len() == 1 { "" } else { "s" },
+                    args.values.len(),
+                    if args.values.len() == 1 {
+                        "was"
+                    } else {
+                        "were"
+                    }
+                ),
+            );
+        }
+
+        let mut locals = HashMap::new();
+        for ((param, _), value) in params.iter().zip(args.values) {
+            locals.insert(param.clone(), value);
+        }
+        for (keyword, value) in args.keywords {
+            if !params.iter().any(|(p, _)| *p == keyword) {
+                return type_error(
+                    pos,
+                    format!("{name}() got an unexpected keyword argument '{keyword}'"),
+                );
+            }
+            if locals.contains_key(&keyword) {
+                return type_error(
+                    pos,
+                    format!("{name}() got multiple values for argument '{keyword}'"),
+                );
+            }
+            locals.insert(keyword, value);
+        }
+        let mut missing = Vec::new();
+        for ((param, _), default) in params.iter().zip(&closure.defaults) {
+            if !locals.contains_key(param) {
+                match default {
+                    Some(value) => {
+                        locals.insert(param.clone(), value.clone());
+                    }
+                    None => missing.push(format!("'{param}'")),
+                }
+            }
+        }
+        if !missing.is_empty() {
+            return type_error(
+                pos,
+                format!(
+                    "{name}() missing {} required positional argument{}: {}",
+                    missing.len(),
+                    if missing.len() == 1 { "" } else { "s" },
+                    missing.join(", ")
+                ),
+            );
+        }
+
+        self.frames.push(Frame {
+            function: function.clone(),
+            locals,
+        });
+        let result = self.run_block(&function.body);
+        self.frames.pop();
+        match result {
+            Ok(()) => Ok(Value::None),
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Break(pos)) => error(pos, "SyntaxError: 'break' outside loop"),
+            Err(Unwind::Continue(pos)) => {
+                error(pos, "SyntaxError: 'continue' not properly in loop")
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub(crate) fn binary(&self, op: BinOp, a: &Value, b: &Value, pos: Pos) -> Flow<Value> {
+        let symbol = match op {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::FloorDiv => "//",
+            BinOp::Mod => "%",
+            BinOp::Pow => "**",
+        };
+        let overflow = || error(pos, "OverflowError: integer is too big");
+        let zero = || error(pos, "ZeroDivisionError: division by zero");
+
+        if let (Some(x), Some(y)) = (a.as_int(), b.as_int()) {
+            let result = match op {
+                BinOp::Add => x.checked_add(y),
+                BinOp::Sub => x.checked_sub(y),
+                BinOp::Mul => x.checked_mul(y),
+                BinOp::Div if y == 0 => return zero(),
+                #[allow(clippy::cast_precision_loss)]
+                BinOp::Div => return Ok(Value::Float(x as f64 / y as f64)),
+                BinOp::FloorDiv | BinOp::Mod if y == 0 => return zero(),
+                // Python rounds down, and the remainder has the divisor's sign
+                BinOp::FloorDiv => x.checked_div_euclid(y).map(|q| {
+                    if y < 0 && x.rem_euclid(y) != 0 {
+                        q + 1
+                    } else {
+                        q
+                    }
+                }),
+                BinOp::Mod => {
+                    let r = x % y;
+                    Some(if r != 0 && (r < 0) != (y < 0) {
+                        r + y
+                    } else {
+                        r
+                    })
+                }
+                BinOp::Pow if y < 0 => {
+                    #[allow(clippy::cast_precision_loss)]
+                    return Ok(Value::Float((x as f64).powf(y as f64)));
+                }
+                BinOp::Pow => u32::try_from(y).ok().and_then(|y| x.checked_pow(y)),
+            };
+            return result.map_or_else(overflow, |n| Ok(Value::Int(n)));
+        }
+
+        if let (Some(x), Some(y)) = (a.as_float(), b.as_float()) {
+            return Ok(Value::Float(match op {
+                BinOp::Add => x + y,
+                BinOp::Sub => x - y,
+                BinOp::Mul => x * y,
+                BinOp::Div | BinOp::FloorDiv | BinOp::Mod if y == 0. => return zero(),
+                BinOp::Div => x / y,
+                BinOp::FloorDiv => (x / y).floor(),
+                BinOp::Mod => x - y * (x / y).floor(),
+                BinOp::Pow if x < 0. && y.fract() != 0. => {
+                    return error(pos, "ValueError: math domain error");
+                }
+                BinOp::Pow => x.powf(y),
+            }));
+        }
+
+        let repeat = |items: Vec<Value>, times: i64| {
+            let times = usize::try_from(times).unwrap_or(0);
+            let mut repeated = Vec::with_capacity(items.len() * times);
+            for _ in 0..times {
+                repeated.extend(items.iter().cloned());
+            }
+            repeated
+        };
+        Ok(match (op, a, b) {
+            (BinOp::Add, Value::Vec2D(x, y), Value::Vec2D(u, v)) => Value::Vec2D(x + u, y + v),
+            (BinOp::Sub, Value::Vec2D(x, y), Value::Vec2D(u, v)) => Value::Vec2D(x - u, y - v),
+            (BinOp::Mul, Value::Vec2D(x, y), Value::Vec2D(u, v)) => Value::Float(x * u + y * v),
+            (BinOp::Mul, Value::Vec2D(x, y), n) | (BinOp::Mul, n, Value::Vec2D(x, y))
+                if n.as_float().is_some() =>
+            {
+                let n = n.as_float().expect("missing number");
+                Value::Vec2D(x * n, y * n)
+            }
+            (BinOp::Add, Value::Str(x), Value::Str(y)) => Value::str(format!("{x}{y}")),
+            (BinOp::Add, Value::List(x), Value::List(y)) => {
+                let mut items = x.borrow().clone();
+                items.extend(y.borrow().iter().cloned());
+                Value::list(items)
+            }
+            (BinOp::Add, Value::Tuple(x), Value::Tuple(y)) => {
+                Value::tuple(x.iter().chain(y.iter()).cloned().collect())
+            }
+            (BinOp::Mul, Value::Str(s), n) | (BinOp::Mul, n, Value::Str(s))
+                if n.as_int().is_some() =>
+            {
+                let times = usize::try_from(n.as_int().expect("missing int")).unwrap_or(0);
+                Value::str(s.repeat(times))
+            }
+            (BinOp::Mul, Value::List(items), n) | (BinOp::Mul, n, Value::List(items))
+                if n.as_int().is_some() =>
+            {
+                let items = items.borrow().clone();
+                Value::list(repeat(items, n.as_int().expect("missing int")))
+            }
+            (BinOp::Mul, Value::Tuple(items), n) | (BinOp::Mul, n, Value::Tuple(items))
+                if n.as_int().is_some() =>
+            {
+                Value::tuple(repeat(items.to_vec(), n.as_int().expect("missing int")))
+            }
+            (BinOp::Mod, Value::Str(template), values) => match format::percent(template, values) {
+                Ok(text) => Value::str(text),
+                Err(e) => return type_error(pos, e),
+            },
+            _ => {
+                return type_error(
+                    pos,
+                    format!(
+                        "unsupported operand type(s) for {symbol}: '{}' and '{}'",
+                        a.type_name(),
+                        b.type_name()
+                    ),
+                );
+            }
+        })
+    }
+}
+
+// the statement's iterable, for error positions
+fn iterable_expr(stmt: &Stmt) -> Option<&Expr> {
+    match &stmt.kind {
+        StmtKind::For(_, iterable, _) => Some(iterable),
+        _ => None,
+    }
+}
+
+fn iterable_pos(stmt: &Stmt, iterable: Option<&Expr>) -> Pos {
+    iterable.map_or(stmt.pos, |expr| expr.pos)
+}
+
+// how two values compare with <, or None if they can't be
+fn ordering(a: &Value, b: &Value) -> Option<Option<Ordering>> {
+    if let (Some(x), Some(y)) = (a.as_int(), b.as_int()) {
+        return Some(Some(x.cmp(&y)));
+    }
+    if let (Some(x), Some(y)) = (a.as_float(), b.as_float()) {
+        return Some(x.partial_cmp(&y));
+    }
+    match (a, b) {
+        (Value::Str(x), Value::Str(y)) => Some(Some(x.cmp(y))),
+        (Value::Tuple(_) | Value::Vec2D(..), Value::Tuple(_) | Value::Vec2D(..))
+        | (Value::List(_), Value::List(_)) => {
+            let (x, y) = (a.items()?, b.items()?);
+            for (x, y) in x.iter().zip(&y) {
+                if !x.equals(y) {
+                    return ordering(x, y);
+                }
+            }
+            Some(Some(x.len().cmp(&y.len())))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn less_than(a: &Value, b: &Value, pos: Pos) -> Flow<bool> {
+    compare(CmpOp::Lt, a, b, pos)
+}
+
+fn compare(op: CmpOp, a: &Value, b: &Value, pos: Pos) -> Flow<bool> {
+    let contains = |container: &Value, item: &Value| -> Flow<bool> {
+        match (container, item) {
+            (Value::Str(s), Value::Str(sub)) => Ok(s.contains(sub.as_ref())),
+            (Value::Str(_), _) => type_error(
+                pos,
+                format!(
+                    "'in <string>' requires string as left operand, not {}",
+                    item.type_name()
+                ),
+            ),
+            (Value::Range(start, stop, step), _) => Ok(item.as_int().is_some_and(|n| {
+                let inside = if *step > 0 {
+                    (*start..*stop).contains(&n)
+                } else {
+                    n <= *start && n > *stop
+                };
+                inside && (n - start) % step == 0
+            })),
+            _ => match container.items() {
+                Some(items) => Ok(items.iter().any(|i| i.equals(item))),
+                None => type_error(
+                    pos,
+                    format!(
+                        "argument of type '{}' is not iterable",
+                        container.type_name()
+                    ),
+                ),
+            },
+        }
+    };
+    let symbol = match op {
+        CmpOp::Eq => return Ok(a.equals(b)),
+        CmpOp::Ne => return Ok(!a.equals(b)),
+        CmpOp::In => return contains(b, a),
+        CmpOp::NotIn => return Ok(!contains(b, a)?),
+        CmpOp::Is => return Ok(a.is(b)),
+        CmpOp::IsNot => return Ok(!a.is(b)),
+        CmpOp::Lt => "<",
+        CmpOp::Gt => ">",
+        CmpOp::Le => "<=",
+        CmpOp::Ge => ">=",
+    };
+    let Some(ordering) = ordering(a, b) else {
+        return type_error(
+            pos,
+            format!(
+                "'{symbol}' not supported between instances of '{}' and '{}'",
+                a.type_name(),
+                b.type_name()
+            ),
+        );
+    };
+    Ok(ordering.is_some_and(|ordering| match op {
+        CmpOp::Lt => ordering.is_lt(),
+        CmpOp::Gt => ordering.is_gt(),
+        CmpOp::Le => ordering.is_le(),
+        _ => ordering.is_ge(),
+    }))
+}
+
+fn index_value(container: &Value, index: &Value, pos: Pos) -> Flow<Value> {
+    let name = container.type_name();
+    if !matches!(
+        container,
+        Value::Str(_) | Value::Tuple(_) | Value::List(_) | Value::Vec2D(..) | Value::Range(..)
+    ) {
+        return type_error(pos, format!("'{name}' object is not subscriptable"));
+    }
+    let Some(i) = index.as_int() else {
+        return type_error(
+            pos,
+            format!(
+                "{name} indices must be integers, not '{}'",
+                index.type_name()
+            ),
+        );
+    };
+    let item = match container {
+        Value::Str(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            position(i, chars.len()).map(|i| Value::str(chars[i].to_string()))
+        }
+        Value::List(items) => {
+            let items = items.borrow();
+            position(i, items.len()).map(|i| items[i].clone())
+        }
+        Value::Tuple(items) => position(i, items.len()).map(|i| items[i].clone()),
+        Value::Vec2D(x, y) => position(i, 2).map(|i| Value::Float(if i == 0 { *x } else { *y })),
+        Value::Range(start, _, step) => usize::try_from(range_len(container))
+            .ok()
+            .and_then(|len| position(i, len))
+            .map(|i| Value::Int(start + i64::try_from(i).unwrap_or(0) * step)),
+        _ => unreachable!("checked above"),
+    };
+    match item {
+        Some(item) => Ok(item),
+        None => error(pos, format!("IndexError: {name} index out of range")),
+    }
+}
+
+// the positions a slice takes from a sequence of `len` items
+fn slice_positions(bounds: [Option<i64>; 3], len: usize) -> Option<Vec<usize>> {
+    let len = i64::try_from(len).ok()?;
+    let step = bounds[2].unwrap_or(1);
+    let clamp = |bound: Option<i64>, default: i64| match bound {
+        None => default,
+        Some(n) if n < 0 => (n + len).max(if step < 0 { -1 } else { 0 }),
+        Some(n) => n.min(if step < 0 { len - 1 } else { len }),
+    };
+    let (start, stop) = if step > 0 {
+        (clamp(bounds[0], 0), clamp(bounds[1], len))
+    } else {
+        (clamp(bounds[0], len - 1), clamp(bounds[1], -1))
+    };
+    let mut positions = Vec::new();
+    let mut i = start;
+    while (step > 0 && i < stop) || (step < 0 && i > stop) {
+        positions.push(usize::try_from(i).ok()?);
+        i += step;
+    }
+    Some(positions)
+}
+
+fn slice(container: &Value, bounds: [Option<i64>; 3], pos: Pos) -> Flow<Value> {
+    if bounds[2] == Some(0) {
+        return error(pos, "ValueError: slice step cannot be zero");
+    }
+    let Some(items) = container
+        .items()
+        .filter(|_| !matches!(container, Value::Vec2D(..)))
+    else {
+        return type_error(
+            pos,
+            format!("'{}' object is not subscriptable", container.type_name()),
+        );
+    };
+    let positions = slice_positions(bounds, items.len()).unwrap_or_default();
+    let picked = positions.into_iter().map(|i| items[i].clone());
+    Ok(match container {
+        Value::Str(_) => Value::str(picked.map(|c| c.to_string()).collect::<String>()),
+        Value::Tuple(_) => Value::tuple(picked.collect()),
+        _ => Value::list(picked.collect()),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn slices_count_like_python() {
+        assert_eq!(
+            slice_positions([None, None, None], 4),
+            Some(vec![0, 1, 2, 3])
+        );
+        assert_eq!(
+            slice_positions([Some(1), Some(-1), None], 4),
+            Some(vec![1, 2])
+        );
+        assert_eq!(
+            slice_positions([None, None, Some(-1)], 3),
+            Some(vec![2, 1, 0])
+        );
+        assert_eq!(slice_positions([Some(-2), None, None], 4), Some(vec![2, 3]));
+        assert_eq!(slice_positions([Some(10), None, None], 4), Some(vec![]));
+        assert_eq!(position(-1, 3), Some(2));
+        assert_eq!(position(3, 3), None);
+    }
+}
@@ -0,0 +1,440 @@
+// Python's format specs, for f-strings, str.format() and the % operator
+
+use crate::value::{float_repr, python_exponent, Value};
+
+#[derive(Debug, Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<char>,
+    sign: Option<char>,
+    width: usize,
+    grouping: Option<char>,
+    precision: Option<usize>,
+    kind: Option<char>,
+}
+
+fn parse_spec(spec: &str) -> Result<Spec, String> {
+    let chars: Vec<char> = spec.chars().collect();
+    let invalid = || Err("Invalid format specifier".to_string());
+    let mut parsed = Spec::default();
+    let mut i = 0;
+    let is_align = |c: Option<&char>| c.is_some_and(|c| "<>^=".contains(*c));
+
+    if is_align(chars.get(1)) {
+        parsed.fill = Some(chars[0]);
+        parsed.align = Some(chars[1]);
+        i = 2;
+    } else if is_align(chars.first()) {
+        parsed.align = Some(chars[0]);
+        i = 1;
+    }
+    if let Some(&c @ ('+' | '-' | ' ')) = chars.get(i) {
+        parsed.sign = Some(c);
+        i += 1;
+    }
+    if chars.get(i) == Some(&'0') {
+        parsed.fill.get_or_insert('0');
+        parsed.align.get_or_insert('=');
+        i += 1;
+    }
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while chars.get(*i).is_some_and(char::is_ascii_digit) {
+            *i += 1;
+        }
+        chars[start..*i]
+            .iter()
+            .collect::<String>()
+            .parse::<usize>()
+            .ok()
+    };
+    parsed.width = digits(&mut i).unwrap_or(0);
+    if let Some(&c @ (',' | '_')) = chars.get(i) {
+        parsed.grouping = Some(c);
+        i += 1;
+    }
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        let Some(precision) = digits(&mut i) else {
+            return Err("Format specifier missing precision".into());
+        };
+        parsed.precision = Some(precision);
+    }
+    if let Some(&c) = chars.get(i) {
+        parsed.kind = Some(c);
+        i += 1;
+    }
+    if i < chars.len() {
+        return invalid();
+    }
+    Ok(parsed)
+}
+
+// like %g: fixed or exponent notation, whichever suits the number, without
+// trailing zeros
+fn general(n: f64, precision: usize) -> String {
+    let precision = precision.max(1);
+    if n == 0. || !n.is_finite() {
+        return float_repr(n).trim_end_matches(".0").to_string();
+    }
+    let scientific = format!("{:.*e}", precision - 1, n);
+    let (mantissa, exponent) = scientific.split_once('e').expect("missing exponent");
+    let exponent: i32 = exponent.parse().expect("bad exponent");
+    let trim = |s: &str| {
+        if s.contains('.') {
+            s.trim_end_matches('0').trim_end_matches('.').to_string()
+        } else {
+            s.to_string()
+        }
+    };
+    #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+    if -4 <= exponent && exponent < precision as i32 {
+        #[allow(clippy::cast_sign_loss)]
+        let decimals = (precision as i32 - 1 - exponent).max(0) as usize;
+        trim(&format!("{n:.decimals$}"))
+    } else {
+        python_exponent(&format!("{}e{exponent}", trim(mantissa)))
+    }
+}
+
+fn group(digits: &str, separator: char) -> String {
+    let (whole, rest) = digits.split_at(digits.find('.').unwrap_or(digits.len()));
+    let mut grouped = String::new();
+    for (i, c) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(c);
+    }
+    grouped + rest
+}
+
+fn pad(sign: &str, body: &str, spec: &Spec, default_align: char) -> String {
+    let fill = spec.fill.unwrap_or(' ');
+    let len = sign.chars().count() + body.chars().count();
+    let padding = spec.width.saturating_sub(len);
+    let fill_with = |n: usize| std::iter::repeat_n(fill, n).collect::<String>();
+    match spec.align.unwrap_or(default_align) {
+        '<' => format!("{sign}{body}{}", fill_with(padding)),
+        '^' => format!(
+            "{}{sign}{body}{}",
+            fill_with(padding / 2),
+            fill_with(padding - padding / 2)
+        ),
+        '=' => format!("{sign}{}{body}", fill_with(padding)),
+        _ => format!("{}{sign}{body}", fill_with(padding)),
+    }
+}
+
+fn format_number(value: &Value, spec: &Spec) -> Result<String, String> {
+    let unknown = |kind: char| {
+        Err(format!(
+            "Unknown format code '{kind}' for object of type '{}'",
+            value.type_name()
+        ))
+    };
+    let int = value.as_int().filter(|_| !matches!(value, Value::Float(_)));
+    let n = value.as_float().expect("not a number");
+    let precision = spec.precision;
+
+    let (negative, digits) = match (spec.kind, int) {
+        (None | Some('d'), Some(i)) => {
+            if precision.is_some() {
+                return Err("Precision not allowed in integer format specifier".into());
+            }
+            (i < 0, i.unsigned_abs().to_string())
+        }
+        (Some(kind @ ('x' | 'X' | 'o' | 'b')), Some(i)) => {
+            let digits = match kind {
+                'x' => format!("{:x}", i.unsigned_abs()),
+                'X' => format!("{:X}", i.unsigned_abs()),
+                'o' => format!("{:o}", i.unsigned_abs()),
+                _ => format!("{:b}", i.unsigned_abs()),
+            };
+            (i < 0, digits)
+        }
+        (Some(kind @ ('d' | 'x' | 'X' | 'o' | 'b' | 'c' | 's')), _) => return unknown(kind),
+        (None, None) => match precision {
+            Some(precision) => (n < 0., general(n.abs(), precision)),
+            None => (n < 0., float_repr(n.abs())),
+        },
+        (Some('f' | 'F'), _) => {
+            let precision = precision.unwrap_or(6);
+            (n < 0., format!("{:.precision$}", n.abs()))
+        }
+        (Some(kind @ ('e' | 'E')), _) => {
+            let text = python_exponent(&format!("{:.*e}", precision.unwrap_or(6), n.abs()));
+            let text = if kind == 'E' {
+                text.to_uppercase()
+            } else {
+                text
+            };
+            (n < 0., text)
+        }
+        (Some(kind @ ('g' | 'G')), _) => {
+            let text = general(n.abs(), precision.unwrap_or(6));
+            let text = if kind == 'G' {
+                text.to_uppercase()
+            } else {
+                text
+            };
+            (n < 0., text)
+        }
+        (Some('%'), _) => {
+            let precision = precision.unwrap_or(6);
+            (n < 0., format!("{:.precision$}%", (n * 100.).abs()))
+        }
+        (Some(kind), _) => return unknown(kind),
+    };
+
+    let digits = match spec.grouping {
+        Some(separator) => group(&digits, separator),
+        None => digits,
+    };
+    let sign = match (negative, spec.sign) {
+        (true, _) => "-",
+        (false, Some('+')) => "+",
+        (false, Some(' ')) => " ",
+        _ => "",
+    };
+    Ok(pad(sign, &digits, spec, '>'))
+}
+
+/// Format a value as `format(value, spec)` does
+pub(crate) fn format(value: &Value, spec: &str) -> Result<String, String> {
+    if spec.is_empty() {
+        return Ok(value.to_string());
+    }
+    let spec = parse_spec(spec)?;
+    match value {
+        Value::Int(_) | Value::Float(_) | Value::Bool(_) => format_number(value, &spec),
+        Value::Str(text) => {
+            if let Some(kind) = spec.kind.filter(|&k| k != 's') {
+                return Err(format!(
+                    "Unknown format code '{kind}' for object of type 'str'"
+                ));
+            }
+            if spec.sign.is_some() || spec.grouping.is_some() {
+                return Err("Sign not allowed in string format specifier".into());
+            }
+            let text: String = match spec.precision {
+                Some(precision) => text.chars().take(precision).collect(),
+                None => text.to_string(),
+            };
+            Ok(pad("", &text, &spec, '<'))
+        }
+        value => Err(format!(
+            "unsupported format string passed to {}.__format__",
+            value.type_name()
+        )),
+    }
+}
+
+// a value after !r or !s
+pub(crate) fn convert(value: &Value, conversion: Option<char>) -> Value {
+    match conversion {
+        Some('r') => Value::str(value.repr()),
+        Some('s') => Value::str(value.to_string()),
+        _ => value.clone(),
+    }
+}
+
+/// `template.format(*positional, **keywords)`
+pub(crate) fn format_method(
+    template: &str,
+    positional: &[Value],
+    keywords: &[(String, Value)],
+) -> Result<String, String> {
+    let mut text = String::new();
+    let mut next = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '}' => return Err("Single '}' encountered in format string".into()),
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => field.push(c),
+                        None => return Err("Single '{' encountered in format string".into()),
+                    }
+                }
+                let (field, spec) = field.split_once(':').unwrap_or((&field, ""));
+                let (name, conversion) = match field.split_once('!') {
+                    Some((name, conversion)) => (name, conversion.chars().next()),
+                    None => (field, None),
+                };
+                let value = if name.is_empty() {
+                    next += 1;
+                    positional.get(next - 1)
+                } else if let Ok(index) = name.parse::<usize>() {
+                    positional.get(index)
+                } else {
+                    keywords.iter().find(|(k, _)| k == name).map(|(_, v)| v)
+                };
+                let Some(value) = value else {
+                    return Err(if name.is_empty() || name.parse::<usize>().is_ok() {
+                        "Replacement index out of range for positional args tuple".into()
+                    } else {
+                        format!("'{name}' isn't one of the keyword arguments")
+                    });
+                };
+                text.push_str(&format(&convert(value, conversion), spec)?);
+            }
+            c => text.push(c),
+        }
+    }
+    Ok(text)
+}
+
+/// `template % values`, the old printf-style formatting
+pub(crate) fn percent(template: &str, values: &Value) -> Result<String, String> {
+    let values = match values {
+        Value::Tuple(items) => items.to_vec(),
+        value => vec![value.clone()],
+    };
+    let mut values = values.into_iter();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+        let mut flags = String::new();
+        while let Some(&c @ ('-' | '+' | ' ' | '0' | '#')) = chars.peek() {
+            flags.push(c);
+            chars.next();
+        }
+        let mut width = String::new();
+        while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+            width.push(c);
+            chars.next();
+        }
+        let Some(kind) = chars.next() else {
+            return Err("incomplete format".into());
+        };
+        if kind == '%' {
+            text.push('%');
+            continue;
+        }
+        let Some(value) = values.next() else {
+            return Err("not enough arguments for format string".into());
+        };
+
+        // right-aligned unless asked otherwise
+        let mut spec = String::new();
+        if flags.contains('-') {
+            spec.push('<');
+        } else if !flags.contains('0') {
+            spec.push('>');
+        }
+        if flags.contains('+') {
+            spec.push('+');
+        } else if flags.contains(' ') {
+            spec.push(' ');
+        }
+        if flags.contains('0') && !flags.contains('-') {
+            spec.push('0');
+        }
+        spec.push_str(&width);
+        let value = match kind {
+            's' => Value::str(value.to_string()),
+            'r' => Value::str(value.repr()),
+            #[allow(clippy::cast_possible_truncation)]
+            'd' | 'i' | 'u' => match value {
+                Value::Float(n) if n.is_finite() => Value::Int(n.trunc() as i64),
+                value => value,
+            },
+            _ => value,
+        };
+        match kind {
+            's' | 'r' => {}
+            'd' | 'i' | 'u' => spec.push('d'),
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'x' | 'X' | 'o' => spec.push(kind),
+            _ => return Err(format!("unsupported format character '{kind}'")),
+        }
+        if matches!(kind, 'd' | 'i' | 'u' | 'f' | 'F' | 'e' | 'E' | 'g' | 'G')
+            && value.as_float().is_none()
+        {
+            return Err(format!(
+                "%{kind} format: a real number is required, not {}",
+                value.type_name()
+            ));
+        }
+        text.push_str(&format(&value, &spec)?);
+    }
+    if values.next().is_some() {
+        return Err("not all arguments converted during string formatting".into());
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_specs() {
+        let cases = [
+            (Value::Float(1.23456), ".2f", "1.23"),
+            (Value::Float(-2.5), "8.3f", "  -2.500"),
+            (Value::Int(42), "05d", "00042"),
+            (Value::Int(-42), "+06", "-00042"),
+            (Value::Int(1_234_567), ",", "1,234,567"),
+            (Value::Int(255), "x", "ff"),
+            (Value::Float(0.25), ".0%", "25%"),
+            (Value::Float(1234.5), ".3g", "1.23e+03"),
+            (Value::Float(0.5), "g", "0.5"),
+            (Value::Float(12345.678), "e", "1.234568e+04"),
+            (Value::str("hi"), "*^6", "**hi**"),
+            (Value::str("hi"), ">4", "  hi"),
+            (Value::Int(7), "<3", "7  "),
+        ];
+        for (value, spec, expected) in cases {
+            assert_eq!(
+                format(&value, spec).as_deref(),
+                Ok(expected),
+                "{value} {spec}"
+            );
+        }
+        assert!(format(&Value::Float(1.), "d").is_err());
+    }
+
+    #[test]
+    fn format_method_and_percent() {
+        assert_eq!(
+            format_method(
+                "{} and {:.1f}, {0!r} {name}",
+                &[Value::str("a"), Value::Float(2.)],
+                &[("name".into(), Value::Int(3))]
+            )
+            .as_deref(),
+            Ok("a and 2.0, 'a' 3")
+        );
+        let values = Value::tuple(vec![Value::Float(2.5), Value::str("x"), Value::Int(7)]);
+        assert_eq!(
+            percent(
+                "%d %.1f%% %s %03d",
+                &Value::tuple(vec![
+                    Value::Float(2.5),
+                    Value::Float(2.5),
+                    Value::str("x"),
+                    Value::Int(7),
+                ])
+            )
+            .as_deref(),
+            Ok("2 2.5% x 007")
+        );
+        assert!(percent("%d %d", &values).is_err());
+    }
+}
@@ -0,0 +1,506 @@
+use crate::PythonError;
+
+/// Where something is in a script, counting from 1
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Pos {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind {
+    Name(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    FString(String), // with its escapes already replaced
+    Op(&'static str),
+    Newline,
+    Indent,
+    Dedent,
+    End,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub pos: Pos,
+}
+
+impl Token {
+    pub(crate) fn is_op(&self, op: &str) -> bool {
+        matches!(self.kind, TokenKind::Op(o) if o == op)
+    }
+
+    pub(crate) fn is_name(&self, name: &str) -> bool {
+        matches!(&self.kind, TokenKind::Name(n) if n == name)
+    }
+}
+
+// longest first, so that "**=" isn't read as "*" "*" "="
+const OPS: &[&str] = &[
+    "**=", "//=", "...", "**", "//", "==", "!=", "<=", ">=", "+=", "-=", "*=", "/=", "%=", "->",
+    "(", ")", "[", "]", "{", "}", ",", ":", ".", ";", "+", "-", "*", "/", "%", "<", ">", "=", "@",
+    "~", "&", "|", "^",
+];
+
+struct Lexer {
+    chars: Vec<char>,
+    at: usize,
+    line: usize,
+    column: usize,
+    tokens: Vec<Token>,
+}
+
+fn error<T>(pos: Pos, message: impl Into<String>) -> Result<T, PythonError> {
+    Err(PythonError::new(
+        pos,
+        format!("SyntaxError: {}", message.into()),
+    ))
+}
+
+impl Lexer {
+    fn peek(&self, ahead: usize) -> Option<char> {
+        self.chars.get(self.at + ahead).copied()
+    }
+
+    fn pos(&self) -> Pos {
+        Pos {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek(0)?;
+        self.at += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn push(&mut self, kind: TokenKind, pos: Pos) {
+        self.tokens.push(Token { kind, pos });
+    }
+
+    // the indentation at the start of a line, or None for a line without code
+    fn indentation(&mut self) -> Option<usize> {
+        let mut width = 0;
+        while let Some(c) = self.peek(0) {
+            match c {
+                ' ' => width += 1,
+                '\t' => width = (width / 8 + 1) * 8,
+                '\x0c' | '\r' => {}
+                _ => break,
+            }
+            self.bump();
+        }
+        match self.peek(0) {
+            None => None,
+            Some('\n') => {
+                self.bump();
+                None
+            }
+            Some('#') => {
+                while self.peek(0).is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+                self.bump();
+                None
+            }
+            Some(_) => Some(width),
+        }
+    }
+
+    fn number(&mut self) -> Result<(), PythonError> {
+        let pos = self.pos();
+        let mut text = String::new();
+        if self.peek(0) == Some('0')
+            && matches!(self.peek(1), Some('x' | 'X' | 'o' | 'O' | 'b' | 'B'))
+        {
+            self.bump();
+            let radix = match self.bump() {
+                Some('x' | 'X') => 16,
+                Some('o' | 'O') => 8,
+                _ => 2,
+            };
+            while let Some(c) = self
+                .peek(0)
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            {
+                self.bump();
+                if c != '_' {
+                    text.push(c);
+                }
+            }
+            return match i64::from_str_radix(&text, radix) {
+                Ok(n) => {
+                    self.push(TokenKind::Int(n), pos);
+                    Ok(())
+                }
+                Err(_) => error(pos, "invalid number"),
+            };
+        }
+
+        let mut float = false;
+        while let Some(c) = self.peek(0) {
+            match c {
+                '0'..='9' => text.push(c),
+                '_' => {}
+                '.' if !float => {
+                    float = true;
+                    text.push(c);
+                }
+                'e' | 'E'
+                    if self.peek(1).is_some_and(|c| c.is_ascii_digit())
+                        || (matches!(self.peek(1), Some('+' | '-'))
+                            && self.peek(2).is_some_and(|c| c.is_ascii_digit())) =>
+                {
+                    float = true;
+                    text.push(c);
+                    self.bump();
+                    text.push(self.peek(0).expect("missing exponent"));
+                }
+                _ => break,
+            }
+            self.bump();
+        }
+        if self.peek(0).is_some_and(|c| c.is_alphabetic() || c == '_') {
+            return error(pos, "invalid number");
+        }
+        let kind = if float {
+            TokenKind::Float(text.parse().or_else(|_| error(pos, "invalid number"))?)
+        } else {
+            match text.parse() {
+                Ok(n) => TokenKind::Int(n),
+                Err(_) => {
+                    return Err(PythonError::new(pos, "OverflowError: integer is too big"));
+                }
+            }
+        };
+        self.push(kind, pos);
+        Ok(())
+    }
+
+    // a string, which starts at `pos` with any prefix letters already read
+    fn string(&mut self, pos: Pos, prefix: &str) -> Result<(), PythonError> {
+        let prefix = prefix.to_lowercase();
+        if prefix.contains('b') || prefix.contains('u') && prefix.len() > 1 {
+            return error(pos, "byte strings aren't supported");
+        }
+        let raw = prefix.contains('r');
+        let quote = self.bump().expect("missing quote");
+        let triple = self.peek(0) == Some(quote) && self.peek(1) == Some(quote);
+        if triple {
+            self.bump();
+            self.bump();
+        }
+
+        let mut text = String::new();
+        loop {
+            let Some(c) = self.bump() else {
+                return error(pos, "this string is missing its closing quote");
+            };
+            match c {
+                c if c == quote => {
+                    if !triple {
+                        break;
+                    }
+                    if self.peek(0) == Some(quote) && self.peek(1) == Some(quote) {
+                        self.bump();
+                        self.bump();
+                        break;
+                    }
+                    text.push(c);
+                }
+                '\n' if !triple => return error(pos, "this string is missing its closing quote"),
+                '\\' if raw => {
+                    text.push(c);
+                    if let Some(c) = self.bump() {
+                        text.push(c);
+                    }
+                }
+                '\\' => self.escape(&mut text, pos)?,
+                c => text.push(c),
+            }
+        }
+        let kind = if prefix.contains('f') {
+            TokenKind::FString(text)
+        } else {
+            TokenKind::Str(text)
+        };
+        self.push(kind, pos);
+        Ok(())
+    }
+
+    fn escape(&mut self, text: &mut String, pos: Pos) -> Result<(), PythonError> {
+        let hex = |lexer: &mut Self, digits: usize| {
+            let code: String = (0..digits).filter_map(|_| lexer.bump()).collect();
+            u32::from_str_radix(&code, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .map_or_else(|| error(pos, "invalid escape in string"), Ok)
+        };
+        match self.bump() {
+            Some('\n') => {}
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some('0') => text.push('\0'),
+            Some('x') => text.push(hex(self, 2)?),
+            Some('u') => text.push(hex(self, 4)?),
+            Some('U') => text.push(hex(self, 8)?),
+            Some(c @ ('\\' | '\'' | '"')) => text.push(c),
+            Some(c) => {
+                text.push('\\');
+                text.push(c);
+            }
+            None => return error(pos, "this string is missing its closing quote"),
+        }
+        Ok(())
+    }
+
+    fn run(mut self) -> Result<Vec<Token>, PythonError> {
+        let mut indents = vec![0];
+        let mut brackets: Vec<(&str, Pos)> = Vec::new(); // newlines inside don't end statements
+        let mut line_start = true;
+
+        loop {
+            if line_start && brackets.is_empty() {
+                let Some(width) = self.indentation() else {
+                    if self.peek(0).is_none() {
+                        break;
+                    }
+                    continue;
+                };
+                let pos = self.pos();
+                let top = *indents.last().expect("missing indentation");
+                if width > top {
+                    indents.push(width);
+                    self.push(TokenKind::Indent, pos);
+                }
+                while width < *indents.last().expect("missing indentation") {
+                    indents.pop();
+                    self.push(TokenKind::Dedent, pos);
+                }
+                if width != *indents.last().expect("missing indentation") {
+                    return Err(PythonError::new(
+                        pos,
+                        "IndentationError: unindent does not match any outer indentation level",
+                    ));
+                }
+                line_start = false;
+            }
+
+            let pos = self.pos();
+            let Some(c) = self.peek(0) else {
+                break;
+            };
+            match c {
+                ' ' | '\t' | '\r' | '\x0c' => {
+                    self.bump();
+                }
+                '#' => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                '\\' if self.peek(1) == Some('\n') => {
+                    self.bump();
+                    self.bump();
+                }
+                '\\' if self.peek(1) == Some('\r') && self.peek(2) == Some('\n') => {
+                    self.bump();
+                    self.bump();
+                    self.bump();
+                }
+                '\n' => {
+                    self.bump();
+                    if brackets.is_empty() {
+                        self.push(TokenKind::Newline, pos);
+                        line_start = true;
+                    }
+                }
+                '0'..='9' => self.number()?,
+                '.' if self.peek(1).is_some_and(|c| c.is_ascii_digit()) => self.number()?,
+                '\'' | '"' => self.string(pos, "")?,
+                c if c.is_alphabetic() || c == '_' => {
+                    let mut name = String::new();
+                    while let Some(c) = self.peek(0).filter(|c| c.is_alphanumeric() || *c == '_') {
+                        name.push(c);
+                        self.bump();
+                    }
+                    let prefix = name.len() <= 2
+                        && name.chars().all(|c| "rRfFbBuU".contains(c))
+                        && matches!(self.peek(0), Some('\'' | '"'));
+                    if prefix {
+                        self.string(pos, &name)?;
+                    } else {
+                        self.push(TokenKind::Name(name), pos);
+                    }
+                }
+                _ => {
+                    let Some(op) = OPS
+                        .iter()
+                        .find(|op| op.chars().enumerate().all(|(i, c)| self.peek(i) == Some(c)))
+                    else {
+                        return error(pos, format!("invalid character '{c}'"));
+                    };
+                    for _ in 0..op.len() {
+                        self.bump();
+                    }
+                    match *op {
+                        "(" | "[" | "{" => brackets.push((op, pos)),
+                        ")" | "]" | "}" => {
+                            let Some((open, open_pos)) = brackets.pop() else {
+                                return error(pos, format!("unmatched '{op}'"));
+                            };
+                            let expected = match open {
+                                "(" => ")",
+                                "[" => "]",
+                                _ => "}",
+                            };
+                            if *op != expected {
+                                return error(
+                                    pos,
+                                    format!(
+                                        "closing '{op}' does not match opening '{open}' on line {}",
+                                        open_pos.line
+                                    ),
+                                );
+                            }
+                        }
+                        _ => {}
+                    }
+                    self.push(TokenKind::Op(op), pos);
+                }
+            }
+        }
+
+        if let Some((open, pos)) = brackets.pop() {
+            return error(pos, format!("'{open}' was never closed"));
+        }
+        let pos = self.pos();
+        if self
+            .tokens
+            .last()
+            .is_some_and(|t| t.kind != TokenKind::Newline)
+        {
+            self.push(TokenKind::Newline, pos);
+        }
+        for _ in 1..indents.len() {
+            self.push(TokenKind::Dedent, pos);
+        }
+        self.push(TokenKind::End, pos);
+        Ok(self.tokens)
+    }
+}
+
+/// Split a script into tokens, with the indentation of each line turned into
+/// `Indent` and `Dedent` tokens as Python does
+pub(crate) fn tokenize(source: &str) -> Result<Vec<Token>, PythonError> {
+    Lexer {
+        chars: source.chars().collect(),
+        at: 0,
+        line: 1,
+        column: 1,
+        tokens: Vec::new(),
+    }
+    .run()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        tokenize(source)
+            .expect("couldn't tokenize")
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn blocks_are_indented() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("for i in range(3):\n    fd(10)  # go\n\n    rt(120)\nhome()"),
+            vec![
+                Name("for".into()),
+                Name("i".into()),
+                Name("in".into()),
+                Name("range".into()),
+                Op("("),
+                Int(3),
+                Op(")"),
+                Op(":"),
+                Newline,
+                Indent,
+                Name("fd".into()),
+                Op("("),
+                Int(10),
+                Op(")"),
+                Newline,
+                Name("rt".into()),
+                Op("("),
+                Int(120),
+                Op(")"),
+                Newline,
+                Dedent,
+                Name("home".into()),
+                Op("("),
+                Op(")"),
+                Newline,
+                End,
+            ]
+        );
+    }
+
+    #[test]
+    fn brackets_join_lines() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("goto(1,\n     -2.5e1)\nx **= 0x10"),
+            vec![
+                Name("goto".into()),
+                Op("("),
+                Int(1),
+                Op(","),
+                Op("-"),
+                Float(25.),
+                Op(")"),
+                Newline,
+                Name("x".into()),
+                Op("**="),
+                Int(16),
+                Newline,
+                End,
+            ]
+        );
+    }
+
+    #[test]
+    fn strings() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds(
+                r#"'it\'s' "a\tb" f"{x}!" r'\n' '''two
+lines'''"#
+            )[..5],
+            [
+                Str("it's".into()),
+                Str("a\tb".into()),
+                FString("{x}!".into()),
+                Str("\\n".into()),
+                Str("two\nlines".into()),
+            ]
+        );
+        let error = tokenize("print('oops)").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 7));
+    }
+}
@@ -0,0 +1,244 @@
+//! Runs the common subset of Python turtle scripts on a slowpoke `Turtle`, so
+//! they can be run under any frontend and compared with their Rust ports:
+//!
+//! ```ignore
+//! Slowpoke::default().run(|turtle| {
+//!     let script = "import turtle\nfor i in range(4):\n    turtle.fd(100)\n    turtle.rt(90)";
+//!     if let Err(e) = slowpoke_python::run(turtle, script) {
+//!         eprintln!("{e}");
+//!     }
+//! });
+//! ```
+//!
+//! What's understood:
+//!
+//! - `import turtle` (or `from turtle import *`), with the module's functions
+//!   working the turtle you pass in, `turtle.Turtle()` to hatch more turtles
+//!   and `turtle.Screen()` for the screen's methods
+//! - turtle and screen methods with positional and keyword arguments, such as
+//!   `t.circle(50, extent=180)`, `t.dot(20, "red")`, `pencolor(1, 0.5, 0)`,
+//!   `screen.colormode(255)`, `t.pos()` and `t.towards(0, 0)`
+//! - `if`/`elif`/`else`, `while`, `for` over `range()` and sequences, `break`,
+//!   `continue`, `def` functions with defaults, recursion and `global`
+//! - numbers, strings (including f-strings, `%` and `.format()`), lists and
+//!   tuples, and the usual operators on them
+//! - builtins like `print`, `len`, `int`, `abs`, `min`, `round` and `sorted`,
+//!   as well as the `math`, `random` and `time` modules
+//!
+//! Classes, exceptions, `lambda`, dictionaries and comprehensions aren't
+//! supported, nor are event handlers such as `onclick()`. Using them is an
+//! error which says so, at the line it's on.
+
+mod builtins;
+mod eval;
+mod format;
+mod lexer;
+mod parser;
+mod turtles;
+mod value;
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt::Display,
+    hash::{BuildHasher, Hasher},
+};
+
+use slowpoke::Turtle;
+
+pub use lexer::Pos;
+
+use eval::Eval;
+
+/// Something wrong with a script, and where. The message starts with the name
+/// of the exception Python would raise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PythonError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl PythonError {
+    pub(crate) fn new<S: Into<String>>(pos: Pos, message: S) -> Self {
+        Self {
+            line: pos.line,
+            column: pos.column,
+            message: message.into(),
+        }
+    }
+
+    // the same error, somewhere else
+    pub(crate) fn moved_to(self, pos: Pos) -> Self {
+        Self {
+            line: pos.line,
+            column: pos.column,
+            ..self
+        }
+    }
+}
+
+impl Display for PythonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for PythonError {}
+
+/// Run a Python turtle script on `turtle`
+///
+/// # Errors
+/// Returns the first syntax error in the script, or the one that stopped it
+///
+/// # Panics
+/// Panics if the thread that runs the script can't be started
+pub fn run(turtle: &mut Turtle, source: &str) -> Result<(), PythonError> {
+    // xorshift needs a seed other than zero
+    let seed = RandomState::new().build_hasher().finish() | 1;
+
+    // a script's values can't be sent between threads, so it's parsed on the
+    // thread that runs it, which has room for deeply recursive functions
+    turtle.with_deep_stack(move |turtle| {
+        let tokens = lexer::tokenize(source)?;
+        let program = parser::parse(&tokens)?;
+        Eval::new(turtle, seed).run(&program)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use slowpoke::{headless::Headless, SlowpokeLib};
+
+    use super::*;
+
+    // where the turtle ended up: x, y and heading
+    fn run_python(source: &'static str) -> (Result<(), PythonError>, [f32; 3]) {
+        let (send, receive) = mpsc::channel();
+        SlowpokeLib::<Headless>::default().run(move |turtle| {
+            turtle.speed(0);
+            let result = run(turtle, source);
+            let _ = send.send((result, [turtle.xcor(), turtle.ycor(), turtle.heading()]));
+            turtle.bye();
+        });
+        receive.recv().expect("the script didn't finish")
+    }
+
+    #[test]
+    fn drives_the_turtle() {
+        let (result, [x, y, heading]) = run_python(
+            "import turtle\n\
+             \n\
+             def square(size, turn=90):\n\
+             \x20   for _ in range(4):\n\
+             \x20       turtle.forward(size)\n\
+             \x20       turtle.left(turn)\n\
+             \n\
+             square(50)\n\
+             turtle.penup()\n\
+             turtle.goto(10 * 3, -(4 + 6))\n\
+             turtle.setheading(to_angle=45 if turtle.isdown() else 30)\n",
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!([x.round(), y.round(), heading.round()], [30., -10., 30.]);
+
+        let (result, [x, y, heading]) = run_python(
+            "from turtle import *\n\
+             from math import sqrt\n\
+             \n\
+             def spiral(n):\n\
+             \x20   if n == 0:\n\
+             \x20       return 0\n\
+             \x20   fd(1)\n\
+             \x20   return 1 + spiral(n - 1)\n\
+             \n\
+             steps = spiral(300)\n\
+             seth(towards(0, 0))\n\
+             fd(sqrt(steps ** 2) / 2)\n\
+             lt(90)\n",
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!([x.round(), y.round(), heading.round()], [150., 0., 270.]);
+    }
+
+    #[test]
+    fn turtles_and_screens() {
+        // a check that fails hands pencolor() what it got, so the error says
+        let (result, [x, y, _]) = run_python(
+            "import turtle\n\
+             screen = turtle.Screen()\n\
+             screen.colormode(255)\n\
+             t = turtle.Turtle()\n\
+             t.pencolor(255, 128, 0)\n\
+             t.circle(20, extent=180, steps=4)\n\
+             turtle.goto(len(screen.turtles()), round(t.xcor() + 0.4))\n\
+             shown = f'{t.pos()} {t.heading():.0f}'\n\
+             if shown != '(0.00,40.00) 180':\n\
+             \x20   turtle.pencolor(shown)\n",
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!([x, y], [2., 0.]);
+    }
+
+    #[test]
+    fn runtime_errors_say_where() {
+        let (result, _) = run_python("size = 10\nfd = size\nforward(size)");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "line 3, column 1: NameError: name 'forward' is not defined"
+        );
+
+        let (result, _) =
+            run_python("import turtle\nfor c in ['red', 'nocolor']:\n  turtle.pencolor(c)");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "line 3, column 3: TurtleGraphicsError: bad color string: nocolor"
+        );
+
+        let (result, _) = run_python("import turtle\nturtle.onclick(print)");
+        let error = result.unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(
+            error.message,
+            "NotImplementedError: onclick() isn't supported"
+        );
+    }
+
+    #[test]
+    fn the_examples_run() {
+        let examples = concat!(env!("CARGO_MANIFEST_DIR"), "/../../python-examples");
+        for entry in std::fs::read_dir(examples).expect("no examples") {
+            let path = entry.expect("can't list the examples").path();
+            // nothing will close the window, so don't wait for that
+            let script: String = std::fs::read_to_string(&path)
+                .expect("can't read the example")
+                .lines()
+                .map(|line| {
+                    let code = line.trim_start();
+                    let indent = &line[..line.len() - code.len()];
+                    match code {
+                        "mainloop()" | "done()" | "turtle.mainloop()" | "turtle.done()" => {
+                            format!("{indent}pass\n")
+                        }
+                        _ => format!("{line}\n"),
+                    }
+                })
+                .collect();
+
+            let (send, receive) = mpsc::channel();
+            SlowpokeLib::<Headless>::default().run(move |turtle| {
+                // every command takes a single frame, whatever its speed
+                turtle.duration(std::time::Duration::ZERO);
+                let _ = send.send(run(turtle, &script));
+                turtle.bye();
+            });
+            let result = receive.recv().expect("the example didn't finish");
+            assert_eq!(result, Ok(()), "{}", path.display());
+        }
+    }
+}
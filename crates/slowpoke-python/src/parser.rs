@@ -0,0 +1,1148 @@
+use std::{collections::HashSet, rc::Rc};
+
+use crate::{
+    lexer::{self, Pos, Token, TokenKind},
+    PythonError,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    FloorDiv,
+    Mod,
+    Pow,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    In,
+    NotIn,
+    Is,
+    IsNot,
+}
+
+#[derive(Debug)]
+pub(crate) enum FPart {
+    Text(String),
+    Value(Expr, Option<char>, String), // with its conversion (!r or !s) and format spec
+}
+
+#[derive(Debug)]
+pub(crate) struct Arg {
+    pub keyword: Option<String>,
+    pub value: Expr,
+}
+
+#[derive(Debug)]
+pub(crate) enum ExprKind {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(Rc<str>),
+    FString(Vec<FPart>),
+    Name(String),
+    Attr(Box<Expr>, String),
+    Call(Box<Expr>, Vec<Arg>),
+    Index(Box<Expr>, Box<Expr>),
+    Slice(Box<Expr>, [Option<Box<Expr>>; 3]), // start, stop and step
+    Tuple(Vec<Expr>),
+    List(Vec<Expr>),
+    Negate(Box<Expr>),
+    Plus(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, Vec<(CmpOp, Expr)>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    IfElse(Box<Expr>, Box<Expr>, Box<Expr>), // condition, then, else
+}
+
+#[derive(Debug)]
+pub(crate) struct Expr {
+    pub kind: ExprKind,
+    pub pos: Pos,
+}
+
+#[derive(Debug)]
+pub(crate) struct Function {
+    pub name: String,
+    pub params: Vec<(String, Option<Expr>)>, // with any default value
+    pub locals: HashSet<String>,             // everything assigned in the body
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug)]
+pub(crate) enum StmtKind {
+    Expr(Expr),
+    Assign(Vec<Expr>, Expr), // a = b = value
+    AugAssign(Expr, BinOp, Expr),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    For(Expr, Expr, Vec<Stmt>),
+    Def(Rc<Function>),
+    Return(Option<Expr>),
+    Pass,
+    Break,
+    Continue,
+    Global(Vec<String>),
+    Import(String, Option<String>),          // module, as name
+    ImportFrom(String, Option<Vec<String>>), // None for *
+}
+
+#[derive(Debug)]
+pub(crate) struct Stmt {
+    pub kind: StmtKind,
+    pub pos: Pos,
+}
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+fn error<T>(pos: Pos, message: impl Into<String>) -> Result<T, PythonError> {
+    Err(PythonError::new(
+        pos,
+        format!("SyntaxError: {}", message.into()),
+    ))
+}
+
+fn describe(token: &Token) -> String {
+    match &token.kind {
+        TokenKind::Name(name) => format!("'{name}'"),
+        TokenKind::Int(n) => n.to_string(),
+        TokenKind::Float(n) => n.to_string(),
+        TokenKind::Str(_) | TokenKind::FString(_) => "a string".into(),
+        TokenKind::Op(op) => format!("'{op}'"),
+        TokenKind::Newline => "the end of the line".into(),
+        TokenKind::Indent => "an indented line".into(),
+        TokenKind::Dedent | TokenKind::End => "the end of the block".into(),
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    at: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> &'a Token {
+        &self.tokens[self.at]
+    }
+
+    fn peek_at(&self, ahead: usize) -> &'a Token {
+        &self.tokens[(self.at + ahead).min(self.tokens.len() - 1)]
+    }
+
+    fn advance(&mut self) -> &'a Token {
+        let token = &self.tokens[self.at];
+        if self.at < self.tokens.len() - 1 {
+            self.at += 1;
+        }
+        token
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        let found = self.peek().is_op(op);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn eat_name(&mut self, name: &str) -> bool {
+        let found = self.peek().is_name(name);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_op(&mut self, op: &str) -> Result<Pos, PythonError> {
+        let token = self.peek();
+        if self.eat_op(op) {
+            Ok(token.pos)
+        } else {
+            error(
+                token.pos,
+                format!("expected '{op}' but found {}", describe(token)),
+            )
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), PythonError> {
+        let token = self.peek();
+        if self.eat_name(keyword) {
+            Ok(())
+        } else {
+            error(
+                token.pos,
+                format!("expected '{keyword}' but found {}", describe(token)),
+            )
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, PythonError> {
+        let token = self.advance();
+        match &token.kind {
+            TokenKind::Name(name) if !KEYWORDS.contains(&name.as_str()) => Ok(name.clone()),
+            _ => error(
+                token.pos,
+                format!("expected a name but found {}", describe(token)),
+            ),
+        }
+    }
+
+    // a dotted module name such as os.path
+    fn module(&mut self) -> Result<String, PythonError> {
+        let mut name = self.identifier()?;
+        while self.eat_op(".") {
+            name.push('.');
+            name.push_str(&self.identifier()?);
+        }
+        Ok(name)
+    }
+
+    fn statements_until_end(&mut self) -> Result<Vec<Stmt>, PythonError> {
+        let mut body = Vec::new();
+        while !matches!(self.peek().kind, TokenKind::End) {
+            self.statement(&mut body)?;
+        }
+        Ok(body)
+    }
+
+    // the statements after a ':', either on the same line or indented below
+    fn block(&mut self) -> Result<Vec<Stmt>, PythonError> {
+        self.expect_op(":")?;
+        let mut body = Vec::new();
+        if !matches!(self.peek().kind, TokenKind::Newline) {
+            self.simple_statements(&mut body)?;
+            return Ok(body);
+        }
+        self.advance();
+        let token = self.advance();
+        if token.kind != TokenKind::Indent {
+            return Err(PythonError::new(
+                token.pos,
+                "IndentationError: expected an indented block",
+            ));
+        }
+        while !matches!(self.peek().kind, TokenKind::Dedent | TokenKind::End) {
+            self.statement(&mut body)?;
+        }
+        self.advance();
+        Ok(body)
+    }
+
+    fn statement(&mut self, body: &mut Vec<Stmt>) -> Result<(), PythonError> {
+        let token = self.peek();
+        let pos = token.pos;
+        let TokenKind::Name(keyword) = &token.kind else {
+            if token.kind == TokenKind::Indent {
+                return Err(PythonError::new(pos, "IndentationError: unexpected indent"));
+            }
+            return self.simple_statements(body);
+        };
+
+        let kind = match keyword.as_str() {
+            "if" => {
+                self.advance();
+                self.if_statement()?
+            }
+            "while" => {
+                self.advance();
+                let condition = self.expression()?;
+                let loop_body = self.block()?;
+                if self.peek().is_name("else") {
+                    return error(self.peek().pos, "while ... else isn't supported");
+                }
+                StmtKind::While(condition, loop_body)
+            }
+            "for" => {
+                self.advance();
+                let target = self.targets()?;
+                self.expect_keyword("in")?;
+                let iterable = self.expression_list()?;
+                let loop_body = self.block()?;
+                if self.peek().is_name("else") {
+                    return error(self.peek().pos, "for ... else isn't supported");
+                }
+                StmtKind::For(target, iterable, loop_body)
+            }
+            "def" => {
+                self.advance();
+                StmtKind::Def(Rc::new(self.function()?))
+            }
+            "class" | "try" | "with" | "async" | "lambda" | "yield" => {
+                return error(pos, format!("{keyword} isn't supported"));
+            }
+            "elif" | "else" => return error(pos, format!("'{keyword}' without an 'if'")),
+            _ => return self.simple_statements(body),
+        };
+        body.push(Stmt { kind, pos });
+        Ok(())
+    }
+
+    // after the "if"
+    fn if_statement(&mut self) -> Result<StmtKind, PythonError> {
+        let condition = self.expression()?;
+        let then = self.block()?;
+        let pos = self.peek().pos;
+        let otherwise = if self.eat_name("elif") {
+            vec![Stmt {
+                kind: self.if_statement()?,
+                pos,
+            }]
+        } else if self.eat_name("else") {
+            self.block()?
+        } else {
+            Vec::new()
+        };
+        Ok(StmtKind::If(condition, then, otherwise))
+    }
+
+    // after the "def"
+    fn function(&mut self) -> Result<Function, PythonError> {
+        let name = self.identifier()?;
+        self.expect_op("(")?;
+        let mut params: Vec<(String, Option<Expr>)> = Vec::new();
+        while !self.peek().is_op(")") {
+            let pos = self.peek().pos;
+            if self.peek().is_op("*") || self.peek().is_op("**") {
+                return error(pos, "*args and **kwargs aren't supported");
+            }
+            let param = self.identifier()?;
+            if params.iter().any(|(p, _)| *p == param) {
+                return error(
+                    pos,
+                    format!("duplicate argument '{param}' in function definition"),
+                );
+            }
+            let default = if self.eat_op("=") {
+                Some(self.expression()?)
+            } else if params.last().is_some_and(|(_, d)| d.is_some()) {
+                return error(
+                    pos,
+                    "a parameter without a default follows one with a default",
+                );
+            } else {
+                None
+            };
+            params.push((param, default));
+            if !self.eat_op(",") {
+                break;
+            }
+        }
+        self.expect_op(")")?;
+        if self.eat_op("->") {
+            self.expression()?;
+        }
+        let body = self.block()?;
+
+        let mut locals: HashSet<String> = params.iter().map(|(p, _)| p.clone()).collect();
+        let mut globals = HashSet::new();
+        assigned_names(&body, &mut locals, &mut globals);
+        locals.retain(|name| !globals.contains(name));
+        Ok(Function {
+            name,
+            params,
+            locals,
+            body,
+        })
+    }
+
+    // statements separated by semicolons, up to the end of the line
+    fn simple_statements(&mut self, body: &mut Vec<Stmt>) -> Result<(), PythonError> {
+        loop {
+            let pos = self.peek().pos;
+            let kind = self.simple_statement()?;
+            body.push(Stmt { kind, pos });
+            if !self.eat_op(";") || matches!(self.peek().kind, TokenKind::Newline) {
+                break;
+            }
+        }
+        let token = self.advance();
+        if token.kind != TokenKind::Newline {
+            return error(token.pos, format!("unexpected {}", describe(token)));
+        }
+        Ok(())
+    }
+
+    fn simple_statement(&mut self) -> Result<StmtKind, PythonError> {
+        let token = self.peek();
+        if let TokenKind::Name(keyword) = &token.kind {
+            match keyword.as_str() {
+                "pass" => {
+                    self.advance();
+                    return Ok(StmtKind::Pass);
+                }
+                "break" => {
+                    self.advance();
+                    return Ok(StmtKind::Break);
+                }
+                "continue" => {
+                    self.advance();
+                    return Ok(StmtKind::Continue);
+                }
+                "return" => {
+                    self.advance();
+                    if matches!(self.peek().kind, TokenKind::Newline) || self.peek().is_op(";") {
+                        return Ok(StmtKind::Return(None));
+                    }
+                    return Ok(StmtKind::Return(Some(self.expression_list()?)));
+                }
+                "global" => {
+                    self.advance();
+                    let mut names = vec![self.identifier()?];
+                    while self.eat_op(",") {
+                        names.push(self.identifier()?);
+                    }
+                    return Ok(StmtKind::Global(names));
+                }
+                "import" => {
+                    self.advance();
+                    let module = self.module()?;
+                    let alias = if self.eat_name("as") {
+                        Some(self.identifier()?)
+                    } else {
+                        None
+                    };
+                    if self.peek().is_op(",") {
+                        return error(self.peek().pos, "import one module at a time");
+                    }
+                    return Ok(StmtKind::Import(module, alias));
+                }
+                "from" => {
+                    self.advance();
+                    let module = self.module()?;
+                    self.expect_keyword("import")?;
+                    if self.eat_op("*") {
+                        return Ok(StmtKind::ImportFrom(module, None));
+                    }
+                    let parens = self.eat_op("(");
+                    let mut names = vec![self.identifier()?];
+                    while self.eat_op(",") {
+                        if parens && self.peek().is_op(")") {
+                            break;
+                        }
+                        names.push(self.identifier()?);
+                    }
+                    if parens {
+                        self.expect_op(")")?;
+                    }
+                    if self.peek().is_name("as") {
+                        return error(self.peek().pos, "from ... import ... as isn't supported");
+                    }
+                    return Ok(StmtKind::ImportFrom(module, Some(names)));
+                }
+                "del" | "assert" | "raise" | "nonlocal" => {
+                    return error(token.pos, format!("{keyword} isn't supported"));
+                }
+                _ => {}
+            }
+        }
+
+        let first = self.expression_list()?;
+        let token = self.peek();
+        if let TokenKind::Op(op) = token.kind {
+            let aug = match op {
+                "+=" => Some(BinOp::Add),
+                "-=" => Some(BinOp::Sub),
+                "*=" => Some(BinOp::Mul),
+                "/=" => Some(BinOp::Div),
+                "//=" => Some(BinOp::FloorDiv),
+                "%=" => Some(BinOp::Mod),
+                "**=" => Some(BinOp::Pow),
+                _ => None,
+            };
+            if let Some(aug) = aug {
+                self.advance();
+                if !matches!(
+                    first.kind,
+                    ExprKind::Name(_) | ExprKind::Attr(..) | ExprKind::Index(..)
+                ) {
+                    return error(first.pos, "this can't be assigned to");
+                }
+                let value = self.expression_list()?;
+                return Ok(StmtKind::AugAssign(first, aug, value));
+            }
+            if op == "=" {
+                let mut targets = vec![first];
+                let mut value;
+                loop {
+                    self.advance();
+                    value = self.expression_list()?;
+                    if !self.peek().is_op("=") {
+                        break;
+                    }
+                    targets.push(value);
+                }
+                for target in &targets {
+                    check_target(target)?;
+                }
+                return Ok(StmtKind::Assign(targets, value));
+            }
+        }
+        Ok(StmtKind::Expr(first))
+    }
+
+    // what a for loop assigns to
+    fn targets(&mut self) -> Result<Expr, PythonError> {
+        let pos = self.peek().pos;
+        // a target can't contain "in" as a comparison
+        let mut items = vec![self.arith()?];
+        let tuple = self.peek().is_op(",");
+        while self.eat_op(",") {
+            if self.peek().is_name("in") {
+                break;
+            }
+            items.push(self.arith()?);
+        }
+        let target = if tuple {
+            Expr {
+                kind: ExprKind::Tuple(items),
+                pos,
+            }
+        } else {
+            items.pop().expect("missing target")
+        };
+        check_target(&target)?;
+        Ok(target)
+    }
+
+    // expressions separated by commas make a tuple
+    fn expression_list(&mut self) -> Result<Expr, PythonError> {
+        let pos = self.peek().pos;
+        let first = self.expression()?;
+        if !self.peek().is_op(",") {
+            return Ok(first);
+        }
+        let mut items = vec![first];
+        while self.eat_op(",") {
+            if self.at_expression_end() {
+                break;
+            }
+            items.push(self.expression()?);
+        }
+        Ok(Expr {
+            kind: ExprKind::Tuple(items),
+            pos,
+        })
+    }
+
+    fn at_expression_end(&self) -> bool {
+        let token = self.peek();
+        matches!(token.kind, TokenKind::Newline | TokenKind::End)
+            || [")", "]", "=", ";", ":"].iter().any(|op| token.is_op(op))
+    }
+
+    fn expression(&mut self) -> Result<Expr, PythonError> {
+        let value = self.or_test()?;
+        if !self.peek().is_name("if") {
+            return Ok(value);
+        }
+        self.advance();
+        let condition = self.or_test()?;
+        self.expect_keyword("else")?;
+        let otherwise = self.expression()?;
+        Ok(Expr {
+            pos: value.pos,
+            kind: ExprKind::IfElse(Box::new(condition), Box::new(value), Box::new(otherwise)),
+        })
+    }
+
+    fn or_test(&mut self) -> Result<Expr, PythonError> {
+        let mut left = self.and_test()?;
+        while self.eat_name("or") {
+            let right = self.and_test()?;
+            left = Expr {
+                pos: left.pos,
+                kind: ExprKind::Or(Box::new(left), Box::new(right)),
+            };
+        }
+        Ok(left)
+    }
+
+    fn and_test(&mut self) -> Result<Expr, PythonError> {
+        let mut left = self.not_test()?;
+        while self.eat_name("and") {
+            let right = self.not_test()?;
+            left = Expr {
+                pos: left.pos,
+                kind: ExprKind::And(Box::new(left), Box::new(right)),
+            };
+        }
+        Ok(left)
+    }
+
+    fn not_test(&mut self) -> Result<Expr, PythonError> {
+        let pos = self.peek().pos;
+        if self.eat_name("not") {
+            let inner = self.not_test()?;
+            return Ok(Expr {
+                kind: ExprKind::Not(Box::new(inner)),
+                pos,
+            });
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, PythonError> {
+        let left = self.arith()?;
+        let mut rest = Vec::new();
+        loop {
+            let token = self.peek();
+            let op = match &token.kind {
+                TokenKind::Op("==") => CmpOp::Eq,
+                TokenKind::Op("!=") => CmpOp::Ne,
+                TokenKind::Op("<") => CmpOp::Lt,
+                TokenKind::Op(">") => CmpOp::Gt,
+                TokenKind::Op("<=") => CmpOp::Le,
+                TokenKind::Op(">=") => CmpOp::Ge,
+                TokenKind::Name(n) if n == "in" => CmpOp::In,
+                TokenKind::Name(n) if n == "is" => {
+                    if self.peek_at(1).is_name("not") {
+                        self.advance();
+                        CmpOp::IsNot
+                    } else {
+                        CmpOp::Is
+                    }
+                }
+                TokenKind::Name(n) if n == "not" && self.peek_at(1).is_name("in") => {
+                    self.advance();
+                    CmpOp::NotIn
+                }
+                _ => break,
+            };
+            self.advance();
+            rest.push((op, self.arith()?));
+        }
+        if rest.is_empty() {
+            return Ok(left);
+        }
+        Ok(Expr {
+            pos: left.pos,
+            kind: ExprKind::Compare(Box::new(left), rest),
+        })
+    }
+
+    fn arith(&mut self) -> Result<Expr, PythonError> {
+        let mut left = self.term()?;
+        loop {
+            let op = if self.peek().is_op("+") {
+                BinOp::Add
+            } else if self.peek().is_op("-") {
+                BinOp::Sub
+            } else {
+                break;
+            };
+            self.advance();
+            let right = self.term()?;
+            left = Expr {
+                pos: left.pos,
+                kind: ExprKind::Binary(op, Box::new(left), Box::new(right)),
+            };
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, PythonError> {
+        let mut left = self.factor()?;
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Op("*") => BinOp::Mul,
+                TokenKind::Op("/") => BinOp::Div,
+                TokenKind::Op("//") => BinOp::FloorDiv,
+                TokenKind::Op("%") => BinOp::Mod,
+                _ => break,
+            };
+            self.advance();
+            let right = self.factor()?;
+            left = Expr {
+                pos: left.pos,
+                kind: ExprKind::Binary(op, Box::new(left), Box::new(right)),
+            };
+        }
+        Ok(left)
+    }
+
+    fn factor(&mut self) -> Result<Expr, PythonError> {
+        let pos = self.peek().pos;
+        if self.eat_op("-") {
+            let inner = self.factor()?;
+            return Ok(Expr {
+                kind: ExprKind::Negate(Box::new(inner)),
+                pos,
+            });
+        }
+        if self.eat_op("+") {
+            let inner = self.factor()?;
+            return Ok(Expr {
+                kind: ExprKind::Plus(Box::new(inner)),
+                pos,
+            });
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, PythonError> {
+        let base = self.primary()?;
+        if !self.eat_op("**") {
+            return Ok(base);
+        }
+        // -2 ** 2 is -4, and 2 ** -1 is 0.5
+        let exponent = self.factor()?;
+        Ok(Expr {
+            pos: base.pos,
+            kind: ExprKind::Binary(BinOp::Pow, Box::new(base), Box::new(exponent)),
+        })
+    }
+
+    fn primary(&mut self) -> Result<Expr, PythonError> {
+        let mut expr = self.atom()?;
+        loop {
+            let pos = self.peek().pos;
+            if self.eat_op(".") {
+                let name = self.identifier()?;
+                let pos = expr.pos;
+                expr = Expr {
+                    kind: ExprKind::Attr(Box::new(expr), name),
+                    pos,
+                };
+            } else if self.eat_op("(") {
+                let args = self.arguments()?;
+                let pos = expr.pos;
+                expr = Expr {
+                    kind: ExprKind::Call(Box::new(expr), args),
+                    pos,
+                };
+            } else if self.eat_op("[") {
+                expr = self.subscript(expr, pos)?;
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    // after the "("
+    fn arguments(&mut self) -> Result<Vec<Arg>, PythonError> {
+        let mut args: Vec<Arg> = Vec::new();
+        while !self.peek().is_op(")") {
+            let token = self.peek();
+            if token.is_op("*") || token.is_op("**") {
+                return error(token.pos, "*args and **kwargs aren't supported");
+            }
+            let keyword = match &token.kind {
+                TokenKind::Name(name) if self.peek_at(1).is_op("=") => {
+                    self.advance();
+                    self.advance();
+                    Some(name.clone())
+                }
+                _ => None,
+            };
+            if keyword.is_none() && args.iter().any(|a| a.keyword.is_some()) {
+                return error(token.pos, "positional argument follows keyword argument");
+            }
+            let value = self.expression()?;
+            args.push(Arg { keyword, value });
+            if !self.eat_op(",") {
+                break;
+            }
+        }
+        self.expect_op(")")?;
+        Ok(args)
+    }
+
+    // after the "["
+    fn subscript(&mut self, base: Expr, pos: Pos) -> Result<Expr, PythonError> {
+        let mut parts: [Option<Box<Expr>>; 3] = [None, None, None];
+        let mut colons = 0;
+        loop {
+            if self.peek().is_op(":") {
+                colons += 1;
+                if colons > 2 {
+                    return error(self.peek().pos, "too many ':' in this slice");
+                }
+                self.advance();
+            } else if self.peek().is_op("]") {
+                break;
+            } else {
+                if parts[colons].is_some() {
+                    return error(self.peek().pos, "expected ']'");
+                }
+                parts[colons] = Some(Box::new(self.expression_list()?));
+            }
+        }
+        self.expect_op("]")?;
+        let kind = if colons == 0 {
+            match parts[0].take() {
+                Some(index) => ExprKind::Index(Box::new(base), index),
+                None => return error(pos, "this [ ] is missing an index"),
+            }
+        } else {
+            ExprKind::Slice(Box::new(base), parts)
+        };
+        Ok(Expr { kind, pos })
+    }
+
+    fn atom(&mut self) -> Result<Expr, PythonError> {
+        let token = self.advance();
+        let pos = token.pos;
+        let kind = match &token.kind {
+            TokenKind::Int(n) => ExprKind::Int(*n),
+            TokenKind::Float(n) => ExprKind::Float(*n),
+            TokenKind::Str(_) | TokenKind::FString(_) => {
+                // neighbouring strings are joined together
+                let mut parts = Vec::new();
+                let mut formatted = false;
+                let mut token = token;
+                loop {
+                    match &token.kind {
+                        TokenKind::Str(text) => parts.push(FPart::Text(text.clone())),
+                        TokenKind::FString(text) => {
+                            formatted = true;
+                            parts.extend(fstring(text, token.pos)?);
+                        }
+                        _ => unreachable!("only strings are joined"),
+                    }
+                    if !matches!(self.peek().kind, TokenKind::Str(_) | TokenKind::FString(_)) {
+                        break;
+                    }
+                    token = self.advance();
+                }
+                if formatted {
+                    ExprKind::FString(parts)
+                } else {
+                    let text: String = parts
+                        .into_iter()
+                        .map(|part| match part {
+                            FPart::Text(text) => text,
+                            FPart::Value(..) => unreachable!("plain strings have no values"),
+                        })
+                        .collect();
+                    ExprKind::Str(text.into())
+                }
+            }
+            TokenKind::Name(name) => match name.as_str() {
+                "None" => ExprKind::None,
+                "True" => ExprKind::Bool(true),
+                "False" => ExprKind::Bool(false),
+                "lambda" => return error(pos, "lambda isn't supported"),
+                name if KEYWORDS.contains(&name) => {
+                    return error(pos, format!("unexpected '{name}'"));
+                }
+                name => ExprKind::Name(name.to_string()),
+            },
+            TokenKind::Op("(") => {
+                if self.eat_op(")") {
+                    ExprKind::Tuple(Vec::new())
+                } else {
+                    let inner = self.expression_list()?;
+                    if self.peek().is_name("for") {
+                        return error(self.peek().pos, "comprehensions aren't supported");
+                    }
+                    self.expect_op(")")?;
+                    // keep the position of the bracket for tuples
+                    return Ok(match inner.kind {
+                        ExprKind::Tuple(items) => Expr {
+                            kind: ExprKind::Tuple(items),
+                            pos,
+                        },
+                        _ => inner,
+                    });
+                }
+            }
+            TokenKind::Op("[") => {
+                let mut items = Vec::new();
+                while !self.peek().is_op("]") {
+                    items.push(self.expression()?);
+                    if self.peek().is_name("for") {
+                        return error(self.peek().pos, "comprehensions aren't supported");
+                    }
+                    if !self.eat_op(",") {
+                        break;
+                    }
+                }
+                self.expect_op("]")?;
+                ExprKind::List(items)
+            }
+            TokenKind::Op("{") => return error(pos, "dictionaries and sets aren't supported"),
+            _ => return error(pos, format!("unexpected {}", describe(token))),
+        };
+        Ok(Expr { kind, pos })
+    }
+}
+
+fn check_target(target: &Expr) -> Result<(), PythonError> {
+    match &target.kind {
+        ExprKind::Name(_) | ExprKind::Attr(..) | ExprKind::Index(..) => Ok(()),
+        ExprKind::Tuple(items) | ExprKind::List(items) => items.iter().try_for_each(check_target),
+        _ => error(target.pos, "this can't be assigned to"),
+    }
+}
+
+fn target_names(target: &Expr, names: &mut HashSet<String>) {
+    match &target.kind {
+        ExprKind::Name(name) => {
+            names.insert(name.clone());
+        }
+        ExprKind::Tuple(items) | ExprKind::List(items) => {
+            for item in items {
+                target_names(item, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+// the names a function body assigns to, which are its local variables, and
+// the ones it says are global
+fn assigned_names(body: &[Stmt], locals: &mut HashSet<String>, globals: &mut HashSet<String>) {
+    for stmt in body {
+        match &stmt.kind {
+            StmtKind::Assign(targets, _) => {
+                for target in targets {
+                    target_names(target, locals);
+                }
+            }
+            StmtKind::AugAssign(target, _, _) => target_names(target, locals),
+            StmtKind::For(target, _, body) => {
+                target_names(target, locals);
+                assigned_names(body, locals, globals);
+            }
+            StmtKind::If(_, then, otherwise) => {
+                assigned_names(then, locals, globals);
+                assigned_names(otherwise, locals, globals);
+            }
+            StmtKind::While(_, body) => assigned_names(body, locals, globals),
+            StmtKind::Def(function) => {
+                locals.insert(function.name.clone());
+            }
+            StmtKind::Global(names) => globals.extend(names.iter().cloned()),
+            StmtKind::Import(module, alias) => {
+                let name = alias
+                    .clone()
+                    .unwrap_or_else(|| module.split('.').next().unwrap_or(module).to_string());
+                locals.insert(name);
+            }
+            StmtKind::ImportFrom(_, Some(names)) => locals.extend(names.iter().cloned()),
+            StmtKind::Expr(_)
+            | StmtKind::ImportFrom(_, None)
+            | StmtKind::Return(_)
+            | StmtKind::Pass
+            | StmtKind::Break
+            | StmtKind::Continue => {}
+        }
+    }
+}
+
+// split an f-string into its text and the values in braces
+fn fstring(text: &str, pos: Pos) -> Result<Vec<FPart>, PythonError> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '}' => return error(pos, "f-string: single '}' is not allowed"),
+            '{' => {
+                // the expression runs to the '!', ':' or '}' outside any
+                // brackets or quotes
+                let mut source = String::new();
+                let mut depth = 0;
+                let mut quote = None;
+                let mut end = None;
+                while let Some(c) = chars.next() {
+                    match (c, quote) {
+                        (q, Some(open)) if q == open => quote = None,
+                        (_, Some(_)) => {}
+                        ('\'' | '"', None) => quote = Some(c),
+                        ('(' | '[' | '{', None) => depth += 1,
+                        (')' | ']', None) => depth -= 1,
+                        ('!', None) if chars.peek() == Some(&'=') => {
+                            source.push(c);
+                            source.extend(chars.next());
+                            continue;
+                        }
+                        ('}', None) if depth > 0 => depth -= 1,
+                        ('!' | ':' | '}', None) if depth == 0 => {
+                            end = Some(c);
+                            break;
+                        }
+                        _ => {}
+                    }
+                    source.push(c);
+                }
+                let (source, conversion) = match end {
+                    Some('!') => {
+                        let conversion = chars.next();
+                        if !matches!(conversion, Some('r' | 's')) {
+                            return error(pos, "f-string: invalid conversion character");
+                        }
+                        end = chars.next();
+                        (source, conversion)
+                    }
+                    Some(_) => (source, None),
+                    None => return error(pos, "f-string: expecting '}'"),
+                };
+                let mut spec = String::new();
+                if end == Some(':') {
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => spec.push(c),
+                            None => return error(pos, "f-string: expecting '}'"),
+                        }
+                    }
+                } else if end != Some('}') {
+                    return error(pos, "f-string: expecting '}'");
+                }
+
+                // f"{x=}" shows the expression as well as its value
+                let trimmed = source.trim_end();
+                let (source, conversion) = match trimmed.strip_suffix('=') {
+                    Some(inner) if !inner.ends_with(['=', '!', '<', '>']) => {
+                        literal.push_str(&source);
+                        let conversion = conversion.or(spec.is_empty().then_some('r'));
+                        (inner.to_string(), conversion)
+                    }
+                    _ => (source, conversion),
+                };
+                if !literal.is_empty() {
+                    parts.push(FPart::Text(std::mem::take(&mut literal)));
+                }
+                let value = fstring_value(&source, pos)?;
+                parts.push(FPart::Value(value, conversion, spec));
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(FPart::Text(literal));
+    }
+    Ok(parts)
+}
+
+// an expression inside an f-string, where errors are reported at the string
+fn fstring_value(source: &str, pos: Pos) -> Result<Expr, PythonError> {
+    if source.trim().is_empty() {
+        return error(pos, "f-string: empty expression not allowed");
+    }
+    let mut tokens = lexer::tokenize(&format!("({source})")).map_err(|e| e.moved_to(pos))?;
+    for token in &mut tokens {
+        token.pos = pos;
+    }
+    let mut parser = Parser {
+        tokens: &tokens,
+        at: 0,
+    };
+    let value = parser.expression()?;
+    if !matches!(parser.peek().kind, TokenKind::Newline) {
+        return error(pos, "f-string: invalid expression");
+    }
+    Ok(value)
+}
+
+/// Parse a whole script
+pub(crate) fn parse(tokens: &[Token]) -> Result<Vec<Stmt>, PythonError> {
+    Parser { tokens, at: 0 }.statements_until_end()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_source(source: &str) -> Result<Vec<Stmt>, PythonError> {
+        parse(&lexer::tokenize(source)?)
+    }
+
+    #[test]
+    fn precedence() {
+        let body = parse_source("x = -2 ** 2 + 3 * 4 < 5 and not y").expect("couldn't parse");
+        let StmtKind::Assign(_, value) = &body[0].kind else {
+            panic!("not an assignment: {:?}", body[0]);
+        };
+        let ExprKind::And(left, right) = &value.kind else {
+            panic!("not 'and': {value:?}");
+        };
+        assert!(matches!(right.kind, ExprKind::Not(_)));
+        let ExprKind::Compare(sum, rest) = &left.kind else {
+            panic!("not a comparison: {left:?}");
+        };
+        assert_eq!(rest[0].0, CmpOp::Lt);
+        let ExprKind::Binary(BinOp::Add, power, _) = &sum.kind else {
+            panic!("not a sum: {sum:?}");
+        };
+        assert!(matches!(&power.kind, ExprKind::Negate(inner)
+            if matches!(inner.kind, ExprKind::Binary(BinOp::Pow, ..))));
+    }
+
+    #[test]
+    fn functions_know_their_locals() {
+        let body = parse_source(
+            "def f(a, b=2):\n    global g\n    c = a\n    g = b\n    for i, j in x: pass\n",
+        )
+        .expect("couldn't parse");
+        let StmtKind::Def(function) = &body[0].kind else {
+            panic!("not a function: {:?}", body[0]);
+        };
+        let mut locals: Vec<_> = function.locals.iter().map(String::as_str).collect();
+        locals.sort_unstable();
+        assert_eq!(locals, ["a", "b", "c", "i", "j"]);
+    }
+
+    #[test]
+    fn fstrings() {
+        let body = parse_source("f'{{x}} {x!r:>5} {y=}'").expect("couldn't parse");
+        let StmtKind::Expr(Expr {
+            kind: ExprKind::FString(parts),
+            ..
+        }) = &body[0].kind
+        else {
+            panic!("not an f-string: {:?}", body[0]);
+        };
+        assert!(matches!(&parts[0], FPart::Text(t) if t == "{x} "));
+        assert!(matches!(&parts[1], FPart::Value(_, Some('r'), spec) if spec == ">5"));
+        assert!(matches!(&parts[2], FPart::Text(t) if t == " y="));
+        assert!(matches!(&parts[3], FPart::Value(_, Some('r'), spec) if spec.is_empty()));
+    }
+
+    #[test]
+    fn errors_say_where() {
+        let error = parse_source("if x:\nfd(10)").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(
+            error.message,
+            "IndentationError: expected an indented block"
+        );
+
+        let error = parse_source("fd(10\nrt(90)").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 3));
+        assert_eq!(error.message, "SyntaxError: '(' was never closed");
+
+        let error = parse_source("x = = 2").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 5: SyntaxError: unexpected '='"
+        );
+    }
+}
@@ -0,0 +1,783 @@
+use std::path::Path;
+
+use slowpoke::{TurtleColor, TurtleMode, TurtleShapeName};
+
+use crate::{
+    eval::{error, int, number, text, type_error, Args, Eval, Flow},
+    lexer::Pos,
+    value::Value,
+};
+
+// what a Turtle can do, which `from turtle import *` also makes functions of
+pub(crate) const TURTLE_METHODS: &[&str] = &[
+    "back",
+    "backward",
+    "begin_fill",
+    "begin_poly",
+    "bk",
+    "circle",
+    "clear",
+    "clearstamp",
+    "clearstamps",
+    "clone",
+    "color",
+    "degrees",
+    "distance",
+    "dot",
+    "down",
+    "end_fill",
+    "end_poly",
+    "fd",
+    "fillcolor",
+    "filling",
+    "forward",
+    "get_poly",
+    "getpen",
+    "getscreen",
+    "getturtle",
+    "goto",
+    "heading",
+    "hideturtle",
+    "home",
+    "ht",
+    "isdown",
+    "isvisible",
+    "left",
+    "lt",
+    "pd",
+    "pencolor",
+    "pendown",
+    "pensize",
+    "penup",
+    "pos",
+    "position",
+    "pu",
+    "radians",
+    "reset",
+    "right",
+    "rt",
+    "setheading",
+    "seth",
+    "setpos",
+    "setposition",
+    "setundobuffer",
+    "setx",
+    "sety",
+    "shape",
+    "showturtle",
+    "speed",
+    "st",
+    "stamp",
+    "teleport",
+    "towards",
+    "undo",
+    "undobufferentries",
+    "up",
+    "width",
+    "write",
+    "xcor",
+    "ycor",
+];
+
+pub(crate) const SCREEN_METHODS: &[&str] = &[
+    "addshape",
+    "bgcolor",
+    "bgpic",
+    "bye",
+    "clear",
+    "clearscreen",
+    "colormode",
+    "delay",
+    "done",
+    "exitonclick",
+    "getshapes",
+    "mainloop",
+    "mode",
+    "numinput",
+    "register_shape",
+    "reset",
+    "resetscreen",
+    "screensize",
+    "setup",
+    "setworldcoordinates",
+    "textinput",
+    "title",
+    "tracer",
+    "turtles",
+    "update",
+    "window_height",
+    "window_width",
+];
+
+// parts of the turtle module that scripts might use, but which can't be run
+pub(crate) const UNSUPPORTED: &[&str] = &[
+    "getcanvas",
+    "listen",
+    "onclick",
+    "ondrag",
+    "onkey",
+    "onkeypress",
+    "onkeyrelease",
+    "onrelease",
+    "onscreenclick",
+    "ontimer",
+    "resizemode",
+    "settiltangle",
+    "shapesize",
+    "shapetransform",
+    "shearfactor",
+    "tilt",
+    "tiltangle",
+    "turtlesize",
+];
+
+pub(crate) fn turtle_method(name: &str) -> Option<&'static str> {
+    TURTLE_METHODS.iter().copied().find(|m| *m == name)
+}
+
+pub(crate) fn screen_method(name: &str) -> Option<&'static str> {
+    SCREEN_METHODS.iter().copied().find(|m| *m == name)
+}
+
+fn unsupported<T>(pos: Pos, what: &str) -> Flow<T> {
+    error(pos, format!("NotImplementedError: {what} isn't supported"))
+}
+
+// tk knows "#rgb" as well as "#rrggbb", and doesn't mind the case of names
+fn color_name(name: &str) -> Option<TurtleColor> {
+    if let Some(hex) = name.strip_prefix('#') {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        return match hex.len() {
+            3 => {
+                let doubled: String = hex.chars().flat_map(|c| [c, c]).collect();
+                Some(TurtleColor::from(format!("#{doubled}").as_str()))
+            }
+            6 => Some(TurtleColor::from(name)),
+            _ => None,
+        };
+    }
+    [name.to_string(), name.to_lowercase()]
+        .iter()
+        .map(|name| TurtleColor::from(name.as_str()))
+        .find(|color| !matches!(color, TurtleColor::CurrentColor))
+}
+
+// `(x, y)`, a Vec2D or a turtle, for goto(), towards() and distance()
+fn point(eval: &mut Eval, value: &Value, who: &str, pos: Pos) -> Flow<(f64, f64)> {
+    if let Value::Turtle(index) = value {
+        let other = eval.turtle(*index).pos();
+        return Ok((f64::from(other.x), f64::from(other.y)));
+    }
+    match value.items().as_deref() {
+        Some([x, y]) if !matches!(value, Value::Str(_)) => {
+            Ok((number(x, who, pos)?, number(y, who, pos)?))
+        }
+        _ => type_error(
+            pos,
+            format!(
+                "{who}() needs x and y, or a position, not '{}'",
+                value.type_name()
+            ),
+        ),
+    }
+}
+
+// the x and y arguments, where y may be left out if x is a position
+fn xy<const N: usize>(
+    eval: &mut Eval,
+    args: &crate::eval::Bound<N>,
+    who: &str,
+) -> Flow<(f64, f64)> {
+    match args.get(1) {
+        Some(y) => Ok((args.number(0)?, number(y, who, args.pos)?)),
+        None => point(eval, args.value(0), who, args.pos),
+    }
+}
+
+impl Eval<'_> {
+    // a color given as a name, as (r, g, b) or as r, g, b
+    fn color(&self, values: &[Value], pos: Pos) -> Flow<TurtleColor> {
+        let bad = |what: &str, value: &dyn std::fmt::Display| {
+            error(
+                pos,
+                format!("TurtleGraphicsError: bad color {what}: {value}"),
+            )
+        };
+        let rgb = match values {
+            [Value::Str(name)] => {
+                return color_name(name).map_or_else(|| bad("string", name), Ok);
+            }
+            [sequence] => match sequence.items() {
+                Some(rgb) if rgb.len() == 3 => rgb,
+                _ => return bad("sequence", sequence),
+            },
+            [_, _, _] => values.to_vec(),
+            _ => {
+                return type_error(
+                    pos,
+                    format!("a color is 1 or 3 values, not {}", values.len()),
+                );
+            }
+        };
+        let shown = Value::tuple(rgb.clone());
+        let mut parts = [0f32; 3];
+        for (part, value) in parts.iter_mut().zip(&rgb) {
+            let Some(n) = value
+                .as_float()
+                .filter(|n| (0. ..=self.colormode).contains(n))
+            else {
+                return bad("sequence", &shown);
+            };
+            #[allow(clippy::cast_possible_truncation)]
+            let n = (n / self.colormode) as f32;
+            *part = n;
+        }
+        Ok(TurtleColor::Color(parts[0], parts[1], parts[2]))
+    }
+
+    // Turtle(), Pen() and RawTurtle()
+    pub(crate) fn new_turtle(&mut self, args: Args) -> Flow<Value> {
+        let args = args.bind("Turtle", ["shape", "undobuffersize", "visible"], 0)?;
+        let mut turtle = self.turtle.hatch();
+        if let Some(shape) = args.get(0) {
+            turtle.shape(text(shape, "Turtle", args.pos)?.as_ref());
+        }
+        if let Some(size) = args.get(1) {
+            let size = int(size, "Turtle", args.pos)?;
+            turtle.setundobuffer(usize::try_from(size).ok());
+        }
+        if args.get(2).is_some() && !args.flag(2) {
+            turtle.hideturtle();
+        }
+        self.turtles.push(turtle);
+        Ok(Value::Turtle(self.turtles.len()))
+    }
+
+    #[allow(clippy::too_many_lines, clippy::cast_possible_truncation)]
+    pub(crate) fn turtle_call(
+        &mut self,
+        index: usize,
+        name: &'static str,
+        args: Args,
+    ) -> Flow<Value> {
+        let pos = args.pos;
+        match name {
+            "forward" | "fd" | "backward" | "back" | "bk" | "right" | "rt" | "left" | "lt"
+            | "setx" | "sety" | "setheading" | "seth" => {
+                let param = match name {
+                    "setx" => "x",
+                    "sety" => "y",
+                    "setheading" | "seth" => "to_angle",
+                    "right" | "rt" | "left" | "lt" => "angle",
+                    _ => "distance",
+                };
+                let n = args.bind(name, [param], 1)?.number(0)?;
+                let turtle = self.turtle(index);
+                match name {
+                    "forward" | "fd" => turtle.forward(n),
+                    "backward" | "back" | "bk" => turtle.backward(n),
+                    "right" | "rt" => turtle.right(n),
+                    "left" | "lt" => turtle.left(n),
+                    "setx" => turtle.setx(n),
+                    "sety" => turtle.sety(n as f32),
+                    _ => turtle.setheading(n),
+                }
+            }
+            "goto" | "setpos" | "setposition" => {
+                let args = args.bind(name, ["x", "y"], 1)?;
+                let (x, y) = xy(self, &args, name)?;
+                self.turtle(index).goto(x, y);
+            }
+            "teleport" => {
+                let args = args.bind("teleport", ["x", "y", "fill_gap"], 0)?;
+                let here = self.turtle(index).pos();
+                let x = args.opt_number(0)?.unwrap_or(f64::from(here.x));
+                let y = args.opt_number(1)?.unwrap_or(f64::from(here.y));
+                let fill_gap = args.flag(2);
+                self.turtle(index).teleport(x, y).with_fill_gap(fill_gap);
+            }
+            "home" => {
+                args.bind("home", [], 0)?;
+                self.turtle(index).home();
+            }
+            "circle" => {
+                let args = args.bind("circle", ["radius", "extent", "steps"], 1)?;
+                let radius = args.number(0)?;
+                let extent = args.opt_number(1)?;
+                let steps = args.get(2).map(|s| int(s, "circle", pos)).transpose()?;
+                // the circle's drawn when it's dropped
+                let circle = self.turtle(index).circle(radius);
+                let circle = match extent {
+                    Some(extent) => circle.with_extent(extent),
+                    None => circle,
+                };
+                match steps {
+                    Some(steps) => {
+                        drop(circle.with_steps(usize::try_from(steps.max(1)).unwrap_or(1)))
+                    }
+                    None => drop(circle),
+                }
+            }
+            "dot" => {
+                args.check_keywords("dot", &[])?;
+                // the size can be left out, or None, and the rest is the color
+                let (size, color) = match args.values.split_first() {
+                    Some((Value::None, color)) => (None, color),
+                    Some((size, color)) if size.as_float().is_some() => {
+                        (Some(number(size, "dot", pos)?), color)
+                    }
+                    _ => (None, args.values.as_slice()),
+                };
+                let color = if color.is_empty() {
+                    None
+                } else {
+                    Some(self.color(color, pos)?)
+                };
+                let dot = self.turtle(index).dot();
+                let dot = match size {
+                    Some(size) => dot.with_size(size),
+                    None => dot,
+                };
+                match color {
+                    Some(color) => drop(dot.with_color(color)),
+                    None => drop(dot),
+                }
+            }
+            "stamp" => {
+                args.bind("stamp", [], 0)?;
+                let id = self.turtle(index).stamp();
+                return Ok(Value::Int(i64::try_from(id).unwrap_or(i64::MAX)));
+            }
+            "clearstamp" => {
+                let id = args.bind("clearstamp", ["stampid"], 1)?.int(0)?;
+                if let Ok(id) = usize::try_from(id) {
+                    self.turtle(index).clearstamp(id);
+                }
+            }
+            "clearstamps" => {
+                let args = args.bind("clearstamps", ["n"], 0)?;
+                let n = args
+                    .get(0)
+                    .map(|n| int(n, "clearstamps", pos))
+                    .transpose()?;
+                let n = isize::try_from(n.unwrap_or(0)).unwrap_or(0);
+                self.turtle(index).clearstamps(n);
+            }
+            "undo" => {
+                args.bind("undo", [], 0)?;
+                self.turtle(index).undo();
+            }
+            "speed" => {
+                let args = args.bind("speed", ["speed"], 0)?;
+                let Some(speed) = args.get(0) else {
+                    return unsupported(pos, "asking for the speed");
+                };
+                match speed {
+                    Value::Str(name) => match name.as_ref() {
+                        "fastest" | "fast" | "normal" | "slow" | "slowest" => {
+                            self.turtle(index).speed(name.as_ref());
+                        }
+                        _ => return error(pos, format!("TurtleGraphicsError: bad speed {name}")),
+                    },
+                    speed => {
+                        // Python treats anything outside 0.5 to 10 as the fastest
+                        let speed = number(speed, "speed", pos)?;
+                        let speed = if (0.5..=10.5).contains(&speed) {
+                            speed.round() as u8
+                        } else {
+                            0
+                        };
+                        self.turtle(index).speed(speed);
+                    }
+                }
+            }
+            "position" | "pos" => {
+                args.bind(name, [], 0)?;
+                let here = self.turtle(index).pos();
+                return Ok(Value::Vec2D(f64::from(here.x), f64::from(here.y)));
+            }
+            "towards" | "distance" => {
+                let args = args.bind(name, ["x", "y"], 1)?;
+                let (x, y) = xy(self, &args, name)?;
+                let turtle = self.turtle(index);
+                return Ok(Value::Float(if name == "towards" {
+                    f64::from(turtle.towards(x, y))
+                } else {
+                    turtle.distance((x as f32, y as f32))
+                }));
+            }
+            "xcor" | "ycor" | "heading" => {
+                args.bind(name, [], 0)?;
+                let turtle = self.turtle(index);
+                return Ok(Value::Float(f64::from(match name {
+                    "xcor" => turtle.xcor(),
+                    "ycor" => turtle.ycor(),
+                    _ => turtle.heading(),
+                })));
+            }
+            "degrees" => {
+                let args = args.bind("degrees", ["fullcircle"], 0)?;
+                let full = args.opt_number(0)?.unwrap_or(360.);
+                self.turtle(index).degrees(full);
+            }
+            "radians" => {
+                args.bind("radians", [], 0)?;
+                self.turtle(index).radians();
+            }
+            "pendown" | "pd" | "down" => {
+                args.bind(name, [], 0)?;
+                self.turtle(index).pendown();
+            }
+            "penup" | "pu" | "up" => {
+                args.bind(name, [], 0)?;
+                self.turtle(index).penup();
+            }
+            "isdown" => {
+                args.bind("isdown", [], 0)?;
+                return Ok(Value::Bool(self.turtle(index).isdown()));
+            }
+            "pensize" | "width" => {
+                let args = args.bind(name, ["width"], 0)?;
+                let Some(width) = args.opt_number(0)? else {
+                    return unsupported(pos, format!("{name}() without arguments").as_str());
+                };
+                self.turtle(index).pensize(width);
+            }
+            "pencolor" | "fillcolor" => {
+                args.check_keywords(name, &[])?;
+                if args.values.is_empty() {
+                    return unsupported(pos, format!("{name}() without arguments").as_str());
+                }
+                let color = self.color(&args.values, pos)?;
+                if name == "pencolor" {
+                    self.turtle(index).pencolor(color);
+                } else {
+                    self.turtle(index).fillcolor(color);
+                }
+            }
+            "color" => {
+                args.check_keywords("color", &[])?;
+                let (pen, fill) = match args.values.as_slice() {
+                    [] => return unsupported(pos, "color() without arguments"),
+                    [pen, fill] => (
+                        self.color(std::slice::from_ref(pen), pos)?,
+                        self.color(std::slice::from_ref(fill), pos)?,
+                    ),
+                    values => {
+                        let color = self.color(values, pos)?;
+                        (color, color)
+                    }
+                };
+                let turtle = self.turtle(index);
+                turtle.pencolor(pen);
+                turtle.fillcolor(fill);
+            }
+            "begin_fill" | "end_fill" | "begin_poly" | "end_poly" | "reset" | "clear"
+            | "showturtle" | "st" | "hideturtle" | "ht" => {
+                args.bind(name, [], 0)?;
+                let turtle = self.turtle(index);
+                match name {
+                    "begin_fill" => turtle.begin_fill(),
+                    "end_fill" => turtle.end_fill(),
+                    "begin_poly" => turtle.begin_poly(),
+                    "end_poly" => turtle.end_poly(),
+                    "reset" => turtle.reset(),
+                    "clear" => turtle.clear(),
+                    "showturtle" | "st" => turtle.showturtle(),
+                    _ => turtle.hideturtle(),
+                }
+            }
+            "filling" | "isvisible" => {
+                args.bind(name, [], 0)?;
+                let turtle = self.turtle(index);
+                return Ok(Value::Bool(if name == "filling" {
+                    turtle.filling()
+                } else {
+                    turtle.isvisible()
+                }));
+            }
+            "write" => {
+                // where the text goes and what it looks like are left to the
+                // frontend
+                let args = args.bind("write", ["arg", "move", "align", "font"], 1)?;
+                let text = args.value(0).to_string();
+                self.turtle(index).write(&text);
+            }
+            "shape" => {
+                let args = args.bind("shape", ["name"], 0)?;
+                let Some(shape) = args.get(0) else {
+                    let shape = self.turtle(index).shape(TurtleShapeName::GetCurrent);
+                    return Ok(Value::str(shape));
+                };
+                let shape = text(shape, "shape", pos)?;
+                let turtle = self.turtle(index);
+                if !turtle.getshapes().iter().any(|s| **s == *shape) {
+                    return error(
+                        pos,
+                        format!("TurtleGraphicsError: There is no shape named {shape}"),
+                    );
+                }
+                turtle.shape(shape.as_ref());
+            }
+            "clone" => {
+                args.bind("clone", [], 0)?;
+                let clone = self.turtle(index).clone_turtle();
+                self.turtles.push(clone);
+                return Ok(Value::Turtle(self.turtles.len()));
+            }
+            "getturtle" | "getpen" => {
+                args.bind(name, [], 0)?;
+                return Ok(Value::Turtle(index));
+            }
+            "getscreen" => {
+                args.bind("getscreen", [], 0)?;
+                return Ok(Value::Screen);
+            }
+            "get_poly" => {
+                args.bind("get_poly", [], 0)?;
+                let points = self.turtle(index).get_poly();
+                return Ok(Value::tuple(
+                    points
+                        .iter()
+                        .map(|[x, y]| Value::Vec2D(f64::from(*x), f64::from(*y)))
+                        .collect(),
+                ));
+            }
+            "setundobuffer" => {
+                let args = args.bind("setundobuffer", ["size"], 1)?;
+                let size = args.get(0).map(|s| int(s, name, pos)).transpose()?;
+                let size = size
+                    .and_then(|s| usize::try_from(s).ok())
+                    .filter(|&s| s > 0);
+                self.turtle(index).setundobuffer(size);
+            }
+            "undobufferentries" => {
+                args.bind("undobufferentries", [], 0)?;
+                let entries = self.turtle(index).undobufferentries();
+                return Ok(Value::Int(i64::try_from(entries).unwrap_or(i64::MAX)));
+            }
+            _ => unreachable!("no turtle method {name}"),
+        }
+        Ok(Value::None)
+    }
+
+    // every turtle the script knows about, starting with its own
+    fn all_turtles(&mut self) -> impl Iterator<Item = &mut slowpoke::Turtle> {
+        std::iter::once(&mut *self.turtle).chain(self.turtles.iter_mut())
+    }
+
+    #[allow(clippy::too_many_lines)]
+    pub(crate) fn screen_call(&mut self, name: &'static str, args: Args) -> Flow<Value> {
+        let pos = args.pos;
+        match name {
+            "setup" => {
+                // fractions of the display and window placement don't apply
+                let args = args.bind("setup", ["width", "height", "startx", "starty"], 0)?;
+                let [width, height] = self.turtle.getscreensize();
+                let pixels = |value: Option<&Value>, current: isize| {
+                    value
+                        .and_then(Value::as_int)
+                        .filter(|&n| n > 1)
+                        .and_then(|n| isize::try_from(n).ok())
+                        .unwrap_or(current)
+                };
+                let size = [pixels(args.get(0), width), pixels(args.get(1), height)];
+                if size != [width, height] {
+                    self.turtle.screensize(size);
+                }
+            }
+            "screensize" => {
+                let args = args.bind("screensize", ["canvwidth", "canvheight", "bg"], 0)?;
+                let [width, height] = self.turtle.getscreensize();
+                if args.get(0).is_none() && args.get(1).is_none() && args.get(2).is_none() {
+                    return Ok(Value::tuple(vec![
+                        Value::Int(width as i64),
+                        Value::Int(height as i64),
+                    ]));
+                }
+                let mut size = [width, height];
+                for (i, side) in size.iter_mut().enumerate() {
+                    if let Some(n) = args.get(i) {
+                        *side = isize::try_from(int(n, "screensize", pos)?).unwrap_or(*side);
+                    }
+                }
+                if let Some(bg) = args.get(2) {
+                    let color = self.color(std::slice::from_ref(bg), pos)?;
+                    self.turtle.bgcolor(color);
+                }
+                self.turtle.screensize(size);
+            }
+            "bgcolor" => {
+                args.check_keywords("bgcolor", &[])?;
+                if args.values.is_empty() {
+                    return unsupported(pos, "bgcolor() without arguments");
+                }
+                let color = self.color(&args.values, pos)?;
+                self.turtle.bgcolor(color);
+            }
+            "title" => {
+                let title = args.bind("title", ["titlestring"], 1)?.value(0).to_string();
+                self.turtle.title(title);
+            }
+            "tracer" => {
+                let args = args.bind("tracer", ["n", "delay"], 0)?;
+                let Some(n) = args.get(0) else {
+                    return unsupported(pos, "tracer() without arguments");
+                };
+                let trace = n.truthy();
+                for turtle in self.all_turtles() {
+                    turtle.tracer(trace);
+                }
+            }
+            "update" => {
+                args.bind("update", [], 0)?;
+            }
+            "delay" => {
+                args.bind("delay", ["delay"], 0)?;
+            }
+            "mode" => {
+                let args = args.bind("mode", ["mode"], 0)?;
+                let Some(mode) = args.get(0) else {
+                    return Ok(Value::str(match self.turtle.getmode() {
+                        TurtleMode::Standard => "standard",
+                        TurtleMode::Logo => "logo",
+                        TurtleMode::World => "world",
+                    }));
+                };
+                let mode = text(mode, "mode", pos)?;
+                if !["standard", "logo", "world"].contains(&mode.as_ref()) {
+                    return error(
+                        pos,
+                        format!("TurtleGraphicsError: No turtle-graphics-mode {mode}"),
+                    );
+                }
+                self.turtle.mode(mode.as_ref());
+            }
+            "setworldcoordinates" => {
+                let args = args.bind("setworldcoordinates", ["llx", "lly", "urx", "ury"], 4)?;
+                let [llx, lly, urx, ury] = [
+                    args.number(0)?,
+                    args.number(1)?,
+                    args.number(2)?,
+                    args.number(3)?,
+                ];
+                if let Err(e) = self.turtle.setworldcoordinates(llx, lly, urx, ury) {
+                    return error(pos, format!("TurtleGraphicsError: {e}"));
+                }
+            }
+            "colormode" => {
+                let args = args.bind("colormode", ["cmode"], 0)?;
+                let Some(mode) = args.opt_number(0)? else {
+                    return Ok(if self.colormode == 1. {
+                        Value::Float(1.)
+                    } else {
+                        Value::Int(255)
+                    });
+                };
+                if mode != 1. && mode != 255. {
+                    return error(pos, "TurtleGraphicsError: bad color mode");
+                }
+                self.colormode = mode;
+            }
+            "mainloop" | "done" | "exitonclick" | "bye" => {
+                args.bind(name, [], 0)?;
+                match name {
+                    "exitonclick" => self.turtle.exitonclick(),
+                    "bye" => self.turtle.bye(),
+                    _ => self.turtle.done(),
+                }
+            }
+            "window_width" | "window_height" => {
+                args.bind(name, [], 0)?;
+                let size = if name == "window_width" {
+                    self.turtle.window_width()
+                } else {
+                    self.turtle.window_height()
+                };
+                return Ok(Value::Int(size as i64));
+            }
+            "textinput" => {
+                let args = args.bind("textinput", ["title", "prompt"], 2)?;
+                let (title, prompt) = (args.text(0)?, args.text(1)?);
+                return Ok(match self.turtle.textinput(&title, &prompt) {
+                    Some(text) => Value::str(text),
+                    None => Value::None,
+                });
+            }
+            "numinput" => {
+                let args = args.bind(
+                    "numinput",
+                    ["title", "prompt", "default", "minval", "maxval"],
+                    2,
+                )?;
+                let (title, prompt) = (args.text(0)?, args.text(1)?);
+                let (low, high) = (args.opt_number(3)?, args.opt_number(4)?);
+                let Some(n) = self.turtle.numinput(&title, &prompt) else {
+                    return Ok(Value::None);
+                };
+                // there's no way to ask again, so keep the answer in range
+                let mut n = f64::from(n);
+                if let Some(low) = low {
+                    n = n.max(low);
+                }
+                if let Some(high) = high {
+                    n = n.min(high);
+                }
+                return Ok(Value::Float(n));
+            }
+            "turtles" => {
+                args.bind("turtles", [], 0)?;
+                return Ok(Value::list(
+                    (0..=self.turtles.len()).map(Value::Turtle).collect(),
+                ));
+            }
+            "clearscreen" | "clear" => {
+                args.bind(name, [], 0)?;
+                self.turtle.clearscreen();
+            }
+            "resetscreen" | "reset" => {
+                args.bind(name, [], 0)?;
+                for turtle in self.all_turtles() {
+                    turtle.reset();
+                }
+            }
+            "register_shape" | "addshape" => {
+                let args = args.bind(name, ["name", "shape"], 1)?;
+                let shape_name = args.text(0)?;
+                let Some(shape) = args.get(1) else {
+                    return unsupported(pos, "an image as a shape");
+                };
+                let mut points = Vec::new();
+                for point in crate::eval::iterate(shape, pos)? {
+                    let (x, y) = match point.items().as_deref() {
+                        Some([x, y]) => (number(x, name, pos)?, number(y, name, pos)?),
+                        _ => return type_error(pos, "a shape is a sequence of (x, y) pairs"),
+                    };
+                    points.push([x as f32, y as f32]);
+                }
+                self.turtle.register_shape(shape_name.as_ref(), points);
+            }
+            "getshapes" => {
+                args.bind("getshapes", [], 0)?;
+                let mut shapes = self.turtle.getshapes();
+                shapes.sort();
+                return Ok(Value::list(shapes.into_iter().map(Value::str).collect()));
+            }
+            "bgpic" => {
+                let args = args.bind("bgpic", ["picname"], 0)?;
+                let Some(picture) = args.get(0) else {
+                    return unsupported(pos, "bgpic() without arguments");
+                };
+                let picture = text(picture, "bgpic", pos)?;
+                if !Path::new(picture.as_ref()).is_file() {
+                    return error(pos, format!("FileNotFoundError: couldn't open '{picture}'"));
+                }
+                self.turtle.bgpic(picture.as_ref());
+            }
+            _ => unreachable!("no screen method {name}"),
+        }
+        Ok(Value::None)
+    }
+}
@@ -0,0 +1,329 @@
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use crate::parser::Function;
+
+// The modules a script can import
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Module {
+    Turtle,
+    Math,
+    Random,
+    Time,
+}
+
+impl Module {
+    pub(crate) fn named(name: &str) -> Option<Self> {
+        match name {
+            "turtle" => Some(Self::Turtle),
+            "math" => Some(Self::Math),
+            "random" => Some(Self::Random),
+            "time" => Some(Self::Time),
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn name(self) -> &'static str {
+        match self {
+            Self::Turtle => "turtle",
+            Self::Math => "math",
+            Self::Random => "random",
+            Self::Time => "time",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Closure {
+    pub function: Rc<Function>,
+    pub defaults: Vec<Option<Value>>, // worked out when the function was defined
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Value {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(Rc<str>),
+    Tuple(Rc<Vec<Value>>),
+    List(Rc<RefCell<Vec<Value>>>),
+    Vec2D(f64, f64),                  // what positions are in the turtle module
+    Range(i64, i64, i64),             // start, stop and step
+    Function(Rc<Closure>),            // defined by the script
+    Builtin(&'static str),            // such as "print" or "math.sqrt"
+    Method(Box<Value>, &'static str), // a method of a turtle, the screen, a list or a string
+    Module(Module),
+    Turtle(usize), // 0 is the turtle the script was given
+    Screen,
+}
+
+impl Value {
+    pub(crate) fn str(text: impl Into<Rc<str>>) -> Self {
+        Self::Str(text.into())
+    }
+
+    pub(crate) fn tuple(items: Vec<Value>) -> Self {
+        Self::Tuple(Rc::new(items))
+    }
+
+    pub(crate) fn list(items: Vec<Value>) -> Self {
+        Self::List(Rc::new(RefCell::new(items)))
+    }
+
+    pub(crate) const fn type_name(&self) -> &'static str {
+        match self {
+            Self::None => "NoneType",
+            Self::Bool(_) => "bool",
+            Self::Int(_) => "int",
+            Self::Float(_) => "float",
+            Self::Str(_) => "str",
+            Self::Tuple(_) => "tuple",
+            Self::List(_) => "list",
+            Self::Vec2D(..) => "Vec2D",
+            Self::Range(..) => "range",
+            Self::Function(_) => "function",
+            Self::Builtin(_) => "builtin_function_or_method",
+            Self::Method(..) => "method",
+            Self::Module(_) => "module",
+            Self::Turtle(_) => "Turtle",
+            Self::Screen => "_Screen",
+        }
+    }
+
+    pub(crate) fn truthy(&self) -> bool {
+        match self {
+            Self::None => false,
+            Self::Bool(b) => *b,
+            Self::Int(n) => *n != 0,
+            Self::Float(n) => *n != 0.,
+            Self::Str(s) => !s.is_empty(),
+            Self::Tuple(items) => !items.is_empty(),
+            Self::List(items) => !items.borrow().is_empty(),
+            Self::Range(..) => range_len(self) > 0,
+            _ => true,
+        }
+    }
+
+    // ints, floats and bools can all be used as numbers
+    pub(crate) fn as_float(&self) -> Option<f64> {
+        match self {
+            #[allow(clippy::cast_precision_loss)]
+            Self::Int(n) => Some(*n as f64),
+            Self::Float(n) => Some(*n),
+            Self::Bool(b) => Some(f64::from(u8::from(*b))),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(n) => Some(*n),
+            Self::Bool(b) => Some(i64::from(*b)),
+            _ => None,
+        }
+    }
+
+    // the items of a sequence, for unpacking and the like
+    pub(crate) fn items(&self) -> Option<Vec<Value>> {
+        match self {
+            Self::Tuple(items) => Some(items.to_vec()),
+            Self::List(items) => Some(items.borrow().clone()),
+            Self::Vec2D(x, y) => Some(vec![Self::Float(*x), Self::Float(*y)]),
+            Self::Str(s) => Some(s.chars().map(|c| Self::str(c.to_string())).collect()),
+            Self::Range(start, _, step) => {
+                let len = range_len(self);
+                Some((0..len).map(|i| Self::Int(start + i * step)).collect())
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn equals(&self, other: &Self) -> bool {
+        if let (Some(a), Some(b)) = (self.as_int(), other.as_int()) {
+            return a == b;
+        }
+        if let (Some(a), Some(b)) = (self.as_float(), other.as_float()) {
+            return a == b;
+        }
+        match (self, other) {
+            (Self::None, Self::None) | (Self::Screen, Self::Screen) => true,
+            (Self::Str(a), Self::Str(b)) => a == b,
+            (Self::Tuple(_) | Self::Vec2D(..), Self::Tuple(_) | Self::Vec2D(..))
+            | (Self::List(_), Self::List(_))
+            | (Self::Range(..), Self::Range(..)) => {
+                let (a, b) = (
+                    self.items().unwrap_or_default(),
+                    other.items().unwrap_or_default(),
+                );
+                a.len() == b.len() && a.iter().zip(&b).all(|(a, b)| a.equals(b))
+            }
+            (Self::Function(a), Self::Function(b)) => Rc::ptr_eq(a, b),
+            (Self::Builtin(a), Self::Builtin(b)) => a == b,
+            (Self::Method(a, m), Self::Method(b, n)) => m == n && a.is(b),
+            (Self::Module(a), Self::Module(b)) => a == b,
+            (Self::Turtle(a), Self::Turtle(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    // `is`: lists are only the same if they're the same list
+    pub(crate) fn is(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::List(a), Self::List(b)) => Rc::ptr_eq(a, b),
+            (Self::Tuple(a), Self::Tuple(b)) => Rc::ptr_eq(a, b),
+            (Self::Int(_) | Self::Float(_), Self::Bool(_))
+            | (Self::Bool(_), Self::Int(_) | Self::Float(_)) => false,
+            _ => self.equals(other),
+        }
+    }
+
+    /// How `repr()` shows a value, which is how it appears inside a list
+    pub(crate) fn repr(&self) -> String {
+        match self {
+            Self::Str(s) => {
+                let quote = if s.contains('\'') && !s.contains('"') {
+                    '"'
+                } else {
+                    '\''
+                };
+                let mut text = String::from(quote);
+                for c in s.chars() {
+                    match c {
+                        '\n' => text.push_str("\\n"),
+                        '\t' => text.push_str("\\t"),
+                        '\r' => text.push_str("\\r"),
+                        '\\' => text.push_str("\\\\"),
+                        c if c == quote => {
+                            text.push('\\');
+                            text.push(c);
+                        }
+                        c => text.push(c),
+                    }
+                }
+                text.push(quote);
+                text
+            }
+            value => value.to_string(),
+        }
+    }
+}
+
+pub(crate) fn range_len(range: &Value) -> i64 {
+    let Value::Range(start, stop, step) = *range else {
+        return 0;
+    };
+    let len = if step > 0 {
+        (stop - start + step - 1) / step
+    } else {
+        (start - stop - step - 1) / -step
+    };
+    len.max(0)
+}
+
+// the shortest text which reads back as the same float, as Python writes it
+pub(crate) fn float_repr(n: f64) -> String {
+    if n.is_nan() {
+        return "nan".into();
+    }
+    if n.is_infinite() {
+        return if n > 0. { "inf" } else { "-inf" }.into();
+    }
+    if n != 0. && (n.abs() >= 1e16 || n.abs() < 1e-4) {
+        return python_exponent(&format!("{n:e}"));
+    }
+    if n.fract() == 0. {
+        return format!("{n:.1}");
+    }
+    n.to_string()
+}
+
+// Rust writes 1.5e-7 where Python writes 1.5e-07
+pub(crate) fn python_exponent(text: &str) -> String {
+    let Some((mantissa, exponent)) = text.split_once('e') else {
+        return text.into();
+    };
+    let (sign, digits) = match exponent.strip_prefix('-') {
+        Some(digits) => ('-', digits),
+        None => ('+', exponent),
+    };
+    format!("{mantissa}e{sign}{digits:0>2}")
+}
+
+fn join(f: &mut std::fmt::Formatter<'_>, items: &[Value]) -> std::fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", item.repr())?;
+    }
+    Ok(())
+}
+
+/// How `str()` and `print()` show a value
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Bool(true) => write!(f, "True"),
+            Self::Bool(false) => write!(f, "False"),
+            Self::Int(n) => write!(f, "{n}"),
+            Self::Float(n) => write!(f, "{}", float_repr(*n)),
+            Self::Str(s) => write!(f, "{s}"),
+            Self::Tuple(items) if items.len() == 1 => write!(f, "({},)", items[0].repr()),
+            Self::Tuple(items) => {
+                write!(f, "(")?;
+                join(f, items)?;
+                write!(f, ")")
+            }
+            Self::List(items) => {
+                write!(f, "[")?;
+                join(f, &items.borrow())?;
+                write!(f, "]")
+            }
+            Self::Vec2D(x, y) => write!(f, "({x:.2},{y:.2})"),
+            Self::Range(start, stop, 1) => write!(f, "range({start}, {stop})"),
+            Self::Range(start, stop, step) => write!(f, "range({start}, {stop}, {step})"),
+            Self::Function(closure) => write!(f, "<function {}>", closure.function.name),
+            Self::Builtin(name) => write!(f, "<built-in function {name}>"),
+            Self::Method(receiver, name) => {
+                write!(f, "<bound method {name} of {}>", receiver.repr())
+            }
+            Self::Module(module) => write!(f, "<module '{}'>", module.name()),
+            Self::Turtle(_) => write!(f, "<turtle.Turtle object>"),
+            Self::Screen => write!(f, "<turtle._Screen object>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shown_as_python_shows_them() {
+        let list = Value::list(vec![
+            Value::Int(1),
+            Value::Float(2.),
+            Value::Float(0.1 + 0.2),
+            Value::str("it's"),
+            Value::tuple(vec![Value::None]),
+            Value::Bool(true),
+        ]);
+        assert_eq!(
+            list.to_string(),
+            "[1, 2.0, 0.30000000000000004, \"it's\", (None,), True]"
+        );
+        assert_eq!(Value::Vec2D(10., -2.5).to_string(), "(10.00,-2.50)");
+        assert_eq!(Value::Float(1.5e-7).to_string(), "1.5e-07");
+        assert_eq!(Value::Float(1e20).to_string(), "1e+20");
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(range_len(&Value::Range(0, 10, 3)), 4);
+        assert_eq!(range_len(&Value::Range(10, 0, -3)), 4);
+        assert_eq!(range_len(&Value::Range(5, 0, 1)), 0);
+        assert!(Value::Range(1, 4, 1).equals(&Value::Range(1, 4, 1)));
+        assert!(!Value::Range(1, 4, 1).equals(&Value::Range(1, 5, 1)));
+    }
+}
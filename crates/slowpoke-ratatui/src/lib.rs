@@ -14,7 +14,7 @@ use crossterm::{
 use lyon_tessellation::geom::{euclid::default::Transform2D, Angle, Point};
 use ratatui::{
     crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
-    layout::{Position, Rect},
+    layout::{Constraint, Layout, Position, Rect},
     style::{Color, Style},
     symbols::Marker,
     text::Line as TextLine,
@@ -27,17 +27,57 @@ use ratatui::{
 
 pub type Slowpoke = SlowpokeLib<RatatuiFramework>;
 pub type Turtle = slowpoke::Turtle;
+pub use slowpoke::{FillStyle, TurtleShapeName};
+
+// so that side panels are written against the same version we draw with
+pub use ratatui;
 
 use slowpoke::{
-    CirclePos, DrawCommand, GetPolyPath, Handler, IndividualTurtle, PopupData, PopupID,
-    SlowpokeLib, TurtleColor, TurtleEvent, TurtleFlags, TurtleGui, TurtleID, TurtleTask, TurtleUI,
-    TurtleUserInterface,
+    CirclePos, DrawCommand, EventResult, Handler, IndividualTurtle, LineDash, LineSegment,
+    LineStyle, PopupData, PopupID, SlowpokeLib, Triangle, TurtleColor, TurtleEvent, TurtleFlags,
+    TurtleGui, TurtleID, TurtleTask, TurtleUI, TurtleUserInterface,
 };
 
 #[derive(Debug)]
 pub struct RatatuiFramework {
     tt: TurtleTask,
     handler: Handler<RatatuiUI, RatatuiInternal>,
+    panel: Option<Box<dyn SidePanel>>,
+}
+
+/// Something drawn to the right of the turtles, such as a prompt. While no
+/// popup is open, it gets every key that's pressed, so the turtles' key
+/// handlers don't run. Ctrl-Q still closes the window.
+pub trait SidePanel: Send + std::fmt::Debug {
+    /// How many columns the panel takes up
+    fn width(&self) -> u16 {
+        40
+    }
+
+    /// A key was pressed (or is repeating)
+    fn key(&mut self, key: KeyEvent);
+
+    /// Whether the panel needs to be drawn again. This is asked on every tick,
+    /// so it's where the panel can pick up anything sent from other threads.
+    fn changed(&mut self) -> bool;
+
+    fn draw(&self, frame: &mut Frame, area: Rect);
+}
+
+/// What a window can show besides the turtles, given to
+/// `Slowpoke::with_options()`
+#[derive(Debug, Default)]
+pub struct RatatuiOptions {
+    side_panel: Option<Box<dyn SidePanel>>,
+}
+
+impl RatatuiOptions {
+    /// Show `panel` beside the turtles
+    #[must_use]
+    pub fn with_side_panel<P: SidePanel + 'static>(mut self, panel: P) -> Self {
+        self.side_panel = Some(Box::new(panel));
+        self
+    }
 }
 
 struct CircleDrawData {
@@ -67,8 +107,26 @@ impl RatatuiUI {
         text_draw_cmds
     }
 
+    // Braille dots are much coarser than pixels, so caps and joins don't show;
+    // dashes are split up here, since ratatui can only draw solid lines
+    fn push_lines(&mut self, lines: &[LineSegment], style: &LineStyle, width: f32, color: Color) {
+        let width = if style.dash == LineDash::Dotted {
+            width.max(4.)
+        } else {
+            width
+        };
+        for line in style.dashes(width, lines) {
+            self.drawing.push(RatatuiDrawCmd::line(
+                (f64::from(line.start.x), f64::from(line.start.y)),
+                (f64::from(line.end.x), f64::from(line.end.y)),
+                color,
+            ));
+        }
+    }
+
     fn convert(&mut self, pct: f32, cmds: &[DrawCommand], turtle: &IndividualTurtle<RatatuiUI>) {
-        let mut _penwidth = 1f32;
+        let mut penwidth = 1f32;
+        let mut linestyle = LineStyle::default();
         let pct = f64::from(pct);
 
         let mut pencolor = RatatuiColor(Color::Rgb(0, 0, 0));
@@ -97,29 +155,33 @@ impl RatatuiUI {
                         (tpos[0], tpos[1])
                     };
                     if l.pen_down {
-                        self.drawing.push(RatatuiDrawCmd::line(
-                            (begin_x, begin_y),
-                            (end_x, end_y),
-                            (&pencolor).into(),
-                        ));
+                        let line = LineSegment {
+                            start: Point::new(begin_x as f32, begin_y as f32),
+                            end: Point::new(end_x as f32, end_y as f32),
+                        };
+                        self.push_lines(&[line], &linestyle, penwidth, (&pencolor).into());
                     }
                 }
                 DrawCommand::Filler | DrawCommand::Filled(_) => {}
                 DrawCommand::SetPenColor(pc) => pencolor = pc.into(),
-                DrawCommand::SetPenWidth(pw) => _penwidth = *pw,
+                DrawCommand::SetPenWidth(pw) => penwidth = *pw,
+                DrawCommand::SetLineStyle(style) => linestyle = style.clone(),
                 DrawCommand::SetFillColor(fc) => fillcolor = fc.into(),
                 DrawCommand::SetPosition(pos) => {
                     tpos = [pos.x.clamp_to(), pos.y.clamp_to()];
                 }
-                DrawCommand::DrawPolygon(p) => {
-                    let path = p.get_path();
-                    for triangle in path.as_slice().windows(3) {
+                DrawCommand::DrawPolygon(p, style) => {
+                    // the triangles come from lyon, which follows the polygon's
+                    // fill rule and holes
+                    // gradients and hatching would be lost on the braille canvas
+                    let color: RatatuiColor = style.flat_color(fillcolor.into()).into();
+                    for triangle in p.triangles() {
                         let lines = get_fill_lines(triangle);
                         for line in lines {
                             self.drawing.push(RatatuiDrawCmd::line(
                                 (line.0.x.into(), line.0.y.into()),
                                 (line.1.x.into(), line.1.y.into()),
-                                (&fillcolor).into(),
+                                (&color).into(),
                             ));
                         }
                     }
@@ -144,9 +206,7 @@ impl RatatuiUI {
                     let angle = Angle::degrees(*angle);
                     let pos = [pos.x, pos.y];
                     let transform = Transform2D::rotation(angle).then_translate(pos.into());
-                    let path = polygon.get_path();
-
-                    for triangle in path.as_slice().windows(3) {
+                    for triangle in polygon.triangles() {
                         let lines = get_fill_lines(triangle);
                         for pair in lines {
                             let p1 = pair.0;
@@ -169,13 +229,14 @@ impl RatatuiUI {
                     } = Self::circle_path(last_element, pct.clamp_to(), points);
                     tpos = [position[0] as f64, position[1] as f64];
                     trot = angle;
-                    for line in line_list {
-                        self.drawing.push(RatatuiDrawCmd::line(
-                            (line.0[0] as f64, line.0[1] as f64),
-                            (line.1[0] as f64, line.1[1] as f64),
-                            (&pencolor).into(),
-                        ));
-                    }
+                    let line_list: Vec<_> = line_list
+                        .into_iter()
+                        .map(|(start, end)| LineSegment {
+                            start: start.into(),
+                            end: end.into(),
+                        })
+                        .collect();
+                    self.push_lines(&line_list, &linestyle, penwidth, (&pencolor).into());
                 }
                 DrawCommand::Text(pos, text) => {
                     self.drawing
@@ -187,7 +248,8 @@ impl RatatuiUI {
                 | DrawCommand::BeginFill
                 | DrawCommand::EndFill
                 | DrawCommand::BeginPoly
-                | DrawCommand::EndPoly => panic!("invalid draw command in gui"),
+                | DrawCommand::EndPoly
+                | DrawCommand::NextContour => panic!("invalid draw command in gui"),
             }
         }
 
@@ -198,7 +260,7 @@ impl RatatuiUI {
             let tpos = [tpos[0] as f32, tpos[1] as f32];
             let transform = Transform2D::rotation(angle).then_translate(tpos.into());
             for poly in &turtle.turtle_shape.poly {
-                for pair in poly.polygon.path.as_slice().windows(2) {
+                for pair in poly.polygon.points().windows(2) {
                     let p1 = pair[0];
                     let p2 = pair[1];
                     let start = transform.transform_point(p1.into());
@@ -320,7 +382,9 @@ impl RatatuiDrawCmd {
 }
 
 impl TurtleUserInterface for RatatuiFramework {
-    fn start(mut flags: TurtleFlags) {
+    type Options = RatatuiOptions;
+
+    fn start(mut flags: TurtleFlags, options: RatatuiOptions) {
         fn new(flags: &TurtleFlags) -> Handler<RatatuiUI, RatatuiInternal> {
             let mut stdout = std::io::stdout();
             let _ = execute!(
@@ -351,13 +415,18 @@ impl TurtleUserInterface for RatatuiFramework {
         tt.run_turtle(func.unwrap());
 
         let tui = new(&flags);
-        let mut rata = Self { tt, handler: tui };
+        let mut rata = Self {
+            tt,
+            handler: tui,
+            panel: options.side_panel,
+        };
         let _ = rata.run();
     }
 }
 
 impl RatatuiFramework {
-    fn run(&mut self) -> Result<Event, std::io::Error> {
+    // runs until the turtles have shut down, or the terminal fails
+    fn run(&mut self) -> Result<(), std::io::Error> {
         let tick_rate = Duration::from_millis(1000 / 60);
         let mut last_tick = Instant::now();
         let mut terminal = ratatui::init();
@@ -376,6 +445,7 @@ impl RatatuiFramework {
         let mut needs_redraw = true;
         loop {
             let size = terminal.size().expect("could not get screen size");
+            let canvas = self.canvas_area(Rect::new(0, 0, size.width, size.height));
 
             if needs_redraw || self.handler.screen.do_redraw {
                 if let Err(e) = terminal.draw(|frame| self.draw(frame)) {
@@ -393,7 +463,7 @@ impl RatatuiFramework {
                     match event {
                         Event::Key(key) => {
                             if self.handle_key_event(key) {
-                                break Ok(event);
+                                break Ok(());
                             }
                         }
                         Event::Mouse(me) => {
@@ -403,19 +473,24 @@ impl RatatuiFramework {
                             //
                             // coords = ((actual display) / (size coords)) * (mevent coords)
 
-                            let mouse_x = me.column as f32 - size.width as f32 / 2.;
-                            let mouse_y = me.row as f32 - size.height as f32 / 2.;
+                            let mouse_x =
+                                me.column as f32 - canvas.x as f32 - canvas.width as f32 / 2.;
+                            let mouse_y =
+                                me.row as f32 - canvas.y as f32 - canvas.height as f32 / 2.;
 
-                            let x = (self.handler.screen.size[0] * mouse_x) / size.width as f32;
-                            let y = (self.handler.screen.size[1] * mouse_y) / size.height as f32;
+                            let x = (self.handler.screen.size[0] * mouse_x) / canvas.width as f32;
+                            let y = (self.handler.screen.size[1] * mouse_y) / canvas.height as f32;
 
                             match me.kind {
                                 MouseEventKind::Down(_button) => {
-                                    let _ = self.tt.handle_event(
+                                    if self.tt.handle_event(
                                         None,
                                         None,
                                         &TurtleEvent::MousePress(x, -y),
-                                    );
+                                    ) == EventResult::ShutDown
+                                    {
+                                        break Ok(());
+                                    }
                                 }
                                 MouseEventKind::Up(_button) => {
                                     let _ = self.tt.handle_event(
@@ -459,7 +534,12 @@ impl RatatuiFramework {
             }
 
             if last_tick.elapsed() >= tick_rate {
-                self.tt.tick(&mut self.handler);
+                if self.tt.tick(&mut self.handler) == EventResult::ShutDown {
+                    break Ok(());
+                }
+                if let Some(panel) = &mut self.panel {
+                    needs_redraw |= panel.changed();
+                }
 
                 // let mut done = true;
                 for (tid, turtle) in &mut self.handler.turtle {
@@ -478,12 +558,37 @@ impl RatatuiFramework {
         }
     }
 
+    // the turtles' part of the screen, with the side panel (if any) to its right
+    fn canvas_area(&self, area: Rect) -> Rect {
+        match &self.panel {
+            Some(panel) => {
+                Layout::horizontal([Constraint::Fill(1), Constraint::Length(panel.width())])
+                    .split(area)[0]
+            }
+            None => area,
+        }
+    }
+
     fn handle_key_event(&mut self, key: KeyEvent) -> bool {
+        let ctrl_q = key.code == KeyCode::Char('q')
+            && (key.modifiers & KeyModifiers::CONTROL) == KeyModifiers::CONTROL;
+        if !ctrl_q
+            && self.handler.popups.is_empty()
+            && let Some(panel) = &mut self.panel
+        {
+            if key.kind != KeyEventKind::Release {
+                panel.key(key);
+            }
+            return false;
+        }
+
         match key.code {
             KeyCode::Char(ch) => {
-                // Ctrl-Q will exit the program no matter what
+                // Ctrl-Q closes the window; pressing it twice will exit the
+                // program no matter what
                 if ch == 'q' && (key.modifiers & KeyModifiers::CONTROL) == KeyModifiers::CONTROL {
-                    return true;
+                    return self.tt.handle_event(None, None, &TurtleEvent::WindowClose)
+                        == EventResult::ShutDown;
                 }
                 if self.handler.popups.is_empty() {
                     let e = if matches!(key.kind, KeyEventKind::Press) {
@@ -547,7 +652,16 @@ impl RatatuiFramework {
         let x_bounds = [-(width / 2.) as f64, (width / 2.) as f64];
         let y_bounds = [-(height / 2.) as f64, (height / 2.) as f64];
 
-        let area = frame.area();
+        let area = self.canvas_area(frame.area());
+        if let Some(panel) = &self.panel {
+            let panel_area = Rect {
+                x: area.right(),
+                width: frame.area().width - area.width,
+                ..area
+            };
+            panel.draw(frame, panel_area);
+        }
+
         let widget = Canvas::default()
             .background_color(self.handler.screen.bgcolor)
             .block(Block::bordered().title(self.handler.title.clone()))
@@ -707,8 +821,8 @@ impl From<RatatuiColor> for ratatui::style::Color {
  * This is a Rust interpretation of the triangle fill algorithm taken from
  * Gabriel Gambetta: https://gabrielgambetta.com/computer-graphics-from-scratch/07-filled-triangles.html
  */
-fn get_fill_lines(points: &[(Point<f32>, Point<f32>)]) -> Vec<(Point<f32>, Point<f32>)> {
-    let mut triangle = [points[0].0, points[1].0, points[2].0];
+fn get_fill_lines(triangle: &Triangle) -> Vec<(Point<f32>, Point<f32>)> {
+    let mut triangle = *triangle;
     if triangle[1].y < triangle[0].y {
         triangle.swap(0, 1);
     }
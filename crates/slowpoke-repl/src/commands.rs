@@ -0,0 +1,191 @@
+// The commands the prompt understands, and how to use them
+
+#[derive(Debug)]
+pub(crate) struct Command {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub usage: &'static str, // the arguments, for `help`
+    pub help: &'static str,
+}
+
+macro_rules! commands {
+    ($($name:literal $([$($alias:literal),*])? $usage:literal $help:literal,)*) => {
+        &[$(Command {
+            name: $name,
+            aliases: &[$($($alias),*)?],
+            usage: $usage,
+            help: $help,
+        },)*]
+    };
+}
+
+const COMMANDS: &[Command] = commands! {
+    // moving
+    "forward" ["fd"] "DISTANCE" "move forward",
+    "backward" ["bk", "back"] "DISTANCE" "move backward",
+    "right" ["rt"] "ANGLE" "turn right",
+    "left" ["lt"] "ANGLE" "turn left",
+    "goto" ["setpos", "setposition"] "X Y" "move to a position",
+    "teleport" "X Y" "jump to a position without drawing",
+    "setx" "X" "move across to X",
+    "sety" "Y" "move up or down to Y",
+    "setheading" ["seth"] "ANGLE" "face a direction",
+    "home" "" "go back to the middle, facing the start direction",
+    "circle" "RADIUS [EXTENT [STEPS]]" "draw a circle, or part of one",
+    "undo" "" "take back the last command",
+    "redo" "" "do the last undone command again",
+    "speed" "0-10|fastest|fast|normal|slow|slowest" "how fast to draw",
+
+    // drawing
+    "pendown" ["pd", "down"] "" "draw when moving",
+    "penup" ["pu", "up"] "" "don't draw when moving",
+    "pensize" ["width"] "WIDTH" "how thick lines are",
+    "pencolor" "COLOR" "the color of lines",
+    "fillcolor" "COLOR" "the color of fills",
+    "begin_fill" "" "start a shape to fill",
+    "end_fill" "" "fill the shape drawn since begin_fill",
+    "dot" "[SIZE [COLOR]]" "draw a dot",
+    "stamp" "" "leave a copy of the turtle behind",
+    "clearstamp" "ID" "remove a stamp",
+    "clearstamps" "[N]" "remove all stamps, the first N, or the last -N",
+    "write" "TEXT" "write some text",
+    "clear" "" "erase this turtle's drawing",
+    "reset" "" "erase this turtle's drawing and send it home",
+    "showturtle" ["st"] "" "show the turtle",
+    "hideturtle" ["ht"] "" "hide the turtle",
+    "shape" "[NAME]" "change the turtle's shape, or show it",
+
+    // asking
+    "position" ["pos"] "" "where the turtle is",
+    "xcor" "" "the turtle's x coordinate",
+    "ycor" "" "the turtle's y coordinate",
+    "heading" "" "which way the turtle faces",
+    "towards" "X Y" "the heading that points at a position",
+    "distance" "X Y" "how far away a position is",
+    "isdown" "" "whether the pen is down",
+    "isvisible" "" "whether the turtle is shown",
+    "filling" "" "whether a fill has been started",
+    "undobufferentries" "" "how many commands can be undone",
+    "shapes" "" "the shapes there are",
+
+    // turtles
+    "hatch" "" "make a new turtle and use it",
+    "clone" "" "copy this turtle and use the copy",
+    "turtles" "" "list the turtles",
+    "use" "N" "send commands to turtle N",
+
+    // the screen
+    "bgcolor" "COLOR" "the background color",
+    "title" "TEXT" "the window's title",
+    "tracer" "on|off" "whether to animate drawing",
+    "mode" "[standard|logo|world]" "where headings start and which way they turn",
+    "setworldcoordinates" "LLX LLY URX URY" "the corners of the screen",
+    "degrees" "[FULLCIRCLE]" "measure angles in degrees",
+    "radians" "" "measure angles in radians",
+    "clearscreen" "" "erase everything",
+
+    // the prompt
+    "help" "[COMMAND]" "list the commands, or explain one",
+    "bye" ["exit", "quit"] "" "close the window",
+};
+
+pub(crate) fn lookup(name: &str) -> Option<&'static Command> {
+    COMMANDS
+        .iter()
+        .find(|c| c.name == name || c.aliases.contains(&name))
+}
+
+/// The names of all the commands, including their short forms
+pub fn names() -> impl Iterator<Item = &'static str> {
+    COMMANDS
+        .iter()
+        .flat_map(|c| std::iter::once(c.name).chain(c.aliases.iter().copied()))
+}
+
+/// The commands that start with `prefix`, in order
+#[must_use]
+pub fn complete(prefix: &str) -> Vec<&'static str> {
+    let mut found: Vec<_> = names().filter(|name| name.starts_with(prefix)).collect();
+    found.sort_unstable();
+    found
+}
+
+// the command that's the fewest typos away from `name`, if it's close
+pub(crate) fn suggest(name: &str) -> Option<&'static str> {
+    names()
+        .map(|known| (typos(name, known), known))
+        .filter(|(typos, _)| *typos <= (name.len() / 3).min(2))
+        .min()
+        .map(|(_, known)| known)
+}
+
+// the edit distance between two words, where swapping two letters is one typo
+fn typos(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let replaced = d[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = replaced.min(d[i - 1][j] + 1).min(d[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+pub(crate) fn help(name: Option<&str>) -> Result<String, String> {
+    let Some(name) = name else {
+        let names: Vec<_> = COMMANDS.iter().map(|c| c.name).collect();
+        return Ok(format!(
+            "{}\n(type help and a command to learn more)",
+            names.join(" ")
+        ));
+    };
+    let Some(cmd) = lookup(name) else {
+        return Err(unknown(name));
+    };
+    let mut text = format!("{} {}", cmd.name, cmd.usage);
+    text.truncate(text.trim_end().len());
+    text.push_str(&format!(": {}", cmd.help));
+    if !cmd.aliases.is_empty() {
+        text.push_str(&format!(" (also {})", cmd.aliases.join(", ")));
+    }
+    Ok(text)
+}
+
+pub(crate) fn unknown(name: &str) -> String {
+    match suggest(name) {
+        Some(known) => format!("there's no command '{name}'; did you mean '{known}'?"),
+        None => format!("there's no command '{name}' (type help to list them)"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names_are_completed_and_corrected() {
+        assert_eq!(complete("pen"), ["pencolor", "pendown", "pensize", "penup"]);
+        assert_eq!(complete("fo"), ["forward"]);
+        assert!(complete("zz").is_empty());
+
+        assert_eq!(suggest("fowrad"), Some("forward"));
+        assert_eq!(suggest("hedaing"), Some("heading"));
+        assert_eq!(suggest("spiral"), None);
+        assert_eq!(
+            help(Some("bk")),
+            Ok("backward DISTANCE: move backward (also bk, back)".to_string())
+        );
+        assert_eq!(
+            help(Some("forwrd")),
+            Err("there's no command 'forwrd'; did you mean 'forward'?".to_string())
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use crate::commands;
+
+/// A key that edits the line being typed, whichever frontend it came from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    Up,   // the line before in the history
+    Down, // the line after in the history
+    Tab,  // complete the command's name
+    Enter,
+}
+
+/// What a key did to the line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    Changed,
+    Unchanged,
+    Submitted(String),          // Enter was pressed, and this is the line
+    Choices(Vec<&'static str>), // the commands Tab could have meant
+}
+
+/// The line being typed at the prompt, with the lines typed before it
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    line: Vec<char>,
+    cursor: usize,
+    history: Vec<String>,
+    recalled: Option<usize>, // the line in the history being shown
+    draft: String,           // what was typed before going back in the history
+}
+
+impl LineEditor {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn line(&self) -> String {
+        self.line.iter().collect()
+    }
+
+    /// Where the cursor is, in characters from the start of the line
+    #[must_use]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    #[must_use]
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Forget what's been typed, but not the history
+    pub fn clear(&mut self) {
+        self.set_line("");
+        self.recalled = None;
+    }
+
+    pub fn key(&mut self, key: Key) -> Edit {
+        match key {
+            Key::Char(ch) => {
+                self.line.insert(self.cursor, ch);
+                self.cursor += 1;
+            }
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.line.remove(self.cursor);
+            }
+            Key::Delete if self.cursor < self.line.len() => {
+                self.line.remove(self.cursor);
+            }
+            Key::Left if self.cursor > 0 => self.cursor -= 1,
+            Key::Right if self.cursor < self.line.len() => self.cursor += 1,
+            Key::Home if self.cursor > 0 => self.cursor = 0,
+            Key::End if self.cursor < self.line.len() => self.cursor = self.line.len(),
+            Key::Up => return self.recall(true),
+            Key::Down => return self.recall(false),
+            Key::Tab => return self.complete(),
+            Key::Enter => {
+                let line = self.line();
+                let trimmed = line.trim();
+                if !trimmed.is_empty() && self.history.last().map(String::as_str) != Some(trimmed) {
+                    self.history.push(trimmed.to_string());
+                }
+                self.clear();
+                return Edit::Submitted(line);
+            }
+            _ => return Edit::Unchanged,
+        }
+        Edit::Changed
+    }
+
+    fn set_line(&mut self, line: &str) {
+        self.line = line.chars().collect();
+        self.cursor = self.line.len();
+    }
+
+    // show an earlier (or later) line from the history
+    fn recall(&mut self, earlier: bool) -> Edit {
+        let recalled = match (self.recalled, earlier) {
+            (None, true) if !self.history.is_empty() => {
+                self.draft = self.line();
+                Some(self.history.len() - 1)
+            }
+            (Some(n), true) if n > 0 => Some(n - 1),
+            (Some(n), false) if n + 1 < self.history.len() => Some(n + 1),
+            (Some(_), false) => None,
+            _ => return Edit::Unchanged,
+        };
+        self.recalled = recalled;
+        let line = match recalled {
+            Some(n) => self.history[n].clone(),
+            None => std::mem::take(&mut self.draft),
+        };
+        self.set_line(&line);
+        Edit::Changed
+    }
+
+    // finish the command's name, if the cursor is at the end of it
+    fn complete(&mut self) -> Edit {
+        let before: String = self.line[..self.cursor].iter().collect();
+        let prefix = before.trim_start();
+        if prefix.contains(' ') || self.line.get(self.cursor).is_some_and(|c| *c != ' ') {
+            return Edit::Unchanged;
+        }
+        let choices = commands::complete(prefix);
+        let common = match choices.as_slice() {
+            [] => return Edit::Unchanged,
+            [only] => format!("{only} "),
+            [first, rest @ ..] => {
+                let mut common = first.to_string();
+                for other in rest {
+                    while !other.starts_with(&common) {
+                        common.pop();
+                    }
+                }
+                common
+            }
+        };
+        if common.len() == prefix.len() {
+            return Edit::Choices(choices);
+        }
+        for ch in common[prefix.len()..].chars() {
+            self.line.insert(self.cursor, ch);
+            self.cursor += 1;
+        }
+        Edit::Changed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn type_in(editor: &mut LineEditor, text: &str) {
+        for ch in text.chars() {
+            editor.key(Key::Char(ch));
+        }
+    }
+
+    #[test]
+    fn edits_and_remembers_lines() {
+        let mut editor = LineEditor::new();
+        type_in(&mut editor, "fd 10");
+        editor.key(Key::Left);
+        editor.key(Key::Backspace);
+        type_in(&mut editor, "5");
+        assert_eq!((editor.line(), editor.cursor()), ("fd 50".to_string(), 4));
+        assert_eq!(editor.key(Key::Enter), Edit::Submitted("fd 50".to_string()));
+
+        type_in(&mut editor, "  rt 90 ");
+        editor.key(Key::Enter);
+        editor.key(Key::Enter);
+        assert_eq!(editor.history(), ["fd 50", "rt 90"]);
+
+        type_in(&mut editor, "lt");
+        editor.key(Key::Up);
+        editor.key(Key::Up);
+        assert_eq!(editor.key(Key::Up), Edit::Unchanged);
+        assert_eq!(editor.line(), "fd 50");
+        editor.key(Key::Down);
+        editor.key(Key::Down);
+        assert_eq!(editor.line(), "lt");
+        assert_eq!(editor.key(Key::Down), Edit::Unchanged);
+    }
+
+    #[test]
+    fn tab_completes_commands() {
+        let mut editor = LineEditor::new();
+        type_in(&mut editor, "fo");
+        editor.key(Key::Tab);
+        assert_eq!(editor.line(), "forward ");
+
+        let mut editor = LineEditor::new();
+        type_in(&mut editor, "pe");
+        assert_eq!(editor.key(Key::Tab), Edit::Changed);
+        assert_eq!(editor.line(), "pen");
+        assert_eq!(
+            editor.key(Key::Tab),
+            Edit::Choices(vec!["pencolor", "pendown", "pensize", "penup"])
+        );
+
+        // only the command's name is completed
+        type_in(&mut editor, "color re");
+        assert_eq!(editor.key(Key::Tab), Edit::Unchanged);
+    }
+}
@@ -0,0 +1,35 @@
+//! A prompt for driving a slowpoke `Turtle` one command at a time, for trying
+//! things out or demonstrating them to a class:
+//!
+//! ```text
+//! > forward 50
+//! > pencolor red
+//! > position
+//! (50, 0)
+//! > hatch
+//! now using turtle 1
+//! ```
+//!
+//! The commands are the turtle's methods, with their arguments separated by
+//! spaces, and `help` lists them. Colors are a name, `#rgb`, `#rrggbb`, or red,
+//! green and blue from 0 to 1 (or to 255). Lines can be edited, Up and Down go
+//! back through the ones typed before, and Tab completes a command's name.
+//!
+//! `run_stdin()` reads commands from the terminal while a frontend shows the
+//! turtles, and with the `ratatui` feature, `panel()` puts the prompt in a side
+//! panel of the ratatui window instead. The `slowpoke-repl` binary does one or
+//! the other, depending on which frontend it was built with.
+
+mod commands;
+mod editor;
+#[cfg(feature = "ratatui")]
+mod panel;
+mod prompt;
+mod shell;
+
+pub use commands::{complete, names};
+pub use editor::{Edit, Key, LineEditor};
+#[cfg(feature = "ratatui")]
+pub use panel::{panel, Panel, PanelShell};
+pub use prompt::{key, run_stdin};
+pub use shell::Shell;
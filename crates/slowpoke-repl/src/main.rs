@@ -0,0 +1,25 @@
+//! Opens a window and a prompt where turtle commands run as they're typed.
+//! With the `ratatui` feature the prompt is beside the turtles in the
+//! terminal; otherwise it's on stdin and the turtles are in an iced window.
+
+use std::process::ExitCode;
+
+#[cfg(feature = "ratatui")]
+fn main() -> ExitCode {
+    let (panel, shell) = slowpoke_repl::panel();
+    slowpoke_ratatui::Slowpoke::default()
+        .with_title("slowpoke")
+        .with_options(slowpoke_ratatui::RatatuiOptions::default().with_side_panel(panel))
+        .run(move |turtle| shell.run(turtle))
+}
+
+#[cfg(all(feature = "iced", not(feature = "ratatui")))]
+fn main() -> ExitCode {
+    println!("type help to list the commands, and bye to finish");
+    slowpoke_iced::Slowpoke::default()
+        .with_title("slowpoke")
+        .run(slowpoke_repl::run_stdin)
+}
+
+#[cfg(not(any(feature = "iced", feature = "ratatui")))]
+compile_error!("slowpoke-repl needs a frontend: build it with the iced or ratatui feature");
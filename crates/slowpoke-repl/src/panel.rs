@@ -0,0 +1,178 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use slowpoke::Turtle;
+use slowpoke_ratatui::{
+    ratatui::{
+        layout::{Position, Rect},
+        text::Line,
+        widgets::{Block, Paragraph},
+        Frame,
+    },
+    SidePanel,
+};
+
+use crate::{
+    prompt::{key, POLL, PROMPT},
+    Edit, LineEditor, Shell,
+};
+
+// how many lines of what was typed and said to keep
+const SCROLLBACK: usize = 1000;
+
+/// The prompt, drawn beside the turtles by `slowpoke_ratatui`. The lines typed
+/// into it are run by its `PanelShell`, on the turtle's thread.
+#[derive(Debug)]
+pub struct Panel {
+    editor: LineEditor,
+    shown: Vec<String>, // the lines typed, and what they said
+    send_line: Sender<String>,
+    receive_text: Receiver<String>,
+    changed: bool,
+}
+
+/// Runs the lines typed into a `Panel`
+#[derive(Debug)]
+pub struct PanelShell {
+    receive_line: Receiver<String>,
+    send_text: Sender<String>,
+}
+
+/// A prompt to show with `RatatuiOptions::with_side_panel()`, and the shell
+/// to run on the turtle's thread, which runs what's typed into it
+#[must_use]
+pub fn panel() -> (Panel, PanelShell) {
+    let (send_line, receive_line) = mpsc::channel();
+    let (send_text, receive_text) = mpsc::channel();
+    let panel = Panel {
+        editor: LineEditor::new(),
+        shown: vec!["type help to list the commands".to_string()],
+        send_line,
+        receive_text,
+        changed: true,
+    };
+    (
+        panel,
+        PanelShell {
+            receive_line,
+            send_text,
+        },
+    )
+}
+
+impl PanelShell {
+    /// Run each line typed into the panel on `turtle`, until `bye`
+    pub fn run(self, turtle: &mut Turtle) {
+        let mut shell = Shell::new();
+        loop {
+            let line = match self.receive_line.recv_timeout(POLL) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    // this unwinds the thread if the window has closed
+                    turtle.isvisible();
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+            let text = match shell.run(turtle, &line) {
+                Ok(Some(text)) | Err(text) => text,
+                Ok(None) => continue,
+            };
+            if self.send_text.send(text).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl Panel {
+    fn show<S: Into<String>>(&mut self, text: S) {
+        self.shown.extend(text.into().lines().map(str::to_string));
+        let extra = self.shown.len().saturating_sub(SCROLLBACK);
+        self.shown.drain(..extra);
+        self.changed = true;
+    }
+
+    fn submit(&mut self, line: String) {
+        self.show(format!("{PROMPT}{line}"));
+        let _ = self.send_line.send(line);
+    }
+}
+
+impl SidePanel for Panel {
+    fn key(&mut self, event: KeyEvent) {
+        self.changed = true;
+        if event.modifiers.contains(KeyModifiers::CONTROL) && event.kind != KeyEventKind::Release {
+            // Ctrl-C forgets the line, and Ctrl-D on an empty line says bye
+            match event.code {
+                KeyCode::Char('c') => {
+                    self.show(format!("{PROMPT}{}^C", self.editor.line()));
+                    self.editor.clear();
+                }
+                KeyCode::Char('d') if self.editor.line().is_empty() => {
+                    self.submit("bye".to_string());
+                }
+                _ => {}
+            }
+            return;
+        }
+        let Some(key) = key(&event) else {
+            return;
+        };
+        match self.editor.key(key) {
+            Edit::Changed | Edit::Unchanged => {}
+            Edit::Choices(choices) => {
+                self.show(format!("{PROMPT}{}", self.editor.line()));
+                self.show(choices.join(" "));
+            }
+            Edit::Submitted(line) => self.submit(line),
+        }
+    }
+
+    fn changed(&mut self) -> bool {
+        while let Ok(text) = self.receive_text.try_recv() {
+            self.show(text);
+        }
+        std::mem::take(&mut self.changed)
+    }
+
+    fn draw(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" prompt ");
+        let inner = block.inner(area);
+
+        // the newest lines, then the one being typed, scrolled to the cursor
+        let rows = usize::from(inner.height).saturating_sub(1);
+        let width = usize::from(inner.width).max(1);
+        let wrapped: Vec<String> = self.shown[self.shown.len().saturating_sub(rows)..]
+            .iter()
+            .flat_map(|line| {
+                let chars: Vec<char> = line.chars().collect();
+                let pieces: Vec<String> = chars
+                    .chunks(width)
+                    .map(|piece| piece.iter().collect())
+                    .collect();
+                if pieces.is_empty() {
+                    vec![String::new()]
+                } else {
+                    pieces
+                }
+            })
+            .collect();
+        let shown = &wrapped[wrapped.len().saturating_sub(rows)..];
+        let cursor = PROMPT.len() + self.editor.cursor();
+        let scroll = (cursor + 1).saturating_sub(usize::from(inner.width));
+        let typed: String = format!("{PROMPT}{}", self.editor.line())
+            .chars()
+            .skip(scroll)
+            .collect();
+        let mut lines: Vec<Line> = shown.iter().map(|line| Line::from(line.as_str())).collect();
+        lines.push(Line::from(typed));
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+        let to_u16 = |n: usize| u16::try_from(n).unwrap_or(u16::MAX);
+        frame.set_cursor_position(Position::new(
+            inner.x + to_u16(cursor - scroll),
+            inner.y + to_u16(shown.len()),
+        ));
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    io::{self, BufRead, IsTerminal, Write},
+    time::Duration,
+};
+
+use crossterm::{
+    cursor::MoveToColumn,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+use slowpoke::Turtle;
+
+use crate::{Edit, Key, LineEditor, Shell};
+
+pub(crate) const PROMPT: &str = "> ";
+
+// how often to check that the window is still open while waiting for a key
+pub(crate) const POLL: Duration = Duration::from_millis(100);
+
+/// Read commands from the terminal and run them on `turtle`, printing what
+/// they say, until `bye` or the end of the input. When stdin is a terminal
+/// the line can be edited, with history and tab completion; otherwise each
+/// line is run as it's read, so a file of commands can be piped in.
+pub fn run_stdin(turtle: &mut Turtle) {
+    let mut shell = Shell::new();
+    if io::stdin().is_terminal() {
+        edit_lines(&mut shell, turtle);
+    } else {
+        for line in io::stdin().lock().lines().map_while(Result::ok) {
+            match shell.run(turtle, &line) {
+                Ok(Some(text)) => println!("{text}"),
+                Ok(None) => {}
+                Err(message) => eprintln!("{message}"),
+            }
+        }
+    }
+}
+
+/// The key the prompt sees for a terminal key press, if it's one it uses
+#[must_use]
+pub fn key(event: &KeyEvent) -> Option<Key> {
+    if event.kind == KeyEventKind::Release {
+        return None;
+    }
+    Some(match event.code {
+        KeyCode::Char(ch) if !event.modifiers.contains(KeyModifiers::CONTROL) => Key::Char(ch),
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Enter => Key::Enter,
+        _ => return None,
+    })
+}
+
+// Puts the terminal back the way it was, even if the turtle's thread unwinds
+// because the window was closed
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+        println!();
+    }
+}
+
+fn edit_lines(shell: &mut Shell, turtle: &mut Turtle) {
+    let Ok(_raw) = RawMode::enable() else {
+        eprintln!("couldn't set up the terminal");
+        return;
+    };
+    let mut editor = LineEditor::new();
+    let mut out = io::stdout();
+    show(&mut out, &editor);
+
+    loop {
+        if !event::poll(POLL).unwrap_or(false) {
+            // this unwinds the thread if the window has closed
+            turtle.isvisible();
+            continue;
+        }
+        let Ok(Event::Key(event)) = event::read() else {
+            continue;
+        };
+        if event.modifiers.contains(KeyModifiers::CONTROL) && event.kind != KeyEventKind::Release {
+            match event.code {
+                // Ctrl-C forgets the line, and Ctrl-D on an empty line says bye
+                KeyCode::Char('c') => {
+                    print_lines(&mut out, &format!("{PROMPT}{}^C", editor.line()));
+                    editor.clear();
+                }
+                KeyCode::Char('d') if editor.line().is_empty() => {
+                    print_lines(&mut out, &format!("{PROMPT}bye"));
+                    let _ = shell.run(turtle, "bye");
+                }
+                _ => {}
+            }
+            show(&mut out, &editor);
+            continue;
+        }
+
+        let Some(key) = key(&event) else {
+            continue;
+        };
+        match editor.key(key) {
+            Edit::Unchanged => continue,
+            Edit::Changed => {}
+            Edit::Choices(choices) => {
+                print_lines(&mut out, &format!("{PROMPT}{}", editor.line()));
+                print_lines(&mut out, &choices.join(" "));
+            }
+            Edit::Submitted(line) => {
+                print_lines(&mut out, &format!("{PROMPT}{line}"));
+                match shell.run(turtle, &line) {
+                    Ok(Some(text)) => print_lines(&mut out, &text),
+                    Ok(None) => {}
+                    Err(message) => print_lines(&mut out, &message),
+                }
+            }
+        }
+        show(&mut out, &editor);
+    }
+}
+
+// raw mode doesn't go back to the start of the line by itself
+fn print_lines(out: &mut impl Write, text: &str) {
+    for line in text.lines() {
+        let _ = queue!(
+            out,
+            MoveToColumn(0),
+            Clear(ClearType::CurrentLine),
+            Print(line),
+            Print("\r\n")
+        );
+    }
+    let _ = out.flush();
+}
+
+fn show(out: &mut impl Write, editor: &LineEditor) {
+    let column = PROMPT.len() + editor.cursor();
+    let _ = queue!(
+        out,
+        MoveToColumn(0),
+        Clear(ClearType::CurrentLine),
+        Print(PROMPT),
+        Print(editor.line()),
+        MoveToColumn(u16::try_from(column).unwrap_or(u16::MAX))
+    );
+    let _ = out.flush();
+}
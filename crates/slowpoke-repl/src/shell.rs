@@ -0,0 +1,519 @@
+use std::collections::HashMap;
+
+use slowpoke::{Turtle, TurtleColor, TurtleShapeName};
+
+use crate::commands::{self, lookup};
+
+/// Runs the commands typed at the prompt, such as `forward 50`, `pencolor red`
+/// or `position`, on the turtle it's given or on the turtles it hatched.
+#[derive(Debug, Default)]
+pub struct Shell {
+    hatched: Vec<Turtle>,
+    current: usize, // 0 is the turtle that's passed in, the rest were hatched
+    stamps: HashMap<usize, Vec<usize>>, // each turtle's stamps, oldest first
+}
+
+// the words after a command's name
+struct Args<'a> {
+    name: &'static str,
+    usage: &'static str,
+    words: Vec<&'a str>,
+    text: &'a str, // everything after the name, for write and title
+}
+
+impl Args<'_> {
+    fn usage(&self) -> String {
+        format!("usage: {} {}", self.name, self.usage)
+            .trim_end()
+            .to_string()
+    }
+
+    // complain unless there are between `min` and `max` words
+    fn count(&self, min: usize, max: usize) -> Result<(), String> {
+        if (min..=max).contains(&self.words.len()) {
+            Ok(())
+        } else {
+            Err(self.usage())
+        }
+    }
+
+    fn number(&self, index: usize) -> Result<f64, String> {
+        let word = self.words.get(index).ok_or_else(|| self.usage())?;
+        word.parse()
+            .ok()
+            .filter(|n: &f64| n.is_finite())
+            .ok_or_else(|| format!("{} needs a number, not '{word}'", self.name))
+    }
+
+    fn opt_number(&self, index: usize) -> Result<Option<f64>, String> {
+        if index < self.words.len() {
+            self.number(index).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn int(&self, index: usize) -> Result<isize, String> {
+        let word = self.words.get(index).ok_or_else(|| self.usage())?;
+        word.parse()
+            .map_err(|_| format!("{} needs a whole number, not '{word}'", self.name))
+    }
+
+    // a name, #rgb or #rrggbb, or red, green and blue from 0 to 1 (or to 255)
+    fn color(&self, index: usize) -> Result<TurtleColor, String> {
+        match &self.words[index..] {
+            [name] => color_name(name).ok_or_else(|| format!("'{name}' isn't a color")),
+            [_, _, _] => {
+                let [r, g, b] = [index, index + 1, index + 2].map(|i| self.number(i));
+                let rgb = [r?, g?, b?];
+                let scale = if rgb.iter().any(|c| *c > 1.) {
+                    255.
+                } else {
+                    1.
+                };
+                if rgb.iter().any(|c| !(0. ..=scale).contains(c)) {
+                    return Err("red, green and blue go from 0 to 1, or to 255".to_string());
+                }
+                let [r, g, b] = rgb.map(|c| (c / scale) as f32);
+                Ok(TurtleColor::from((r, g, b)))
+            }
+            _ => Err(self.usage()),
+        }
+    }
+
+    fn flag(&self, index: usize) -> Result<bool, String> {
+        match self.words.get(index).copied() {
+            Some("on" | "true" | "yes" | "1") => Ok(true),
+            Some("off" | "false" | "no" | "0") => Ok(false),
+            _ => Err(self.usage()),
+        }
+    }
+
+    // the text, without quotes around it
+    fn text(&self) -> &str {
+        let text = self.text.trim();
+        ['"', '\'']
+            .iter()
+            .find_map(|q| text.strip_prefix(*q)?.strip_suffix(*q))
+            .unwrap_or(text)
+    }
+}
+
+fn color_name(name: &str) -> Option<TurtleColor> {
+    if let Some(hex) = name.strip_prefix('#') {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        return match hex.len() {
+            3 => {
+                let doubled: String = hex.chars().flat_map(|c| [c, c]).collect();
+                Some(TurtleColor::from(format!("#{doubled}").as_str()))
+            }
+            6 => Some(TurtleColor::from(name)),
+            _ => None,
+        };
+    }
+    Some(TurtleColor::from(name.to_lowercase().as_str()))
+        .filter(|color| !matches!(color, TurtleColor::CurrentColor))
+}
+
+// at most two decimal places, and none if they're zeros
+fn number(n: f64) -> String {
+    let text = format!("{n:.2}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0" } else { text }.to_string()
+}
+
+fn position(turtle: &Turtle) -> String {
+    let pos = turtle.pos();
+    format!("({}, {})", number(pos.x.into()), number(pos.y.into()))
+}
+
+impl Shell {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run one line on `turtle`, or on the hatched turtle that's in use, and
+    /// return what it printed, if anything. Blank lines and anything after a
+    /// `#` are ignored. `bye` closes the window, so it doesn't return.
+    ///
+    /// # Errors
+    /// Returns what's wrong with the line, such as a command that doesn't
+    /// exist or arguments it doesn't take
+    pub fn run(&mut self, turtle: &mut Turtle, line: &str) -> Result<Option<String>, String> {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let (name, text) = line.split_once(' ').unwrap_or((line, ""));
+        if name.is_empty() {
+            return Ok(None);
+        }
+        let name = name.to_lowercase();
+        let cmd = lookup(&name).ok_or_else(|| commands::unknown(&name))?;
+        let args = Args {
+            name: cmd.name,
+            usage: cmd.usage,
+            words: text.split_whitespace().collect(),
+            text,
+        };
+        if cmd.usage.is_empty() {
+            args.count(0, 0)?;
+        }
+
+        match cmd.name {
+            "hatch" | "clone" | "turtles" | "use" => self.turtles(turtle, &args).map(Some),
+            "help" => {
+                args.count(0, 1)?;
+                commands::help(args.words.first().copied()).map(Some)
+            }
+            "bye" => {
+                turtle.bye();
+                Ok(None)
+            }
+            _ => {
+                let stamps = self.stamps.entry(self.current).or_default();
+                match self.current {
+                    0 => command(turtle, stamps, &args),
+                    n => command(&mut self.hatched[n - 1], stamps, &args),
+                }
+            }
+        }
+    }
+
+    fn turtles(&mut self, turtle: &mut Turtle, args: &Args) -> Result<String, String> {
+        Ok(match args.name {
+            "hatch" | "clone" => {
+                let current = match self.current {
+                    0 => &mut *turtle,
+                    n => &mut self.hatched[n - 1],
+                };
+                let new = if args.name == "hatch" {
+                    current.hatch()
+                } else {
+                    current.clone_turtle()
+                };
+                self.hatched.push(new);
+                self.current = self.hatched.len();
+                format!("now using turtle {}", self.current)
+            }
+            "use" => {
+                args.count(1, 1)?;
+                let n = args.int(0)?;
+                if !(0..=self.hatched.len() as isize).contains(&n) {
+                    return Err(format!("there's no turtle {n}"));
+                }
+                self.current = n as usize;
+                format!("now using turtle {n}")
+            }
+            _ => {
+                let all = std::iter::once(&*turtle).chain(&self.hatched);
+                let lines: Vec<_> = all
+                    .enumerate()
+                    .map(|(n, t)| {
+                        let mark = if n == self.current { " (in use)" } else { "" };
+                        let heading = number(t.heading().into());
+                        format!("{n}: at {} heading {heading}{mark}", position(t))
+                    })
+                    .collect();
+                lines.join("\n")
+            }
+        })
+    }
+}
+
+// run a command on a single turtle, returning what it says, if anything
+fn command(
+    turtle: &mut Turtle,
+    stamps: &mut Vec<usize>,
+    args: &Args,
+) -> Result<Option<String>, String> {
+    let say = |text: String| Ok(Some(text));
+    match args.name {
+        "forward" | "backward" | "right" | "left" | "setx" | "sety" | "setheading" | "pensize" => {
+            args.count(1, 1)?;
+            let n = args.number(0)?;
+            match args.name {
+                "forward" => turtle.forward(n),
+                "backward" => turtle.backward(n),
+                "right" => turtle.right(n),
+                "left" => turtle.left(n),
+                "setx" => turtle.setx(n),
+                "sety" => turtle.sety(n as f32),
+                "setheading" => turtle.setheading(n),
+                _ => turtle.pensize(n),
+            }
+        }
+        "goto" | "teleport" | "towards" | "distance" => {
+            args.count(2, 2)?;
+            let (x, y) = (args.number(0)?, args.number(1)?);
+            match args.name {
+                "goto" => turtle.goto(x, y),
+                "teleport" => drop(turtle.teleport(x, y)),
+                "towards" => return say(number(turtle.towards(x, y).into())),
+                _ => return say(number(turtle.distance((x as f32, y as f32)))),
+            }
+        }
+        "home" => turtle.home(),
+        "circle" => {
+            args.count(1, 3)?;
+            let radius = args.number(0)?;
+            let extent = args.opt_number(1)?;
+            let steps = if args.words.len() > 2 {
+                Some(
+                    usize::try_from(args.int(2)?)
+                        .map_err(|_| format!("circle can't have {} steps", args.words[2]))?,
+                )
+            } else {
+                None
+            };
+            let circle = turtle.circle(radius);
+            let circle = match extent {
+                Some(extent) => circle.with_extent(extent),
+                None => circle,
+            };
+            drop(match steps {
+                Some(steps) => circle.with_steps(steps),
+                None => circle,
+            });
+        }
+        "undo" => turtle.undo(),
+        "redo" => turtle.redo(),
+        "speed" => {
+            args.count(1, 1)?;
+            match args.words[0] {
+                name @ ("fastest" | "fast" | "normal" | "slow" | "slowest") => turtle.speed(name),
+                word => match word.parse::<u8>() {
+                    Ok(speed) if speed <= 10 => turtle.speed(speed),
+                    _ => return Err(args.usage()),
+                },
+            }
+        }
+        "pendown" => turtle.pendown(),
+        "penup" => turtle.penup(),
+        "pencolor" | "fillcolor" | "bgcolor" => {
+            args.count(1, 3)?;
+            let color = args.color(0)?;
+            match args.name {
+                "pencolor" => turtle.pencolor(color),
+                "fillcolor" => turtle.fillcolor(color),
+                _ => turtle.bgcolor(color),
+            }
+        }
+        "begin_fill" => turtle.begin_fill(),
+        "end_fill" => turtle.end_fill(),
+        "dot" => {
+            args.count(0, 4)?;
+            let dot = turtle.dot();
+            let dot = match args.opt_number(0)? {
+                Some(size) => dot.with_size(size),
+                None => dot,
+            };
+            drop(if args.words.len() > 1 {
+                dot.with_color(args.color(1)?)
+            } else {
+                dot
+            });
+        }
+        "stamp" => {
+            let stamp = turtle.stamp();
+            stamps.push(stamp);
+            return say(format!("stamp {stamp}"));
+        }
+        "clearstamp" => {
+            args.count(1, 1)?;
+            let id = args.int(0)?;
+            let Some(index) = stamps.iter().position(|&stamp| stamp as isize == id) else {
+                return Err(format!("there's no stamp {id}"));
+            };
+            turtle.clearstamp(stamps.remove(index));
+        }
+        "clearstamps" => {
+            args.count(0, 1)?;
+            let which = if args.words.is_empty() {
+                0
+            } else {
+                args.int(0)?
+            };
+            turtle.clearstamps(which);
+            let count = (which.unsigned_abs()).min(stamps.len());
+            match which {
+                0 => stamps.clear(),
+                1.. => drop(stamps.drain(..count)),
+                _ => drop(stamps.drain(stamps.len() - count..)),
+            }
+        }
+        "write" => turtle.write(args.text()),
+        "clear" => {
+            stamps.clear();
+            turtle.clear();
+        }
+        "reset" => {
+            stamps.clear();
+            turtle.reset();
+        }
+        "showturtle" => turtle.showturtle(),
+        "hideturtle" => turtle.hideturtle(),
+        "shape" => {
+            args.count(0, 1)?;
+            let Some(name) = args.words.first() else {
+                return say(turtle.shape(TurtleShapeName::GetCurrent));
+            };
+            if !turtle.getshapes().iter().any(|shape| shape == name) {
+                return Err(format!(
+                    "there's no shape '{name}' (type shapes to list them)"
+                ));
+            }
+            turtle.shape(*name);
+        }
+        "position" => return say(position(turtle)),
+        "xcor" => return say(number(turtle.xcor().into())),
+        "ycor" => return say(number(turtle.ycor().into())),
+        "heading" => return say(number(turtle.heading().into())),
+        "isdown" => return say(turtle.isdown().to_string()),
+        "isvisible" => return say(turtle.isvisible().to_string()),
+        "filling" => return say(turtle.filling().to_string()),
+        "undobufferentries" => return say(turtle.undobufferentries().to_string()),
+        "shapes" => return say(turtle.getshapes().join(" ")),
+        "title" => turtle.title(args.text()),
+        "tracer" => {
+            args.count(1, 1)?;
+            turtle.tracer(args.flag(0)?);
+        }
+        "mode" => {
+            args.count(0, 1)?;
+            match args.words.first().copied() {
+                Some(name @ ("standard" | "logo" | "world")) => turtle.mode(name),
+                Some(_) => return Err(args.usage()),
+                None => return say(format!("{:?}", turtle.getmode()).to_lowercase()),
+            }
+        }
+        "setworldcoordinates" => {
+            args.count(4, 4)?;
+            let [llx, lly, urx, ury] = [0, 1, 2, 3].map(|i| args.number(i));
+            turtle.setworldcoordinates(llx?, lly?, urx?, ury?)?;
+        }
+        "degrees" => {
+            args.count(0, 1)?;
+            turtle.degrees(args.opt_number(0)?.unwrap_or(360.));
+        }
+        "radians" => turtle.radians(),
+        "clearscreen" => {
+            stamps.clear();
+            turtle.clearscreen();
+        }
+        _ => unreachable!("{} isn't handled", args.name),
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use slowpoke::{headless::Headless, SlowpokeLib};
+
+    use super::*;
+
+    // what each line replied
+    fn run_lines(lines: &'static [&'static str]) -> Vec<Result<Option<String>, String>> {
+        let (send, receive) = mpsc::channel();
+        SlowpokeLib::<Headless>::default().run(move |turtle| {
+            turtle.speed(0);
+            let mut shell = Shell::new();
+            let replies = lines.iter().map(|line| shell.run(turtle, line)).collect();
+            let _ = send.send(replies);
+            turtle.bye();
+        });
+        receive.recv().expect("the lines didn't finish")
+    }
+
+    fn text(text: &str) -> Result<Option<String>, String> {
+        Ok(Some(text.to_string()))
+    }
+
+    #[test]
+    fn commands_drive_the_turtle() {
+        let replies = run_lines(&[
+            "forward 50",
+            "  LT 90  # turn around the corner",
+            "fd 25.5",
+            "position",
+            "heading",
+            "undo",
+            "pos",
+            "towards 0 0",
+            "pencolor 255 128 0",
+            "",
+        ]);
+        assert_eq!(
+            replies,
+            [
+                Ok(None),
+                Ok(None),
+                Ok(None),
+                text("(50, 25.5)"),
+                text("90"),
+                Ok(None),
+                text("(50, 0)"),
+                text("180"),
+                Ok(None),
+                Ok(None),
+            ]
+        );
+    }
+
+    #[test]
+    fn hatched_turtles_take_turns() {
+        let replies = run_lines(&["rt 90", "hatch", "fd 10", "turtles", "use 0", "xcor"]);
+        assert_eq!(replies[1], text("now using turtle 1"));
+        assert_eq!(
+            replies[3],
+            text("0: at (0, 0) heading 270\n1: at (10, 0) heading 0 (in use)")
+        );
+        assert_eq!(replies[4], text("now using turtle 0"));
+        assert_eq!(replies[5], text("0"));
+    }
+
+    #[test]
+    fn only_stamps_that_were_made_are_cleared() {
+        let (send, receive) = mpsc::channel();
+        SlowpokeLib::<Headless>::default().run(move |turtle| {
+            let mut shell = Shell::new();
+            let mut run = |line: &str| shell.run(turtle, line);
+            let stamp = run("stamp").expect("no stamp").expect("no stamp id");
+            let clear = format!("clear{stamp}");
+            let replies = [run("clearstamp 999"), run(&clear), run(&clear)];
+            let _ = send.send(replies);
+            turtle.bye();
+        });
+        let replies = receive.recv().expect("the lines didn't finish");
+        assert_eq!(replies[0], Err("there's no stamp 999".to_string()));
+        assert_eq!(replies[1], Ok(None));
+        assert!(matches!(&replies[2], Err(e) if e.starts_with("there's no stamp")));
+    }
+
+    #[test]
+    fn mistakes_are_explained() {
+        let replies = run_lines(&[
+            "fowrad 10",
+            "forward",
+            "forward ten",
+            "pencolor nocolor",
+            "pencolor 0 300 0",
+            "use 3",
+            "mode sideways",
+        ]);
+        assert_eq!(
+            replies,
+            [
+                Err("there's no command 'fowrad'; did you mean 'forward'?".to_string()),
+                Err("usage: forward DISTANCE".to_string()),
+                Err("forward needs a number, not 'ten'".to_string()),
+                Err("'nocolor' isn't a color".to_string()),
+                Err("red, green and blue go from 0 to 1, or to 255".to_string()),
+                Err("there's no turtle 3".to_string()),
+                Err("usage: mode [standard|logo|world]".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+
+use lyon_tessellation::{
+    geom::euclid::default::{Box2D, Transform2D},
+    math::Angle,
+};
+
+use crate::{gui::ops::Point, polygon::TurtleShape, DrawCommand, LineSegment, ScreenPosition};
+
+// the size (in pixels) of the squares the screen is split into for lookups
+const CELL_SIZE: f32 = 32.;
+const EPSILON: f32 = 1e-4;
+
+// the cell a point on the screen is in
+#[allow(clippy::cast_possible_truncation)]
+fn cell(point: Point) -> (i32, i32) {
+    (
+        (point.x / CELL_SIZE).floor() as i32,
+        (point.y / CELL_SIZE).floor() as i32,
+    )
+}
+
+// every cell of the rectangle with these opposite corners
+fn cells_between(a: Point, b: Point) -> impl Iterator<Item = (i32, i32)> {
+    let (low, high) = (cell(a.min(b)), cell(a.max(b)));
+    (low.0..=high.0).flat_map(move |x| (low.1..=high.1).map(move |y| (x, y)))
+}
+
+// Line segments, filed under each cell of the screen they pass through, so
+// that looking for what's near a segment only checks a few of them
+#[derive(Debug, Default)]
+struct SegmentGrid {
+    segments: Vec<LineSegment>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SegmentGrid {
+    fn cells(segment: &LineSegment) -> impl Iterator<Item = (i32, i32)> {
+        cells_between(segment.start, segment.end)
+    }
+
+    fn insert(&mut self, segment: LineSegment) {
+        let index = self.segments.len();
+        for cell in Self::cells(&segment) {
+            self.cells.entry(cell).or_default().push(index);
+        }
+        self.segments.push(segment);
+    }
+
+    // the indices of segments which might touch this one
+    fn near(&self, segment: &LineSegment) -> Vec<usize> {
+        let mut found: Vec<usize> = Self::cells(segment)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+// Turtles, filed under the cell of the screen each one is in, so that looking
+// for the ones near a point only checks those close by. Turtles are placed
+// again whenever they may have moved; that's cheap when they stay in their cell.
+#[derive(Debug, Default)]
+pub(crate) struct TurtleGrid {
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    placed: Vec<Option<(i32, i32)>>, // the cell each turtle is filed under
+}
+
+impl TurtleGrid {
+    pub(crate) fn place(&mut self, index: usize, pos: ScreenPosition<f32>) {
+        let cell = cell(Point::new(pos.x, pos.y));
+        if index >= self.placed.len() {
+            self.placed.resize(index + 1, None);
+        }
+        if self.placed[index] == Some(cell) {
+            return;
+        }
+        self.remove(index);
+        self.cells.entry(cell).or_default().push(index);
+        self.placed[index] = Some(cell);
+    }
+
+    // forget every turtle from `len` on
+    pub(crate) fn truncate(&mut self, len: usize) {
+        for index in len..self.placed.len() {
+            self.remove(index);
+        }
+        self.placed.truncate(len);
+    }
+
+    fn remove(&mut self, index: usize) {
+        let Some(cell) = self.placed.get_mut(index).and_then(Option::take) else {
+            return;
+        };
+        if let Some(turtles) = self.cells.get_mut(&cell) {
+            turtles.retain(|&t| t != index);
+            if turtles.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    // the turtles in the cells the rectangle with these corners covers
+    pub(crate) fn within(&self, a: ScreenPosition<f32>, b: ScreenPosition<f32>) -> Vec<usize> {
+        let (a, b) = (Point::new(a.x, a.y), Point::new(b.x, b.y));
+        let (low, high) = (cell(a.min(b)), cell(a.max(b)));
+        let area = (u64::from(high.0.abs_diff(low.0)) + 1)
+            .saturating_mul(u64::from(high.1.abs_diff(low.1)) + 1);
+
+        let mut found = Vec::new();
+        if area > self.cells.len() as u64 {
+            // quicker to go through the cells that have turtles in
+            for (&(x, y), turtles) in &self.cells {
+                if (low.0..=high.0).contains(&x) && (low.1..=high.1).contains(&y) {
+                    found.extend(turtles);
+                }
+            }
+        } else {
+            for cell in cells_between(a, b) {
+                found.extend(self.cells.get(&cell).into_iter().flatten());
+            }
+        }
+        found
+    }
+}
+
+// The lines a turtle has drawn, so it can find out if it ran into them
+#[derive(Debug, Default)]
+pub(crate) struct DrawnPath {
+    grid: Option<SegmentGrid>, // built when first asked for
+    last_move: Vec<LineSegment>,
+    move_start: usize, // the first segment in the grid drawn by the last move
+}
+
+impl DrawnPath {
+    // call whenever the drawing changes other than by adding to it
+    pub(crate) fn invalidate(&mut self) {
+        self.grid = None;
+    }
+
+    pub(crate) const fn is_built(&self) -> bool {
+        self.grid.is_some()
+    }
+
+    pub(crate) fn rebuild(&mut self, cmds: &[DrawCommand]) {
+        self.grid = Some(SegmentGrid::default());
+        self.last_move.clear();
+        for cmd in cmds {
+            self.add(cmd);
+        }
+    }
+
+    pub(crate) fn add(&mut self, cmd: &DrawCommand) {
+        let Some(grid) = self.grid.as_mut() else {
+            return;
+        };
+
+        let (segments, pen_down) = match cmd {
+            DrawCommand::Line(line) => (
+                vec![LineSegment {
+                    start: Point::new(line.begin.x as f32, line.begin.y as f32),
+                    end: Point::new(line.end.x as f32, line.end.y as f32),
+                }],
+                line.pen_down,
+            ),
+            DrawCommand::Circle(points) => (
+                points
+                    .windows(2)
+                    .map(|p| LineSegment {
+                        start: Point::new(p[0].x as f32, p[0].y as f32),
+                        end: Point::new(p[1].x as f32, p[1].y as f32),
+                    })
+                    .collect(),
+                points.first().is_some_and(|p| p.pen_down),
+            ),
+            _ => return,
+        };
+
+        self.move_start = grid.segments.len();
+        if pen_down {
+            for segment in &segments {
+                grid.insert(*segment);
+            }
+        }
+        self.last_move = segments;
+    }
+
+    /// Whether the last move went over anything drawn before it. Touching the
+    /// line it carried on from doesn't count.
+    pub(crate) fn last_move_crosses(&self) -> bool {
+        let (Some(grid), Some(first)) = (&self.grid, self.last_move.first()) else {
+            return false;
+        };
+
+        self.last_move.iter().any(|segment| {
+            grid.near(segment)
+                .into_iter()
+                .filter(|&index| index < self.move_start)
+                .filter_map(|index| intersection(segment, &grid.segments[index]))
+                .any(|point| (point - first.start).length() > EPSILON)
+        })
+    }
+}
+
+// Where two segments meet, if they do. When they lie along the same line, the
+// point where they stop overlapping, furthest along `a`.
+fn intersection(a: &LineSegment, b: &LineSegment) -> Option<Point> {
+    let r = a.end - a.start;
+    let s = b.end - b.start;
+    let rr = r.dot(r);
+    if rr < EPSILON {
+        return None;
+    }
+
+    let qp = b.start - a.start;
+    let denom = r.cross(s);
+    if denom.abs() < EPSILON {
+        if qp.cross(r).abs() > EPSILON {
+            return None; // parallel
+        }
+        let t0 = qp.dot(r) / rr;
+        let t1 = t0 + s.dot(r) / rr;
+        let low = t0.min(t1).max(0.);
+        let high = t0.max(t1).min(1.);
+        return (low <= high).then(|| a.start + r * high);
+    }
+
+    let t = qp.cross(s) / denom;
+    let u = qp.cross(r) / denom;
+    ((0. ..=1.).contains(&t) && (0. ..=1.).contains(&u)).then(|| a.start + r * t)
+}
+
+// even-odd rule
+fn contains(polygon: &[Point], point: Point) -> bool {
+    let mut inside = false;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn edges(polygon: &[Point]) -> impl Iterator<Item = LineSegment> + '_ {
+    polygon.iter().enumerate().map(|(i, start)| LineSegment {
+        start: *start,
+        end: polygon[(i + 1) % polygon.len()],
+    })
+}
+
+fn polygons_overlap(a: &[Point], b: &[Point]) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    edges(a).any(|e| edges(b).any(|f| intersection(&e, &f).is_some()))
+        || contains(a, b[0])
+        || contains(b, a[0])
+}
+
+/// The turtle's shape where it is on the screen, one polygon per contour
+pub(crate) fn shape_outline(
+    shape: &TurtleShape,
+    pos: ScreenPosition<f32>,
+    angle: f32,
+) -> Vec<Vec<Point>> {
+    let transform =
+        Transform2D::rotation(Angle::degrees(angle)).then_translate([pos.x, pos.y].into());
+    shape
+        .poly
+        .iter()
+        .flat_map(|component| component.polygon.contour_points())
+        .map(|contour| {
+            contour
+                .iter()
+                .map(|p| transform.transform_point(Point::new(p[0], p[1])))
+                .collect()
+        })
+        .collect()
+}
+
+pub(crate) fn outlines_overlap(a: &[Vec<Point>], b: &[Vec<Point>]) -> bool {
+    // shapes whose bounds are apart can't overlap, and most turtles are apart
+    let (a_box, b_box) = (bounds(a), bounds(b));
+    if a_box.max.x < b_box.min.x
+        || b_box.max.x < a_box.min.x
+        || a_box.max.y < b_box.min.y
+        || b_box.max.y < a_box.min.y
+    {
+        return false;
+    }
+    a.iter().any(|p| b.iter().any(|q| polygons_overlap(p, q)))
+}
+
+fn bounds(outline: &[Vec<Point>]) -> Box2D<f32> {
+    Box2D::from_points(outline.iter().flatten())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LineInfo;
+
+    fn line(begin: [i32; 2], end: [i32; 2]) -> DrawCommand {
+        DrawCommand::Line(LineInfo {
+            begin: ScreenPosition::new(begin[0], begin[1]),
+            end: ScreenPosition::new(end[0], end[1]),
+            pen_down: true,
+        })
+    }
+
+    #[test]
+    fn crossing_a_drawn_line() {
+        let mut path = DrawnPath::default();
+        path.rebuild(&[
+            line([0, 0], [100, 0]),
+            line([100, 0], [100, 100]),
+            line([100, 100], [50, 100]),
+        ]);
+        // turning back onto the line we came from doesn't count
+        assert!(!path.last_move_crosses());
+
+        path.add(&line([50, 100], [50, -100]));
+        assert!(path.last_move_crosses());
+
+        path.add(&line([50, -100], [-50, -100]));
+        assert!(!path.last_move_crosses());
+        path.add(&line([-50, -100], [-50, 0]));
+
+        // running along a drawn line counts too
+        path.add(&line([-50, 0], [20, 0]));
+        assert!(path.last_move_crosses());
+    }
+
+    #[test]
+    fn far_apart_segments_share_no_cells() {
+        let mut grid = SegmentGrid::default();
+        grid.insert(LineSegment {
+            start: Point::new(0., 0.),
+            end: Point::new(10., 10.),
+        });
+        let far = LineSegment {
+            start: Point::new(500., 500.),
+            end: Point::new(510., 500.),
+        };
+        assert!(grid.near(&far).is_empty());
+    }
+
+    #[test]
+    fn turtles_are_found_by_their_cells() {
+        let mut grid = TurtleGrid::default();
+        for index in 0..1000 {
+            grid.place(index, ScreenPosition::new(index as f32 * 10., 0.));
+        }
+        let near = |grid: &TurtleGrid| {
+            let mut near =
+                grid.within(ScreenPosition::new(95., 5.), ScreenPosition::new(125., -5.));
+            near.sort_unstable();
+            near
+        };
+        // the two cells from 64 to 128 pixels
+        assert_eq!(near(&grid), (7..=12).collect::<Vec<_>>());
+
+        grid.place(500, ScreenPosition::new(100., 1.));
+        grid.place(8, ScreenPosition::new(-100., 0.));
+        assert_eq!(near(&grid), [7, 9, 10, 11, 12, 500]);
+
+        grid.truncate(10);
+        assert_eq!(near(&grid), [7, 9]);
+        let everywhere = ScreenPosition::new(f32::MAX, f32::MAX);
+        assert_eq!(grid.within(-everywhere, everywhere).len(), 10);
+    }
+
+    #[test]
+    fn shapes_overlap_when_one_is_inside_the_other() {
+        let square = |size: f32| {
+            vec![vec![
+                Point::new(-size, -size),
+                Point::new(size, -size),
+                Point::new(size, size),
+                Point::new(-size, size),
+            ]]
+        };
+        assert!(outlines_overlap(&square(10.), &square(2.)));
+
+        let shape = TurtleShape::default();
+        let here = shape_outline(&shape, ScreenPosition::new(0., 0.), 0.);
+        let near = shape_outline(&shape, ScreenPosition::new(-5., 0.), 90.);
+        let far = shape_outline(&shape, ScreenPosition::new(100., 0.), 0.);
+        assert!(outlines_overlap(&here, &near));
+        assert!(!outlines_overlap(&here, &far));
+    }
+}
@@ -1,6 +1,13 @@
 use std::time::Duration;
 
-use crate::{color_names::TurtleColor, polygon::TurtleShapeName, speed::Speed, Shape, Turtle};
+use crate::{
+    color_names::TurtleColor,
+    polygon::TurtleShapeName,
+    speed::{Easing, Speed},
+    turtle::types::{TimerID, TurtleID},
+    undo::UndoBufferSize,
+    FillRule, FillStyle, LineCap, LineDash, LineJoin, Shape, Turtle, TurtleMode,
+};
 
 //
 // A DrawRequest is something that the turtle thread asks us to put on the screen.
@@ -18,18 +25,30 @@ pub enum TimedDrawCmd {
     Motion(MotionCmd),
     Rotate(RotateCmd),
     Circle(f32, f32, usize),
+    Curve(CurveCmd, usize), // curve, steps
     Undo,
+    Redo,
 }
 
 #[derive(Clone, Debug)]
 pub enum MotionCmd {
     Forward(f32),
     GoTo(f32, f32),
-    Teleport(f32, f32),
+    Teleport(f32, f32, bool), // x, y, fill_gap
     SetX(f32),
     SetY(f32),
 }
 
+// Bezier control points are relative to the turtle: x is the distance forward
+// and y the distance to the left
+#[derive(Clone, Debug)]
+pub enum CurveCmd {
+    Quadratic([f32; 2], [f32; 2]),       // control, end
+    Cubic([f32; 2], [f32; 2], [f32; 2]), // control, control, end
+    Ellipse(f32, f32, f32),              // radius forward, radius to the left, extent
+    Arc([f32; 2], f32),                  // center, extent
+}
+
 #[derive(Clone, Debug)]
 pub enum RotateCmd {
     Right(f32),
@@ -43,12 +62,18 @@ pub enum InstantaneousDrawCmd {
     PenUp,
     PenColor(TurtleColor),
     FillColor(TurtleColor),
+    FillStyle(FillStyle),
+    FillRule(FillRule),
     PenWidth(f32),
+    LineDash(LineDash),
+    LineCap(LineCap),
+    LineJoin(LineJoin),
     Dot(Option<f32>, TurtleColor),
     Stamp,
     Tracer(bool),
     BeginFill,
     EndFill,
+    NextContour,
     BeginPoly,
     EndPoly,
     SetDegrees(f32),
@@ -65,22 +90,31 @@ pub enum ScreenCmd {
     ClearStamp(usize),
     ClearStamps(isize),
     Speed(Speed),
+    Duration(Option<Duration>), // how long each command takes, instead of going by speed
+    Easing(Easing),
+    UndoBuffer(UndoBufferSize),
     ShowTurtle(bool),
     SetSize([isize; 2]),
     RegisterShape(String, Shape),
     SetTitle(String),
+    SetWorldCoordinates(f32, f32, f32, f32), // llx, lly, urx, ury
+    Mode(TurtleMode),
     ExitOnClick,
     Bye,
 }
 
+// A handler of `None` removes the current binding
 #[derive(Copy, Clone, Debug)]
 pub enum InputCmd {
-    KeyPress(fn(&mut Turtle, char), char),
-    KeyRelease(fn(&mut Turtle, char), char),
-    MousePress(fn(&mut Turtle, x: f32, y: f32)),
-    MouseRelease(fn(&mut Turtle, x: f32, y: f32)),
-    MouseDrag(fn(&mut Turtle, x: f32, y: f32)),
-    Timer(fn(&mut Turtle, Duration), Duration),
+    KeyPress(Option<fn(&mut Turtle, char)>, char),
+    KeyRelease(Option<fn(&mut Turtle, char)>, char),
+    MousePress(Option<fn(&mut Turtle, x: f32, y: f32)>),
+    MouseRelease(Option<fn(&mut Turtle, x: f32, y: f32)>),
+    MouseDrag(Option<fn(&mut Turtle, x: f32, y: f32)>),
+    Close(Option<fn(&mut Turtle)>),
+    Listen(bool),
+    Timer(fn(&mut Turtle, Duration), Duration, bool), // func, delay, repeat
+    CancelTimer(TimerID),
 }
 
 // Commands which return data
@@ -95,8 +129,12 @@ pub enum DataCmd {
     TurtleShape(TurtleShapeName),
     UndoBufferEntries,
     Towards(f32, f32),
+    Overlaps(TurtleID),
+    TurtlesNear(f32, f32, f32), // x, y, radius
+    CrossesDrawing,
     Position,
     Heading,
+    GetMode,
     Stamp,
     Visibility,
     TextInput(String, String), // title, prompt
@@ -110,6 +148,7 @@ pub enum Command {
     Input(InputCmd),
     Data(DataCmd),
     Hatch,
+    CloneTurtle,
     ShutDown,
 }
 
@@ -118,6 +157,19 @@ impl DrawRequest {
         matches!(self, Self::InstantaneousDraw(InstantaneousDrawCmd::Stamp))
     }
 
+    // undo(), redo() and tracer() don't go in the undo buffer
+    pub(crate) const fn is_undoable(&self) -> bool {
+        !matches!(
+            self,
+            Self::TimedDraw(TimedDrawCmd::Undo | TimedDrawCmd::Redo)
+                | Self::InstantaneousDraw(InstantaneousDrawCmd::Tracer(_))
+        )
+    }
+
+    pub(crate) const fn is_timed(&self) -> bool {
+        matches!(self, Self::TimedDraw(_))
+    }
+
     pub(crate) const fn tracer_true(&self) -> bool {
         matches!(
             self,
@@ -1,6 +1,6 @@
 use crate::{
     command::Command,
-    turtle::types::{TurtleID, TurtleThread},
+    turtle::types::{TimerID, TurtleID, TurtleThread},
     Turtle,
 };
 
@@ -9,8 +9,10 @@ pub enum Response {
     Done,
     Cancel,
     Heading(f32),
-    Position(crate::ScreenPosition<i32>),
+    Mode(crate::TurtleMode),
+    Position(crate::ScreenPosition<f32>), // in world coordinates
     StampID(crate::StampID),
+    TimerID(TimerID),
     Turtle(crate::Turtle),
     Count(usize),
     Name(String),
@@ -21,8 +23,10 @@ pub enum Response {
     NumInput(f32),
     IsPenDown(bool),
     IsFilling(bool),
+    Touching(bool),
     ShapeList(Vec<String>),
     Turtles(Vec<Turtle>),
+    Error(String), // the command couldn't be done, and why
 }
 
 #[derive(Debug)]
@@ -0,0 +1,206 @@
+#![allow(clippy::cast_precision_loss)]
+
+use lyon_tessellation::geom::euclid::default::Vector2D;
+
+use crate::ScreenPosition;
+
+/// How headings are measured. In `Standard` and `World` mode the turtle starts
+/// facing east and angles run counterclockwise; in `Logo` mode it starts facing
+/// north and angles run clockwise.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TurtleMode {
+    #[default]
+    Standard,
+    Logo,
+    World, // set by `setworldcoordinates()`
+}
+
+impl TurtleMode {
+    // The turtle's internal angle runs clockwise from east (on the screen),
+    // in degrees. These convert to and from a heading in this mode.
+    pub(crate) fn heading(self, angle: f32) -> f32 {
+        match self {
+            Self::Standard | Self::World => -angle,
+            Self::Logo => angle + 90.,
+        }
+        .rem_euclid(360.)
+    }
+
+    pub(crate) fn angle(self, heading: f32) -> f32 {
+        match self {
+            Self::Standard | Self::World => -heading,
+            Self::Logo => heading - 90.,
+        }
+    }
+}
+
+/// # Panics
+/// Panics if the name isn't "standard", "logo" or "world"
+impl From<&str> for TurtleMode {
+    fn from(value: &str) -> Self {
+        match value {
+            "standard" => Self::Standard,
+            "logo" => Self::Logo,
+            "world" => Self::World,
+            _ => panic!("no turtle-graphics mode {value}"),
+        }
+    }
+}
+
+// Maps the user's ("world") coordinates onto the screen. Screen coordinates are
+// pixels with (0, 0) in the center of the window and y pointing down; world
+// coordinates have y pointing up, as in python's turtle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct WorldCoords {
+    origin: ScreenPosition<f32>, // where the world's (0, 0) is on the screen
+    scale: [f32; 2],             // pixels per world unit
+    corners: Option<[f32; 4]>,   // from setworldcoordinates(), to redo on resizes
+}
+
+impl Default for WorldCoords {
+    fn default() -> Self {
+        Self {
+            origin: ScreenPosition::new(0., 0.),
+            scale: [1., 1.],
+            corners: None,
+        }
+    }
+}
+
+impl WorldCoords {
+    // map the rectangle with lower left (llx, lly) and upper right (urx, ury)
+    // onto a window of the given size, which both need a width and a height
+    pub(crate) fn new(
+        winsize: [isize; 2],
+        llx: f32,
+        lly: f32,
+        urx: f32,
+        ury: f32,
+    ) -> Result<Self, String> {
+        Self::check([llx, lly, urx, ury])?;
+        let width = winsize[0] as f32;
+        let height = winsize[1] as f32;
+        let scale = [width / (urx - llx), height / (ury - lly)];
+        if !scale.iter().all(|s| s.is_normal()) {
+            return Err(format!(
+                "there's no room for the world in a {width}x{height} window"
+            ));
+        }
+
+        Ok(Self {
+            origin: ScreenPosition::new(-width / 2. - llx * scale[0], height / 2. + lly * scale[1]),
+            scale,
+            corners: Some([llx, lly, urx, ury]),
+        })
+    }
+
+    // a world has to have a width and a height
+    fn check([llx, lly, urx, ury]: [f32; 4]) -> Result<(), String> {
+        if (urx - llx).is_normal() && (ury - lly).is_normal() {
+            Ok(())
+        } else {
+            Err(format!(
+                "({llx}, {lly}) and ({urx}, {ury}) aren't the corners of a rectangle"
+            ))
+        }
+    }
+
+    // the same world mapped onto a window of a new size, if it was set up by
+    // setworldcoordinates() and it still fits
+    pub(crate) fn resized(self, winsize: [isize; 2]) -> Option<Self> {
+        let [llx, lly, urx, ury] = self.corners?;
+        Self::new(winsize, llx, lly, urx, ury)
+            .ok()
+            .filter(|world| *world != self)
+    }
+
+    pub(crate) fn to_screen(self, x: f32, y: f32) -> ScreenPosition<f32> {
+        ScreenPosition::new(
+            self.origin.x + x * self.scale[0],
+            self.origin.y - y * self.scale[1],
+        )
+    }
+
+    pub(crate) fn to_world(self, pos: ScreenPosition<f32>) -> ScreenPosition<f32> {
+        ScreenPosition::new(
+            (pos.x - self.origin.x) / self.scale[0],
+            (self.origin.y - pos.y) / self.scale[1],
+        )
+    }
+
+    // How far the turtle moves on the screen when it goes `distance` world
+    // units with the given heading (in degrees, clockwise when the scale is
+    // uniform, the same as the turtle's angle)
+    pub(crate) fn to_screen_vector(self, distance: f32, angle: f32) -> Vector2D<f32> {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Vector2D::new(
+            distance * cos * self.scale[0],
+            distance * sin * self.scale[1],
+        )
+    }
+
+    // With different x and y scales, headings are distorted on the screen.
+    // The result stays within 180 degrees of `angle` so that rotations are
+    // animated in the right direction.
+    pub(crate) fn screen_angle(self, angle: f32) -> f32 {
+        Self::distort(angle, self.scale[0], self.scale[1])
+    }
+
+    // the inverse of screen_angle(), which the tests check
+    #[cfg(test)]
+    fn world_angle(self, angle: f32) -> f32 {
+        Self::distort(angle, 1. / self.scale[0], 1. / self.scale[1])
+    }
+
+    fn distort(angle: f32, xscale: f32, yscale: f32) -> f32 {
+        if xscale == yscale {
+            return angle;
+        }
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let delta = (yscale * sin).atan2(xscale * cos).to_degrees() - angle;
+        angle + (delta + 180.).rem_euclid(360.) - 180.
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn corners_map_to_window_edges() {
+        let world = WorldCoords::new([400, 200], -1., 0., 1., 10.).unwrap();
+
+        assert_eq!(world.to_screen(-1., 0.), ScreenPosition::new(-200., 100.));
+        assert_eq!(world.to_screen(1., 10.), ScreenPosition::new(200., -100.));
+        assert_eq!(
+            world.to_world(ScreenPosition::new(0., 0.)),
+            ScreenPosition::new(0., 5.)
+        );
+
+        // a bigger window shows the same world, and an empty one shows nothing
+        let bigger = world.resized([800, 400]).unwrap();
+        assert_eq!(bigger.to_screen(1., 10.), ScreenPosition::new(400., -200.));
+        assert_eq!(world.resized([400, 200]), None);
+        assert!(WorldCoords::new([400, 200], 1., 0., 1., 10.).is_err());
+        assert!(WorldCoords::new([0, 200], -1., 0., 1., 10.).is_err());
+        assert_eq!(WorldCoords::default().resized([800, 400]), None);
+    }
+
+    #[test]
+    fn headings_follow_the_mode() {
+        // facing north, on the screen
+        assert!((TurtleMode::Standard.heading(-90.) - 90.).abs() < 0.001);
+        assert!(TurtleMode::Logo.heading(-90.).abs() < 0.001);
+        assert!((TurtleMode::Logo.heading(TurtleMode::Logo.angle(270.)) - 270.).abs() < 0.001);
+    }
+
+    #[test]
+    fn angles_keep_their_winding() {
+        let world = WorldCoords::new([400, 200], 0., 0., 1., 1.).unwrap();
+
+        assert!((world.screen_angle(45.) - 26.565_05).abs() < 0.001);
+        assert!((world.screen_angle(360.) - 360.).abs() < 0.001);
+        assert!((world.world_angle(world.screen_angle(-100.)) + 100.).abs() < 0.001);
+        assert!((world.screen_angle(-100.) + 109.425).abs() < 0.001);
+    }
+}
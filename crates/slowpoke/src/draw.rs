@@ -1,7 +1,9 @@
 mod animation;
 mod appearance;
 mod circle;
+mod collision;
 mod color_control;
+mod curve;
 mod dot;
 mod drawing_state;
 mod filling;
@@ -30,6 +32,13 @@ impl Turtle {
         self.do_hatch()
     }
 
+    /// Create a new turtle which is a copy of this one: same position,
+    /// heading, pen and fill colors, pen width, shape, visibility and speed.
+    #[must_use]
+    pub fn clone_turtle(&mut self) -> Self {
+        self.do_clone()
+    }
+
     /// # Panics
     /// Panics when it can't read the file
     pub fn bgpic<P: AsRef<Path>>(&mut self, path: P) {
@@ -52,7 +61,7 @@ impl Turtle {
 }
 
 // TODO: move to src/turtle/types.rs ??
-impl From<&Turtle> for ScreenPosition<i32> {
+impl From<&Turtle> for ScreenPosition<f32> {
     fn from(other_turtle: &Turtle) -> Self {
         other_turtle.pos()
     }
@@ -1,5 +1,8 @@
+use std::time::Duration;
+
 use crate::{
-    command::{DrawRequest, InstantaneousDrawCmd},
+    command::{DrawRequest, InstantaneousDrawCmd, ScreenCmd},
+    speed::Easing,
     Turtle,
 };
 
@@ -10,6 +13,19 @@ impl Turtle {
         ));
     }
 
+    /// Make every command take the same time to draw, however far it goes.
+    /// `None` goes back to timing them by speed and length. A speed of 0
+    /// still draws everything at once.
+    pub fn duration<D: Into<Option<Duration>>>(&mut self, duration: D) {
+        self.do_screen(ScreenCmd::Duration(duration.into()));
+    }
+
+    /// Set how the turtle speeds up and slows down during each command, e.g.
+    /// "easeinout", or a function from the time passed to the amount drawn
+    pub fn easing<E: Into<Easing>>(&mut self, easing: E) {
+        self.do_screen(ScreenCmd::Easing(easing.into()));
+    }
+
     // TODO: set delay in ms (use 0 to get current delay)
     // pub fn delay(delay: usize) -> usize {}
 
@@ -0,0 +1,61 @@
+#![allow(clippy::cast_possible_truncation)]
+use crate::{command::DataCmd, comms::Response, Turtle};
+
+/*
+ * Collisions
+ */
+impl Turtle {
+    /// Whether this turtle's shape overlaps the other turtle's, as drawn.
+    /// This compares just the two outlines; a turtle that `clearscreen()`
+    /// removed overlaps nothing.
+    ///
+    /// # Panics
+    /// Panics when there's a library bug
+    pub fn overlaps(&self, other: &Turtle) -> bool {
+        let response = self.do_data(DataCmd::Overlaps(other.id()));
+        match response {
+            Response::Touching(touching) => touching,
+            // the other turtle was removed by clearscreen()
+            Response::Error(_) => false,
+            _ => panic!("invalid response from turtle: {response:?}"),
+        }
+    }
+
+    /// All the turtles (including this one) within `radius` of the point,
+    /// nearest first. Turtles are kept in a grid of the screen as they move,
+    /// so only the ones close to the point are looked at.
+    ///
+    /// # Panics
+    /// Panics when there's a library bug
+    pub fn turtles_near<X: Into<f64>, Y: Into<f64>, R: Into<f64>>(
+        &self,
+        x: X,
+        y: Y,
+        radius: R,
+    ) -> Vec<Turtle> {
+        let response = self.do_data(DataCmd::TurtlesNear(
+            x.into() as f32,
+            y.into() as f32,
+            radius.into() as f32,
+        ));
+        if let Response::Turtles(turtles) = response {
+            turtles
+        } else {
+            panic!("invalid response from turtle: {response:?}");
+        }
+    }
+
+    /// Whether the turtle's last move went across (or along) a line it drew
+    /// earlier. Meeting the line it set off from doesn't count.
+    ///
+    /// # Panics
+    /// Panics when there's a library bug
+    pub fn intersects_drawn_path(&self) -> bool {
+        let response = self.do_data(DataCmd::CrossesDrawing);
+        if let Response::Touching(touching) = response {
+            touching
+        } else {
+            panic!("invalid response from turtle: {response:?}");
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     color_names::TurtleColor,
     command::{DrawRequest, InstantaneousDrawCmd},
-    Turtle,
+    FillStyle, Turtle,
 };
 
 impl Turtle {
@@ -19,4 +19,17 @@ impl Turtle {
             InstantaneousDrawCmd::FillColor(color.into()),
         ));
     }
+
+    /// Fill shapes with a gradient or hatching instead of a flat color, e.g.
+    /// `turtle.fillstyle(FillStyle::radial("white", "blue"))` or
+    /// `turtle.fillstyle("radial white blue")`. Use "solid" to go back to the
+    /// fill color.
+    ///
+    /// # Panics
+    /// Panics if a style given as a string can't be read
+    pub fn fillstyle<S: Into<FillStyle>>(&mut self, style: S) {
+        self.do_draw(DrawRequest::InstantaneousDraw(
+            InstantaneousDrawCmd::FillStyle(style.into()),
+        ));
+    }
 }
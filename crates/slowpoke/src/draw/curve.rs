@@ -0,0 +1,123 @@
+use crate::{
+    command::{CurveCmd, DrawRequest, TimedDrawCmd},
+    Turtle,
+};
+
+pub struct TurtleCurveProps<'a> {
+    curve: CurveCmd,
+    steps: usize,
+    turtle: &'a mut Turtle,
+}
+
+pub struct TurtleEllipseProps<'a> {
+    radius: [f64; 2],
+    steps: usize,
+    extent: f64,
+    turtle: &'a mut Turtle,
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn point(p: [f64; 2]) -> [f32; 2] {
+    [p[0] as f32, p[1] as f32]
+}
+
+impl Turtle {
+    /// Draw a quadratic Bezier curve. The points are relative to the turtle:
+    /// `[x, y]` is `x` units forward and `y` units to the left. The turtle ends
+    /// up at `end`, facing along the curve.
+    pub fn curve_to(&mut self, control: [f64; 2], end: [f64; 2]) -> TurtleCurveProps<'_> {
+        TurtleCurveProps {
+            curve: CurveCmd::Quadratic(point(control), point(end)),
+            steps: 32,
+            turtle: self,
+        }
+    }
+
+    /// Draw a cubic Bezier curve, with points relative to the turtle as in
+    /// `curve_to()`.
+    pub fn cubic_curve_to(
+        &mut self,
+        control1: [f64; 2],
+        control2: [f64; 2],
+        end: [f64; 2],
+    ) -> TurtleCurveProps<'_> {
+        TurtleCurveProps {
+            curve: CurveCmd::Cubic(point(control1), point(control2), point(end)),
+            steps: 32,
+            turtle: self,
+        }
+    }
+
+    /// Move `extent` degrees around the point `(x, y)`, counterclockwise if
+    /// `extent` is positive. The turtle ends up facing along the arc.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn arc<X: Into<f64>, Y: Into<f64>, E: Into<f64>>(
+        &mut self,
+        x: X,
+        y: Y,
+        extent: E,
+    ) -> TurtleCurveProps<'_> {
+        TurtleCurveProps {
+            curve: CurveCmd::Arc(point([x.into(), y.into()]), extent.into() as f32),
+            steps: 32,
+            turtle: self,
+        }
+    }
+
+    /// Draw an ellipse, like `circle()`: `radius_x` is the radius along the
+    /// turtle's heading and `radius_y` the radius to its left. The center is
+    /// `radius_y` units to the left of the turtle (to the right, if negative).
+    pub fn ellipse<X: Into<f64>, Y: Into<f64>>(
+        &mut self,
+        radius_x: X,
+        radius_y: Y,
+    ) -> TurtleEllipseProps<'_> {
+        TurtleEllipseProps {
+            radius: [radius_x.into(), radius_y.into()],
+            steps: 32,
+            extent: 360.,
+            turtle: self,
+        }
+    }
+}
+
+impl TurtleCurveProps<'_> {
+    pub fn with_steps<S: Into<usize>>(mut self, steps: S) -> Self {
+        self.steps = steps.into();
+        self
+    }
+}
+
+impl TurtleEllipseProps<'_> {
+    pub fn with_steps<S: Into<usize>>(mut self, steps: S) -> Self {
+        self.steps = steps.into();
+        self
+    }
+
+    pub fn with_extent<E: Into<f64>>(mut self, extent: E) -> Self {
+        self.extent = extent.into();
+        self
+    }
+}
+
+impl Drop for TurtleCurveProps<'_> {
+    fn drop(&mut self) {
+        self.turtle
+            .do_draw(DrawRequest::TimedDraw(TimedDrawCmd::Curve(
+                self.curve.clone(),
+                self.steps,
+            )));
+    }
+}
+
+impl Drop for TurtleEllipseProps<'_> {
+    #[allow(clippy::cast_possible_truncation)]
+    fn drop(&mut self) {
+        let [radius_x, radius_y] = point(self.radius);
+        self.turtle
+            .do_draw(DrawRequest::TimedDraw(TimedDrawCmd::Curve(
+                CurveCmd::Ellipse(radius_x, radius_y, self.extent as f32),
+                self.steps,
+            )));
+    }
+}
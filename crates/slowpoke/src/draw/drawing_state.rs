@@ -1,7 +1,7 @@
 use crate::{
     command::{DataCmd, DrawRequest, InstantaneousDrawCmd},
     comms::Response,
-    Turtle,
+    LineCap, LineDash, LineJoin, Turtle,
 };
 
 impl Turtle {
@@ -65,6 +65,28 @@ impl Turtle {
         self.pensize(width);
     }
 
+    /// Draw solid, "dashed" or "dotted" lines, or dashes with a custom pattern
+    /// of drawn and skipped lengths, e.g. `[12., 4., 2., 4.]`
+    pub fn linestyle<D: Into<LineDash>>(&mut self, dash: D) {
+        self.do_draw(DrawRequest::InstantaneousDraw(
+            InstantaneousDrawCmd::LineDash(dash.into()),
+        ));
+    }
+
+    /// How the ends of lines look: "butt" (the default), "round" or "square"
+    pub fn linecap<C: Into<LineCap>>(&mut self, cap: C) {
+        self.do_draw(DrawRequest::InstantaneousDraw(
+            InstantaneousDrawCmd::LineCap(cap.into()),
+        ));
+    }
+
+    /// How corners look: "round" (the default), "miter" or "bevel"
+    pub fn linejoin<J: Into<LineJoin>>(&mut self, join: J) {
+        self.do_draw(DrawRequest::InstantaneousDraw(
+            InstantaneousDrawCmd::LineJoin(join.into()),
+        ));
+    }
+
     pub fn clear(&mut self) {
         self.do_draw(DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::Clear));
     }
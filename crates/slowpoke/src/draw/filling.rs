@@ -1,7 +1,7 @@
 use crate::{
     command::{DataCmd, DrawRequest, InstantaneousDrawCmd},
     comms::Response,
-    Turtle,
+    FillRule, Turtle,
 };
 
 impl Turtle {
@@ -20,6 +20,23 @@ impl Turtle {
         ));
     }
 
+    /// Start another contour in the shape being filled, e.g. the inside of
+    /// an "O". Call it before moving to where the new contour begins: the
+    /// next point the turtle moves to is its first point. Where contours
+    /// overlap depends on `fillrule()`.
+    pub fn next_contour(&mut self) {
+        self.do_draw(DrawRequest::InstantaneousDraw(
+            InstantaneousDrawCmd::NextContour,
+        ));
+    }
+
+    /// "evenodd" (the default) or "nonzero": see [`FillRule`]
+    pub fn fillrule<R: Into<FillRule>>(&mut self, rule: R) {
+        self.do_draw(DrawRequest::InstantaneousDraw(
+            InstantaneousDrawCmd::FillRule(rule.into()),
+        ));
+    }
+
     pub fn end_fill(&mut self) {
         self.do_draw(DrawRequest::InstantaneousDraw(
             InstantaneousDrawCmd::EndFill,
@@ -2,6 +2,7 @@ use crate::{command::DataCmd, comms::Response, Turtle};
 
 impl Turtle {
     /// # Panics
+    /// Panics when there's a library bug
     pub fn textinput(&self, title: &str, prompt: &str) -> Option<String> {
         match self.do_data(DataCmd::TextInput(title.into(), prompt.into())) {
             Response::TextInput(string) => Some(string),
@@ -11,6 +12,7 @@ impl Turtle {
     }
 
     /// # Panics
+    /// Panics when there's a library bug
     pub fn numinput(&self, title: &str, prompt: &str) -> Option<f32> {
         match self.do_data(DataCmd::NumInput(title.into(), prompt.into())) {
             Response::NumInput(num) => Some(num),
@@ -5,6 +5,32 @@ use crate::{
     StampID, Turtle,
 };
 
+pub struct TurtleTeleportProps<'a> {
+    x: f64,
+    y: f64,
+    fill_gap: bool,
+    turtle: &'a mut Turtle,
+}
+
+impl TurtleTeleportProps<'_> {
+    /// Keep filling the same shape across the teleport, as if the turtle
+    /// had moved there with the pen up
+    pub fn with_fill_gap(mut self, fill_gap: bool) -> Self {
+        self.fill_gap = fill_gap;
+        self
+    }
+}
+
+impl Drop for TurtleTeleportProps<'_> {
+    #[allow(clippy::cast_possible_truncation)]
+    fn drop(&mut self) {
+        self.turtle
+            .do_draw(DrawRequest::TimedDraw(TimedDrawCmd::Motion(
+                MotionCmd::Teleport(self.x as f32, self.y as f32, self.fill_gap),
+            )));
+    }
+}
+
 impl Turtle {
     /*
      * Move and draw
@@ -67,7 +93,7 @@ impl Turtle {
         let x = xpos.into() as f32;
         let y = ypos.into() as f32;
         self.do_draw(DrawRequest::TimedDraw(TimedDrawCmd::Motion(
-            MotionCmd::GoTo(x, -y),
+            MotionCmd::GoTo(x, y),
         )));
     }
 
@@ -79,13 +105,20 @@ impl Turtle {
         self.goto(xpos, ypos);
     }
 
-    #[allow(clippy::cast_possible_truncation)]
-    pub fn teleport<X: Into<f64>, Y: Into<f64>>(&mut self, xpos: X, ypos: Y) {
-        let x = xpos.into() as f32;
-        let y = ypos.into() as f32;
-        self.do_draw(DrawRequest::TimedDraw(TimedDrawCmd::Motion(
-            MotionCmd::Teleport(x, -y),
-        )));
+    /// Move without drawing. While filling, the shape drawn so far is filled
+    /// and a new one starts where the turtle lands, unless you call
+    /// `.with_fill_gap(true)`.
+    pub fn teleport<X: Into<f64>, Y: Into<f64>>(
+        &mut self,
+        xpos: X,
+        ypos: Y,
+    ) -> TurtleTeleportProps<'_> {
+        TurtleTeleportProps {
+            x: xpos.into(),
+            y: ypos.into(),
+            fill_gap: false,
+            turtle: self,
+        }
     }
 
     pub fn setx<N: Into<f64>>(&mut self, xpos: N) {
@@ -99,7 +132,7 @@ impl Turtle {
     pub fn sety<N: Into<f32>>(&mut self, ypos: N) {
         let y = ypos.into();
         self.do_draw(DrawRequest::TimedDraw(TimedDrawCmd::Motion(
-            MotionCmd::SetY(-y),
+            MotionCmd::SetY(y),
         )));
     }
 
@@ -107,7 +140,7 @@ impl Turtle {
         #[allow(clippy::cast_possible_truncation)]
         let heading = heading.into() as f32;
         self.do_draw(DrawRequest::TimedDraw(TimedDrawCmd::Rotate(
-            RotateCmd::SetHeading(heading - 90.),
+            RotateCmd::SetHeading(heading),
         )));
     }
 
@@ -131,6 +164,7 @@ impl Turtle {
         }
     }
 
+    /// Remove a stamp. An id that isn't one of this turtle's stamps is ignored.
     pub fn clearstamp(&mut self, id: StampID) {
         self.do_screen(ScreenCmd::ClearStamp(id));
     }
@@ -145,6 +179,12 @@ impl Turtle {
         self.do_draw(DrawRequest::TimedDraw(TimedDrawCmd::Undo));
     }
 
+    /// Replay the last command that was undone. Issuing any other drawing
+    /// command forgets everything that could be redone.
+    pub fn redo(&mut self) {
+        self.do_draw(DrawRequest::TimedDraw(TimedDrawCmd::Redo));
+    }
+
     pub fn speed<S: Into<Speed>>(&mut self, speed: S) {
         self.do_screen(ScreenCmd::Speed(speed.into()));
     }
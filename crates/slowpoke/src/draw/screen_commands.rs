@@ -1,8 +1,8 @@
 use crate::{
     color_names::TurtleColor,
-    command::{DataCmd, ScreenCmd},
+    command::{Command, DataCmd, ScreenCmd},
     comms::Response,
-    Turtle,
+    Turtle, TurtleMode,
 };
 
 impl Turtle {
@@ -31,6 +31,7 @@ impl Turtle {
     }
 
     /// # Panics
+    /// Panics when there's a library bug
     pub fn getscreensize(&self) -> [isize; 2] {
         let response = self.do_data(DataCmd::GetScreenSize);
         if let Response::ScreenSize(size) = response {
@@ -48,10 +49,78 @@ impl Turtle {
         self.getscreensize()[0]
     }
 
+    /// Switch to "standard", "logo" or "world" mode. This resets every turtle,
+    /// and switching to standard or logo mode also undoes `setworldcoordinates()`.
+    pub fn mode<M: Into<TurtleMode>>(&mut self, mode: M) {
+        self.do_screen(ScreenCmd::Mode(mode.into()));
+    }
+
+    /// # Panics
+    /// Panics when there's a library bug
+    pub fn getmode(&self) -> TurtleMode {
+        let response = self.do_data(DataCmd::GetMode);
+        if let Response::Mode(mode) = response {
+            mode
+        } else {
+            panic!("invalid response from turtle: {response:?}");
+        }
+    }
+
+    /// Map the rectangle with lower left corner (`llx`, `lly`) and upper right
+    /// corner (`urx`, `ury`) onto the window. Positions, distances, headings and
+    /// mouse clicks are all in these coordinates afterwards, and the turtles
+    /// stay at the same coordinates, so they may move on the screen. Anything
+    /// already drawn is not rescaled, so call this before drawing. This also
+    /// switches to "world" mode.
+    ///
+    /// When the x and y scales differ, angles on the screen are distorted.
+    /// The mapping is redone whenever the window is resized.
+    ///
+    /// # Errors
+    /// Returns an error, and leaves the coordinates alone, when the rectangle
+    /// has no width or no height, or the window has no room for it
+    ///
+    /// # Panics
+    /// Panics when there's a library bug
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn setworldcoordinates<N: Into<f64>>(
+        &mut self,
+        llx: N,
+        lly: N,
+        urx: N,
+        ury: N,
+    ) -> Result<(), String> {
+        let [llx, lly, urx, ury] = [llx, lly, urx, ury].map(|n| n.into() as f32);
+        let response = self.do_command(Command::Screen(ScreenCmd::SetWorldCoordinates(
+            llx, lly, urx, ury,
+        )));
+        match response {
+            Response::Mode(_) => Ok(()),
+            Response::Error(e) => Err(e),
+            _ => panic!("invalid response from turtle: {response:?}"),
+        }
+    }
+
+    /// Keep the window open until it is closed, by the user or another
+    /// turtle's `bye()`, then return. Unlike `exitonclick()`, clicks are still
+    /// delivered to any `onclick()` handlers.
+    pub fn done(&mut self) {
+        self.wait_for_shutdown();
+    }
+
+    /// The same as `done()`.
+    pub fn mainloop(&mut self) {
+        self.done();
+    }
+
+    /// Wait for a mouse click, then close the window. This does not return;
+    /// the calling thread unwinds once the window has closed.
     pub fn exitonclick(&mut self) {
         self.do_screen(ScreenCmd::ExitOnClick);
     }
 
+    /// Close the window, which makes `run()` return. This does not return;
+    /// the calling thread unwinds once the window has closed.
     pub fn bye(&mut self) {
         self.do_screen(ScreenCmd::Bye);
     }
@@ -1,6 +1,7 @@
 use crate::{
-    command::{DataCmd, DrawRequest, InstantaneousDrawCmd},
+    command::{DataCmd, DrawRequest, InstantaneousDrawCmd, ScreenCmd},
     comms::Response,
+    undo::UndoBufferSize,
     Turtle,
 };
 
@@ -26,6 +27,13 @@ impl Turtle {
         }
     }
 
+    /// Limit how many commands can be undone (the default is 1000), or turn
+    /// undo off with `None`. Older commands are forgotten, which keeps memory
+    /// bounded for long-running drawings.
+    pub fn setundobuffer<S: Into<UndoBufferSize>>(&mut self, size: S) {
+        self.do_screen(ScreenCmd::UndoBuffer(size.into()));
+    }
+
     /// # Panics
     pub fn undobufferentries(&self) -> usize {
         if let Response::Count(count) = self.do_data(DataCmd::UndoBufferEntries) {
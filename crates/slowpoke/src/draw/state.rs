@@ -7,15 +7,15 @@ use crate::{command::DataCmd, comms::Response, ScreenPosition, Turtle};
 impl Turtle {
     /// # Panics
     /// Panics when there's a library bug
-    pub fn position(&self) -> ScreenPosition<i32> {
+    pub fn position(&self) -> ScreenPosition<f32> {
         if let Response::Position(pos) = self.do_data(DataCmd::Position) {
-            [pos.x, -pos.y].into()
+            pos
         } else {
             panic!("invalid response from turtle");
         }
     }
 
-    pub fn pos(&self) -> ScreenPosition<i32> {
+    pub fn pos(&self) -> ScreenPosition<f32> {
         self.position()
     }
 
@@ -31,11 +31,11 @@ impl Turtle {
         }
     }
 
-    pub fn xcor(&self) -> i32 {
+    pub fn xcor(&self) -> f32 {
         self.position().x
     }
 
-    pub fn ycor(&self) -> i32 {
+    pub fn ycor(&self) -> f32 {
         self.position().y
     }
 
@@ -49,9 +49,9 @@ impl Turtle {
         }
     }
 
-    pub fn distance<D: Into<ScreenPosition<i32>>>(&self, other: D) -> f64 {
+    pub fn distance<D: Into<ScreenPosition<f32>>>(&self, other: D) -> f64 {
         let self_pos = self.pos();
-        let other_pos: ScreenPosition<i32> = other.into();
+        let other_pos: ScreenPosition<f32> = other.into();
 
         let dx = f64::from(other_pos.x - self_pos.x);
         let dy = f64::from(other_pos.y - self_pos.y);
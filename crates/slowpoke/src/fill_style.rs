@@ -0,0 +1,393 @@
+use lyon_tessellation::geom::euclid::default::Vector2D;
+
+use crate::{gui::ops::Point, LineSegment, PolygonPath, Triangle, TurtleColor};
+
+// radial gradients are shaded across triangles no longer than this (in pixels)
+const MAX_TRIANGLE_EDGE: f32 = 8.;
+
+/// Which parts of a shape count as inside when its outline crosses itself or
+/// it has more than one contour. With `EvenOdd`, areas surrounded an even
+/// number of times are holes; with `NonZero`, only areas the contours wind
+/// around equally often in both directions are.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum FillRule {
+    #[default]
+    EvenOdd,
+    NonZero,
+}
+
+impl FillRule {
+    fn is_inside(self, winding: i32) -> bool {
+        match self {
+            Self::EvenOdd => winding % 2 != 0,
+            Self::NonZero => winding != 0,
+        }
+    }
+}
+
+/// # Panics
+/// Panics if the name isn't "evenodd" or "nonzero"
+impl From<&str> for FillRule {
+    fn from(value: &str) -> Self {
+        match value {
+            "evenodd" => Self::EvenOdd,
+            "nonzero" => Self::NonZero,
+            _ => panic!("no fill rule {value}"),
+        }
+    }
+}
+
+/// How `end_fill()` fills a shape. Gradients and hatching are fitted to the
+/// shape's bounds; angles are in degrees, counterclockwise from east.
+#[derive(Debug, Default, Copy, Clone)]
+pub enum FillStyle {
+    #[default]
+    Solid, // the fill color
+    LinearGradient(TurtleColor, TurtleColor, f32), // from, to, angle
+    RadialGradient(TurtleColor, TurtleColor),      // center, edge
+    Hatch(TurtleColor, f32, f32),                  // line color, spacing, angle
+}
+
+impl FillStyle {
+    pub fn linear<F: Into<TurtleColor>, T: Into<TurtleColor>, A: Into<f64>>(
+        from: F,
+        to: T,
+        angle: A,
+    ) -> Self {
+        #[allow(clippy::cast_possible_truncation)]
+        Self::LinearGradient(from.into(), to.into(), angle.into() as f32)
+    }
+
+    pub fn radial<C: Into<TurtleColor>, E: Into<TurtleColor>>(center: C, edge: E) -> Self {
+        Self::RadialGradient(center.into(), edge.into())
+    }
+
+    /// Lines of the given color drawn over the fill color
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn hatch<C: Into<TurtleColor>, S: Into<f64>, A: Into<f64>>(
+        color: C,
+        spacing: S,
+        angle: A,
+    ) -> Self {
+        Self::Hatch(color.into(), spacing.into() as f32, angle.into() as f32)
+    }
+
+    /// A single color to use for UIs that can't draw gradients or patterns
+    #[must_use]
+    pub fn flat_color(self, fill: TurtleColor) -> TurtleColor {
+        match self {
+            Self::Solid | Self::Hatch(..) => fill,
+            Self::LinearGradient(from, to, _) | Self::RadialGradient(from, to) => {
+                mix(from.color_or(&fill), to.color_or(&fill), 0.5)
+            }
+        }
+    }
+
+    /// For linear gradients, the points where the gradient starts and ends,
+    /// along with their colors
+    #[must_use]
+    pub fn linear_gradient(
+        self,
+        fill: TurtleColor,
+        outline: &[LineSegment],
+    ) -> Option<(Point, TurtleColor, Point, TurtleColor)> {
+        let Self::LinearGradient(from, to, angle) = self else {
+            return None;
+        };
+        let direction = direction(angle);
+        let (min, max) = extent(outline, direction);
+        let center = bounds_center(outline);
+        let offset = center.to_vector().dot(direction);
+        Some((
+            center + direction * (min - offset),
+            from.color_or(&fill),
+            center + direction * (max - offset),
+            to.color_or(&fill),
+        ))
+    }
+
+    /// The shape's triangles with the color at each corner. Colors vary
+    /// linearly across each triangle, so for a radial gradient pass the
+    /// polygon's `small_triangles()`.
+    #[must_use]
+    pub fn shaded_triangles(
+        self,
+        fill: TurtleColor,
+        outline: &[LineSegment],
+        triangles: &[Triangle],
+    ) -> Vec<[(Point, TurtleColor); 3]> {
+        if outline.is_empty() {
+            return Vec::new();
+        }
+
+        let color_at: Box<dyn Fn(Point) -> TurtleColor> = match self {
+            Self::Solid | Self::Hatch(..) => Box::new(move |_| fill),
+            Self::LinearGradient(..) => {
+                let (start, from, end, to) = self
+                    .linear_gradient(fill, outline)
+                    .expect("linear gradient");
+                let length = (end - start).square_length().max(f32::EPSILON);
+                Box::new(move |p| mix(from, to, (p - start).dot(end - start) / length))
+            }
+            Self::RadialGradient(center_color, edge_color) => {
+                let center = bounds_center(outline);
+                let radius = outline
+                    .iter()
+                    .map(|s| (s.start - center).length())
+                    .fold(f32::EPSILON, f32::max);
+                let (center_color, edge_color) =
+                    (center_color.color_or(&fill), edge_color.color_or(&fill));
+                Box::new(move |p| mix(center_color, edge_color, (p - center).length() / radius))
+            }
+        };
+
+        triangles
+            .iter()
+            .map(|t| t.map(|p| (p, color_at(p))))
+            .collect()
+    }
+
+    /// The hatch lines, clipped to the shape, and their color
+    #[must_use]
+    pub fn hatch_lines(
+        self,
+        fill: TurtleColor,
+        rule: FillRule,
+        outline: &[LineSegment],
+    ) -> Option<(TurtleColor, Vec<LineSegment>)> {
+        let Self::Hatch(color, spacing, angle) = self else {
+            return None;
+        };
+        let spacing = spacing.abs().max(1.);
+        let along = direction(angle);
+        let across = Vector2D::new(-along.y, along.x);
+
+        if outline.is_empty() {
+            return Some((color.color_or(&fill), Vec::new()));
+        }
+
+        // every contour is closed
+        let path: PolygonPath = outline.into();
+        let mut edges = Vec::new();
+        for contour in path.contour_points() {
+            for (i, start) in contour.iter().enumerate() {
+                let end = contour[(i + 1) % contour.len()];
+                edges.push(LineSegment {
+                    start: (*start).into(),
+                    end: end.into(),
+                });
+            }
+        }
+
+        let mut lines = Vec::new();
+        let (min, max) = extent(outline, across);
+        let mut offset = (min / spacing).ceil() * spacing;
+        while offset < max {
+            // where the line crosses the outline, and in which direction
+            let mut crossings: Vec<(Point, i32)> = edges
+                .iter()
+                .filter_map(|edge| {
+                    let a = edge.start.to_vector().dot(across) - offset;
+                    let b = edge.end.to_vector().dot(across) - offset;
+                    ((a < 0.) != (b < 0.)).then(|| {
+                        let winding = if a < 0. { 1 } else { -1 };
+                        (edge.start.lerp(edge.end, a / (a - b)), winding)
+                    })
+                })
+                .collect();
+            crossings.sort_by(|(p, _), (q, _)| {
+                p.to_vector()
+                    .dot(along)
+                    .total_cmp(&q.to_vector().dot(along))
+            });
+
+            let mut winding = 0;
+            let mut start = None;
+            for (point, direction) in crossings {
+                winding += direction;
+                match (start, rule.is_inside(winding)) {
+                    (None, true) => start = Some(point),
+                    (Some(begin), false) => {
+                        lines.push(LineSegment {
+                            start: begin,
+                            end: point,
+                        });
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+            offset += spacing;
+        }
+
+        Some((color.color_or(&fill), lines))
+    }
+}
+
+/// Reads a style such as "solid", "linear red yellow 45",
+/// "radial white blue" or "hatch black 8 30". Colors are names or #rrggbb.
+///
+/// # Panics
+/// Panics if the style isn't one of those, or a number can't be read
+impl From<&str> for FillStyle {
+    fn from(value: &str) -> Self {
+        let mut words = value.split_whitespace();
+        let mut next = || {
+            words
+                .next()
+                .unwrap_or_else(|| panic!("fill style {value:?} ends early"))
+        };
+        let number = |word: &str| -> f32 {
+            word.parse()
+                .unwrap_or_else(|_| panic!("can't read {word} in fill style {value:?}"))
+        };
+        let style = match next() {
+            "solid" => Self::Solid,
+            "linear" => Self::LinearGradient(next().into(), next().into(), number(next())),
+            "radial" => Self::RadialGradient(next().into(), next().into()),
+            "hatch" => Self::Hatch(next().into(), number(next()), number(next())),
+            _ => panic!("no fill style {value:?}"),
+        };
+        assert!(
+            words.next().is_none(),
+            "fill style {value:?} has too many words"
+        );
+        style
+    }
+}
+
+fn mix(from: TurtleColor, to: TurtleColor, t: f32) -> TurtleColor {
+    let t = t.clamp(0., 1.);
+    let from: [f32; 4] = from.into();
+    let to: [f32; 4] = to.into();
+    let mut mixed = from;
+    for (m, to) in mixed.iter_mut().zip(to) {
+        *m += (to - *m) * t;
+    }
+    mixed.into()
+}
+
+// the screen's y axis points down
+fn direction(angle: f32) -> Vector2D<f32> {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    Vector2D::new(cos, -sin)
+}
+
+// the smallest and largest distance along `direction` of the outline's points
+fn extent(outline: &[LineSegment], direction: Vector2D<f32>) -> (f32, f32) {
+    outline
+        .iter()
+        .flat_map(|s| [s.start, s.end])
+        .map(|p| p.to_vector().dot(direction))
+        .fold((f32::MAX, f32::MIN), |(min, max), d| {
+            (min.min(d), max.max(d))
+        })
+}
+
+fn bounds_center(outline: &[LineSegment]) -> Point {
+    let (xmin, xmax) = extent(outline, Vector2D::new(1., 0.));
+    let (ymin, ymax) = extent(outline, Vector2D::new(0., 1.));
+    Point::new((xmin + xmax) / 2., (ymin + ymax) / 2.)
+}
+
+// split triangles in four until none of their edges are too long
+pub(crate) fn subdivide(mut triangles: Vec<Triangle>) -> Vec<Triangle> {
+    let mut result = Vec::new();
+    while let Some([a, b, c]) = triangles.pop() {
+        let longest = [(a - b), (b - c), (c - a)]
+            .iter()
+            .map(|v| v.length())
+            .fold(0., f32::max);
+        if longest <= MAX_TRIANGLE_EDGE {
+            result.push([a, b, c]);
+        } else {
+            let (ab, bc, ca) = (a.lerp(b, 0.5), b.lerp(c, 0.5), c.lerp(a, 0.5));
+            triangles.extend([[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn square() -> Vec<LineSegment> {
+        let corners = [[0., 0.], [20., 0.], [20., 20.], [0., 20.], [0., 0.]];
+        corners
+            .windows(2)
+            .map(|w| LineSegment {
+                start: w[0].into(),
+                end: w[1].into(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gradients_span_the_shape() {
+        let style = FillStyle::linear("black", "white", 90);
+        let (start, _, end, _) = style.linear_gradient("red".into(), &square()).unwrap();
+
+        // bottom to top, on the screen
+        assert_eq!(start, Point::new(10., 20.));
+        assert_eq!(end, Point::new(10., 0.));
+    }
+
+    #[test]
+    fn styles_can_be_written_out() {
+        assert!(matches!(FillStyle::from("solid"), FillStyle::Solid));
+        assert!(matches!(
+            FillStyle::from("linear red #ffff00 45"),
+            FillStyle::LinearGradient(_, TurtleColor::Color(1., 1., 0.), 45.)
+        ));
+        assert!(matches!(
+            FillStyle::from(" radial  white blue "),
+            FillStyle::RadialGradient(..)
+        ));
+        assert!(matches!(
+            FillStyle::from("hatch black 8 30"),
+            FillStyle::Hatch(_, 8., 30.)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "ends early")]
+    fn styles_need_all_their_words() {
+        let _ = FillStyle::from("hatch black 8");
+    }
+
+    #[test]
+    fn hatching_is_clipped_to_the_shape() {
+        let style = FillStyle::hatch("black", 5, 0);
+        let (_, lines) = style
+            .hatch_lines("red".into(), FillRule::EvenOdd, &square())
+            .unwrap();
+
+        // the lines at y = 0 and y = 20 only touch the corners
+        assert_eq!(lines.len(), 3);
+        for line in lines {
+            assert_eq!((line.end - line.start).length(), 20.);
+        }
+    }
+
+    #[test]
+    fn hatching_follows_the_fill_rule() {
+        // two squares, one inside the other, both drawn counterclockwise
+        let mut outline = square();
+        outline.extend(square().iter().map(|s| LineSegment {
+            start: s.start * 0.6 + Vector2D::new(4., 4.),
+            end: s.end * 0.6 + Vector2D::new(4., 4.),
+        }));
+        let style = FillStyle::hatch("black", 5, 0);
+
+        // every line goes through the inner square, which is a hole for even-odd
+        let (_, lines) = style
+            .hatch_lines("red".into(), FillRule::EvenOdd, &outline)
+            .unwrap();
+        assert_eq!(lines.len(), 6);
+        let (_, lines) = style
+            .hatch_lines("red".into(), FillRule::NonZero, &outline)
+            .unwrap();
+        assert_eq!(lines.len(), 3);
+    }
+}
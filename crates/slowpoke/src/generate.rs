@@ -3,16 +3,18 @@
 
 use std::f32::consts::PI;
 
-use lyon_tessellation::{
-    geom::euclid::{default::Point2D, default::Transform2D},
-    math::Angle,
+use lyon_tessellation::geom::{
+    euclid::default::{Point2D, Vector2D},
+    CubicBezierSegment, QuadraticBezierSegment,
 };
 
 use crate::{
     color_names::TurtleColor,
-    command::{DrawRequest, InstantaneousDrawCmd, MotionCmd, RotateCmd, TimedDrawCmd},
+    command::{CurveCmd, DrawRequest, InstantaneousDrawCmd, MotionCmd, RotateCmd, TimedDrawCmd},
+    coords::{TurtleMode, WorldCoords},
+    line_style::LineStyle,
     polygon::PolygonPath,
-    ScreenPosition,
+    FillRule, FillStyle, ScreenPosition,
 };
 
 #[derive(Debug, Default, Clone)]
@@ -44,15 +46,17 @@ pub enum DrawCommand {
     Filled(usize),
     BeginFill,
     EndFill,
+    NextContour,
     BeginPoly,
     EndPoly,
     StampTurtle,
     Line(LineInfo),
     SetPenColor(TurtleColor),
     SetPenWidth(f32),
+    SetLineStyle(LineStyle),
     SetFillColor(TurtleColor),
     SetPosition(ScreenPosition<i32>),
-    DrawPolygon(PolygonPath),
+    DrawPolygon(PolygonPath, FillStyle),
     SetHeading(f32, f32),
     Dot(Point2D<f32>, f32, TurtleColor), // center, radius, color
     DrawPolyAt(PolygonPath, ScreenPosition<f32>, f32), // poly, pos, angle
@@ -67,15 +71,44 @@ impl DrawCommand {
             Self::Line(..) | Self::SetHeading(..) | Self::Circle(..)
         )
     }
+
+    // how far the turtle moves (in pixels), or turns (in degrees) if it stays
+    // put; the time it takes to draw depends on this
+    pub(crate) fn length(&self) -> f32 {
+        fn distance(dx: i32, dy: i32) -> f32 {
+            f64::from(dx).hypot(f64::from(dy)) as f32
+        }
+
+        match self {
+            Self::Line(line) => distance(line.end.x - line.begin.x, line.end.y - line.begin.y),
+            Self::SetHeading(start, end) => (end - start).abs(),
+            Self::Circle(points) => {
+                let arc: f32 = points
+                    .windows(2)
+                    .map(|p| distance(p[1].x - p[0].x, p[1].y - p[0].y))
+                    .sum();
+                match (points.first(), points.last()) {
+                    (Some(first), Some(last)) if arc == 0. => (last.angle - first.angle).abs(),
+                    _ => arc,
+                }
+            }
+            _ => 0.,
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct CurrentTurtleState {
-    pub transform: Transform2D<f32>,
-    pub angle: f32,
+    position: ScreenPosition<f32>,
+    angle: f32, // in world coordinates
+    world: WorldCoords,
+    mode: TurtleMode,
     pen_down: bool,
     pen_width: f32,
+    line_style: LineStyle,
     fill_color: TurtleColor,
+    fill_style: FillStyle,
+    fill_rule: FillRule,
     pen_color: TurtleColor,
     circle_units: f32, // number of "degrees" in a circle
 }
@@ -86,17 +119,13 @@ pub(crate) trait TurtlePosition<T> {
 
 impl TurtlePosition<f32> for CurrentTurtleState {
     fn pos(&self) -> ScreenPosition<f32> {
-        self.transform
-            .transform_point(ScreenPosition::new(0f32, 0f32))
+        self.position
     }
 }
 
 impl TurtlePosition<i32> for CurrentTurtleState {
     fn pos(&self) -> ScreenPosition<i32> {
-        let point = self
-            .transform
-            .transform_point(ScreenPosition::new(0f32, 0f32));
-        ScreenPosition::new(point.x as i32, point.y as i32)
+        ScreenPosition::new(self.position.x as i32, self.position.y as i32)
     }
 }
 
@@ -104,19 +133,67 @@ impl Default for CurrentTurtleState {
     fn default() -> Self {
         Self {
             pen_down: true,
-            transform: Transform2D::identity(),
+            position: ScreenPosition::new(0., 0.),
             angle: 0.,
+            world: WorldCoords::default(),
+            mode: TurtleMode::default(),
             pen_width: 1.,
+            line_style: LineStyle::default(),
             pen_color: "black".into(),
             fill_color: "black".into(),
+            fill_style: FillStyle::default(),
+            fill_rule: FillRule::default(),
             circle_units: 360.,
         }
     }
 }
 
 impl CurrentTurtleState {
-    pub fn angle(&self) -> f32 {
-        self.angle
+    // the turtle's heading in the current mode, in degrees
+    pub fn heading(&self) -> f32 {
+        self.mode.heading(self.angle)
+    }
+
+    // the heading from the turtle to the given point, in degrees
+    pub fn towards(&self, x: f32, y: f32) -> f32 {
+        let pos = self.world_pos();
+        let angle = -(y - pos.y).atan2(x - pos.x).to_degrees();
+        self.mode.heading(angle)
+    }
+
+    // the angle at which to draw the turtle
+    pub fn screen_angle(&self) -> f32 {
+        self.world.screen_angle(self.angle)
+    }
+
+    pub fn world_pos(&self) -> ScreenPosition<f32> {
+        self.world.to_world(self.position)
+    }
+
+    // Adopt the screen's coordinate system and mode without moving the turtle;
+    // call `reset()` to send it home
+    pub(crate) fn set_coords(&mut self, world: WorldCoords, mode: TurtleMode) {
+        self.world = world;
+        self.mode = mode;
+    }
+
+    // Switch to a new coordinate system, keeping the turtle at the same world
+    // coordinates. Returns the commands needed to move it there on the screen.
+    pub(crate) fn set_world(&mut self, world: WorldCoords) -> Vec<DrawCommand> {
+        let pos = self.world_pos();
+        let old_point = self.get_point();
+        let old_angle = self.screen_angle();
+        self.world = world;
+        self.position = world.to_screen(pos.x, pos.y);
+
+        let mut cmds = Vec::new();
+        if self.get_point() != old_point {
+            cmds.push(DrawCommand::SetPosition(self.get_point()));
+        }
+        if old_angle != self.screen_angle() {
+            cmds.push(DrawCommand::SetHeading(old_angle, self.screen_angle()));
+        }
+        cmds
     }
 
     fn get_point(&self) -> ScreenPosition<i32> {
@@ -131,7 +208,7 @@ impl CurrentTurtleState {
     fn get_circlepos(&self) -> CirclePos {
         let point = self.get_point();
         CirclePos {
-            angle: self.angle,
+            angle: self.screen_angle(),
             x: point.x,
             y: point.y,
             pen_down: self.pen_down,
@@ -141,8 +218,9 @@ impl CurrentTurtleState {
     pub(crate) fn get_state(&self) -> Vec<DrawCommand> {
         vec![
             DrawCommand::SetPosition(self.get_point()),
-            DrawCommand::SetHeading(0., self.angle),
+            DrawCommand::SetHeading(0., self.screen_angle()),
             DrawCommand::SetPenWidth(self.pen_width),
+            DrawCommand::SetLineStyle(self.line_style.clone()),
             DrawCommand::SetPenColor(self.pen_color),
             DrawCommand::SetFillColor(self.fill_color),
         ]
@@ -154,35 +232,29 @@ impl CurrentTurtleState {
                 TimedDrawCmd::Circle(radius, extent, steps) => {
                     return Some(self.create_circle(*radius, *extent, *steps));
                 }
+                TimedDrawCmd::Curve(curve, steps) => {
+                    return Some(self.create_curve(curve, *steps));
+                }
                 TimedDrawCmd::Motion(motion) => {
                     return Some(self.create_motion(motion));
                 }
                 TimedDrawCmd::Rotate(rotation) => {
-                    let start = self.angle;
+                    let start = self.screen_angle();
                     match rotation {
                         RotateCmd::Right(angle) => {
-                            let angle = angle * (360. / self.circle_units);
-                            let radians = Angle::degrees(angle);
-                            self.transform = self.transform.pre_rotate(radians);
-                            self.angle += angle;
+                            self.angle += angle * (360. / self.circle_units);
                         }
                         RotateCmd::Left(angle) => {
-                            let angle = angle * (360. / self.circle_units);
-                            let radians = Angle::degrees(-angle);
-                            self.transform = self.transform.pre_rotate(radians);
-                            self.angle -= angle;
+                            self.angle -= angle * (360. / self.circle_units);
                         }
                         RotateCmd::SetHeading(h) => {
                             let h = h * (360. / self.circle_units);
-                            let h = 180. - h;
-                            let radians = Angle::degrees(h - self.angle + 90.);
-                            self.transform = self.transform.pre_rotate(radians);
-                            self.angle = h + 90.;
+                            self.angle = self.mode.angle(h);
                         }
                     }
-                    return Some(DrawCommand::SetHeading(start, self.angle));
+                    return Some(DrawCommand::SetHeading(start, self.screen_angle()));
                 }
-                TimedDrawCmd::Undo => {}
+                TimedDrawCmd::Undo | TimedDrawCmd::Redo => {}
             },
             DrawRequest::InstantaneousDraw(id) => match id {
                 InstantaneousDrawCmd::Reset => {
@@ -209,10 +281,28 @@ impl CurrentTurtleState {
                     self.fill_color = *fc;
                     return Some(DrawCommand::SetFillColor(*fc));
                 }
+                InstantaneousDrawCmd::FillStyle(style) => {
+                    self.fill_style = *style;
+                }
+                InstantaneousDrawCmd::FillRule(rule) => {
+                    self.fill_rule = *rule;
+                }
                 InstantaneousDrawCmd::PenWidth(pw) => {
                     self.pen_width = *pw / 2.;
                     return Some(DrawCommand::SetPenWidth(*pw / 2.));
                 }
+                InstantaneousDrawCmd::LineDash(dash) => {
+                    self.line_style.dash = dash.clone();
+                    return Some(DrawCommand::SetLineStyle(self.line_style.clone()));
+                }
+                InstantaneousDrawCmd::LineCap(cap) => {
+                    self.line_style.cap = *cap;
+                    return Some(DrawCommand::SetLineStyle(self.line_style.clone()));
+                }
+                InstantaneousDrawCmd::LineJoin(join) => {
+                    self.line_style.join = *join;
+                    return Some(DrawCommand::SetLineStyle(self.line_style.clone()));
+                }
                 InstantaneousDrawCmd::Dot(size, color) => {
                     let size = if let Some(size) = size {
                         *size
@@ -233,6 +323,7 @@ impl CurrentTurtleState {
                 }
                 InstantaneousDrawCmd::BeginFill => return Some(DrawCommand::BeginFill),
                 InstantaneousDrawCmd::EndFill => return Some(DrawCommand::EndFill),
+                InstantaneousDrawCmd::NextContour => return Some(DrawCommand::NextContour),
                 InstantaneousDrawCmd::BeginPoly => return Some(DrawCommand::BeginPoly),
                 InstantaneousDrawCmd::EndPoly => return Some(DrawCommand::EndPoly),
                 InstantaneousDrawCmd::Text(t) => {
@@ -244,34 +335,50 @@ impl CurrentTurtleState {
         None
     }
 
-    pub(crate) fn undo(&mut self, cmd: &DrawCommand) {
-        match cmd {
-            DrawCommand::Line(line) => {
-                let x = line.begin.x as f32;
-                let y = line.begin.y as f32;
-                self.transform = Transform2D::translation(x, y);
-            }
-            DrawCommand::SetHeading(start, _) => {
-                self.angle = *start;
-            }
-            _ => {}
-        }
-    }
-
-    pub(crate) fn radians_to_turtle(&self, radians: f32) -> f32 {
-        radians * (self.circle_units / (2. * PI))
+    // go back to an earlier state, in the screen's current coordinate system
+    pub(crate) fn restore(&mut self, saved: Self) {
+        *self = Self {
+            world: self.world,
+            mode: self.mode,
+            ..saved
+        };
     }
 
     pub(crate) fn degrees_to_turtle(&self, degrees: f32) -> f32 {
         degrees * (self.circle_units / 360.)
     }
 
+    pub(crate) fn fill_style(&self) -> FillStyle {
+        self.fill_style
+    }
+
+    pub(crate) fn fill_rule(&self) -> FillRule {
+        self.fill_rule
+    }
+
     pub(crate) fn get_pen_state(&self) -> bool {
         self.pen_down
     }
 
-    pub(crate) fn reset(&mut self) {
-        *self = Self::default();
+    // The coordinate system and mode belong to the screen, so they survive a
+    // reset. Returns the commands needed to show the turtle back at home.
+    pub(crate) fn reset(&mut self) -> Vec<DrawCommand> {
+        *self = Self {
+            world: self.world,
+            mode: self.mode,
+            ..Self::default()
+        };
+        self.position = self.world.to_screen(0., 0.);
+        self.angle = self.mode.angle(0.);
+
+        let mut cmds = Vec::new();
+        if self.get_point() != ScreenPosition::new(0, 0) {
+            cmds.push(DrawCommand::SetPosition(self.get_point()));
+        }
+        if self.screen_angle() != 0. {
+            cmds.push(DrawCommand::SetHeading(0., self.screen_angle()));
+        }
+        cmds
     }
 
     fn create_circle(&mut self, radius: f32, extent: f32, steps: usize) -> DrawCommand {
@@ -283,50 +390,129 @@ impl CurrentTurtleState {
         let theta_r = rsign * (theta_d * (2. * PI / 360.));
         let len = 2. * radius.abs() * (theta_r / 2.).sin();
 
-        let half_d: Angle = Angle::degrees(theta_d / 2.);
-        let angle_d = Angle::degrees(theta_d);
-
         for s in 0..steps {
             if s == 0 {
-                self.transform = self.transform.pre_rotate(half_d);
                 self.angle += theta_d / 2.;
             } else {
-                self.transform = self.transform.pre_rotate(angle_d);
                 self.angle += theta_d;
             }
 
-            self.transform = self.transform.pre_translate([len, 0.].into());
+            self.position += self.world.to_screen_vector(len, self.angle);
             pointlist.push(self.get_circlepos());
         }
 
-        self.transform = self.transform.pre_rotate(half_d);
         self.angle += theta_d / 2.;
 
         DrawCommand::Circle(pointlist)
     }
 
+    fn create_curve(&mut self, curve: &CurveCmd, steps: usize) -> DrawCommand {
+        // the turtle's frame, in world coordinates
+        let start = self.world_pos();
+        let start = Point2D::new(start.x, start.y);
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let forward = Vector2D::new(cos, -sin);
+        let left = Vector2D::new(sin, cos);
+        let to_world = |p: [f32; 2]| forward * p[0] + left * p[1];
+
+        match curve {
+            CurveCmd::Quadratic(ctrl, to) => {
+                let bezier = QuadraticBezierSegment {
+                    from: Point2D::zero(),
+                    ctrl: (*ctrl).into(),
+                    to: (*to).into(),
+                };
+                self.trace_curve(steps, |t| {
+                    let p = bezier.sample(t);
+                    let d = bezier.derivative(t);
+                    (start + to_world([p.x, p.y]), to_world([d.x, d.y]))
+                })
+            }
+            CurveCmd::Cubic(ctrl1, ctrl2, to) => {
+                let bezier = CubicBezierSegment {
+                    from: Point2D::zero(),
+                    ctrl1: (*ctrl1).into(),
+                    ctrl2: (*ctrl2).into(),
+                    to: (*to).into(),
+                };
+                self.trace_curve(steps, |t| {
+                    let p = bezier.sample(t);
+                    let d = bezier.derivative(t);
+                    (start + to_world([p.x, p.y]), to_world([d.x, d.y]))
+                })
+            }
+            CurveCmd::Ellipse(rx, ry, extent) => {
+                // the center is `ry` to the left; the turtle starts at the
+                // "bottom" of the ellipse, heading along it
+                let extent = (extent * (360. / self.circle_units)).to_radians();
+                let (rx, ry) = (*rx, *ry);
+                self.trace_curve(steps, |t| {
+                    let theta = -PI / 2. + extent * t;
+                    let (sin, cos) = theta.sin_cos();
+                    let p = to_world([rx * cos, ry + ry * sin]);
+                    let d = to_world([-rx * sin, ry * cos]) * extent.signum();
+                    (start + p, d)
+                })
+            }
+            CurveCmd::Arc(center, extent) => {
+                let center = Point2D::new(center[0], center[1]);
+                let radius = start - center;
+                let extent = (extent * (360. / self.circle_units)).to_radians();
+                self.trace_curve(steps, |t| {
+                    let (sin, cos) = (extent * t).sin_cos();
+                    let r = Vector2D::new(
+                        radius.x * cos - radius.y * sin,
+                        radius.x * sin + radius.y * cos,
+                    );
+                    (center + r, Vector2D::new(-r.y, r.x) * extent.signum())
+                })
+            }
+        }
+    }
+
+    // Move the turtle along a curve, given its position and direction (in
+    // world coordinates) for t between 0 and 1
+    fn trace_curve<F>(&mut self, steps: usize, point_at: F) -> DrawCommand
+    where
+        F: Fn(f32) -> (Point2D<f32>, Vector2D<f32>),
+    {
+        let mut pointlist = vec![self.get_circlepos()];
+        let steps = steps.max(1);
+
+        for s in 1..=steps {
+            let (pos, tangent) = point_at(s as f32 / steps as f32);
+            self.position = self.world.to_screen(pos.x, pos.y);
+            if tangent.square_length() > 0. {
+                // turn the shortest way, so the animation doesn't spin around
+                let angle = -tangent.y.atan2(tangent.x).to_degrees();
+                self.angle += (angle - self.angle + 180.).rem_euclid(360.) - 180.;
+            }
+            pointlist.push(self.get_circlepos());
+        }
+
+        DrawCommand::Circle(pointlist)
+    }
+
     fn create_motion(&mut self, motion: &MotionCmd) -> DrawCommand {
         let begin = self.get_point();
-        let start = self.get_floatpoint();
-        let angle = Angle::degrees(self.angle);
 
         let mut pen_down = self.pen_down;
         match motion {
             MotionCmd::Forward(dist) => {
-                self.transform = self.transform.pre_translate([*dist, 0.].into());
+                self.position += self.world.to_screen_vector(*dist, self.angle);
             }
-            MotionCmd::Teleport(x, y) => {
-                self.transform = Transform2D::translation(*x, *y).pre_rotate(angle);
+            MotionCmd::Teleport(x, y, _) => {
+                self.position = self.world.to_screen(*x, *y);
                 pen_down = false;
             }
             MotionCmd::GoTo(x, y) => {
-                self.transform = Transform2D::translation(*x, *y).pre_rotate(angle);
+                self.position = self.world.to_screen(*x, *y);
             }
             MotionCmd::SetX(x) => {
-                self.transform = Transform2D::translation(*x, start.y).then_rotate(angle);
+                self.position.x = self.world.to_screen(*x, 0.).x;
             }
             MotionCmd::SetY(y) => {
-                self.transform = Transform2D::translation(start.x, *y).then_rotate(angle);
+                self.position.y = self.world.to_screen(0., *y).y;
             }
         }
         let end = self.get_point();
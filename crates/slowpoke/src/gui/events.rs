@@ -7,6 +7,7 @@ pub enum TurtleEvent {
     MousePress(f32, f32),    // click-x, click-y
     MouseRelease(f32, f32),  // click-x, click-y
     MouseDrag(f32, f32),     // x and y
+    WindowClose,             // the user asked to close the main window
     _Timer,
     Unhandled, // TODO: remove this, and implement TryFrom<Iced::Event> for Self
 }
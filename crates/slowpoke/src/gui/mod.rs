@@ -13,9 +13,6 @@ pub trait TurtleGui {
     // Generate a new connection to the windowing system
     fn new_turtle(&mut self) -> TurtleID;
 
-    // shut down the UI
-    fn shut_down(&mut self);
-
     // Clear this turtle's drawing, but leave the turtle in place
     fn clear_turtle(&mut self, turtle: TurtleID);
 
@@ -25,8 +22,8 @@ pub trait TurtleGui {
     // stamp the turtle's shape onto the canvas
     fn stamp(&mut self, turtle: TurtleID, pos: ScreenPosition<f32>, angle: f32) -> usize;
 
-    // clear a given stamp id
-    fn clear_stamp(&mut self, turtle: TurtleID, stamp: usize);
+    // clear a given stamp id, returning false if the turtle has no such stamp
+    fn clear_stamp(&mut self, turtle: TurtleID, stamp: usize) -> bool;
 
     // clear the first/last quantity of stamps
     fn clear_stamps(&mut self, turtle: TurtleID, count: StampCount);
@@ -34,6 +31,9 @@ pub trait TurtleGui {
     // get the name of the current turtle's shape
     fn get_turtle_shape_name(&mut self, turtle: TurtleID) -> String;
 
+    // get a copy of the current turtle's shape
+    fn get_shape(&self, turtle: TurtleID) -> TurtleShape;
+
     // Call this to add a drawing command to the screen. These will be drawn
     // before the "current_command" gets drawn
     fn append_command(&mut self, turtle: TurtleID, cmd: DrawCommand);
@@ -50,8 +50,11 @@ pub trait TurtleGui {
     // remove last command and start to undo the next
     fn pop(&mut self, turtle: TurtleID) -> Option<DrawCommand>;
 
-    // how many commands can be undone
-    fn undo_count(&self, turtle: TurtleID) -> usize;
+    // copy the turtle's drawing, so that it can be restored after a clear
+    fn save_drawing(&self, turtle: TurtleID) -> Vec<DrawCommand>;
+
+    // replace the turtle's drawing with a saved copy
+    fn restore_drawing(&mut self, turtle: TurtleID, cmds: Vec<DrawCommand>);
 
     // read a numeric value from the user
     fn numinput(&mut self, turtle: TurtleID, thread: TurtleThread, title: &str, prompt: &str);
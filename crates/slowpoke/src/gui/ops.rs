@@ -3,21 +3,38 @@ use lyon_tessellation::{
     math::Angle,
 };
 
-use crate::{polygon::PolygonPath, turtle::handler::ConversionInfo, CirclePos, IndividualTurtle};
-use crate::{DrawCommand, LineInfo, TurtleColor};
+use crate::{
+    polygon::{PolygonPath, Triangle},
+    turtle::handler::{Checkpoint, ConversionInfo},
+    CirclePos, IndividualTurtle,
+};
+use crate::{DrawCommand, FillRule, FillStyle, LineInfo, LineStyle, TurtleColor};
 
 pub(crate) type Point = Point2D<f32>;
 
+// commands between checkpoints, so undo doesn't go back to the beginning
+const CHECKPOINT_INTERVAL: usize = 256;
+// long runs of lines are split up so each frame only redraws the end of it
+const MAX_PATH_POINTS: usize = 256;
+
 #[derive(Debug)]
 pub enum TurtleDraw {
-    DrawLines(TurtleColor, f32, Vec<LineSegment>),
+    DrawLines(TurtleColor, f32, LineStyle, Vec<LineSegment>),
     DrawDot(Point, f32, TurtleColor),
     DrawText(Point, String),
-    /* fill color, line color, line width, list of segments */
-    FillPolygon(TurtleColor, TurtleColor, f32, Vec<LineSegment>),
+    /* fill color, line color, line width, fill style, fill rule, list of segments, triangles */
+    FillPolygon(
+        TurtleColor,
+        TurtleColor,
+        f32,
+        FillStyle,
+        FillRule,
+        Vec<LineSegment>,
+        Vec<Triangle>,
+    ),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct LineSegment {
     pub start: Point,
     pub end: Point,
@@ -45,151 +62,230 @@ impl LineSegment {
 //  - turtle.cmds.len() decreased, or
 //  - pct decreased
 impl TurtleDraw {
+    // Only commands added since the last call are converted, along with the one
+    // being drawn. When earlier commands change (undo, clearing stamps, filling)
+    // the conversion restarts from the last checkpoint before the change.
     pub(crate) fn convert<UI>(fraction: f32, turtle: &mut IndividualTurtle<UI>) {
-        fn make_path(path: &mut Vec<(bool, Point)>) -> Vec<LineSegment> {
-            let mut segments = Vec::new();
-            let mut cur_pos = path.remove(0).1;
-            for (pen, pos) in path.drain(..) {
-                if pen {
-                    segments.push(LineSegment {
-                        start: cur_pos,
-                        end: pos,
-                    });
-                }
-                cur_pos = pos;
+        let len = turtle.cmds.len();
+        // the last command is still being drawn unless it's complete
+        let finished = if fraction < 1. {
+            len.saturating_sub(1)
+        } else {
+            len
+        };
+        let mut checkpoints = std::mem::take(&mut turtle.cvt.checkpoints);
+
+        let mut changed = turtle.cvt.get_trunc_pos().unwrap_or(len).min(finished);
+        let converted = checkpoints.last().map_or(0, |c| c.cmd_index).min(len);
+        for cmd in &turtle.cmds[converted..] {
+            // filling replaces the Filler at the start of the polygon
+            if let DrawCommand::Filled(index) = cmd {
+                changed = changed.min(*index);
             }
-            segments
         }
 
-        // let mut cur_path = turtle.cvt.cur_path.clone();
-        // turtle.cvt.cur_path = Vec::new();
-        let mut cur_path = Vec::new();
-        let mut iter = turtle.cmds.iter().peekable();
-
-        turtle.ops.clear();
-        turtle.cvt = ConversionInfo::new();
-
-        while let Some(element) = iter.next() {
-            let last_element = iter.peek().is_none() && fraction < 1.;
-            if !matches!(element, DrawCommand::Line(..))
-                && !matches!(element, DrawCommand::SetHeading(..))
-                && !cur_path.is_empty()
-            {
-                let path = make_path(&mut cur_path);
-                if !path.is_empty() {
-                    turtle.ops.push(TurtleDraw::DrawLines(
-                        turtle.cvt.pencolor,
-                        turtle.cvt.penwidth,
-                        path,
-                    ));
-                }
+        let finished_ops = checkpoints.last().map_or(0, |c| c.ops_len);
+        while checkpoints.last().is_some_and(|c| c.cmd_index > changed) {
+            checkpoints.pop();
+        }
+        let (mut index, ops_len, cvt) = checkpoints.last().map_or_else(
+            || (0, 0, ConversionInfo::new()),
+            |c| (c.cmd_index, c.ops_len, c.cvt.snapshot()),
+        );
+        if ops_len < finished_ops {
+            turtle.rewound = Some(turtle.rewound.map_or(ops_len, |r| r.min(ops_len)));
+        }
+        turtle.ops.truncate(ops_len);
+        turtle.cvt = cvt;
+
+        let mut added = false;
+        while index < finished {
+            Self::convert_command(
+                &mut turtle.ops,
+                &mut turtle.cvt,
+                &turtle.cmds[index],
+                false,
+                1.,
+            );
+            if turtle.cvt.cur_path.len() > MAX_PATH_POINTS {
+                Self::flush_path(&mut turtle.ops, &mut turtle.cvt);
             }
+            index += 1;
+            added = true;
+        }
 
-            if last_element && fraction == 0.0 {
-                continue;
+        if added {
+            // keep checkpoints apart, except for the one we continue from next time
+            if let [.., previous, last] = checkpoints.as_slice()
+                && last.cmd_index - previous.cmd_index < CHECKPOINT_INTERVAL
+            {
+                checkpoints.pop();
             }
+            checkpoints.push(Checkpoint {
+                cmd_index: index,
+                ops_len: turtle.ops.len(),
+                cvt: turtle.cvt.snapshot(),
+            });
+        }
 
-            match element {
-                DrawCommand::Line(line) => {
-                    let (start, end) = Self::start_and_end(last_element, fraction, line);
-                    turtle.cvt.position = [end.x, end.y];
-                    if cur_path.is_empty() {
-                        cur_path.push((line.pen_down, start));
-                    }
-                    cur_path.push((line.pen_down, end));
-                }
-                DrawCommand::SetPenColor(pc) => {
-                    turtle.cvt.pencolor = *pc;
-                }
-                DrawCommand::SetPenWidth(pw) => turtle.cvt.penwidth = *pw,
-                DrawCommand::SetFillColor(fc) => {
-                    turtle.cvt.fillcolor = *fc;
-                }
-                DrawCommand::DrawPolygon(p) => {
-                    turtle.ops.push(TurtleDraw::FillPolygon(
-                        turtle.cvt.fillcolor,
-                        turtle.cvt.pencolor,
-                        turtle.cvt.penwidth,
-                        p.get_path(),
-                    ));
-                }
-                DrawCommand::SetHeading(start, end) => {
-                    let rotation = if last_element {
-                        *start + (*end - *start) * fraction
-                    } else {
-                        *end
-                    };
-                    turtle.cvt.angle = rotation;
-                }
-                DrawCommand::Dot(center, radius, color) => {
-                    let center: Point = Point2D::new(center.x, center.y);
-                    turtle
-                        .ops
-                        .push(TurtleDraw::DrawDot(center, *radius, *color));
-                }
-                DrawCommand::DrawPolyAt(polygon, pos, angle) => {
-                    let path = polygon.get_path();
-                    let angle = Angle::degrees(*angle);
-                    let xform = Transform2D::rotation(angle).then_translate([pos.x, pos.y].into());
-                    let path = path.transform(&xform);
-                    turtle.ops.push(TurtleDraw::FillPolygon(
-                        turtle.cvt.fillcolor,
-                        turtle.cvt.pencolor,
-                        turtle.cvt.penwidth,
-                        path,
-                    ));
-                }
-                DrawCommand::Circle(points) => {
-                    let (path, final_pos, final_angle) =
-                        Self::circle_path(last_element, fraction, points);
-                    turtle.cvt.position = final_pos.into();
-                    turtle.cvt.angle = final_angle;
-                    turtle.ops.push(TurtleDraw::DrawLines(
-                        turtle.cvt.pencolor,
-                        turtle.cvt.penwidth,
-                        path,
-                    ));
-                }
-                DrawCommand::SetPosition(pos) => {
-                    turtle.cvt.position = [pos.x as f32, pos.y as f32];
-                }
-                DrawCommand::Text(pos, text) => {
-                    let pos = Point::new(pos.x, pos.y);
-                    turtle.ops.push(TurtleDraw::DrawText(pos, text.to_string()));
-                }
-                DrawCommand::Filler => {
-                    turtle.cvt.polygon_start_point = Some(turtle.ops.len());
-                }
-                DrawCommand::Filled(fill_point) => {
-                    turtle.cvt.last_fill_point = Some(*fill_point);
-                }
-                DrawCommand::StampTurtle
-                | DrawCommand::Clear
-                | DrawCommand::Reset
-                | DrawCommand::BeginFill
-                | DrawCommand::EndFill
-                | DrawCommand::BeginPoly
-                | DrawCommand::EndPoly => panic!("invalid draw command in gui"),
-            }
+        if index < len && fraction > 0. {
+            Self::convert_command(
+                &mut turtle.ops,
+                &mut turtle.cvt,
+                &turtle.cmds[index],
+                true,
+                fraction,
+            );
         }
 
-        if !cur_path.is_empty() {
-            turtle.cvt.set_trunc_pos(turtle.ops.len());
-            turtle.cvt.cur_path = cur_path.clone();
+        if !turtle.cvt.cur_path.is_empty() {
+            let mut cur_path = turtle.cvt.cur_path.clone();
             let path = make_path(&mut cur_path);
             if !path.is_empty() {
                 turtle.ops.push(TurtleDraw::DrawLines(
                     turtle.cvt.pencolor,
                     turtle.cvt.penwidth,
+                    turtle.cvt.linestyle.clone(),
                     path,
                 ));
             }
         }
 
         if !turtle.hide_turtle {
-            turtle.cvt.set_trunc_pos(turtle.ops.len());
             turtle.ops.extend(Self::calculate_turtle(turtle));
         }
+
+        turtle.cvt.checkpoints = checkpoints;
+    }
+
+    // turn the lines drawn so far into an op, continuing from the last point
+    fn flush_path(ops: &mut Vec<TurtleDraw>, cvt: &mut ConversionInfo) {
+        let last = cvt.cur_path.last().copied();
+        Self::end_path(ops, cvt);
+        cvt.cur_path.extend(last);
+    }
+
+    fn end_path(ops: &mut Vec<TurtleDraw>, cvt: &mut ConversionInfo) {
+        if cvt.cur_path.is_empty() {
+            return;
+        }
+        let path = make_path(&mut cvt.cur_path);
+        if !path.is_empty() {
+            ops.push(TurtleDraw::DrawLines(
+                cvt.pencolor,
+                cvt.penwidth,
+                cvt.linestyle.clone(),
+                path,
+            ));
+        }
+    }
+
+    fn convert_command(
+        ops: &mut Vec<TurtleDraw>,
+        cvt: &mut ConversionInfo,
+        element: &DrawCommand,
+        last_element: bool,
+        fraction: f32,
+    ) {
+        if !matches!(element, DrawCommand::Line(..) | DrawCommand::SetHeading(..)) {
+            Self::end_path(ops, cvt);
+        }
+
+        match element {
+            DrawCommand::Line(line) => {
+                let (start, end) = Self::start_and_end(last_element, fraction, line);
+                cvt.position = [end.x, end.y];
+                if cvt.cur_path.is_empty() {
+                    cvt.cur_path.push((line.pen_down, start));
+                }
+                cvt.cur_path.push((line.pen_down, end));
+            }
+            DrawCommand::SetPenColor(pc) => {
+                cvt.pencolor = *pc;
+            }
+            DrawCommand::SetPenWidth(pw) => cvt.penwidth = *pw,
+            DrawCommand::SetLineStyle(style) => cvt.linestyle = style.clone(),
+            DrawCommand::SetFillColor(fc) => {
+                cvt.fillcolor = *fc;
+            }
+            DrawCommand::DrawPolygon(p, style) => {
+                // radial gradients are shaded across many small triangles
+                let triangles = if let FillStyle::RadialGradient(..) = style {
+                    p.small_triangles()
+                } else {
+                    p.triangles()
+                };
+                ops.push(TurtleDraw::FillPolygon(
+                    cvt.fillcolor,
+                    cvt.pencolor,
+                    cvt.penwidth,
+                    *style,
+                    p.rule(),
+                    p.get_path(),
+                    triangles.to_vec(),
+                ));
+            }
+            DrawCommand::SetHeading(start, end) => {
+                let rotation = if last_element {
+                    *start + (*end - *start) * fraction
+                } else {
+                    *end
+                };
+                cvt.angle = rotation;
+            }
+            DrawCommand::Dot(center, radius, color) => {
+                let center: Point = Point2D::new(center.x, center.y);
+                ops.push(TurtleDraw::DrawDot(center, *radius, *color));
+            }
+            DrawCommand::DrawPolyAt(polygon, pos, angle) => {
+                let path = polygon.get_path();
+                let angle = Angle::degrees(*angle);
+                let xform = Transform2D::rotation(angle).then_translate([pos.x, pos.y].into());
+                let path = path.transform(&xform);
+                ops.push(TurtleDraw::FillPolygon(
+                    cvt.fillcolor,
+                    cvt.pencolor,
+                    cvt.penwidth,
+                    FillStyle::Solid,
+                    polygon.rule(),
+                    path,
+                    transform_triangles(polygon.triangles(), &xform),
+                ));
+            }
+            DrawCommand::Circle(points) => {
+                let (path, final_pos, final_angle) =
+                    Self::circle_path(last_element, fraction, points);
+                cvt.position = final_pos.into();
+                cvt.angle = final_angle;
+                ops.push(TurtleDraw::DrawLines(
+                    cvt.pencolor,
+                    cvt.penwidth,
+                    cvt.linestyle.clone(),
+                    path,
+                ));
+            }
+            DrawCommand::SetPosition(pos) => {
+                cvt.position = [pos.x as f32, pos.y as f32];
+            }
+            DrawCommand::Text(pos, text) => {
+                let pos = Point::new(pos.x, pos.y);
+                ops.push(TurtleDraw::DrawText(pos, text.to_string()));
+            }
+            DrawCommand::Filler => {
+                cvt.polygon_start_point = Some(ops.len());
+            }
+            DrawCommand::Filled(fill_point) => {
+                cvt.last_fill_point = Some(*fill_point);
+            }
+            DrawCommand::StampTurtle
+            | DrawCommand::Clear
+            | DrawCommand::Reset
+            | DrawCommand::BeginFill
+            | DrawCommand::EndFill
+            | DrawCommand::BeginPoly
+            | DrawCommand::EndPoly
+            | DrawCommand::NextContour => panic!("invalid draw command in gui"),
+        }
     }
 
     fn start_and_end(last_element: bool, pct: f32, line: &LineInfo) -> (Point, Point) {
@@ -262,7 +358,10 @@ impl TurtleDraw {
                 fillcolor,
                 pencolor,
                 turtle.cvt.penwidth,
+                FillStyle::Solid,
+                poly.polygon.rule(),
                 path,
+                transform_triangles(poly.polygon.triangles(), &transform),
             ));
         }
 
@@ -270,6 +369,28 @@ impl TurtleDraw {
     }
 }
 
+fn transform_triangles(triangles: &[Triangle], xform: &Transform2D<f32>) -> Vec<Triangle> {
+    triangles
+        .iter()
+        .map(|t| t.map(|p| xform.transform_point(p)))
+        .collect()
+}
+
+fn make_path(path: &mut Vec<(bool, Point)>) -> Vec<LineSegment> {
+    let mut segments = Vec::new();
+    let mut cur_pos = path.remove(0).1;
+    for (pen, pos) in path.drain(..) {
+        if pen {
+            segments.push(LineSegment {
+                start: cur_pos,
+                end: pos,
+            });
+        }
+        cur_pos = pos;
+    }
+    segments
+}
+
 trait ConvertSimplePolygon {
     fn get_path(&self) -> Vec<LineSegment>;
 }
@@ -277,13 +398,12 @@ trait ConvertSimplePolygon {
 impl ConvertSimplePolygon for PolygonPath {
     fn get_path(&self) -> Vec<LineSegment> {
         let mut path = Vec::new();
-        let mut iter = self.path.iter();
-        let mut start_pos = iter.next().unwrap();
-        for end_pos in iter {
-            let start = Point::new(start_pos[0], start_pos[1]);
-            let end = Point::new(end_pos[0], end_pos[1]);
-            path.push(LineSegment { start, end });
-            start_pos = end_pos;
+        for contour in self.contour_points() {
+            for pair in contour.windows(2) {
+                let start = Point::new(pair[0][0], pair[0][1]);
+                let end = Point::new(pair[1][0], pair[1][1]);
+                path.push(LineSegment { start, end });
+            }
         }
         path
     }
@@ -373,7 +493,7 @@ mod test {
 
         println!("-- polygon --");
         let polygon = PolygonPath::new(&[[0., 0.], [10., 0.], [0., 10.]]);
-        turtle.cmds[index] = DrawCommand::DrawPolygon(polygon);
+        turtle.cmds[index] = DrawCommand::DrawPolygon(polygon, FillStyle::Solid);
         turtle.cmds.push(DrawCommand::Filled(index));
         TurtleDraw::convert(1., &mut turtle);
         assert_eq!(turtle.ops.len(), 3);
@@ -420,7 +540,7 @@ mod test {
 
         println!("-- polygon --");
         let polygon = PolygonPath::new(&[[0., 0.], [10., 0.], [0., 10.]]);
-        turtle.cmds[index] = DrawCommand::DrawPolygon(polygon);
+        turtle.cmds[index] = DrawCommand::DrawPolygon(polygon, FillStyle::Solid);
         turtle.cmds.push(DrawCommand::Filled(index));
         TurtleDraw::convert(1., &mut turtle);
         dbg!(&turtle.ops);
@@ -474,7 +594,7 @@ mod test {
 
         println!("-- 2nd polygon --");
         let polygon = PolygonPath::new(&[[100., 100.], [110., 100.], [100., 110.]]);
-        turtle.cmds[index] = DrawCommand::DrawPolygon(polygon);
+        turtle.cmds[index] = DrawCommand::DrawPolygon(polygon, FillStyle::Solid);
         turtle.cmds.push(DrawCommand::Filled(index));
         TurtleDraw::convert(1., &mut turtle);
         dbg!(&turtle.ops);
@@ -599,4 +719,87 @@ mod test {
         assert_eq!(turtle.cvt.cur_path.len(), 0);
         assert_eq!(turtle.ops.len(), 3);
     }
+
+    #[test]
+    fn incremental_conversion_matches_a_rebuild() {
+        fn rebuild(cmds: &[DrawCommand]) -> String {
+            let mut turtle = get_turtle();
+            turtle.cmds = cmds.to_vec();
+            TurtleDraw::convert(1., &mut turtle);
+            format!("{:?}", turtle.ops)
+        }
+
+        let mut turtle = get_turtle();
+        for i in 0..600 {
+            let cmd = if i % 300 == 299 {
+                DrawCommand::Dot(Point::new(0., 0.), 2., "black".into())
+            } else {
+                DrawCommand::Line(LineInfo {
+                    begin: ScreenPosition::new(i, 0),
+                    end: ScreenPosition::new(i + 1, i % 7),
+                    pen_down: true,
+                })
+            };
+            turtle.cmds.push(cmd);
+            TurtleDraw::convert(0.5, &mut turtle);
+            TurtleDraw::convert(1., &mut turtle);
+        }
+        assert_eq!(format!("{:?}", turtle.ops), rebuild(&turtle.cmds));
+
+        // undo past a checkpoint
+        turtle.cmds.truncate(400);
+        TurtleDraw::convert(1., &mut turtle);
+        assert_eq!(format!("{:?}", turtle.ops), rebuild(&turtle.cmds));
+    }
+
+    #[test]
+    fn undo_then_draw_matches_a_rebuild() {
+        let mut turtle = get_turtle();
+        for i in 0..10 {
+            turtle.cmds.push(DrawCommand::Line(LineInfo {
+                begin: ScreenPosition::new(i, 0),
+                end: ScreenPosition::new(i + 1, 10),
+                pen_down: true,
+            }));
+            TurtleDraw::convert(1., &mut turtle);
+        }
+
+        // a new command takes the undone one's place before it's converted
+        turtle.pop();
+        turtle
+            .cmds
+            .push(DrawCommand::Dot(Point::new(0., 0.), 2., "black".into()));
+        TurtleDraw::convert(1., &mut turtle);
+
+        let mut rebuilt = get_turtle();
+        rebuilt.cmds = turtle.cmds.clone();
+        TurtleDraw::convert(1., &mut rebuilt);
+        assert_eq!(format!("{:?}", turtle.ops), format!("{:?}", rebuilt.ops));
+    }
+
+    #[test]
+    fn finished_ops_only_change_after_a_rewind() {
+        let mut turtle = get_turtle();
+        turtle
+            .cmds
+            .push(DrawCommand::Dot(Point::new(0., 0.), 2., "black".into()));
+        turtle
+            .cmds
+            .push(DrawCommand::Dot(Point::new(5., 0.), 2., "black".into()));
+
+        // the second dot is still being drawn
+        TurtleDraw::convert(0.5, &mut turtle);
+        assert_eq!(turtle.finished_ops(), 1);
+        assert_eq!(turtle.take_rewound(), None);
+
+        TurtleDraw::convert(1., &mut turtle);
+        assert_eq!(turtle.finished_ops(), 2);
+        assert_eq!(turtle.take_rewound(), None);
+
+        turtle.cmds.pop();
+        TurtleDraw::convert(1., &mut turtle);
+        assert_eq!(turtle.finished_ops(), 1);
+        assert_eq!(turtle.take_rewound(), Some(1));
+        assert_eq!(turtle.take_rewound(), None);
+    }
 }
@@ -0,0 +1,98 @@
+//! A UI that shows nothing, for testing programs that drive turtles. It runs
+//! the turtles as fast as it can, gives the window whatever size it's asked
+//! for, and cancels every popup.
+
+use std::{collections::HashMap, thread};
+
+use crate::{
+    EventResult, Handler, PopupData, PopupID, SlowpokeLib, TurtleColor, TurtleEvent, TurtleFlags,
+    TurtleGui, TurtleID, TurtleTask, TurtleThread, TurtleUI, TurtleUserInterface,
+};
+
+pub type Slowpoke = SlowpokeLib<Headless>;
+
+#[derive(Debug)]
+pub struct Headless;
+
+#[derive(Debug, Default)]
+pub(crate) struct NoScreen {
+    last_popup: PopupID,
+    resize: Option<(TurtleID, TurtleThread, [isize; 2])>, // waiting to be done
+}
+
+impl TurtleUI for NoScreen {
+    fn generate_popup(&mut self, _popupdata: &PopupData) -> PopupID {
+        self.last_popup.get()
+    }
+
+    fn resize(&mut self, _width: isize, _height: isize) {}
+
+    fn resize_request(
+        &mut self,
+        turtle: TurtleID,
+        thread: TurtleThread,
+        width: isize,
+        height: isize,
+    ) {
+        self.resize = Some((turtle, thread, [width, height]));
+    }
+
+    fn set_bg_color(&mut self, _bgcolor: TurtleColor) {}
+}
+
+// the turtles' side of the window, with the main turtle in it
+pub(crate) fn handler() -> Handler<(), NoScreen> {
+    let mut gui = Handler {
+        last_id: TurtleID::default(),
+        turtle: HashMap::new(),
+        popups: HashMap::new(),
+        title: String::new(),
+        screen: NoScreen::default(),
+    };
+    gui.new_turtle();
+    gui
+}
+
+impl TurtleUserInterface for Headless {
+    type Options = ();
+
+    fn start(mut flags: TurtleFlags, (): ()) {
+        let func = flags.start_func.take().expect("missing start function");
+        let mut task = TurtleTask::new(&mut flags);
+        let mut gui = handler();
+        task.run_turtle(func);
+        while task.tick(&mut gui) == EventResult::Continue {
+            if let Some((turtle, thread, [width, height])) = gui.screen.resize.take() {
+                task.handle_event(
+                    Some(turtle),
+                    Some(thread),
+                    &TurtleEvent::WindowResize(width, height),
+                );
+            }
+            for (_, popup) in gui.popups.drain() {
+                task.popup_cancelled(popup.turtle(), popup.thread());
+            }
+            thread::yield_now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn the_window_answers() {
+        let (send, receive) = mpsc::channel();
+        Slowpoke::default().run(move |turtle| {
+            turtle.screensize([300, 200]);
+            let _ = send.send((turtle.getscreensize(), turtle.numinput("Number", "Go on")));
+            turtle.bye();
+        });
+        let (size, number) = receive.recv().expect("the window didn't answer");
+        assert_eq!(size, [300, 200]);
+        assert_eq!(number, None);
+    }
+}
@@ -1,16 +1,30 @@
+pub use coords::TurtleMode;
+pub use fill_style::{FillRule, FillStyle};
+pub use line_style::{LineCap, LineDash, LineJoin, LineStyle};
 use lyon_tessellation::geom::euclid::{Point2D, UnknownUnit};
 pub use polygon::{Shape, TurtleShapeName};
+pub use speed::Easing;
 pub use turtle::{SlowpokeLib, Turtle};
 
+mod collision;
 pub mod color_names;
 mod command;
 mod comms;
+mod coords;
 mod draw;
+pub mod fill_style;
 mod generate;
 mod gui;
+#[cfg(any(test, feature = "test-support"))]
+pub mod headless;
+pub mod line_style;
 mod polygon;
+mod record;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod speed;
 mod turtle;
+pub mod undo;
 mod user_events;
 
 pub const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
@@ -30,8 +44,8 @@ pub use gui::{
     popup::PopupData,
     TurtleGui,
 };
-pub use polygon::{GetPolyPath, PolygonPath, ShapeComponent};
+pub use polygon::{GetPolyPath, PolygonPath, ShapeComponent, Triangle};
 pub use turtle::handler::{Handler, IndividualTurtle, TurtleUI};
 pub use turtle::task::{EventResult, TurtleTask};
-pub use turtle::types::{PopupID, TurtleID, TurtleThread};
+pub use turtle::types::{PopupID, TimerID, TurtleID, TurtleThread};
 pub use turtle::{TurtleFlags, TurtleUserInterface};
@@ -0,0 +1,205 @@
+use crate::LineSegment;
+
+// the shortest length in a dash pattern, in pixels, other than 0
+const MIN_DASH: f32 = 0.5;
+
+/// The pattern a pen draws with. Dash patterns are lengths in pixels,
+/// alternating between drawn and skipped. Each length is either 0 or at least
+/// half a pixel; any other pattern draws solid lines.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum LineDash {
+    #[default]
+    Solid,
+    Dashed(Vec<f32>),
+    Dotted,
+}
+
+/// How the ends of lines are drawn
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+/// How the corners between connected lines are drawn
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    #[default]
+    Round,
+    Bevel,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LineStyle {
+    pub dash: LineDash,
+    pub cap: LineCap,
+    pub join: LineJoin,
+}
+
+impl LineStyle {
+    /// The dash pattern for a pen of the given width, or `None` for solid lines
+    #[must_use]
+    pub fn dash_pattern(&self, width: f32) -> Option<Vec<f32>> {
+        match &self.dash {
+            LineDash::Solid => None,
+            LineDash::Dashed(pattern) => {
+                // a pattern with nothing to skip (or nothing to draw) is solid,
+                // and so is one too fine to see, which would take forever
+                (pattern.iter().any(|l| *l > 0.)
+                    && pattern.iter().all(|l| *l == 0. || *l >= MIN_DASH))
+                .then(|| pattern.clone())
+            }
+            LineDash::Dotted => {
+                let width = width.max(1.);
+                Some(vec![width, width * 2.])
+            }
+        }
+    }
+
+    /// Split the segments into the dashes that are actually drawn, for UIs
+    /// that can't draw dashed lines themselves. The pattern carries on from
+    /// one segment to the next as long as they're connected.
+    #[must_use]
+    pub fn dashes(&self, width: f32, segments: &[LineSegment]) -> Vec<LineSegment> {
+        let Some(pattern) = self.dash_pattern(width) else {
+            return segments.to_vec();
+        };
+
+        let mut result = Vec::new();
+        let mut index = 0;
+        let mut remaining = pattern[0];
+        let mut last_end = None;
+
+        for segment in segments {
+            if last_end != Some(segment.start) {
+                index = 0;
+                remaining = pattern[0];
+            }
+            last_end = Some(segment.end);
+
+            let length = (segment.end - segment.start).length();
+            let mut done = 0.;
+            // every round of the pattern covers at least MIN_DASH, so this is
+            // plenty, even if rounding stops `done` from reaching `length`
+            let mut steps = pattern.len() * ((length / MIN_DASH) as usize + 1);
+            while done < length && steps > 0 {
+                steps -= 1;
+                let step = remaining.min(length - done);
+                if index % 2 == 0 && step > 0. {
+                    result.push(LineSegment {
+                        start: segment.start.lerp(segment.end, done / length),
+                        end: segment.start.lerp(segment.end, (done + step) / length),
+                    });
+                }
+                done += step;
+                remaining -= step;
+                if remaining <= 0. {
+                    index = (index + 1) % pattern.len();
+                    remaining = pattern[index];
+                }
+            }
+        }
+        result
+    }
+}
+
+/// # Panics
+/// Panics if the name isn't "solid", "dashed" or "dotted"
+impl From<&str> for LineDash {
+    fn from(value: &str) -> Self {
+        match value {
+            "solid" => Self::Solid,
+            "dashed" => Self::Dashed(vec![10., 5.]),
+            "dotted" => Self::Dotted,
+            _ => panic!("no line style {value}"),
+        }
+    }
+}
+
+impl From<&[f32]> for LineDash {
+    fn from(value: &[f32]) -> Self {
+        Self::Dashed(value.to_vec())
+    }
+}
+
+impl<const N: usize> From<[f32; N]> for LineDash {
+    fn from(value: [f32; N]) -> Self {
+        Self::Dashed(value.to_vec())
+    }
+}
+
+/// # Panics
+/// Panics if the name isn't "butt", "round" or "square"
+impl From<&str> for LineCap {
+    fn from(value: &str) -> Self {
+        match value {
+            "butt" => Self::Butt,
+            "round" => Self::Round,
+            "square" => Self::Square,
+            _ => panic!("no line cap {value}"),
+        }
+    }
+}
+
+/// # Panics
+/// Panics if the name isn't "miter", "round" or "bevel"
+impl From<&str> for LineJoin {
+    fn from(value: &str) -> Self {
+        match value {
+            "miter" => Self::Miter,
+            "round" => Self::Round,
+            "bevel" => Self::Bevel,
+            _ => panic!("no line join {value}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use lyon_tessellation::geom::euclid::default::Point2D;
+
+    fn segment(start: [f32; 2], end: [f32; 2]) -> LineSegment {
+        LineSegment {
+            start: Point2D::new(start[0], start[1]),
+            end: Point2D::new(end[0], end[1]),
+        }
+    }
+
+    #[test]
+    fn dashes_continue_around_corners() {
+        let style = LineStyle {
+            dash: [4., 2.].into(),
+            ..LineStyle::default()
+        };
+        let dashes = style.dashes(
+            1.,
+            &[segment([0., 0.], [5., 0.]), segment([5., 0.], [5., 5.])],
+        );
+
+        // 4 on, 2 off (1 before the corner, 1 after), 4 on
+        assert_eq!(dashes.len(), 2);
+        assert_eq!(dashes[0].end, Point2D::new(4., 0.));
+        assert_eq!(dashes[1].start, Point2D::new(5., 1.));
+        assert_eq!(dashes[1].end, Point2D::new(5., 5.));
+    }
+
+    #[test]
+    fn solid_lines_are_unchanged() {
+        let style = LineStyle {
+            dash: [0., 0.].into(),
+            ..LineStyle::default()
+        };
+        assert_eq!(style.dashes(1., &[segment([0., 0.], [5., 0.])]).len(), 1);
+
+        let style = LineStyle {
+            dash: [1e-9, 0.].into(),
+            ..LineStyle::default()
+        };
+        assert_eq!(style.dash_pattern(1.), None);
+        assert_eq!(style.dashes(1., &[segment([0., 0.], [1e6, 0.])]).len(), 1);
+    }
+}
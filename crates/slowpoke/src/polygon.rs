@@ -1,6 +1,9 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
 
-use crate::{color_names::TurtleColor, LineSegment};
+use crate::{color_names::TurtleColor, fill_style::subdivide, FillRule, LineSegment};
 
 const CLASSIC: [[f32; 2]; 5] = [[0., 0.], [-15., 6.], [-10., 0.], [-15., -6.], [0., 0.]];
 const ARROW: [[f32; 2]; 4] = [[0., 0.], [-10., 12.], [-10., -12.], [0., 0.]];
@@ -169,27 +172,151 @@ impl From<&str> for TurtleShapeName {
     }
 }
 
+pub type Triangle = [Point<f32>; 3];
+
 #[derive(Clone, Debug)]
 pub struct PolygonPath {
-    pub path: Vec<[f32; 2]>,
+    path: Vec<[f32; 2]>,
+    contours: Vec<usize>, // where each contour after the first starts in `path`
+    rule: FillRule,
+    triangles: OnceLock<Arc<[Triangle]>>, // shared by clones, filled in when first needed
+    small_triangles: OnceLock<Arc<[Triangle]>>, // the same, split up for shading
 }
 
 impl PolygonPath {
     pub fn new(diagram: &[[f32; 2]]) -> Self {
         Self {
             path: diagram.to_vec(),
+            contours: Vec::new(),
+            rule: FillRule::default(),
+            triangles: OnceLock::new(),
+            small_triangles: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn with_contours(diagram: &[[f32; 2]], contours: &[usize], rule: FillRule) -> Self {
+        Self {
+            path: diagram.to_vec(),
+            contours: contours.to_vec(),
+            rule,
+            triangles: OnceLock::new(),
+            small_triangles: OnceLock::new(),
+        }
+    }
+
+    /// Every point, one contour after another
+    #[must_use]
+    pub fn points(&self) -> &[[f32; 2]] {
+        &self.path
+    }
+
+    /// Where each contour after the first starts in `points()`
+    #[must_use]
+    pub fn contours(&self) -> &[usize] {
+        &self.contours
+    }
+
+    #[must_use]
+    pub fn rule(&self) -> FillRule {
+        self.rule
+    }
+
+    /// The points of each contour
+    pub fn contour_points(&self) -> impl Iterator<Item = &[[f32; 2]]> {
+        let starts = std::iter::once(0).chain(self.contours.iter().copied());
+        let ends = self
+            .contours
+            .iter()
+            .copied()
+            .chain(std::iter::once(self.path.len()));
+        starts
+            .zip(ends)
+            .map(|(start, end)| &self.path[start..end])
+            .filter(|contour| !contour.is_empty())
+    }
+
+    /// The polygon split into triangles, following its fill rule. The work is
+    /// only done the first time; a polygon can't be changed once it's made.
+    ///
+    /// # Panics
+    /// Panics if the polygon has no points
+    pub fn triangles(&self) -> &[Triangle] {
+        self.triangles.get_or_init(|| self.tessellate().into())
+    }
+
+    /// The triangles split up until none of their edges are more than a few
+    /// pixels long, so that colors shaded across them look smooth. Like
+    /// `triangles()`, this is only worked out once.
+    ///
+    /// # Panics
+    /// Panics if the polygon has no points
+    pub fn small_triangles(&self) -> &[Triangle] {
+        self.small_triangles
+            .get_or_init(|| subdivide(self.triangles().to_vec()).into())
+    }
+
+    // This code has been adapted from the example
+    // in the lyon_tesselation docs.
+    // See https://docs.rs/lyon_tessellation/latest/lyon_tessellation/struct.FillTessellator.html
+    fn tessellate(&self) -> Vec<Triangle> {
+        let mut path_builder = Path::builder();
+        assert!(!self.path.is_empty(), "needs at least one point");
+        for contour in self.contour_points() {
+            let mut iter = contour.iter();
+            let p = iter.next().expect("needs at least one point");
+            path_builder.begin(point(p[0], p[1]));
+            for p in iter {
+                path_builder.line_to(point(p[0], p[1]));
+            }
+            path_builder.end(true);
         }
+        let path = path_builder.build();
+        let rule = match self.rule {
+            FillRule::EvenOdd => lyon_tessellation::FillRule::EvenOdd,
+            FillRule::NonZero => lyon_tessellation::FillRule::NonZero,
+        };
+        let mut buffers: VertexBuffers<Point<f32>, u16> = VertexBuffers::new();
+        {
+            let mut vertex_builder = simple_builder(&mut buffers);
+            let mut tessellator = FillTessellator::new();
+            tessellator
+                .tessellate_path(
+                    &path,
+                    &FillOptions::default().with_fill_rule(rule),
+                    &mut vertex_builder,
+                )
+                .expect("tesselation failed");
+        }
+
+        buffers
+            .indices
+            .chunks(3)
+            .map(|t| [t[0], t[1], t[2]].map(|i| buffers.vertices[i as usize]))
+            .collect()
     }
 }
 
+// A new contour starts wherever a segment doesn't begin at the end of the
+// previous one
 impl From<&[LineSegment]> for PolygonPath {
     fn from(value: &[LineSegment]) -> Self {
         let mut path = Vec::new();
+        let mut contours = Vec::new();
         path.push(value[0].start.into());
-        for v in value {
+        for (i, v) in value.iter().enumerate() {
+            if i > 0 && value[i - 1].end != v.start {
+                contours.push(path.len());
+                path.push(v.start.into());
+            }
             path.push(v.end.into());
         }
-        Self { path }
+        Self {
+            path,
+            contours,
+            rule: FillRule::default(),
+            triangles: OnceLock::new(),
+            small_triangles: OnceLock::new(),
+        }
     }
 }
 
@@ -217,40 +344,26 @@ pub trait GetPolyPath {
     fn get_path(&self) -> Vec<(Point<f32>, Point<f32>)>;
 }
 
+// the edges of each triangle
 impl GetPolyPath for PolygonPath {
-    // This code has been adapted from the example
-    // in the lyon_tesselation docs.
-    // See https://docs.rs/lyon_tessellation/latest/lyon_tessellation/struct.FillTessellator.html
     fn get_path(&self) -> Vec<(Point<f32>, Point<f32>)> {
-        let mut path_builder = Path::builder();
-        let mut iter = self.path.iter();
-        let p = iter.next().expect("needs at least one point");
-        path_builder.begin(point(p[0], p[1]));
-        for p in iter {
-            path_builder.line_to(point(p[0], p[1]));
-        }
-        path_builder.end(true);
-        let path = path_builder.build();
-        let mut buffers: VertexBuffers<Point<f32>, u16> = VertexBuffers::new();
-        {
-            let mut vertex_builder = simple_builder(&mut buffers);
-            let mut tessellator = FillTessellator::new();
-            tessellator
-                .tessellate_path(&path, &FillOptions::default(), &mut vertex_builder)
-                .expect("tesselation failed");
-        }
-
-        let mut result = Vec::new();
+        self.triangles()
+            .iter()
+            .flat_map(|[p0, p1, p2]| [(*p0, *p1), (*p1, *p2), (*p2, *p0)])
+            .collect()
+    }
+}
 
-        for triangle in buffers.indices.as_slice().chunks(3) {
-            let p0 = triangle[0] as usize;
-            let p1 = triangle[1] as usize;
-            let p2 = triangle[2] as usize;
-            result.push((buffers.vertices[p0], buffers.vertices[p1]));
-            result.push((buffers.vertices[p1], buffers.vertices[p2]));
-            result.push((buffers.vertices[p2], buffers.vertices[p0]));
-        }
+#[cfg(test)]
+mod test {
+    use super::*;
 
-        result
+    #[test]
+    fn tessellation_is_shared_by_clones() {
+        let polygon = PolygonPath::new(&SQUARE);
+        assert!(!polygon.triangles().is_empty());
+        let copy = polygon.clone();
+        assert!(std::ptr::eq(polygon.triangles(), copy.triangles()));
+        assert_eq!(polygon.get_path().len(), 3 * polygon.triangles().len());
     }
 }
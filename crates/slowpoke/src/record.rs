@@ -0,0 +1,1031 @@
+//! Recording everything the turtles ask for, so it can be played back later.
+//!
+//! The format is described at `SlowpokeLib::with_recording()`.
+
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    command::{
+        Command, CurveCmd, DataCmd, DrawRequest, InputCmd, InstantaneousDrawCmd, MotionCmd,
+        RotateCmd, ScreenCmd, TimedDrawCmd,
+    },
+    comms::{Request, Response},
+    gui::events::TurtleEvent,
+    speed::Easing,
+    turtle::types::{TurtleID, TurtleThread},
+    FillRule, FillStyle, LineCap, LineDash, LineJoin, PolygonPath, Shape, ShapeComponent,
+    TurtleColor, TurtleMode, TurtleShapeName,
+};
+
+const HEADER: &str = "slowpoke recording 1";
+
+// Writes each entry to the recording as it happens
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    file: BufWriter<File>,
+    start: Instant,
+    failed: bool,
+    error: Arc<Mutex<Option<io::Error>>>, // given back to whoever asked for the recording
+}
+
+impl Recorder {
+    pub(crate) fn create<P: AsRef<Path>>(
+        path: P,
+        error: Arc<Mutex<Option<io::Error>>>,
+    ) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{HEADER}")?;
+        Ok(Self {
+            file,
+            start: Instant::now(),
+            failed: false,
+            error,
+        })
+    }
+
+    pub(crate) fn request(&mut self, req: &Request) {
+        let mut words = Words::default();
+        words
+            .word("cmd")
+            .word(req.turtle.value())
+            .word(req.thread.value());
+        write_command(&mut words, &req.cmd);
+        self.write(&words);
+    }
+
+    pub(crate) fn result(&mut self, turtle: TurtleID, thread: TurtleThread, response: &Response) {
+        let mut words = Words::default();
+        words
+            .word("result")
+            .word(turtle.value())
+            .word(thread.value());
+        write_response(&mut words, response);
+        self.write(&words);
+    }
+
+    pub(crate) fn event(&mut self, event: &TurtleEvent) {
+        let mut words = Words::default();
+        words.word("event");
+        write_event(&mut words, event);
+        self.write(&words);
+    }
+
+    pub(crate) fn flush(&mut self) {
+        if !self.failed
+            && let Err(e) = self.file.flush()
+        {
+            self.give_up(e);
+        }
+    }
+
+    fn write(&mut self, words: &Words) {
+        if self.failed {
+            return;
+        }
+        let time = self.start.elapsed().as_micros();
+        if let Err(e) = writeln!(self.file, "{time} {}", words.0.join(" ")) {
+            self.give_up(e);
+        }
+    }
+
+    // carry on without recording, rather than stopping the turtles
+    fn give_up(&mut self, e: io::Error) {
+        if let Ok(mut error) = self.error.lock() {
+            *error = Some(e);
+        }
+        self.failed = true;
+    }
+}
+
+/// The requests from a recording, ready to be replayed
+#[derive(Debug, Default)]
+pub(crate) struct Recording {
+    requests: VecDeque<(Duration, Request)>,
+    start: Option<Instant>, // set when the replay starts
+}
+
+impl Recording {
+    pub(crate) fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(HEADER) {
+            return Err(invalid("not a slowpoke recording"));
+        }
+
+        let mut recording = Self::default();
+        for (number, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry =
+                read_entry(&line).map_err(|e| invalid(format!("line {}: {e}", number + 2)))?;
+            if let (time, Some(req)) = entry {
+                recording.requests.push_back((time, req));
+            }
+        }
+        Ok(recording)
+    }
+
+    /// The requests whose time has come, in the order they were made
+    pub(crate) fn due(&mut self, now: Instant) -> Vec<Request> {
+        let start = *self.start.get_or_insert(now);
+        let elapsed = now.duration_since(start);
+        let mut due = Vec::new();
+        while let Some((time, _)) = self.requests.front()
+            && *time <= elapsed
+        {
+            due.push(self.requests.pop_front().expect("missing request").1);
+        }
+        due
+    }
+}
+
+fn invalid<E: Display>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+// `None` for lines which aren't needed to replay: input commands, results and
+// events
+fn read_entry(line: &str) -> Result<(Duration, Option<Request>), String> {
+    let mut words = Reader::new(line)?;
+    let time = Duration::from_micros(words.parse()?);
+    let req = match words.word()?.as_str() {
+        "cmd" => {
+            let turtle = TurtleID::from(words.parse::<usize>()?);
+            let thread = TurtleThread::new(words.parse()?);
+            let Some(cmd) = read_command(&mut words)? else {
+                return Ok((time, None));
+            };
+            words.finish()?;
+            Some(Request {
+                turtle,
+                thread,
+                cmd,
+            })
+        }
+        "result" | "event" => None,
+        kind => return Err(format!("unknown entry {kind}")),
+    };
+    Ok((time, req))
+}
+
+// The words on a line being written
+#[derive(Debug, Default)]
+struct Words(Vec<String>);
+
+impl Words {
+    fn word<D: Display>(&mut self, word: D) -> &mut Self {
+        self.0.push(word.to_string());
+        self
+    }
+
+    fn text(&mut self, text: &str) -> &mut Self {
+        self.0.push(format!("{text:?}"));
+        self
+    }
+
+    fn bool(&mut self, value: bool) -> &mut Self {
+        self.word(value)
+    }
+
+    fn color(&mut self, color: TurtleColor) -> &mut Self {
+        match color {
+            TurtleColor::CurrentColor => self.word("current"),
+            TurtleColor::Color(r, g, b) => self.word(r).word(g).word(b),
+        }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+        // an empty word would disappear
+        self.word(format!("x{hex}"))
+    }
+
+    fn points(&mut self, points: &[[f32; 2]]) -> &mut Self {
+        self.word(points.len());
+        for p in points {
+            self.word(p[0]).word(p[1]);
+        }
+        self
+    }
+
+    fn component(&mut self, component: &ShapeComponent) -> &mut Self {
+        let polygon = &component.polygon;
+        self.color(component.fill)
+            .color(component.outline)
+            .points(polygon.points())
+            .word(polygon.contours().len());
+        for start in polygon.contours() {
+            self.word(start);
+        }
+        self.word(fill_rule_name(polygon.rule()))
+    }
+}
+
+// A word that's been read. Strings are kept apart from the other words, so
+// that they can be empty or hold spaces.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Word {
+    Plain(String),
+    Text(String),
+}
+
+// Split a line into its words
+fn split_words(line: &str) -> Result<Vec<Word>, String> {
+    let mut words = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c == ' ' {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut quoted = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => {
+                        quoted.push('\\');
+                        quoted.push(chars.next().ok_or("unfinished string")?);
+                    }
+                    Some(c) => quoted.push(c),
+                    None => return Err("unfinished string".into()),
+                }
+            }
+            let text =
+                unescape(&quoted).ok_or_else(|| format!("can't read the string \"{quoted}\""))?;
+            words.push(Word::Text(text));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek()
+                && c != ' '
+            {
+                word.push(c);
+                chars.next();
+            }
+            words.push(Word::Plain(word));
+        }
+    }
+    Ok(words)
+}
+
+// The words on a line being read
+struct Reader {
+    words: std::vec::IntoIter<Word>,
+}
+
+impl Reader {
+    fn new(line: &str) -> Result<Self, String> {
+        split_words(line).map(Self::from_words)
+    }
+
+    fn from_words(words: Vec<Word>) -> Self {
+        Self {
+            words: words.into_iter(),
+        }
+    }
+
+    // a string is taken as a word too, so that the server's clients can send
+    // every word as a string
+    fn word(&mut self) -> Result<String, String> {
+        match self.words.next() {
+            Some(Word::Plain(word) | Word::Text(word)) => Ok(word),
+            None => Err("the line ends early".into()),
+        }
+    }
+
+    fn parse<T: FromStr>(&mut self) -> Result<T, String> {
+        let word = self.word()?;
+        word.parse().map_err(|_| format!("can't read {word}"))
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        match self.words.next() {
+            Some(Word::Plain(word)) => Err(format!("unexpected {word}")),
+            Some(Word::Text(text)) => Err(format!("unexpected {text:?}")),
+            None => Ok(()),
+        }
+    }
+
+    fn text(&mut self) -> Result<String, String> {
+        match self.words.next() {
+            Some(Word::Text(text)) => Ok(text),
+            Some(Word::Plain(word)) => Err(format!("{word} isn't a string")),
+            None => Err("the line ends early".into()),
+        }
+    }
+
+    fn color(&mut self) -> Result<TurtleColor, String> {
+        let word = self.word()?;
+        if word == "current" {
+            return Ok(TurtleColor::CurrentColor);
+        }
+        let r = word.parse().map_err(|_| format!("can't read {word}"))?;
+        Ok(TurtleColor::Color(r, self.parse()?, self.parse()?))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, String> {
+        let word = self.word()?;
+        let hex = word
+            .strip_prefix('x')
+            .filter(|hex| hex.len() % 2 == 0 && hex.is_ascii())
+            .ok_or_else(|| format!("{word} isn't hex"))?;
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    fn points(&mut self) -> Result<Vec<[f32; 2]>, String> {
+        let count: usize = self.parse()?;
+        (0..count)
+            .map(|_| Ok([self.parse()?, self.parse()?]))
+            .collect()
+    }
+
+    fn component(&mut self) -> Result<ShapeComponent, String> {
+        let fill = self.color()?;
+        let outline = self.color()?;
+        let path = self.points()?;
+        let count: usize = self.parse()?;
+        let contours = (0..count)
+            .map(|_| self.parse())
+            .collect::<Result<Vec<usize>, _>>()?;
+        let rule = match self.word()?.as_str() {
+            "evenodd" => FillRule::EvenOdd,
+            "nonzero" => FillRule::NonZero,
+            rule => return Err(format!("unknown fill rule {rule}")),
+        };
+        Ok(ShapeComponent {
+            polygon: PolygonPath::with_contours(&path, &contours, rule),
+            fill,
+            outline,
+        })
+    }
+
+    fn optional<T, F: FnOnce(&mut Self) -> Result<T, String>>(
+        &mut self,
+        read: F,
+    ) -> Result<Option<T>, String> {
+        if let Some(Word::Plain(word)) = self.words.as_slice().first()
+            && word == "none"
+        {
+            self.words.next();
+            Ok(None)
+        } else {
+            read(self).map(Some)
+        }
+    }
+}
+
+// the escapes that `{:?}` writes in strings
+fn unescape(quoted: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        text.push(match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'u' => {
+                let hex: String = chars
+                    .by_ref()
+                    .skip_while(|c| *c == '{')
+                    .take_while(|c| *c != '}')
+                    .collect();
+                char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+            }
+            c => c, // \\, \" and \'
+        });
+    }
+    Some(text)
+}
+
+const fn fill_rule_name(rule: FillRule) -> &'static str {
+    match rule {
+        FillRule::EvenOdd => "evenodd",
+        FillRule::NonZero => "nonzero",
+    }
+}
+
+fn write_command(w: &mut Words, cmd: &Command) {
+    match cmd {
+        Command::Draw(DrawRequest::TimedDraw(cmd)) => write_timed(w.word("draw"), cmd),
+        Command::Draw(DrawRequest::InstantaneousDraw(cmd)) => write_instant(w.word("draw"), cmd),
+        Command::Screen(cmd) => write_screen(w.word("screen"), cmd),
+        Command::Input(cmd) => write_input(w.word("input"), cmd),
+        Command::Data(cmd) => write_data(w.word("data"), cmd),
+        Command::Hatch => {
+            w.word("hatch");
+        }
+        Command::CloneTurtle => {
+            w.word("clone");
+        }
+        Command::ShutDown => {
+            w.word("shutdown");
+        }
+    }
+}
+
+// `None` for input commands, which can't be replayed
+fn read_command(r: &mut Reader) -> Result<Option<Command>, String> {
+    Ok(Some(match r.word()?.as_str() {
+        "draw" => Command::Draw(read_draw(r)?),
+        "screen" => Command::Screen(read_screen(r)?),
+        "input" => return Ok(None),
+        "data" => Command::Data(read_data(r)?),
+        "hatch" => Command::Hatch,
+        "clone" => Command::CloneTurtle,
+        "shutdown" => Command::ShutDown,
+        kind => return Err(format!("unknown command {kind}")),
+    }))
+}
+
+// A drawing, screen or data command, from its kind ("draw", "screen" or
+// "data") and the words after that, as a client of the server sends it
+#[cfg(feature = "server")]
+pub(crate) fn read_request(kind: &str, words: Vec<Word>) -> Result<Command, String> {
+    let mut r = Reader::from_words(words);
+    let cmd = match kind {
+        "draw" => Command::Draw(read_draw(&mut r)?),
+        "screen" => Command::Screen(read_screen(&mut r)?),
+        "data" => Command::Data(read_data(&mut r)?),
+        kind => return Err(format!("unknown kind of command {kind}")),
+    };
+    r.finish()?;
+    Ok(cmd)
+}
+
+// The words of a response, starting with its name, e.g. `position 10 20`
+#[cfg(feature = "server")]
+pub(crate) fn response_words(response: &Response) -> Vec<Word> {
+    let mut words = Words::default();
+    write_response(&mut words, response);
+    split_words(&words.0.join(" ")).expect("a response can be read back")
+}
+
+fn write_timed(w: &mut Words, cmd: &TimedDrawCmd) {
+    match cmd {
+        TimedDrawCmd::Motion(MotionCmd::Forward(d)) => w.word("forward").word(d),
+        TimedDrawCmd::Motion(MotionCmd::GoTo(x, y)) => w.word("goto").word(x).word(y),
+        TimedDrawCmd::Motion(MotionCmd::Teleport(x, y, fill_gap)) => {
+            w.word("teleport").word(x).word(y).bool(*fill_gap)
+        }
+        TimedDrawCmd::Motion(MotionCmd::SetX(x)) => w.word("setx").word(x),
+        TimedDrawCmd::Motion(MotionCmd::SetY(y)) => w.word("sety").word(y),
+        TimedDrawCmd::Rotate(RotateCmd::Right(a)) => w.word("right").word(a),
+        TimedDrawCmd::Rotate(RotateCmd::Left(a)) => w.word("left").word(a),
+        TimedDrawCmd::Rotate(RotateCmd::SetHeading(a)) => w.word("setheading").word(a),
+        TimedDrawCmd::Circle(radius, extent, steps) => {
+            w.word("circle").word(radius).word(extent).word(steps)
+        }
+        TimedDrawCmd::Curve(CurveCmd::Quadratic(c, e), steps) => w
+            .word("quadratic")
+            .word(c[0])
+            .word(c[1])
+            .word(e[0])
+            .word(e[1])
+            .word(steps),
+        TimedDrawCmd::Curve(CurveCmd::Cubic(c1, c2, e), steps) => w
+            .word("cubic")
+            .word(c1[0])
+            .word(c1[1])
+            .word(c2[0])
+            .word(c2[1])
+            .word(e[0])
+            .word(e[1])
+            .word(steps),
+        TimedDrawCmd::Curve(CurveCmd::Ellipse(forward, left, extent), steps) => w
+            .word("ellipse")
+            .word(forward)
+            .word(left)
+            .word(extent)
+            .word(steps),
+        TimedDrawCmd::Curve(CurveCmd::Arc(c, extent), steps) => {
+            w.word("arc").word(c[0]).word(c[1]).word(extent).word(steps)
+        }
+        TimedDrawCmd::Undo => w.word("undo"),
+        TimedDrawCmd::Redo => w.word("redo"),
+    };
+}
+
+fn write_instant(w: &mut Words, cmd: &InstantaneousDrawCmd) {
+    match cmd {
+        InstantaneousDrawCmd::PenDown => w.word("pendown"),
+        InstantaneousDrawCmd::PenUp => w.word("penup"),
+        InstantaneousDrawCmd::PenColor(c) => w.word("pencolor").color(*c),
+        InstantaneousDrawCmd::FillColor(c) => w.word("fillcolor").color(*c),
+        InstantaneousDrawCmd::FillStyle(style) => {
+            w.word("fillstyle");
+            match style {
+                FillStyle::Solid => w.word("solid"),
+                FillStyle::LinearGradient(from, to, angle) => {
+                    w.word("linear").color(*from).color(*to).word(angle)
+                }
+                FillStyle::RadialGradient(center, edge) => {
+                    w.word("radial").color(*center).color(*edge)
+                }
+                FillStyle::Hatch(color, spacing, angle) => {
+                    w.word("hatch").color(*color).word(spacing).word(angle)
+                }
+            }
+        }
+        InstantaneousDrawCmd::FillRule(rule) => w.word("fillrule").word(fill_rule_name(*rule)),
+        InstantaneousDrawCmd::PenWidth(width) => w.word("pensize").word(width),
+        InstantaneousDrawCmd::LineDash(dash) => {
+            w.word("linedash");
+            match dash {
+                LineDash::Solid => w.word("solid"),
+                LineDash::Dotted => w.word("dotted"),
+                LineDash::Dashed(pattern) => {
+                    w.word("dashed").word(pattern.len());
+                    for length in pattern {
+                        w.word(length);
+                    }
+                    w
+                }
+            }
+        }
+        InstantaneousDrawCmd::LineCap(cap) => w.word("linecap").word(match cap {
+            LineCap::Butt => "butt",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        }),
+        InstantaneousDrawCmd::LineJoin(join) => w.word("linejoin").word(match join {
+            LineJoin::Miter => "miter",
+            LineJoin::Round => "round",
+            LineJoin::Bevel => "bevel",
+        }),
+        InstantaneousDrawCmd::Dot(size, color) => {
+            w.word("dot");
+            match size {
+                Some(size) => w.word(size),
+                None => w.word("none"),
+            };
+            w.color(*color)
+        }
+        InstantaneousDrawCmd::Stamp => w.word("stamp"),
+        InstantaneousDrawCmd::Tracer(t) => w.word("tracer").bool(*t),
+        InstantaneousDrawCmd::BeginFill => w.word("begin_fill"),
+        InstantaneousDrawCmd::EndFill => w.word("end_fill"),
+        InstantaneousDrawCmd::NextContour => w.word("next_contour"),
+        InstantaneousDrawCmd::BeginPoly => w.word("begin_poly"),
+        InstantaneousDrawCmd::EndPoly => w.word("end_poly"),
+        InstantaneousDrawCmd::SetDegrees(d) => w.word("degrees").word(d),
+        InstantaneousDrawCmd::Clear => w.word("clear"),
+        InstantaneousDrawCmd::Reset => w.word("reset"),
+        InstantaneousDrawCmd::Text(text) => w.word("write").text(text),
+    };
+}
+
+fn read_draw(r: &mut Reader) -> Result<DrawRequest, String> {
+    use DrawRequest::{InstantaneousDraw as Instantaneous, TimedDraw as Timed};
+    use InstantaneousDrawCmd as I;
+
+    Ok(match r.word()?.as_str() {
+        "forward" => Timed(TimedDrawCmd::Motion(MotionCmd::Forward(r.parse()?))),
+        "goto" => Timed(TimedDrawCmd::Motion(MotionCmd::GoTo(
+            r.parse()?,
+            r.parse()?,
+        ))),
+        "teleport" => Timed(TimedDrawCmd::Motion(MotionCmd::Teleport(
+            r.parse()?,
+            r.parse()?,
+            r.parse()?,
+        ))),
+        "setx" => Timed(TimedDrawCmd::Motion(MotionCmd::SetX(r.parse()?))),
+        "sety" => Timed(TimedDrawCmd::Motion(MotionCmd::SetY(r.parse()?))),
+        "right" => Timed(TimedDrawCmd::Rotate(RotateCmd::Right(r.parse()?))),
+        "left" => Timed(TimedDrawCmd::Rotate(RotateCmd::Left(r.parse()?))),
+        "setheading" => Timed(TimedDrawCmd::Rotate(RotateCmd::SetHeading(r.parse()?))),
+        "circle" => Timed(TimedDrawCmd::Circle(r.parse()?, r.parse()?, r.parse()?)),
+        "quadratic" => {
+            let curve = CurveCmd::Quadratic([r.parse()?, r.parse()?], [r.parse()?, r.parse()?]);
+            Timed(TimedDrawCmd::Curve(curve, r.parse()?))
+        }
+        "cubic" => {
+            let curve = CurveCmd::Cubic(
+                [r.parse()?, r.parse()?],
+                [r.parse()?, r.parse()?],
+                [r.parse()?, r.parse()?],
+            );
+            Timed(TimedDrawCmd::Curve(curve, r.parse()?))
+        }
+        "ellipse" => {
+            let curve = CurveCmd::Ellipse(r.parse()?, r.parse()?, r.parse()?);
+            Timed(TimedDrawCmd::Curve(curve, r.parse()?))
+        }
+        "arc" => {
+            let curve = CurveCmd::Arc([r.parse()?, r.parse()?], r.parse()?);
+            Timed(TimedDrawCmd::Curve(curve, r.parse()?))
+        }
+        "undo" => Timed(TimedDrawCmd::Undo),
+        "redo" => Timed(TimedDrawCmd::Redo),
+        "pendown" => Instantaneous(I::PenDown),
+        "penup" => Instantaneous(I::PenUp),
+        "pencolor" => Instantaneous(I::PenColor(r.color()?)),
+        "fillcolor" => Instantaneous(I::FillColor(r.color()?)),
+        "fillstyle" => Instantaneous(I::FillStyle(match r.word()?.as_str() {
+            "solid" => FillStyle::Solid,
+            "linear" => FillStyle::LinearGradient(r.color()?, r.color()?, r.parse()?),
+            "radial" => FillStyle::RadialGradient(r.color()?, r.color()?),
+            "hatch" => FillStyle::Hatch(r.color()?, r.parse()?, r.parse()?),
+            style => return Err(format!("unknown fill style {style}")),
+        })),
+        "fillrule" => Instantaneous(I::FillRule(match r.word()?.as_str() {
+            "evenodd" => FillRule::EvenOdd,
+            "nonzero" => FillRule::NonZero,
+            rule => return Err(format!("unknown fill rule {rule}")),
+        })),
+        "pensize" => Instantaneous(I::PenWidth(r.parse()?)),
+        "linedash" => Instantaneous(I::LineDash(match r.word()?.as_str() {
+            "solid" => LineDash::Solid,
+            "dotted" => LineDash::Dotted,
+            "dashed" => {
+                let count: usize = r.parse()?;
+                LineDash::Dashed((0..count).map(|_| r.parse()).collect::<Result<_, _>>()?)
+            }
+            dash => return Err(format!("unknown line dash {dash}")),
+        })),
+        "linecap" => Instantaneous(I::LineCap(match r.word()?.as_str() {
+            "butt" => LineCap::Butt,
+            "round" => LineCap::Round,
+            "square" => LineCap::Square,
+            cap => return Err(format!("unknown line cap {cap}")),
+        })),
+        "linejoin" => Instantaneous(I::LineJoin(match r.word()?.as_str() {
+            "miter" => LineJoin::Miter,
+            "round" => LineJoin::Round,
+            "bevel" => LineJoin::Bevel,
+            join => return Err(format!("unknown line join {join}")),
+        })),
+        "dot" => Instantaneous(I::Dot(r.optional(Reader::parse)?, r.color()?)),
+        "stamp" => Instantaneous(I::Stamp),
+        "tracer" => Instantaneous(I::Tracer(r.parse()?)),
+        "begin_fill" => Instantaneous(I::BeginFill),
+        "end_fill" => Instantaneous(I::EndFill),
+        "next_contour" => Instantaneous(I::NextContour),
+        "begin_poly" => Instantaneous(I::BeginPoly),
+        "end_poly" => Instantaneous(I::EndPoly),
+        "degrees" => Instantaneous(I::SetDegrees(r.parse()?)),
+        "clear" => Instantaneous(I::Clear),
+        "reset" => Instantaneous(I::Reset),
+        "write" => Instantaneous(I::Text(r.text()?)),
+        cmd => return Err(format!("unknown drawing command {cmd}")),
+    })
+}
+
+fn write_screen(w: &mut Words, cmd: &ScreenCmd) {
+    match cmd {
+        ScreenCmd::ClearScreen => w.word("clearscreen"),
+        ScreenCmd::Background(c) => w.word("bgcolor").color(*c),
+        ScreenCmd::BgPic(picture) => w.word("bgpic").bytes(picture),
+        ScreenCmd::ClearStamp(id) => w.word("clearstamp").word(id),
+        ScreenCmd::ClearStamps(count) => w.word("clearstamps").word(count),
+        ScreenCmd::Speed(s) => w.word("speed").word(s.get()),
+        ScreenCmd::Duration(d) => {
+            w.word("duration");
+            match d {
+                Some(d) => w.word(d.as_secs_f64()),
+                None => w.word("none"),
+            }
+        }
+        ScreenCmd::Easing(e) => w.word("easing").word(match e {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "easein",
+            Easing::EaseOut => "easeout",
+            Easing::EaseInOut => "easeinout",
+            // a function can't be saved, so this replays as linear
+            Easing::Custom(_) => "custom",
+        }),
+        ScreenCmd::UndoBuffer(size) => {
+            w.word("undobuffer");
+            match size.get() {
+                Some(size) => w.word(size),
+                None => w.word("none"),
+            }
+        }
+        ScreenCmd::ShowTurtle(show) => w.word("showturtle").bool(*show),
+        ScreenCmd::SetSize([width, height]) => w.word("setup").word(width).word(height),
+        ScreenCmd::RegisterShape(name, shape) => {
+            w.word("register_shape").text(name);
+            match shape {
+                Shape::Polygon(component) => w.word("polygon").component(component),
+                Shape::Image(picture) => w.word("image").bytes(picture),
+                Shape::Compound(components) => {
+                    w.word("compound").word(components.len());
+                    for component in components {
+                        w.component(component);
+                    }
+                    w
+                }
+            }
+        }
+        ScreenCmd::SetTitle(title) => w.word("title").text(title),
+        ScreenCmd::SetWorldCoordinates(llx, lly, urx, ury) => w
+            .word("setworldcoordinates")
+            .word(llx)
+            .word(lly)
+            .word(urx)
+            .word(ury),
+        ScreenCmd::Mode(mode) => w.word("mode").word(mode_name(*mode)),
+        ScreenCmd::ExitOnClick => w.word("exitonclick"),
+        ScreenCmd::Bye => w.word("bye"),
+    };
+}
+
+const fn mode_name(mode: TurtleMode) -> &'static str {
+    match mode {
+        TurtleMode::Standard => "standard",
+        TurtleMode::Logo => "logo",
+        TurtleMode::World => "world",
+    }
+}
+
+fn read_screen(r: &mut Reader) -> Result<ScreenCmd, String> {
+    Ok(match r.word()?.as_str() {
+        "clearscreen" => ScreenCmd::ClearScreen,
+        "bgcolor" => ScreenCmd::Background(r.color()?),
+        "bgpic" => ScreenCmd::BgPic(r.bytes()?),
+        "clearstamp" => ScreenCmd::ClearStamp(r.parse()?),
+        "clearstamps" => ScreenCmd::ClearStamps(r.parse()?),
+        "speed" => ScreenCmd::Speed(r.parse::<u8>()?.into()),
+        "duration" => ScreenCmd::Duration(r.optional(|r| r.parse().map(Duration::from_secs_f64))?),
+        "easing" => ScreenCmd::Easing(match r.word()?.as_str() {
+            "linear" | "custom" => Easing::Linear,
+            "easein" => Easing::EaseIn,
+            "easeout" => Easing::EaseOut,
+            "easeinout" => Easing::EaseInOut,
+            easing => return Err(format!("unknown easing {easing}")),
+        }),
+        "undobuffer" => ScreenCmd::UndoBuffer(r.optional(Reader::parse::<usize>)?.into()),
+        "showturtle" => ScreenCmd::ShowTurtle(r.parse()?),
+        "setup" => ScreenCmd::SetSize([r.parse()?, r.parse()?]),
+        "register_shape" => {
+            let name = r.text()?;
+            let shape = match r.word()?.as_str() {
+                "polygon" => Shape::Polygon(r.component()?),
+                "image" => Shape::Image(r.bytes()?),
+                "compound" => {
+                    let count: usize = r.parse()?;
+                    Shape::Compound(
+                        (0..count)
+                            .map(|_| r.component())
+                            .collect::<Result<_, _>>()?,
+                    )
+                }
+                shape => return Err(format!("unknown shape {shape}")),
+            };
+            ScreenCmd::RegisterShape(name, shape)
+        }
+        "title" => ScreenCmd::SetTitle(r.text()?),
+        "setworldcoordinates" => {
+            ScreenCmd::SetWorldCoordinates(r.parse()?, r.parse()?, r.parse()?, r.parse()?)
+        }
+        "mode" => ScreenCmd::Mode(match r.word()?.as_str() {
+            "standard" => TurtleMode::Standard,
+            "logo" => TurtleMode::Logo,
+            "world" => TurtleMode::World,
+            mode => return Err(format!("unknown mode {mode}")),
+        }),
+        "exitonclick" => ScreenCmd::ExitOnClick,
+        "bye" => ScreenCmd::Bye,
+        cmd => return Err(format!("unknown screen command {cmd}")),
+    })
+}
+
+fn write_input(w: &mut Words, cmd: &InputCmd) {
+    let set = |set: bool| if set { "set" } else { "unset" };
+    match cmd {
+        InputCmd::KeyPress(f, key) => w
+            .word("onkeypress")
+            .text(&key.to_string())
+            .word(set(f.is_some())),
+        InputCmd::KeyRelease(f, key) => w
+            .word("onkeyrelease")
+            .text(&key.to_string())
+            .word(set(f.is_some())),
+        InputCmd::MousePress(f) => w.word("onmousepress").word(set(f.is_some())),
+        InputCmd::MouseRelease(f) => w.word("onmouserelease").word(set(f.is_some())),
+        InputCmd::MouseDrag(f) => w.word("onmousedrag").word(set(f.is_some())),
+        InputCmd::Close(f) => w.word("onclose").word(set(f.is_some())),
+        InputCmd::Listen(listen) => w.word("listen").bool(*listen),
+        InputCmd::Timer(_, delay, repeat) => {
+            w.word("ontimer").word(delay.as_secs_f64()).bool(*repeat)
+        }
+        InputCmd::CancelTimer(id) => w.word("canceltimer").word(id.value()),
+    };
+}
+
+fn write_data(w: &mut Words, cmd: &DataCmd) {
+    match cmd {
+        DataCmd::GetTurtles => w.word("turtles"),
+        DataCmd::GetShapes => w.word("getshapes"),
+        DataCmd::GetScreenSize => w.word("screensize"),
+        DataCmd::GetPoly => w.word("get_poly"),
+        DataCmd::GetPenState => w.word("isdown"),
+        DataCmd::GetFillingState => w.word("filling"),
+        DataCmd::TurtleShape(TurtleShapeName::GetCurrent) => w.word("shape").word("none"),
+        DataCmd::TurtleShape(TurtleShapeName::Shape(name)) => w.word("shape").text(name),
+        DataCmd::UndoBufferEntries => w.word("undobufferentries"),
+        DataCmd::Towards(x, y) => w.word("towards").word(x).word(y),
+        DataCmd::Overlaps(other) => w.word("overlaps").word(other.value()),
+        DataCmd::TurtlesNear(x, y, radius) => w.word("turtles_near").word(x).word(y).word(radius),
+        DataCmd::CrossesDrawing => w.word("intersects_drawn_path"),
+        DataCmd::Position => w.word("position"),
+        DataCmd::Heading => w.word("heading"),
+        DataCmd::GetMode => w.word("mode"),
+        DataCmd::Stamp => w.word("stamp"),
+        DataCmd::Visibility => w.word("isvisible"),
+        DataCmd::TextInput(title, prompt) => w.word("textinput").text(title).text(prompt),
+        DataCmd::NumInput(title, prompt) => w.word("numinput").text(title).text(prompt),
+    };
+}
+
+fn read_data(r: &mut Reader) -> Result<DataCmd, String> {
+    Ok(match r.word()?.as_str() {
+        "turtles" => DataCmd::GetTurtles,
+        "getshapes" => DataCmd::GetShapes,
+        "screensize" => DataCmd::GetScreenSize,
+        "get_poly" => DataCmd::GetPoly,
+        "isdown" => DataCmd::GetPenState,
+        "filling" => DataCmd::GetFillingState,
+        "shape" => DataCmd::TurtleShape(match r.optional(Reader::text)? {
+            Some(name) => TurtleShapeName::Shape(name),
+            None => TurtleShapeName::GetCurrent,
+        }),
+        "undobufferentries" => DataCmd::UndoBufferEntries,
+        "towards" => DataCmd::Towards(r.parse()?, r.parse()?),
+        "overlaps" => DataCmd::Overlaps(TurtleID::from(r.parse::<usize>()?)),
+        "turtles_near" => DataCmd::TurtlesNear(r.parse()?, r.parse()?, r.parse()?),
+        "intersects_drawn_path" => DataCmd::CrossesDrawing,
+        "position" => DataCmd::Position,
+        "heading" => DataCmd::Heading,
+        "mode" => DataCmd::GetMode,
+        "stamp" => DataCmd::Stamp,
+        "isvisible" => DataCmd::Visibility,
+        "textinput" => DataCmd::TextInput(r.text()?, r.text()?),
+        "numinput" => DataCmd::NumInput(r.text()?, r.text()?),
+        cmd => return Err(format!("unknown data command {cmd}")),
+    })
+}
+
+fn write_response(w: &mut Words, response: &Response) {
+    match response {
+        Response::Done => w.word("done"),
+        Response::Cancel => w.word("cancel"),
+        Response::Heading(h) => w.word("heading").word(h),
+        Response::Mode(mode) => w.word("mode").word(mode_name(*mode)),
+        Response::Position(pos) => w.word("position").word(pos.x).word(pos.y),
+        Response::StampID(id) => w.word("stamp").word(id),
+        Response::TimerID(id) => w.word("timer").word(id.value()),
+        Response::Turtle(turtle) => w.word("turtle").word(turtle.id().value()),
+        Response::Count(count) => w.word("count").word(count),
+        Response::Name(name) => w.word("name").text(name),
+        Response::Polygon(points) => w.word("polygon").points(points),
+        Response::Visibility(v) => w.word("visible").bool(*v),
+        Response::ScreenSize([width, height]) => w.word("screensize").word(width).word(height),
+        Response::TextInput(text) => w.word("textinput").text(text),
+        Response::NumInput(num) => w.word("numinput").word(num),
+        Response::IsPenDown(down) => w.word("isdown").bool(*down),
+        Response::IsFilling(filling) => w.word("filling").bool(*filling),
+        Response::Touching(touching) => w.word("touching").bool(*touching),
+        Response::ShapeList(names) => {
+            w.word("shapes").word(names.len());
+            for name in names {
+                w.text(name);
+            }
+            w
+        }
+        Response::Turtles(turtles) => {
+            w.word("turtles").word(turtles.len());
+            for turtle in turtles {
+                w.word(turtle.id().value());
+            }
+            w
+        }
+        Response::Error(message) => w.word("error").text(message),
+    };
+}
+
+fn write_event(w: &mut Words, event: &TurtleEvent) {
+    match event {
+        TurtleEvent::WindowResize(width, height) => w.word("resize").word(width).word(height),
+        TurtleEvent::KeyPress(key) => w.word("keypress").text(&key.to_string()),
+        TurtleEvent::KeyRelease(key) => w.word("keyrelease").text(&key.to_string()),
+        TurtleEvent::MousePosition(x, y) => w.word("mousemove").word(x).word(y),
+        TurtleEvent::MousePress(x, y) => w.word("mousepress").word(x).word(y),
+        TurtleEvent::MouseRelease(x, y) => w.word("mouserelease").word(x).word(y),
+        TurtleEvent::MouseDrag(x, y) => w.word("mousedrag").word(x).word(y),
+        TurtleEvent::WindowClose => w.word("close"),
+        TurtleEvent::_Timer => w.word("timer"),
+        TurtleEvent::Unhandled => w.word("unhandled"),
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(cmd: Command) {
+        let mut words = Words::default();
+        words.word(1234).word("cmd").word(2).word(3);
+        write_command(&mut words, &cmd);
+        let line = words.0.join(" ");
+
+        let (time, req) = read_entry(&line).unwrap_or_else(|e| panic!("{line}: {e}"));
+        assert_eq!(time, Duration::from_micros(1234));
+        let req = req.unwrap_or_else(|| panic!("{line} isn't a request"));
+        assert_eq!(req.turtle, TurtleID::from(2));
+        assert_eq!(req.thread, TurtleThread::new(3));
+        assert_eq!(format!("{:?}", req.cmd), format!("{cmd:?}"), "{line}");
+    }
+
+    #[test]
+    fn commands_survive_a_round_trip() {
+        let mut shape = Shape::compound();
+        shape.addcomponent(
+            &[[0., 0.], [1.5, -2.], [0.1, 0.2]],
+            "red",
+            TurtleColor::CurrentColor,
+        );
+
+        for cmd in [
+            Command::Draw(DrawRequest::TimedDraw(TimedDrawCmd::Motion(
+                MotionCmd::Teleport(-0.1, 1e10, true),
+            ))),
+            Command::Draw(DrawRequest::TimedDraw(TimedDrawCmd::Curve(
+                CurveCmd::Cubic([1., 2.], [3., 4.], [5., 6.]),
+                12,
+            ))),
+            Command::Draw(DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::Dot(
+                None,
+                TurtleColor::Color(0.25, 0.5, 1.),
+            ))),
+            Command::Draw(DrawRequest::InstantaneousDraw(
+                InstantaneousDrawCmd::LineDash(LineDash::Dashed(vec![4., 2.5])),
+            )),
+            Command::Draw(DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::Text(
+                "say \"hi\"\\ \n\u{1f422} ".into(),
+            ))),
+            Command::Screen(ScreenCmd::RegisterShape("a b".into(), shape)),
+            Command::Screen(ScreenCmd::BgPic(vec![0, 1, 254, 255])),
+            Command::Screen(ScreenCmd::Duration(Some(Duration::from_millis(1500)))),
+            Command::Screen(ScreenCmd::UndoBuffer(None.into())),
+            Command::Screen(ScreenCmd::SetTitle(String::new())),
+            Command::Data(DataCmd::TurtleShape(TurtleShapeName::GetCurrent)),
+            Command::Data(DataCmd::NumInput("title".into(), "how many?".into())),
+            Command::Hatch,
+            Command::ShutDown,
+        ] {
+            round_trip(cmd);
+        }
+    }
+
+    #[test]
+    fn replays_requests_in_time() {
+        let file = format!(
+            "{HEADER}\n\
+             0 cmd 0 0 draw forward 10\n\
+             10 cmd 0 0 input onkeypress \"a\" set\n\
+             20 result 0 0 heading 90\n\
+             1000000 event keypress \" \"\n\
+             2000000 cmd 0 0 screen bye\n"
+        );
+        let mut recording = Recording::read(file.as_bytes()).unwrap();
+        let start = Instant::now();
+        assert_eq!(recording.due(start).len(), 1);
+        assert!(recording.due(start + Duration::from_secs(1)).is_empty());
+        assert_eq!(recording.due(start + Duration::from_secs(2)).len(), 1);
+
+        let error = Recording::read(format!("{HEADER}\n0 cmd 0 0 draw fly\n").as_bytes());
+        assert!(error.unwrap_err().to_string().contains("line 2"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn write_errors_are_given_back() {
+        let error = Arc::default();
+        let mut recorder = Recorder::create("/dev/full", Arc::clone(&error)).unwrap();
+        recorder.event(&TurtleEvent::WindowResize(10, 10));
+        recorder.flush();
+        assert!(error.lock().unwrap().is_some());
+    }
+}
@@ -0,0 +1,309 @@
+//! Letting other programs drive turtles, over TCP or a Unix socket.
+//!
+//! Each client that connects gets a turtle of its own, hatched from the one
+//! running the server. It sends one JSON object per line, naming the kind of
+//! command and giving its words as they're written in a recording:
+//!
+//! ```text
+//! {"draw": ["forward", 100]}
+//! {"screen": ["title", "Hello"]}
+//! {"data": ["position"], "id": 7}
+//! ```
+//!
+//! Numbers, `true` and `false` stand for themselves, `null` is `none`, and
+//! strings are either a word like `forward` or some text. The server answers
+//! each line with one line, naming the response and giving its words, or with
+//! an error:
+//!
+//! ```text
+//! {"done": []}
+//! {"position": [100, 0], "id": 7}
+//! {"error": "unknown drawing command fly"}
+//! ```
+//!
+//! Ids in commands, such as a stamp to clear, are checked, so a client can't
+//! upset the window by sending one that doesn't exist. An `id` in a request
+//! is sent back with its answer. A `bye` screen command
+//! closes the window, so it isn't answered.
+
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    net::{SocketAddr, TcpListener, ToSocketAddrs},
+    thread,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::{os::unix::net::UnixListener, path::PathBuf};
+
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    comms::Response,
+    record::{read_request, response_words, Word},
+    Turtle,
+};
+
+// how often to look for new clients, and check that the window is still open
+const POLL: Duration = Duration::from_millis(100);
+
+/// Waits for clients to connect, and runs their commands
+#[derive(Debug)]
+pub struct Server {
+    listener: Listener,
+}
+
+#[derive(Debug)]
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf), // the socket's file, removed when done
+}
+
+impl Server {
+    /// Listen on a TCP address such as `"127.0.0.1:7007"`. Port 0 picks a
+    /// free port, which `local_addr()` tells.
+    ///
+    /// # Errors
+    /// Returns an error when the address can't be listened on
+    pub fn tcp<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener: Listener::Tcp(listener),
+        })
+    }
+
+    /// Listen on a Unix socket, created at `path`
+    ///
+    /// # Errors
+    /// Returns an error when the socket can't be created, e.g. because the
+    /// file already exists
+    #[cfg(unix)]
+    pub fn unix<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
+        let path = path.into();
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener: Listener::Unix(listener, path),
+        })
+    }
+
+    /// The address clients connect to, for a TCP server
+    ///
+    /// # Errors
+    /// Returns an error for a Unix socket, or when the address isn't known
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match &self.listener {
+            Listener::Tcp(listener) => listener.local_addr(),
+            #[cfg(unix)]
+            Listener::Unix(..) => Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "a Unix socket has no network address",
+            )),
+        }
+    }
+
+    /// Hatch a turtle for each client that connects, and run what it sends
+    /// on its own thread. This runs until the window closes, which unwinds
+    /// the thread, so it only returns if the server can't go on listening,
+    /// with the reason why.
+    pub fn run(self, turtle: &mut Turtle) -> io::Error {
+        loop {
+            match self.accept() {
+                Ok((reader, writer)) => {
+                    let client = turtle.hatch();
+                    thread::spawn(move || serve(&client, reader, writer));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(POLL);
+                    // this unwinds the thread if the window has closed
+                    turtle.isvisible();
+                }
+                // a client that gave up before it was accepted
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::ConnectionAborted
+                            | ErrorKind::ConnectionReset
+                            | ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => return e,
+            }
+        }
+    }
+
+    // the next client, if one is waiting, read from and written to separately
+    fn accept(&self) -> io::Result<(Box<dyn BufRead + Send>, Box<dyn Write + Send>)> {
+        match &self.listener {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                Ok((
+                    Box::new(BufReader::new(stream.try_clone()?)),
+                    Box::new(stream),
+                ))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                Ok((
+                    Box::new(BufReader::new(stream.try_clone()?)),
+                    Box::new(stream),
+                ))
+            }
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Listener::Unix(_, path) = &self.listener {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+// answer each line a client sends, until it hangs up
+fn serve(turtle: &Turtle, reader: Box<dyn BufRead + Send>, mut writer: Box<dyn Write + Send>) {
+    for line in reader.lines().map_while(Result::ok) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = answer(turtle, &line);
+        if writeln!(writer, "{reply}").is_err() {
+            return;
+        }
+    }
+}
+
+fn answer(turtle: &Turtle, line: &str) -> Value {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return error(format!("that isn't JSON: {e}")),
+    };
+    let mut reply = match read_json(&request).and_then(|(kind, words)| read_request(kind, words)) {
+        Ok(cmd) => match turtle.do_command(cmd) {
+            Response::Error(e) => error(e),
+            response => json_from_words(response_words(&response)),
+        },
+        Err(e) => error(e),
+    };
+    if let (Some(id), Value::Object(reply)) = (request.get("id"), &mut reply) {
+        reply.insert("id".into(), id.clone());
+    }
+    reply
+}
+
+fn error(message: String) -> Value {
+    let mut reply = Map::new();
+    reply.insert("error".into(), Value::String(message));
+    Value::Object(reply)
+}
+
+// the kind of command and its words, from e.g. `{"draw": ["forward", 100]}`
+fn read_json(request: &Value) -> Result<(&str, Vec<Word>), String> {
+    let Value::Object(request) = request else {
+        return Err("a request is a JSON object".into());
+    };
+    let mut kinds = request.iter().filter(|(key, _)| *key != "id");
+    let (Some((kind, words)), None) = (kinds.next(), kinds.next()) else {
+        return Err("a request has one of draw, screen or data".into());
+    };
+    let Value::Array(words) = words else {
+        return Err(format!("{kind} needs a list of words"));
+    };
+    let words = words
+        .iter()
+        .map(|word| match word {
+            Value::String(text) => Ok(Word::Text(text.clone())),
+            Value::Number(n) => Ok(Word::Plain(n.to_string())),
+            Value::Bool(b) => Ok(Word::Plain(b.to_string())),
+            Value::Null => Ok(Word::Plain("none".into())),
+            _ => Err(format!("{word} can't be a word")),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((kind, words))
+}
+
+// e.g. `{"position": [100, 0]}`, from the response's words
+fn json_from_words(words: Vec<Word>) -> Value {
+    let mut words = words.into_iter();
+    let name = match words.next() {
+        Some(Word::Plain(name) | Word::Text(name)) => name,
+        None => String::new(),
+    };
+    let values = words
+        .map(|word| match word {
+            Word::Text(text) => Value::String(text),
+            Word::Plain(word) => {
+                if let Ok(b) = word.parse() {
+                    Value::Bool(b)
+                } else if let Ok(n) = word.parse::<i64>() {
+                    Value::from(n)
+                } else if let Some(n) = word.parse().ok().and_then(Number::from_f64) {
+                    Value::Number(n)
+                } else {
+                    Value::String(word)
+                }
+            }
+        })
+        .collect();
+    let mut reply = Map::new();
+    reply.insert(name, Value::Array(values));
+    Value::Object(reply)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        command::{Command, DataCmd, DrawRequest, ScreenCmd},
+        TurtleShapeName,
+    };
+
+    fn request(line: &str) -> Result<Command, String> {
+        let request = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        let (kind, words) = read_json(&request)?;
+        read_request(kind, words)
+    }
+
+    #[test]
+    fn json_is_read_as_words() {
+        assert!(matches!(
+            request(r#"{"draw": ["forward", 100], "id": 3}"#),
+            Ok(Command::Draw(DrawRequest::TimedDraw(_)))
+        ));
+        assert!(matches!(
+            request(r#"{"screen": ["title", "Hello there"]}"#),
+            Ok(Command::Screen(ScreenCmd::SetTitle(title))) if title == "Hello there"
+        ));
+        assert!(matches!(
+            request(r#"{"data": ["shape", null]}"#),
+            Ok(Command::Data(DataCmd::TurtleShape(
+                TurtleShapeName::GetCurrent
+            )))
+        ));
+        assert_eq!(
+            request(r#"{"draw": ["forward"]}"#).err(),
+            Some("the line ends early".to_string())
+        );
+        assert!(request(r#"{"draw": ["forward", 1], "data": ["heading"]}"#).is_err());
+        assert!(request(r#"["forward", 1]"#).is_err());
+
+        let reply = json_from_words(vec![
+            Word::Plain("position".into()),
+            Word::Plain("100".into()),
+            Word::Plain("-0.5".into()),
+        ]);
+        assert_eq!(reply.to_string(), r#"{"position":[100,-0.5]}"#);
+        let reply = json_from_words(vec![
+            Word::Plain("shapes".into()),
+            Word::Plain("1".into()),
+            Word::Text("classic".into()),
+        ]);
+        assert_eq!(reply.to_string(), r#"{"shapes":[1,"classic"]}"#);
+    }
+}
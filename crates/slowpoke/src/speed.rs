@@ -1,3 +1,6 @@
+// at speed 1, drawing a line this long (or turning this many degrees) takes a second
+pub(crate) const SPEED_UNIT: f32 = 100.;
+
 #[derive(Debug, Copy, Clone)]
 pub struct Speed(u8);
 
@@ -55,3 +58,67 @@ impl From<&str> for Speed {
         }
     }
 }
+
+/// How the turtle speeds up and slows down while it draws each command
+#[derive(Debug, Default, Copy, Clone)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    Custom(fn(f32) -> f32), // takes and returns a fraction from 0 to 1
+}
+
+impl Easing {
+    /// How much of the command is drawn once `time` (from 0 to 1) of it has
+    /// passed
+    #[must_use]
+    pub fn apply(self, time: f32) -> f32 {
+        let t = time.clamp(0., 1.);
+        let eased = match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2. - t),
+            Self::EaseInOut => t * t * (3. - 2. * t),
+            Self::Custom(func) => func(t),
+        };
+        eased.clamp(0., 1.)
+    }
+}
+
+/// # Panics
+/// Panics if the name isn't "linear", "easein", "easeout" or "easeinout"
+impl From<&str> for Easing {
+    fn from(value: &str) -> Self {
+        match value {
+            "linear" => Self::Linear,
+            "easein" => Self::EaseIn,
+            "easeout" => Self::EaseOut,
+            "easeinout" => Self::EaseInOut,
+            _ => panic!("no easing {value}"),
+        }
+    }
+}
+
+impl From<fn(f32) -> f32> for Easing {
+    fn from(func: fn(f32) -> f32) -> Self {
+        Self::Custom(func)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn easing_starts_and_ends_in_place() {
+        for easing in ["linear", "easein", "easeout", "easeinout"] {
+            let easing = Easing::from(easing);
+            assert_eq!(easing.apply(0.), 0.);
+            assert_eq!(easing.apply(1.), 1.);
+        }
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    }
+}
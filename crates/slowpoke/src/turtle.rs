@@ -2,26 +2,38 @@ pub(crate) mod handler;
 pub(crate) mod task;
 pub(crate) mod types;
 
-use types::{TurtleID, TurtleThread};
+use types::{TimerID, TurtleID, TurtleThread};
 
 use std::{
-    cell::RefCell,
+    any::Any,
+    cell::{Cell, RefCell},
     collections::{HashMap, VecDeque},
+    io,
     marker::PhantomData,
-    sync::mpsc::{self, Receiver, Sender, TryRecvError},
-    thread::JoinHandle,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
 
 use crate::{
+    collision::DrawnPath,
     command::{
-        Command, DataCmd, DrawRequest, InputCmd, InstantaneousDrawCmd, ScreenCmd, TimedDrawCmd,
+        Command, DataCmd, DrawRequest, InputCmd, InstantaneousDrawCmd, MotionCmd, ScreenCmd,
+        TimedDrawCmd,
     },
     comms::{Request, Response},
     generate::{CurrentTurtleState, DrawCommand, TurtlePosition},
     gui::{Progression, TurtleGui},
     polygon::PolygonPath,
-    speed::Speed,
+    record::{Recorder, Recording},
+    speed::{Easing, Speed, SPEED_UNIT},
+    undo::UndoBufferSize,
     ScreenPosition,
 };
 
@@ -33,17 +45,23 @@ struct TurtleCommand {
 }
 
 // T == user interface code
-pub struct SlowpokeLib<T> {
+pub struct SlowpokeLib<T: TurtleUserInterface> {
     pub(crate) size: [isize; 2],
     pub(crate) title: String,
+    recording: Option<PathBuf>,
+    recording_error: Arc<Mutex<Option<io::Error>>>, // why the recording stopped
+    options: Cell<T::Options>,                      // handed to the next window that's opened
     data: PhantomData<T>,
 }
 
-impl<T> Default for SlowpokeLib<T> {
+impl<T: TurtleUserInterface> Default for SlowpokeLib<T> {
     fn default() -> Self {
         Self {
             size: [800, 800],
             title: "Turtle".to_string(),
+            recording: None,
+            recording_error: Arc::default(),
+            options: Cell::default(),
             data: PhantomData,
         }
     }
@@ -67,8 +85,79 @@ impl<T: std::fmt::Debug + TurtleUserInterface> SlowpokeLib<T> {
         self
     }
 
-    pub fn run<F: FnOnce(&mut Turtle) + Send + 'static>(&self, func: F) {
-        Turtle::run(self, func);
+    /// Save everything the turtles do to a file, which `replay()` can play
+    /// back. If the file can't be written to part way through, the turtles
+    /// carry on without it, and `recording_error()` says why.
+    ///
+    /// A recording is a text file. The first line is `slowpoke recording 1`,
+    /// and every line after that is one entry:
+    ///
+    /// ```text
+    /// <time> cmd <turtle> <thread> <command>
+    /// <time> result <turtle> <thread> <response>
+    /// <time> event <event>
+    /// ```
+    ///
+    /// `<time>` is in microseconds since the window opened. `cmd` lines are
+    /// the requests turtles sent, `result` lines are what data requests (and
+    /// hatching or cloning) answered, and `event` lines are key presses, mouse
+    /// clicks and window changes from the user interface.
+    ///
+    /// Everything after the kind is a list of words separated by spaces,
+    /// starting with the command's name, e.g. `draw forward 100`,
+    /// `screen title "Hello"` or `data towards 0 0`. Numbers are written out
+    /// in full, strings are quoted as in Rust, colors are either `current` or
+    /// three numbers from 0 to 1, and pictures are hex. Input commands record
+    /// which handler was set (`set` or `unset`) since the handler itself can't
+    /// be saved. Nor can a custom easing function: it's written as `custom`
+    /// and replayed as `linear`.
+    ///
+    /// Replaying only uses `cmd` lines: key and mouse handlers aren't known,
+    /// but every command they sent was recorded along with the rest.
+    #[must_use]
+    pub fn with_recording<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.recording = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Why the last window's recording stopped early, if it did
+    ///
+    /// # Panics
+    /// Panics if the window panicked while saving the error
+    pub fn recording_error(&self) -> Option<io::Error> {
+        self.recording_error.lock().unwrap().take()
+    }
+
+    /// Settings that only this user interface has, such as a side panel for
+    /// ratatui. They're used by the next window that's opened.
+    #[must_use]
+    pub fn with_options(self, options: T::Options) -> Self {
+        self.options.set(options);
+        self
+    }
+
+    /// Open the window and run `func` on the main turtle. This returns once the
+    /// window has been closed, either by the user, `bye()` or `exitonclick()`.
+    /// The exit code is a failure if the main turtle's thread panicked.
+    ///
+    /// # Panics
+    /// Panics if the recording set by `with_recording()` can't be created
+    pub fn run<F: FnOnce(&mut Turtle) + Send + 'static>(&self, func: F) -> ExitCode {
+        Turtle::run(self, func)
+    }
+
+    /// Open the window and play back a file saved by `with_recording()`, with
+    /// the same timing, instead of running any turtle code. Key presses and
+    /// mouse clicks do nothing, but whatever their handlers drew is replayed.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read or isn't a recording
+    ///
+    /// # Panics
+    /// Panics if the recording set by `with_recording()` can't be created
+    pub fn replay<P: AsRef<Path>>(&self, path: P) -> io::Result<ExitCode> {
+        let recording = Recording::load(path)?;
+        Ok(Turtle::launch(self, Box::new(|_| {}), Some(recording)))
     }
 }
 
@@ -90,29 +179,102 @@ impl Drop for Turtle {
     }
 }
 
+/// A frontend which displays the turtles and feeds them events.
+///
+/// Lifecycle:
+/// - `start` is given the frontend's options, creates a `TurtleTask`, runs the
+///   main turtle on its own thread, and then runs the event loop, calling
+///   `TurtleTask::tick` regularly.
+/// - The window stays open after the main turtle's function returns; it is
+///   closed by `bye()`, `exitonclick()`, or the user closing the window.
+/// - When the user closes the window, frontends send `TurtleEvent::WindowClose`
+///   instead of closing. Every `onclose()` handler is then run to completion
+///   before `tick` returns `EventResult::ShutDown`. A second close request
+///   shuts down without waiting for them.
+/// - On `EventResult::ShutDown` the frontend closes its windows and returns
+///   from `start` rather than exiting the process. Dropping the `TurtleTask`
+///   wakes up any turtle threads still waiting on it: `done()` returns, and
+///   any other turtle call unwinds that thread.
 pub trait TurtleUserInterface {
-    fn start(flags: TurtleFlags);
+    /// Settings only this frontend has, from `SlowpokeLib::with_options()`
+    type Options: Default;
+
+    fn start(flags: TurtleFlags, options: Self::Options);
+}
+
+// Used to unwind a turtle's thread once the user interface has gone away
+pub(crate) struct Disconnected;
+
+impl Disconnected {
+    pub(crate) fn is_disconnect(payload: &(dyn Any + Send)) -> bool {
+        payload.is::<Self>()
+    }
 }
 
+pub(crate) const EXIT_PANIC: u8 = 101;
+
+// for with_deep_stack()
+const DEEP_STACK_SIZE: usize = 256 * 1024 * 1024;
+
 impl Turtle {
+    /// # Panics
+    /// Panics if the recording set by `with_recording()` can't be created
     pub fn run<T: TurtleUserInterface, F: FnOnce(&mut Turtle) + Send + 'static>(
         args: &SlowpokeLib<T>,
         func: F,
-    ) {
+    ) -> ExitCode {
+        Self::launch(args, Box::new(func), None)
+    }
+
+    /// Run `func` with this turtle on a thread with a deep stack, for
+    /// interpreters whose programs recurse a lot. Only the part of the stack
+    /// that's used is actually allocated. When the window closes, the turtle
+    /// unwinds that thread and then the one that called this.
+    ///
+    /// # Panics
+    /// Panics if the thread can't be started
+    pub fn with_deep_stack<R: Send, F: FnOnce(&mut Turtle) -> R + Send>(&mut self, func: F) -> R {
+        thread::scope(|scope| {
+            let handle = thread::Builder::new()
+                .stack_size(DEEP_STACK_SIZE)
+                .spawn_scoped(scope, || func(self))
+                .expect("couldn't start a thread with a deep stack");
+            handle
+                .join()
+                .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+        })
+    }
+
+    fn launch<T: TurtleUserInterface>(
+        args: &SlowpokeLib<T>,
+        func: Box<TurtleStartFunc>,
+        replay: Option<Recording>,
+    ) -> ExitCode {
+        let recorder = args.recording.as_ref().map(|path| {
+            Recorder::create(path, args.recording_error.clone())
+                .unwrap_or_else(|e| panic!("can't record to {}: {e}", path.display()))
+        });
+
         let xsize = to_f32(args.size[0]);
         let ysize = to_f32(args.size[1]);
 
         let (issue_command, receive_command) = mpsc::channel();
+        let exit_status = Arc::new(AtomicU8::new(0));
 
         let flags = TurtleFlags {
-            start_func: Some(Box::new(func)),
+            start_func: Some(func),
             issue_command: Some(issue_command),
             receive_command: Some(receive_command),
             title: args.title.clone(),
             size: [xsize, ysize],
+            exit_status: exit_status.clone(),
+            recorder,
+            replay,
         };
 
-        T::start(flags);
+        T::start(flags, args.options.take());
+
+        ExitCode::from(exit_status.load(Ordering::SeqCst))
     }
 
     pub(crate) fn init(
@@ -138,14 +300,18 @@ impl Turtle {
         let _ = self.do_command(Command::Screen(cmd));
     }
 
-    pub(crate) fn do_input(&self, cmd: InputCmd) {
-        let _ = self.do_command(Command::Input(cmd));
+    pub(crate) fn do_input(&self, cmd: InputCmd) -> Response {
+        self.do_command(Command::Input(cmd))
     }
 
     pub(crate) fn do_data(&self, cmd: DataCmd) -> Response {
         self.do_command(Command::Data(cmd))
     }
 
+    pub(crate) const fn id(&self) -> TurtleID {
+        self.turtle
+    }
+
     pub(crate) fn do_hatch(&self) -> Turtle {
         let response = self.do_command(Command::Hatch);
         if let Response::Turtle(t) = response {
@@ -155,6 +321,15 @@ impl Turtle {
         }
     }
 
+    pub(crate) fn do_clone(&self) -> Turtle {
+        let response = self.do_command(Command::CloneTurtle);
+        if let Response::Turtle(t) = response {
+            t
+        } else {
+            panic!("no turtle");
+        }
+    }
+
     fn req(&self, cmd: Command) -> Request {
         Request {
             turtle: self.turtle,
@@ -163,8 +338,13 @@ impl Turtle {
         }
     }
 
-    fn do_command(&self, cmd: Command) -> Response {
-        let is_data_cmd = matches!(cmd, Command::Data(_));
+    pub(crate) fn do_command(&self, cmd: Command) -> Response {
+        let is_data_cmd = matches!(
+            cmd,
+            Command::Data(_)
+                | Command::Input(InputCmd::Timer(..))
+                | Command::Screen(ScreenCmd::SetWorldCoordinates(..))
+        );
         let tracer_was_off = !*self.tracer.borrow();
         if let Command::Draw(DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::Tracer(t))) = &cmd
         {
@@ -183,7 +363,7 @@ impl Turtle {
                             match self.command_complete.try_recv() {
                                 Ok(response) => return_value = response,
                                 Err(TryRecvError::Empty) => return return_value,
-                                Err(TryRecvError::Disconnected) => panic!("lost main thread"),
+                                Err(TryRecvError::Disconnected) => return Self::disconnected(),
                             }
                         }
                     }
@@ -192,54 +372,86 @@ impl Turtle {
                 }
             } else if is_data_cmd {
                 loop {
-                    if let Ok(result) = self.command_complete.recv() {
-                        if !matches!(result, Response::Done) {
-                            return result;
-                        }
+                    match self.command_complete.recv() {
+                        Ok(Response::Done) => {}
+                        Ok(result) => return result,
+                        Err(_) => return Self::disconnected(),
                     }
                 }
             } else {
                 loop {
                     match self.command_complete.try_recv() {
                         Ok(response) => {
+                            // an error is from a command that couldn't be done,
+                            // which nobody waited to hear about
                             assert!(
-                                matches!(response, Response::Done),
+                                matches!(response, Response::Done | Response::Error(_)),
                                 "Received data response: {response:?} to command {cmd_string}"
                             );
                         }
                         Err(TryRecvError::Empty) => return Response::Done,
-                        Err(TryRecvError::Disconnected) => panic!("lost main thread"),
+                        Err(TryRecvError::Disconnected) => return Self::disconnected(),
                     }
                 }
             }
         }
 
-        /* main thread has gone away; wait here to meet our doom */
-        loop {
-            std::thread::park();
+        Self::disconnected()
+    }
+
+    // Wait for the user interface to shut down, ignoring any responses to
+    // drawing commands which are still being animated.
+    pub(crate) fn wait_for_shutdown(&self) {
+        while self.command_complete.recv().is_ok() {}
+    }
+
+    // The user interface has shut down, so there's nobody left to draw for us.
+    // Unwind this thread (without running the panic hook) so that destructors
+    // run, unless we're already unwinding, e.g. from a `Drop` that draws.
+    fn disconnected() -> Response {
+        if std::thread::panicking() {
+            Response::Cancel
+        } else {
+            std::panic::resume_unwind(Box::new(Disconnected))
         }
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 struct PolygonBuilder {
     last_point: Option<ScreenPosition<i32>>,
     verticies: Vec<[f32; 2]>,
+    contours: Vec<usize>, // where each contour after the first starts
+    start_contour: bool,  // the next point starts a new contour
 }
 
 impl PolygonBuilder {
     fn start(&mut self, pos: ScreenPosition<i32>) {
         self.last_point = Some(pos);
         self.verticies = vec![[to_f32(pos.x as isize), to_f32(pos.y as isize)]];
+        self.contours.clear();
+        self.start_contour = false;
     }
 
     fn update(&mut self, pos: ScreenPosition<i32>) {
-        if let Some(p) = self.last_point {
-            if p != pos {
-                let new_point = [to_f32(pos.x as isize), to_f32(pos.y as isize)];
-                self.verticies.push(new_point);
-                self.last_point = Some(pos);
+        if let Some(p) = self.last_point
+            && (p != pos || self.start_contour)
+        {
+            if self.start_contour {
+                self.contours.push(self.verticies.len());
+                self.start_contour = false;
             }
+            let new_point = [to_f32(pos.x as isize), to_f32(pos.y as isize)];
+            self.verticies.push(new_point);
+            self.last_point = Some(pos);
+        }
+    }
+
+    // The current contour is finished; the next one starts wherever the
+    // turtle moves to next
+    fn next_contour(&mut self) {
+        if self.last_point.is_some() {
+            self.start_contour = true;
         }
     }
 
@@ -248,6 +460,40 @@ impl PolygonBuilder {
             self.verticies.push(self.verticies[0]);
         }
     }
+
+    // Enough to undo the next command. Most commands only add points, so how
+    // many there are is all that's needed; only starting a new polygon needs
+    // the old one kept whole.
+    fn save(&self, starts_new: bool) -> PolygonSave {
+        PolygonSave {
+            last_point: self.last_point,
+            start_contour: self.start_contour,
+            verticies: self.verticies.len(),
+            contours: self.contours.len(),
+            replaced: starts_new.then(|| (self.verticies.clone(), self.contours.clone())),
+        }
+    }
+
+    fn restore(&mut self, save: PolygonSave) {
+        if let Some((verticies, contours)) = save.replaced {
+            self.verticies = verticies;
+            self.contours = contours;
+        } else {
+            self.verticies.truncate(save.verticies);
+            self.contours.truncate(save.contours);
+        }
+        self.last_point = save.last_point;
+        self.start_contour = save.start_contour;
+    }
+}
+
+#[derive(Debug)]
+struct PolygonSave {
+    last_point: Option<ScreenPosition<i32>>,
+    start_contour: bool,
+    verticies: usize,
+    contours: usize,
+    replaced: Option<(Vec<[f32; 2]>, Vec<usize>)>, // the polygon a new one replaced
 }
 
 #[derive(Default, Debug)]
@@ -259,33 +505,121 @@ pub(crate) struct DrawState {
     tracer: bool,
     respond_immediately: bool,
     speed: Speed,
+    duration: Option<Duration>, // set by the user, to ignore speed and length
+    easing: Easing,
+    length: f32, // of the command being drawn
     current_stamp: usize,
     fill_poly: PolygonBuilder,
     shape_poly: PolygonBuilder,
     turtle: CurrentTurtleState,
+    drawn: DrawnPath,
+    undo_size: UndoBufferSize,
+    undo_buffer: VecDeque<UndoEntry>,
+    undoing: Option<UndoEntry>, // restored once the undo animation finishes
+    redo_buffer: Vec<DrawRequest>,
+}
+
+// Everything needed to put a turtle and its drawing back the way they were
+// before a command, like the undo buffer in python's turtle
+#[derive(Debug)]
+struct UndoEntry {
+    request: DrawRequest, // kept for redo()
+    turtle: CurrentTurtleState,
+    fill_poly: PolygonSave,
+    shape_poly: PolygonSave,
+    insert_fill: Option<usize>,
+    gui_len: usize,                    // length of the gui's command list
+    drawing: Option<Vec<DrawCommand>>, // the whole drawing, for clear and reset
+    animate: bool,                     // the command can be undone in reverse
+    length: f32,                       // how far the command moved or turned
 }
 
 impl DrawState {
+    // how much of the current command to draw
+    pub(crate) fn fraction(&self) -> f32 {
+        if (0. ..1.).contains(&self.percent) {
+            self.easing.apply(self.percent)
+        } else {
+            self.percent
+        }
+    }
+
+    // how much further through the current command `delta_t` seconds take us
+    fn step(&self, delta_t: f32) -> f32 {
+        if let Some(duration) = self.duration {
+            delta_t / duration.as_secs_f32().max(f32::EPSILON)
+        } else if self.length > 0. {
+            delta_t * f32::from(self.speed.get()) * SPEED_UNIT / self.length
+        } else {
+            1.
+        }
+    }
+
     fn reset(&mut self) {
-        *self = Self::default();
+        // the undo buffer's size survives a reset, its contents don't
+        *self = Self {
+            undo_size: self.undo_size,
+            ..Self::default()
+        };
+    }
+
+    fn set_undo_size(&mut self, size: UndoBufferSize) {
+        self.undo_size = size;
+        let limit = self.trim_undo_buffer();
+        // a smaller buffer gives back the room the forgotten commands took
+        self.undo_buffer.shrink_to(limit);
+        self.redo_buffer.shrink_to(limit);
+    }
+
+    // forget the oldest commands once there are too many to undo, returning
+    // how many are kept
+    fn trim_undo_buffer(&mut self) -> usize {
+        let limit = self.undo_size.get().unwrap_or(0);
+        let excess = self.undo_buffer.len().saturating_sub(limit);
+        self.undo_buffer.drain(..excess);
+        let excess = self.redo_buffer.len().saturating_sub(limit);
+        self.redo_buffer.drain(..excess);
+        limit
     }
 }
 
 #[derive(Debug)]
 struct TurtleTimer {
     time: Duration,
-    prev: Instant,
+    armed: Instant,
+    repeat: bool,
+    running: Option<TurtleThread>, // the thread running the last callback, if any
     func: fn(&mut Turtle, Duration),
 }
 
 impl TurtleTimer {
-    fn new(func: fn(&mut Turtle, Duration), time: Duration) -> Self {
+    fn new(func: fn(&mut Turtle, Duration), time: Duration, repeat: bool, now: Instant) -> Self {
         Self {
             time,
-            prev: Instant::now(),
+            armed: now,
+            repeat,
+            running: None,
             func,
         }
     }
+
+    // A repeating timer doesn't fire again until the previous callback has
+    // returned, so that a slow callback can't pile up threads on one turtle
+    fn is_due(&self, now: Instant) -> bool {
+        self.running.is_none() && now.duration_since(self.armed) >= self.time
+    }
+
+    // Returns the time since the timer was armed, and re-arms it if it repeats.
+    fn fire(&mut self, now: Instant) -> Duration {
+        let elapsed = now.duration_since(self.armed);
+        self.armed += self.time;
+        if now.duration_since(self.armed) >= self.time {
+            // we fell behind (e.g. the callback took longer than the interval),
+            // so skip the missed ticks instead of firing them all at once
+            self.armed = now;
+        }
+        elapsed
+    }
 }
 
 #[derive(Default, Debug)]
@@ -295,7 +629,9 @@ struct EventHandlers {
     onmousepress: Option<fn(&mut Turtle, x: f32, y: f32)>,
     onmouserelease: Option<fn(&mut Turtle, x: f32, y: f32)>,
     onmousedrag: Option<fn(&mut Turtle, x: f32, y: f32)>,
-    ontimer: Option<TurtleTimer>,
+    onclose: Option<fn(&mut Turtle)>,
+    timers: HashMap<TimerID, TurtleTimer>,
+    ignore_keys: bool, // set by `unlisten()`
     pending_keys: bool,
     requesting_thread: TurtleThread, // The thread that made the last drawing request
 }
@@ -331,7 +667,7 @@ impl TurtleData {
     }
 
     fn pending_key_event(&mut self) -> bool {
-        if self.event.pending_keys {
+        if self.event.ignore_keys || self.event.pending_keys {
             true
         } else {
             self.event.pending_keys = true;
@@ -346,9 +682,12 @@ impl TurtleData {
         Turtle::init(issue_command, command_complete, self.turtle_id, thread)
     }
 
-    fn convert_command<G: TurtleGui>(&mut self, cmd: &DrawRequest, gui: &mut G) {
+    // returns the length of what was drawn
+    fn convert_command<G: TurtleGui>(&mut self, cmd: &DrawRequest, gui: &mut G) -> f32 {
+        let mut length = 0.;
         if let Some(command) = self.state.turtle.apply(cmd) {
             let tid = self.turtle_id;
+            length = command.length();
 
             if matches!(command, DrawCommand::Filler) {
                 self.state.insert_fill = Some(gui.get_position(tid));
@@ -356,10 +695,17 @@ impl TurtleData {
 
             match &command {
                 DrawCommand::Line(lineinfo) => {
-                    // TODO: when "teleporting" instead of goto/setpos, we're only supposed
-                    // to continue the current polygon if fill_gap=True (see python docs)
-                    self.state.fill_poly.update(lineinfo.end);
+                    // as in python, teleporting fills what's been drawn and
+                    // starts a new fill where the turtle lands, unless asked
+                    // to fill the gap
+                    if Self::is_teleport(cmd) && self.state.insert_fill.is_some() {
+                        self.end_fill(gui);
+                        self.begin_fill(gui);
+                    } else {
+                        self.state.fill_poly.update(lineinfo.end);
+                    }
                     self.state.shape_poly.update(lineinfo.end);
+                    self.state.drawn.add(&command);
                     gui.append_command(tid, command);
                 }
                 DrawCommand::Circle(circle) => {
@@ -367,38 +713,33 @@ impl TurtleData {
                         self.state.fill_poly.update([c.x, c.y].into());
                         self.state.shape_poly.update([c.x, c.y].into());
                     }
+                    self.state.drawn.add(&command);
                     gui.append_command(tid, command);
                 }
-                DrawCommand::DrawPolygon(_) => {
+                DrawCommand::DrawPolygon(..) => {
                     panic!("oops");
                 }
                 DrawCommand::StampTurtle => {
-                    self.state.current_stamp =
-                        gui.stamp(tid, self.state.turtle.pos(), self.state.turtle.angle);
+                    self.state.current_stamp = gui.stamp(
+                        tid,
+                        self.state.turtle.pos(),
+                        self.state.turtle.screen_angle(),
+                    );
                 }
                 DrawCommand::BeginPoly => {
                     let pos_copy = self.state.turtle.pos();
                     self.state.shape_poly.start(pos_copy);
                 }
+                DrawCommand::NextContour => {
+                    self.state.fill_poly.next_contour();
+                }
                 DrawCommand::EndPoly => {
                     self.state.shape_poly.close();
                 }
-                DrawCommand::BeginFill => {
-                    let pos_copy = self.state.turtle.pos();
-                    self.state.fill_poly.start(pos_copy);
-                    self.state.insert_fill = Some(gui.get_position(tid));
-                    gui.append_command(tid, DrawCommand::Filler);
-                }
-                DrawCommand::EndFill => {
-                    if !self.state.fill_poly.verticies.is_empty() {
-                        let polygon = PolygonPath::new(&self.state.fill_poly.verticies);
-                        self.state.fill_poly.last_point = None;
-                        if let Some(index) = self.state.insert_fill.take() {
-                            gui.fill_polygon(tid, DrawCommand::DrawPolygon(polygon), index);
-                        }
-                    }
-                }
+                DrawCommand::BeginFill => self.begin_fill(gui),
+                DrawCommand::EndFill => self.end_fill(gui),
                 DrawCommand::Clear => {
+                    self.state.drawn.invalidate();
                     gui.clear_turtle(tid);
                     for cmd in self.state.turtle.get_state() {
                         gui.append_command(tid, cmd);
@@ -406,14 +747,18 @@ impl TurtleData {
                 }
 
                 DrawCommand::Reset => {
+                    self.state.drawn.invalidate();
                     gui.clear_turtle(tid);
-                    self.state.turtle.reset();
+                    for cmd in self.state.turtle.reset() {
+                        gui.append_command(tid, cmd);
+                    }
                 }
 
                 DrawCommand::Filler
                 | DrawCommand::Text(..)
                 | DrawCommand::Filled(_)
                 | DrawCommand::SetPenWidth(_)
+                | DrawCommand::SetLineStyle(_)
                 | DrawCommand::SetFillColor(_)
                 | DrawCommand::SetPosition(_)
                 | DrawCommand::SetHeading(..)
@@ -424,6 +769,41 @@ impl TurtleData {
                 }
             }
         }
+        length
+    }
+
+    // a teleport that doesn't fill the gap
+    fn is_teleport(cmd: &DrawRequest) -> bool {
+        matches!(
+            cmd,
+            DrawRequest::TimedDraw(TimedDrawCmd::Motion(MotionCmd::Teleport(_, _, false)))
+        )
+    }
+
+    fn begin_fill<G: TurtleGui>(&mut self, gui: &mut G) {
+        let tid = self.turtle_id;
+        self.state.fill_poly.start(self.state.turtle.pos());
+        self.state.insert_fill = Some(gui.get_position(tid));
+        gui.append_command(tid, DrawCommand::Filler);
+    }
+
+    fn end_fill<G: TurtleGui>(&mut self, gui: &mut G) {
+        if !self.state.fill_poly.verticies.is_empty() {
+            let polygon = PolygonPath::with_contours(
+                &self.state.fill_poly.verticies,
+                &self.state.fill_poly.contours,
+                self.state.turtle.fill_rule(),
+            );
+            let style = self.state.turtle.fill_style();
+            self.state.fill_poly.last_point = None;
+            if let Some(index) = self.state.insert_fill.take() {
+                gui.fill_polygon(
+                    self.turtle_id,
+                    DrawCommand::DrawPolygon(polygon, style),
+                    index,
+                );
+            }
+        }
     }
 
     fn is_instantaneous(&self) -> bool {
@@ -447,11 +827,11 @@ impl TurtleData {
         if self.state.drawing_done {
             self.state.percent = 1.;
         } else {
-            let multiplier = f32::from(s);
+            let step = self.state.step(delta_t);
 
             match self.state.progression {
-                Progression::Forward => self.state.percent += delta_t * multiplier,
-                Progression::Reverse => self.state.percent -= delta_t * multiplier,
+                Progression::Forward => self.state.percent += step,
+                Progression::Reverse => self.state.percent -= step,
             }
         }
 
@@ -470,10 +850,8 @@ impl TurtleData {
         {
             self.state.drawing_done = false;
 
-            if matches!(self.state.progression, Progression::Reverse) {
-                if let Some(cmd) = gui.pop(self.turtle_id) {
-                    self.state.turtle.undo(&cmd);
-                }
+            if let Some(entry) = self.state.undoing.take() {
+                self.restore(entry, gui);
             }
 
             if cmd.tracer_true() {
@@ -497,25 +875,105 @@ impl TurtleData {
             } = self.queue.pop_front().unwrap();
             self.event.requesting_thread = thread;
 
-            self.convert_command(&cmd, gui);
+            // what actually gets drawn: redo() replays an undone command
+            let is_redo = matches!(cmd, DrawRequest::TimedDraw(TimedDrawCmd::Redo));
+            let mut action = None;
+            if is_redo {
+                action = self.state.redo_buffer.pop();
+            } else if cmd.is_undoable() {
+                self.state.redo_buffer.clear();
+            }
+            let action = action.unwrap_or_else(|| cmd.clone());
+            let instantaneous = is_redo && !(action.is_undoable() && action.is_timed());
+
+            let entry = (action.is_undoable()
+                && self.state.undo_size.get().is_some_and(|size| size > 0))
+            .then(|| self.undo_entry(&action, gui));
+
+            self.state.length = self.convert_command(&action, gui);
+
+            if let Some(entry) = entry {
+                self.state.undo_buffer.push_back(UndoEntry {
+                    length: self.state.length,
+                    ..entry
+                });
+                self.state.trim_undo_buffer();
+            }
 
             if let DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::Tracer(t)) = &cmd {
                 self.state.tracer = *t;
             }
 
             if matches!(cmd, DrawRequest::TimedDraw(TimedDrawCmd::Undo)) {
-                self.state.progression = Progression::Reverse;
+                self.state.progression = Progression::Forward;
                 self.state.percent = 1.;
-                gui.undo(turtle);
+                if let Some(entry) = self.state.undo_buffer.pop_back() {
+                    self.state.redo_buffer.push(entry.request.clone());
+                    if entry.animate {
+                        // draw the command backwards, then restore the state
+                        self.state.progression = Progression::Reverse;
+                        self.state.length = entry.length;
+                        self.state.undoing = Some(entry);
+                        gui.undo(turtle);
+                    } else {
+                        self.restore(entry, gui);
+                    }
+                }
             } else {
                 self.state.progression = Progression::Forward;
-                self.state.percent = 0.;
+                self.state.percent = if instantaneous { 1. } else { 0. };
             }
 
             self.current_command = Some(cmd);
         }
     }
 
+    fn undo_entry<G: TurtleGui>(&self, cmd: &DrawRequest, gui: &G) -> UndoEntry {
+        let replaces_drawing = matches!(
+            cmd,
+            DrawRequest::InstantaneousDraw(
+                InstantaneousDrawCmd::Clear | InstantaneousDrawCmd::Reset
+            )
+        );
+
+        UndoEntry {
+            request: cmd.clone(),
+            turtle: self.state.turtle.clone(),
+            fill_poly: self.state.fill_poly.save(
+                matches!(
+                    cmd,
+                    DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::BeginFill)
+                ) || (Self::is_teleport(cmd) && self.state.insert_fill.is_some()),
+            ),
+            shape_poly: self.state.shape_poly.save(matches!(
+                cmd,
+                DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::BeginPoly)
+            )),
+            insert_fill: self.state.insert_fill,
+            gui_len: gui.get_position(self.turtle_id),
+            drawing: replaces_drawing.then(|| gui.save_drawing(self.turtle_id)),
+            animate: cmd.is_timed(),
+            length: 0.,
+        }
+    }
+
+    fn restore<G: TurtleGui>(&mut self, entry: UndoEntry, gui: &mut G) {
+        self.state.drawn.invalidate();
+        if let Some(drawing) = entry.drawing {
+            gui.restore_drawing(self.turtle_id, drawing);
+        } else {
+            // popping (rather than truncating) lets the gui un-fill polygons
+            while gui.get_position(self.turtle_id) > entry.gui_len {
+                gui.pop(self.turtle_id);
+            }
+        }
+
+        self.state.turtle.restore(entry.turtle);
+        self.state.fill_poly.restore(entry.fill_poly);
+        self.state.shape_poly.restore(entry.shape_poly);
+        self.state.insert_fill = entry.insert_fill;
+    }
+
     fn send_response(&mut self, thread: TurtleThread, is_stamp: bool) {
         if let Some(responder) = self.responder.get(&thread) {
             let _ = responder.send(if is_stamp {
@@ -540,9 +998,319 @@ pub struct TurtleFlags {
     pub receive_command: Option<Receiver<Request>>,
     pub title: String,
     pub size: [f32; 2],
+    pub exit_status: Arc<AtomicU8>,
+    pub(crate) recorder: Option<Recorder>,
+    pub(crate) replay: Option<Recording>,
 }
 
 #[allow(clippy::cast_precision_loss)]
 fn to_f32<I: Into<isize>>(val: I) -> f32 {
     val.into() as f32
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        command::{CurveCmd, RotateCmd},
+        headless::{self, NoScreen},
+        turtle::handler::Handler,
+    };
+
+    fn setup() -> (TurtleData, Handler<(), NoScreen>) {
+        let gui = headless::handler();
+        let mut td = TurtleData::new();
+        td.turtle_id = TurtleID::default(); // the main turtle
+        td.state.speed = 0.into();
+        (td, gui)
+    }
+
+    fn run(td: &mut TurtleData, gui: &mut Handler<(), NoScreen>, cmd: DrawRequest) {
+        td.queue.push_back(TurtleCommand {
+            cmd,
+            turtle: td.turtle_id,
+            thread: TurtleThread::default(),
+        });
+        while !td.queue.is_empty() || td.current_command.is_some() {
+            td.time_passes(gui, 0.1);
+        }
+    }
+
+    #[test]
+    fn undo_restores_state() {
+        let (mut td, mut gui) = setup();
+        let undo = DrawRequest::TimedDraw(TimedDrawCmd::Undo);
+
+        run(
+            &mut td,
+            &mut gui,
+            DrawRequest::TimedDraw(TimedDrawCmd::Circle(50., 90., 10)),
+        );
+        run(
+            &mut td,
+            &mut gui,
+            DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::PenUp),
+        );
+        run(
+            &mut td,
+            &mut gui,
+            DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::Clear),
+        );
+        assert_eq!(td.state.undo_buffer.len(), 3);
+
+        run(&mut td, &mut gui, undo.clone());
+        assert!(matches!(
+            gui.turtle[&td.turtle_id].cmds[0],
+            DrawCommand::Circle(_)
+        ));
+
+        run(&mut td, &mut gui, undo.clone());
+        assert!(td.state.turtle.get_pen_state());
+
+        run(&mut td, &mut gui, undo.clone());
+        let pos: ScreenPosition<f32> = td.state.turtle.pos();
+        assert_eq!(pos, ScreenPosition::new(0., 0.));
+        assert!(gui.turtle[&td.turtle_id].cmds.is_empty());
+        assert_eq!(td.state.undo_buffer.len(), 0);
+    }
+
+    #[test]
+    fn redo_replays_undone_commands() {
+        let (mut td, mut gui) = setup();
+        let circle = DrawRequest::TimedDraw(TimedDrawCmd::Circle(50., 90., 10));
+        let undo = DrawRequest::TimedDraw(TimedDrawCmd::Undo);
+        let redo = DrawRequest::TimedDraw(TimedDrawCmd::Redo);
+
+        run(&mut td, &mut gui, circle);
+        let end: ScreenPosition<f32> = td.state.turtle.pos();
+        run(&mut td, &mut gui, undo.clone());
+        run(&mut td, &mut gui, redo.clone());
+        assert_eq!(td.state.turtle.pos(), end);
+        assert_eq!(gui.turtle[&td.turtle_id].cmds.len(), 1);
+
+        // a new command means there's nothing left to redo
+        run(&mut td, &mut gui, undo);
+        run(
+            &mut td,
+            &mut gui,
+            DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::PenUp),
+        );
+        run(&mut td, &mut gui, redo);
+        assert!(gui.turtle[&td.turtle_id].cmds.is_empty());
+        assert_eq!(td.state.undo_buffer.len(), 1);
+    }
+
+    #[test]
+    fn undo_buffer_is_bounded() {
+        let (mut td, mut gui) = setup();
+        let forward = || DrawRequest::TimedDraw(TimedDrawCmd::Motion(MotionCmd::Forward(10.)));
+
+        let undo = || DrawRequest::TimedDraw(TimedDrawCmd::Undo);
+
+        td.state.set_undo_size(2.into());
+        for _ in 0..5 {
+            run(&mut td, &mut gui, forward());
+        }
+        assert_eq!(td.state.undo_buffer.len(), 2);
+
+        // only the newest commands can be undone
+        for _ in 0..3 {
+            run(&mut td, &mut gui, undo());
+        }
+        assert_eq!(td.state.turtle.pos(), ScreenPosition::new(30., 0.));
+        assert_eq!(gui.turtle[&td.turtle_id].cmds.len(), 3);
+
+        td.state.set_undo_size(1.into());
+        assert_eq!(td.state.redo_buffer.len(), 1);
+        td.state.set_undo_size(None.into());
+        assert!(td.state.undo_buffer.is_empty());
+        assert!(td.state.redo_buffer.is_empty());
+        run(&mut td, &mut gui, forward());
+        assert!(td.state.undo_buffer.is_empty());
+    }
+
+    #[test]
+    fn curves_end_facing_along_the_curve() {
+        let (mut td, mut gui) = setup();
+        let curve = |c| DrawRequest::TimedDraw(TimedDrawCmd::Curve(c, 16));
+
+        run(
+            &mut td,
+            &mut gui,
+            curve(CurveCmd::Quadratic([50., 0.], [100., 100.])),
+        );
+        let pos: ScreenPosition<f32> = td.state.turtle.pos();
+        assert!((pos - ScreenPosition::new(100., -100.)).length() < 0.001);
+        assert!((td.state.turtle.heading() - 63.434_95).abs() < 0.001);
+
+        // a full ellipse comes back to where it started
+        run(&mut td, &mut gui, curve(CurveCmd::Ellipse(80., -30., 360.)));
+        let end: ScreenPosition<f32> = td.state.turtle.pos();
+        assert!((end - pos).length() < 0.001);
+        assert!((td.state.turtle.heading() - 63.434_95).abs() < 0.001);
+
+        run(
+            &mut td,
+            &mut gui,
+            DrawRequest::TimedDraw(TimedDrawCmd::Undo),
+        );
+        assert_eq!(gui.turtle[&td.turtle_id].cmds.len(), 1);
+    }
+
+    #[test]
+    fn fills_can_have_holes() {
+        let (mut td, mut gui) = setup();
+        let instant = |cmd| DrawRequest::InstantaneousDraw(cmd);
+        let goto = |x, y| DrawRequest::TimedDraw(TimedDrawCmd::Motion(MotionCmd::GoTo(x, y)));
+        let teleport =
+            |x, y| DrawRequest::TimedDraw(TimedDrawCmd::Motion(MotionCmd::Teleport(x, y, true)));
+
+        run(&mut td, &mut gui, instant(InstantaneousDrawCmd::BeginFill));
+        for (x, y) in [(30., 0.), (30., 30.), (0., 30.), (0., 0.)] {
+            run(&mut td, &mut gui, goto(x, y));
+        }
+        run(
+            &mut td,
+            &mut gui,
+            instant(InstantaneousDrawCmd::NextContour),
+        );
+        run(&mut td, &mut gui, teleport(10., 10.));
+        for (x, y) in [(20., 10.), (20., 20.), (10., 20.), (10., 10.)] {
+            run(&mut td, &mut gui, goto(x, y));
+        }
+        run(&mut td, &mut gui, instant(InstantaneousDrawCmd::EndFill));
+
+        let polygon = gui.turtle[&td.turtle_id]
+            .cmds
+            .iter()
+            .find_map(|cmd| match cmd {
+                DrawCommand::DrawPolygon(polygon, _) => Some(polygon.clone()),
+                _ => None,
+            })
+            .expect("no polygon");
+        assert_eq!(polygon.contours(), [5]);
+        assert_eq!(polygon.contour_points().count(), 2);
+    }
+
+    #[test]
+    fn teleport_can_fill_the_gap() {
+        let (mut td, mut gui) = setup();
+        let goto = |x, y| DrawRequest::TimedDraw(TimedDrawCmd::Motion(MotionCmd::GoTo(x, y)));
+
+        run(
+            &mut td,
+            &mut gui,
+            DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::BeginFill),
+        );
+        run(&mut td, &mut gui, goto(30., 0.));
+        run(
+            &mut td,
+            &mut gui,
+            DrawRequest::TimedDraw(TimedDrawCmd::Motion(MotionCmd::Teleport(30., 30., true))),
+        );
+        run(&mut td, &mut gui, goto(0., 0.));
+
+        assert!(td.state.fill_poly.contours.is_empty());
+        assert_eq!(td.state.fill_poly.verticies.len(), 4);
+    }
+
+    #[test]
+    fn teleport_starts_a_new_fill() {
+        let (mut td, mut gui) = setup();
+        let goto = |x, y| DrawRequest::TimedDraw(TimedDrawCmd::Motion(MotionCmd::GoTo(x, y)));
+        let tid = td.turtle_id;
+        let polygons = |gui: &Handler<(), NoScreen>| {
+            gui.turtle[&tid]
+                .cmds
+                .iter()
+                .filter(|cmd| matches!(cmd, DrawCommand::DrawPolygon(..)))
+                .count()
+        };
+
+        run(
+            &mut td,
+            &mut gui,
+            DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::BeginFill),
+        );
+        run(&mut td, &mut gui, goto(30., 0.));
+        run(&mut td, &mut gui, goto(30., 30.));
+        run(
+            &mut td,
+            &mut gui,
+            DrawRequest::TimedDraw(TimedDrawCmd::Motion(MotionCmd::Teleport(50., 0., false))),
+        );
+        assert_eq!(polygons(&gui), 1);
+        assert!(td.state.fill_poly.contours.is_empty());
+        assert_eq!(td.state.fill_poly.verticies, vec![[50., 0.]]);
+
+        // undoing the teleport un-fills the first shape and carries it on
+        run(
+            &mut td,
+            &mut gui,
+            DrawRequest::TimedDraw(TimedDrawCmd::Undo),
+        );
+        assert_eq!(polygons(&gui), 0);
+        assert_eq!(td.state.fill_poly.verticies.len(), 3);
+        assert!(td.state.insert_fill.is_some());
+    }
+
+    #[test]
+    fn undo_keeps_polygon_lengths_not_copies() {
+        let (mut td, mut gui) = setup();
+        let goto = |x, y| DrawRequest::TimedDraw(TimedDrawCmd::Motion(MotionCmd::GoTo(x, y)));
+        let begin_fill = || DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::BeginFill);
+        let undo = || DrawRequest::TimedDraw(TimedDrawCmd::Undo);
+
+        run(&mut td, &mut gui, begin_fill());
+        for (x, y) in [(30., 0.), (30., 30.), (0., 30.)] {
+            run(&mut td, &mut gui, goto(x, y));
+        }
+        let entry = td.state.undo_buffer.back().expect("no undo entry");
+        assert!(entry.fill_poly.replaced.is_none());
+        assert_eq!(entry.fill_poly.verticies, 3);
+
+        run(&mut td, &mut gui, undo());
+        assert_eq!(td.state.fill_poly.verticies.len(), 3);
+        run(&mut td, &mut gui, goto(0., 30.));
+
+        // starting a new fill keeps the old one, to go back to
+        run(&mut td, &mut gui, begin_fill());
+        assert_eq!(td.state.fill_poly.verticies.len(), 1);
+        run(&mut td, &mut gui, undo());
+        assert_eq!(td.state.fill_poly.verticies.len(), 4);
+    }
+
+    #[test]
+    fn longer_commands_take_longer() {
+        fn ticks(td: &mut TurtleData, gui: &mut Handler<(), NoScreen>, cmd: DrawRequest) -> usize {
+            td.queue.push_back(TurtleCommand {
+                cmd,
+                turtle: td.turtle_id,
+                thread: TurtleThread::default(),
+            });
+            let mut ticks = 0;
+            while !td.queue.is_empty() || td.current_command.is_some() {
+                td.time_passes(gui, 0.01);
+                ticks += 1;
+            }
+            ticks
+        }
+        let forward = |d| DrawRequest::TimedDraw(TimedDrawCmd::Motion(MotionCmd::Forward(d)));
+        let right = |a| DrawRequest::TimedDraw(TimedDrawCmd::Rotate(RotateCmd::Right(a)));
+
+        let (mut td, mut gui) = setup();
+        td.state.speed = 5.into();
+        let short = ticks(&mut td, &mut gui, forward(10.));
+        let long = ticks(&mut td, &mut gui, forward(500.));
+        assert!(long > 10 * short);
+        let small = ticks(&mut td, &mut gui, right(10.));
+        let large = ticks(&mut td, &mut gui, right(180.));
+        assert!(large > 5 * small);
+
+        // unless every command is given the same time
+        td.state.duration = Some(Duration::from_millis(200));
+        let short = ticks(&mut td, &mut gui, forward(10.));
+        let long = ticks(&mut td, &mut gui, forward(500.));
+        assert_eq!(short, long);
+    }
+}
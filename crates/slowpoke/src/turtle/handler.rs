@@ -1,10 +1,11 @@
-use std::{cell::RefCell, cmp::Ordering, collections::HashMap};
+use std::{cell::RefCell, collections::HashMap};
 
 use either::Either;
 
 use crate::{
     color_names::TurtleColor,
     gui::{ops::TurtleDraw, popup::PopupData, StampCount},
+    line_style::LineStyle,
     polygon::TurtleShape,
     ScreenPosition,
 };
@@ -19,59 +20,64 @@ pub struct IndividualTurtle<U> {
     pub turtle_shape: TurtleShape,
     pub hide_turtle: bool,
     pub(crate) cvt: ConversionInfo,
+    pub(crate) rewound: Option<usize>,
     pub ui: RefCell<U>,
 }
 
-#[derive(Debug, Default)]
-pub(crate) struct Progress {
-    pub cmd_index: usize, // index into turtle.cmds[]
-    pub fraction: f32,    // fractional part completed of the current command being drawn
-}
-
-impl Progress {
-    pub(crate) fn _set_progress(&mut self, cmd_index: usize, fraction: f32) {
-        self.cmd_index = cmd_index;
-        self.fraction = fraction;
-    }
-
-    pub(crate) fn _of(cmd_index: usize, fraction: f32) -> Self {
-        Self {
-            cmd_index,
-            fraction,
-        }
+impl<U> IndividualTurtle<U> {
+    /// The number of ops at the start of `ops` which only change after an
+    /// undo, a clear or a stamp being removed. The rest are redone each frame.
+    #[must_use]
+    pub fn finished_ops(&self) -> usize {
+        self.cvt.checkpoints.last().map_or(0, |c| c.ops_len)
     }
-}
 
-impl PartialEq for Progress {
-    fn eq(&self, other: &Self) -> bool {
-        self.cmd_index == other.cmd_index && self.fraction.eq(&other.fraction)
+    /// The index of the first finished op that has been redone since the last
+    /// call, if any
+    pub fn take_rewound(&mut self) -> Option<usize> {
+        self.rewound.take()
     }
-}
 
-impl PartialOrd for Progress {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match self.cmd_index.cmp(&other.cmd_index) {
-            Ordering::Less => Some(Ordering::Less),
-            Ordering::Equal => self.fraction.partial_cmp(&other.fraction),
-            Ordering::Greater => Some(Ordering::Greater),
+    // take back the last command; its ops are redone at the next conversion,
+    // even if another command takes its place first
+    pub(crate) fn pop(&mut self) -> Option<DrawCommand> {
+        let cmd = self.cmds.pop();
+        if cmd.is_some() {
+            self.cvt.set_trunc_pos(self.cmds.len());
+        }
+        if let Some(DrawCommand::Filled(index)) = &cmd {
+            self.cmds[*index] = DrawCommand::Filler;
+            self.cvt.set_trunc_pos(*index);
         }
+        cmd
     }
 }
 
-#[derive(Debug, Default)]
+// The state of the conversion from `cmds` to `ops` after some number of commands
+#[derive(Debug, Default, Clone)]
 pub(crate) struct ConversionInfo {
-    pub _last_progress: Progress,
     pub last_fill_point: Option<usize>,
-
-    trunc_pos: Option<usize>,
     pub polygon_start_point: Option<usize>,
 
     pub cur_path: Vec<(bool, crate::gui::ops::Point)>,
     pub pencolor: TurtleColor,
     pub penwidth: f32,
+    pub linestyle: LineStyle,
     pub fillcolor: TurtleColor,
     pub position: [f32; 2],
     pub angle: f32,
+
+    // the lowest index into `cmds` that changed since the last conversion
+    trunc_pos: Option<usize>,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+// Where to pick up converting from, without going back to the first command
+#[derive(Debug, Clone)]
+pub(crate) struct Checkpoint {
+    pub cmd_index: usize, // commands before this one are converted
+    pub ops_len: usize,   // the number of ops they turned into
+    pub cvt: ConversionInfo,
 }
 
 impl ConversionInfo {
@@ -92,9 +98,26 @@ impl ConversionInfo {
         }
     }
 
-    pub(crate) fn _get_trunc_pos(&mut self) -> Option<usize> {
+    pub(crate) fn get_trunc_pos(&mut self) -> Option<usize> {
         self.trunc_pos.take()
     }
+
+    // a copy of the state, without the checkpoints
+    pub(crate) fn snapshot(&self) -> Self {
+        Self {
+            last_fill_point: self.last_fill_point,
+            polygon_start_point: self.polygon_start_point,
+            cur_path: self.cur_path.clone(),
+            pencolor: self.pencolor,
+            penwidth: self.penwidth,
+            linestyle: self.linestyle.clone(),
+            fillcolor: self.fillcolor,
+            position: self.position,
+            angle: self.angle,
+            trunc_pos: None,
+            checkpoints: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -109,6 +132,17 @@ pub struct Handler<U, S: TurtleUI> {
 pub trait TurtleUI {
     fn generate_popup(&mut self, popupdata: &PopupData) -> PopupID;
     fn resize(&mut self, width: isize, height: isize);
+    // `thread` waits for a WindowResize event naming it, which UIs that can
+    // resize straight away may send once this returns
+    fn resize_request(
+        &mut self,
+        _turtle: TurtleID,
+        _thread: TurtleThread,
+        width: isize,
+        height: isize,
+    ) {
+        self.resize(width, height);
+    }
     fn set_bg_color(&mut self, bgcolor: TurtleColor);
 }
 
@@ -157,15 +191,11 @@ impl<T: Default, U: Default + TurtleUI> TurtleGui for Handler<T, U> {
         id
     }
 
-    fn shut_down(&mut self) {
-        std::process::exit(0);
-    }
-
     fn clear_turtle(&mut self, turtle: TurtleID) {
         let turtle = self.turtle.get_mut(&turtle).expect("missing turtle");
         turtle.cmds.clear();
         turtle.has_new_cmd = true;
-        turtle.cvt = ConversionInfo::new();
+        turtle.cvt.set_trunc_pos(0);
     }
 
     fn set_shape(&mut self, turtle: TurtleID, shape: TurtleShape) {
@@ -184,30 +214,37 @@ impl<T: Default, U: Default + TurtleUI> TurtleGui for Handler<T, U> {
         turtle.cmds.len() - 1
     }
 
-    fn clear_stamp(&mut self, turtle: TurtleID, stamp: usize) {
+    fn clear_stamp(&mut self, turtle: TurtleID, stamp: usize) -> bool {
         let turtle = self.turtle.get_mut(&turtle).expect("missing turtle");
-        assert!(matches!(
-            turtle.cmds[stamp],
-            DrawCommand::DrawPolyAt(_, _, _)
-        ));
+        if !matches!(turtle.cmds.get(stamp), Some(DrawCommand::DrawPolyAt(..))) {
+            return false;
+        }
         turtle.cmds[stamp] = DrawCommand::Filler;
+        turtle.cvt.set_trunc_pos(stamp);
         turtle.has_new_cmd = true;
+        true
     }
 
     fn clear_stamps(&mut self, turtle: TurtleID, count: StampCount) {
         let turtle = self.turtle.get_mut(&turtle).expect("missing turtle");
         let all = turtle.cmds.len();
         let (mut iter, mut count) = match count {
-            StampCount::Forward(count) => (Either::Right(turtle.cmds.iter_mut()), count),
-            StampCount::Reverse(count) => (Either::Left(turtle.cmds.iter_mut().rev()), count),
-            StampCount::All => (Either::Right(turtle.cmds.iter_mut()), all),
+            StampCount::Forward(count) => {
+                (Either::Right(turtle.cmds.iter_mut().enumerate()), count)
+            }
+            StampCount::Reverse(count) => (
+                Either::Left(turtle.cmds.iter_mut().enumerate().rev()),
+                count,
+            ),
+            StampCount::All => (Either::Right(turtle.cmds.iter_mut().enumerate()), all),
         };
 
         while count > 0 {
-            if let Some(cmd) = iter.next() {
+            if let Some((index, cmd)) = iter.next() {
                 if matches!(cmd, DrawCommand::DrawPolyAt(_, _, _)) {
                     count -= 1;
                     *cmd = DrawCommand::Filler;
+                    turtle.cvt.set_trunc_pos(index);
                 }
             } else {
                 break;
@@ -222,6 +259,11 @@ impl<T: Default, U: Default + TurtleUI> TurtleGui for Handler<T, U> {
         turtle.turtle_shape.name.clone()
     }
 
+    fn get_shape(&self, turtle: TurtleID) -> TurtleShape {
+        let turtle = self.turtle.get(&turtle).expect("missing turtle");
+        turtle.turtle_shape.clone()
+    }
+
     fn append_command(&mut self, turtle: TurtleID, cmd: DrawCommand) {
         let turtle = self.turtle.get_mut(&turtle).expect("missing turtle");
         turtle.cmds.push(cmd);
@@ -237,26 +279,31 @@ impl<T: Default, U: Default + TurtleUI> TurtleGui for Handler<T, U> {
         turtle.has_new_cmd = true;
         turtle.cmds[index] = cmd;
         turtle.cmds.push(DrawCommand::Filled(index));
+        turtle.cvt.set_trunc_pos(index);
     }
 
-    fn undo_count(&self, turtle: TurtleID) -> usize {
-        self.turtle.get(&turtle).expect("missing turtle").cmds.len()
+    fn save_drawing(&self, turtle: TurtleID) -> Vec<DrawCommand> {
+        self.turtle
+            .get(&turtle)
+            .expect("missing turtle")
+            .cmds
+            .clone()
     }
 
-    fn undo(&mut self, turtle: TurtleID) {
+    fn restore_drawing(&mut self, turtle: TurtleID, cmds: Vec<DrawCommand>) {
         let turtle = self.turtle.get_mut(&turtle).expect("missing turtle");
+        turtle.cmds = cmds;
         turtle.has_new_cmd = true;
+        turtle.cvt.set_trunc_pos(0);
     }
 
-    fn pop(&mut self, turtle: TurtleID) -> Option<DrawCommand> {
+    fn undo(&mut self, turtle: TurtleID) {
         let turtle = self.turtle.get_mut(&turtle).expect("missing turtle");
-        let cmd = turtle.cmds.pop();
-
-        if let Some(DrawCommand::Filled(index)) = &cmd {
-            turtle.cmds[*index] = DrawCommand::Filler;
-        }
+        turtle.has_new_cmd = true;
+    }
 
-        cmd
+    fn pop(&mut self, turtle: TurtleID) -> Option<DrawCommand> {
+        self.turtle.get_mut(&turtle).expect("missing turtle").pop()
     }
 
     fn numinput(&mut self, turtle: TurtleID, thread: TurtleThread, title: &str, prompt: &str) {
@@ -275,8 +322,8 @@ impl<T: Default, U: Default + TurtleUI> TurtleGui for Handler<T, U> {
         self.screen.set_bg_color(color);
     }
 
-    fn resize(&mut self, _turtle: TurtleID, _thread: TurtleThread, width: isize, height: isize) {
-        self.screen.resize(width, height);
+    fn resize(&mut self, turtle: TurtleID, thread: TurtleThread, width: isize, height: isize) {
+        self.screen.resize_request(turtle, thread, width, height);
     }
 
     fn set_visible(&mut self, turtle: TurtleID, visible: bool) {
@@ -1,20 +1,31 @@
 use std::{
     collections::HashMap,
-    sync::mpsc::{self, Receiver, Sender},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
 };
 
 use crate::{
+    collision::{self, TurtleGrid},
     color_names::TurtleColor,
     command::{Command, DataCmd, DrawRequest, InputCmd, InstantaneousDrawCmd, ScreenCmd},
     comms::{Request, Response},
+    coords::{TurtleMode, WorldCoords},
+    generate::TurtlePosition,
     gui::{events::TurtleEvent, Progression, StampCount, TurtleGui},
     polygon::{generate_default_shapes, ShapeComponent, TurtleShape},
+    record::{Recorder, Recording},
     ScreenPosition, Shape, Turtle, TurtleShapeName,
 };
 
-use super::{types::TurtleThread, TurtleCommand, TurtleData, TurtleFlags, TurtleID, TurtleTimer};
-
-use crate::generate::TurtlePosition;
+use super::{
+    types::{TimerID, TurtleThread},
+    Disconnected, TurtleCommand, TurtleData, TurtleFlags, TurtleID, TurtleTimer, EXIT_PANIC,
+};
 
 #[derive(Debug)]
 pub struct TurtleTask {
@@ -23,9 +34,22 @@ pub struct TurtleTask {
     turtle_list: Vec<TurtleData>,
     shapes: HashMap<String, TurtleShape>,
     winsize: [isize; 2],
+    world: WorldCoords,
+    mode: TurtleMode,
     exit_on_click: bool,
+    next_timer: TimerID,
+    now: Instant,                           // the clock reading at the latest tick
+    turtles_at: TurtleGrid,                 // for finding the turtles near a point
+    closing: Vec<(TurtleID, TurtleThread)>, // running `onclose` handlers
+    close_requested: bool,
+    shutting_down: bool,
+    exit_status: Arc<AtomicU8>,
+    main_finished: Option<Receiver<()>>, // disconnects when the main turtle's thread ends
+    recorder: Option<Recorder>,
+    replay: Option<Recording>, // play this back instead of listening to the turtles
 }
 
+// evaluates to the thread id of the newly spawned turtle
 macro_rules! spawn {
     ($task:expr, $td:expr, $idx:expr, $func:expr, $($args:tt)*) => {
         {
@@ -38,10 +62,14 @@ macro_rules! spawn {
             let _ = std::thread::spawn(move || {
                 $func(&mut _new_turtle, $($args)*);
             });
+
+            _thread
         }
     };
 }
 
+// Frontends should close their window and return from
+// `TurtleUserInterface::start` when they see `ShutDown`
 #[derive(PartialEq)]
 pub enum EventResult {
     Continue,
@@ -57,24 +85,45 @@ impl TurtleTask {
             receive_command,
             turtle_list: vec![TurtleData::new()],
             shapes: generate_default_shapes(),
-            winsize: [0, 0],
+            #[allow(clippy::cast_possible_truncation)]
+            winsize: [flags.size[0] as isize, flags.size[1] as isize],
+            world: WorldCoords::default(),
+            mode: TurtleMode::default(),
             exit_on_click: false,
+            next_timer: TimerID::default(),
+            now: Instant::now(),
+            turtles_at: TurtleGrid::default(),
+            closing: Vec::new(),
+            close_requested: false,
+            shutting_down: false,
+            exit_status: flags.exit_status.clone(),
+            main_finished: None,
+            recorder: flags.recorder.take(),
+            replay: flags.replay.take(),
         }
     }
 
     pub fn progress(&self, tid: TurtleID) -> (f32, Progression) {
         (
-            self.turtle_list[tid].state.percent,
+            self.turtle_list[tid].state.fraction(),
             self.turtle_list[tid].state.progression,
         )
     }
 
     pub fn popup_result(&mut self, turtle: TurtleID, thread: TurtleThread, response: Response) {
-        let _ = self.turtle_list[turtle].responder[&thread].send(response);
+        self.respond(turtle, thread, response);
     }
 
     pub fn popup_cancelled(&mut self, turtle: TurtleID, thread: TurtleThread) {
-        let _ = self.turtle_list[turtle].responder[&thread].send(Response::Cancel);
+        self.respond(turtle, thread, Response::Cancel);
+    }
+
+    // answer a request for data, keeping a note of the answer in the recording
+    fn respond(&mut self, turtle: TurtleID, thread: TurtleThread, response: Response) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.result(turtle, thread, &response);
+        }
+        let _ = self.turtle_list[turtle].responder[&thread].send(response);
     }
 
     pub fn handle_event(
@@ -83,65 +132,86 @@ impl TurtleTask {
         thread: Option<TurtleThread>,
         event: &TurtleEvent,
     ) -> EventResult {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.event(event);
+        }
+
         match event {
             TurtleEvent::WindowResize(width, height) => {
                 self.winsize = [*width, *height];
-                if turtle.is_none() {
-                    assert!(thread.is_none());
-                } else {
-                    let turtle = turtle.expect("missing turtle from window resize");
+                if let Some(turtle) = turtle {
                     let thread = thread.expect("missing thread from window resize");
                     let _ = self.turtle_list[turtle].responder[&thread].send(Response::Done);
+                } else {
+                    assert!(thread.is_none());
                 }
             }
             TurtleEvent::KeyPress(ch) => {
                 for (idx, turtle) in self.turtle_list.iter_mut().enumerate() {
-                    if let Some(func) = turtle.event.onkeypress.get(ch).copied() {
-                        if !turtle.pending_key_event() {
-                            let ch = *ch;
-                            spawn!(self, turtle, idx, func, ch);
-                        }
+                    if let Some(func) = turtle.event.onkeypress.get(ch).copied()
+                        && !turtle.pending_key_event()
+                    {
+                        let ch = *ch;
+                        spawn!(self, turtle, idx, func, ch);
                     }
                 }
             }
             TurtleEvent::KeyRelease(ch) => {
                 for (idx, turtle) in self.turtle_list.iter_mut().enumerate() {
-                    if let Some(func) = turtle.event.onkeyrelease.get(ch).copied() {
-                        if !turtle.pending_key_event() {
-                            let ch = *ch;
-                            spawn!(self, turtle, idx, func, ch);
-                        }
+                    if let Some(func) = turtle.event.onkeyrelease.get(ch).copied()
+                        && !turtle.pending_key_event()
+                    {
+                        let ch = *ch;
+                        spawn!(self, turtle, idx, func, ch);
                     }
                 }
             }
             TurtleEvent::MousePress(x, y) => {
                 if self.exit_on_click {
+                    self.shutting_down = true;
                     return EventResult::ShutDown;
                 }
+                let (x, y) = self.mouse_to_world(*x, *y);
                 for (idx, turtle) in self.turtle_list.iter_mut().enumerate() {
                     if let Some(func) = turtle.event.onmousepress {
-                        let (x, y) = (*x, *y);
                         spawn!(self, turtle, idx, func, x, y);
                     }
                 }
             }
             TurtleEvent::MouseRelease(x, y) => {
+                let (x, y) = self.mouse_to_world(*x, *y);
                 for (idx, turtle) in self.turtle_list.iter_mut().enumerate() {
                     if let Some(func) = turtle.event.onmouserelease {
-                        let (x, y) = (*x, *y);
                         spawn!(self, turtle, idx, func, x, y);
                     }
                 }
             }
             TurtleEvent::MousePosition(_, _) => unreachable!(),
             TurtleEvent::MouseDrag(x, y) => {
+                let (x, y) = self.mouse_to_world(*x, *y);
                 for (idx, turtle) in self.turtle_list.iter_mut().enumerate() {
                     if let Some(func) = turtle.event.onmousedrag {
-                        let (x, y) = (*x, *y);
                         spawn!(self, turtle, idx, func, x, y);
                     }
                 }
             }
+            TurtleEvent::WindowClose => {
+                // a second request means the user doesn't want to wait
+                if !self.close_requested {
+                    self.close_requested = true;
+                    for (idx, turtle) in self.turtle_list.iter_mut().enumerate() {
+                        if let Some(func) = turtle.event.onclose {
+                            let thread = spawn!(self, turtle, idx, func,);
+                            self.closing.push((idx.into(), thread));
+                        }
+                    }
+                    if !self.closing.is_empty() {
+                        return EventResult::Continue;
+                    }
+                }
+                self.shutting_down = true;
+                return EventResult::ShutDown;
+            }
             TurtleEvent::_Timer => todo!(),
             TurtleEvent::Unhandled => {}
         }
@@ -149,35 +219,161 @@ impl TurtleTask {
         EventResult::Continue
     }
 
+    // mouse events arrive in pixels, centered on the window with y pointing up
+    fn mouse_to_world(&self, x: f32, y: f32) -> (f32, f32) {
+        let pos = self.world.to_world(ScreenPosition::new(x, -y));
+        (pos.x, pos.y)
+    }
+
     pub fn run_turtle<F: FnOnce(&mut Turtle) + Send + 'static>(&mut self, func: F) {
         let turtle = TurtleID::new(0);
         let thread = TurtleThread::new(0);
         let issue_command = self.issue_command.as_ref().unwrap().clone();
         let mut primary = self.turtle_list[turtle].spawn(thread, issue_command);
-        self.turtle_list[turtle].join_handle = Some(std::thread::spawn(move || func(&mut primary)));
+        let (finished, main_finished) = mpsc::channel();
+        self.main_finished = Some(main_finished);
+        self.turtle_list[turtle].join_handle = Some(std::thread::spawn(move || {
+            // dropped however the thread ends, even by unwinding
+            let _finished: Sender<()> = finished;
+            func(&mut primary);
+        }));
     }
 
-    pub fn tick<G: TurtleGui>(&mut self, gui: &mut G) {
-        while let Ok(req) = self.receive_command.as_ref().unwrap().try_recv() {
-            self.handle_command(req, gui);
+    // every turtle stays at the same world coordinates, so it may move on the
+    // screen
+    fn set_world<G: TurtleGui>(&mut self, world: WorldCoords, gui: &mut G) {
+        self.world = world;
+        for td in &mut self.turtle_list {
+            td.state.drawn.invalidate();
+            for cmd in td.state.turtle.set_world(self.world) {
+                gui.append_command(td.turtle_id, cmd);
+            }
+            td.state.turtle.set_coords(self.world, self.mode);
+            self.turtles_at
+                .place(td.turtle_id.value(), td.state.turtle.pos());
+        }
+    }
+
+    pub fn tick<G: TurtleGui>(&mut self, gui: &mut G) -> EventResult {
+        self.tick_at(gui, Instant::now())
+    }
+
+    // Timers and animation go by the clock reading `now`, however often the
+    // frontend calls us. A long gap, e.g. while the window was being dragged,
+    // counts as `MAX_STEP`, so the turtles don't jump.
+    fn tick_at<G: TurtleGui>(&mut self, gui: &mut G, now: Instant) -> EventResult {
+        const MAX_STEP: f32 = 0.1;
+
+        let delta_t = now
+            .saturating_duration_since(self.now)
+            .as_secs_f32()
+            .min(MAX_STEP);
+        self.now = now;
+
+        // the world fills the window, whatever size it's been made
+        if let Some(world) = self.world.resized(self.winsize) {
+            self.set_world(world, gui);
+        }
+
+        if let Some(replay) = &mut self.replay {
+            // the only things the turtles can say during a replay are goodbyes
+            // from the handles the replayed commands gave out, which nobody uses
+            while self.receive_command.as_ref().unwrap().try_recv().is_ok() {}
+            for req in replay.due(now) {
+                self.replay_command(req, gui);
+            }
+        } else {
+            while let Ok(req) = self.receive_command.as_ref().unwrap().try_recv() {
+                self.handle_command(req, gui);
+            }
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.flush();
+        }
+
+        if self.close_requested {
+            let turtle_list = &self.turtle_list;
+            self.closing
+                .retain(|(turtle, thread)| turtle_list[*turtle].responder.contains_key(thread));
+            if self.closing.is_empty() {
+                self.shutting_down = true;
+            }
+        }
+
+        if self.shutting_down {
+            return EventResult::ShutDown;
         }
 
         for turtle in &mut self.turtle_list {
-            let timer = match &turtle.event.ontimer {
-                Some(timer) if timer.prev.elapsed() > timer.time => {
-                    Some((timer.prev.elapsed(), timer.func))
-                }
-                _ => None,
-            };
+            let due: Vec<TimerID> = turtle
+                .event
+                .timers
+                .iter()
+                .filter(|(_, timer)| timer.is_due(now))
+                .map(|(id, _)| *id)
+                .collect();
 
-            if let Some((duration, func)) = timer {
-                spawn!(self, turtle, turtle.turtle_id, func, duration);
-                turtle.event.ontimer = None;
+            for id in due {
+                let timer = turtle.event.timers.get_mut(&id).expect("missing timer");
+                let duration = timer.fire(now);
+                let (func, repeat) = (timer.func, timer.repeat);
+                let thread = spawn!(self, turtle, turtle.turtle_id, func, duration);
+                if repeat {
+                    turtle
+                        .event
+                        .timers
+                        .get_mut(&id)
+                        .expect("missing timer")
+                        .running = Some(thread);
+                } else {
+                    turtle.event.timers.remove(&id);
+                }
             }
 
-            turtle.time_passes(gui, 0.01); // TODO: use actual time delta
-            gui.convert(turtle.state.percent, &turtle.turtle_id);
+            turtle.time_passes(gui, delta_t);
+            gui.convert(turtle.state.fraction(), &turtle.turtle_id);
+            self.turtles_at
+                .place(turtle.turtle_id.value(), turtle.state.turtle.pos());
+        }
+
+        EventResult::Continue
+    }
+
+    // a new turtle with the same position, heading, pen, speed and appearance
+    fn clone_turtle<G: TurtleGui>(&mut self, original: TurtleID, gui: &mut G) -> Turtle {
+        let new_turtle = self.hatch_turtle(gui);
+        let turtle = new_turtle.turtle;
+
+        let state = self.turtle_list[original].state.turtle.clone();
+        let speed = self.turtle_list[original].state.speed;
+        for cmd in state.get_state() {
+            gui.append_command(turtle, cmd);
+        }
+        self.turtle_list[turtle].state.turtle = state;
+        self.turtle_list[turtle].state.speed = speed;
+        self.place_turtle(turtle);
+
+        gui.set_shape(turtle, gui.get_shape(original));
+        gui.set_visible(turtle, gui.is_visible(original));
+
+        new_turtle
+    }
+
+    // send the turtle home, in the screen's current coordinate system and mode
+    fn reset_turtle<G: TurtleGui>(&mut self, turtle: TurtleID, gui: &mut G) {
+        self.turtle_list[turtle].state.drawn.invalidate();
+        let state = &mut self.turtle_list[turtle].state.turtle;
+        state.set_coords(self.world, self.mode);
+        gui.clear_turtle(turtle);
+        for cmd in state.reset() {
+            gui.append_command(turtle, cmd);
         }
+        self.place_turtle(turtle);
+    }
+
+    fn place_turtle(&mut self, turtle: TurtleID) {
+        let pos = self.turtle_list[turtle].state.turtle.pos();
+        self.turtles_at.place(turtle.value(), pos);
     }
 
     pub(crate) fn hatch_turtle<G: TurtleGui>(&mut self, gui: &mut G) -> Turtle {
@@ -189,6 +385,7 @@ impl TurtleTask {
         td.responder.insert(thread, finished);
         td.turtle_id = turtle;
         self.turtle_list.push(td);
+        self.reset_turtle(turtle, gui);
 
         Turtle::init(
             self.issue_command.as_ref().unwrap().clone(),
@@ -212,7 +409,20 @@ impl TurtleTask {
             .clone();
         match cmd {
             ScreenCmd::Bye => {
-                gui.shut_down();
+                // Note: no response here either; the calling thread is woken
+                // up when the user interface goes away
+                self.shutting_down = true;
+            }
+            ScreenCmd::Mode(mode) => {
+                // as in python, changing the mode resets every turtle
+                self.mode = mode;
+                if mode != TurtleMode::World {
+                    self.world = WorldCoords::default();
+                }
+                for idx in 0..self.turtle_list.len() {
+                    self.reset_turtle(idx.into(), gui);
+                }
+                let _ = resp.send(Response::Done);
             }
             ScreenCmd::ExitOnClick => {
                 // Note: this does not send back a response as it is meant to just
@@ -223,6 +433,20 @@ impl TurtleTask {
                 gui.set_title(s);
                 let _ = resp.send(Response::Done);
             }
+            ScreenCmd::SetWorldCoordinates(llx, lly, urx, ury) => {
+                match WorldCoords::new(self.winsize, llx, lly, urx, ury) {
+                    Ok(world) => {
+                        self.mode = TurtleMode::World;
+                        self.set_world(world, gui);
+                        // not `Done`, so the caller can tell it from the
+                        // answers to earlier commands
+                        let _ = resp.send(Response::Mode(self.mode));
+                    }
+                    Err(e) => {
+                        let _ = resp.send(Response::Error(e));
+                    }
+                }
+            }
             ScreenCmd::BgPic(_picdata) => todo!(),
             ScreenCmd::RegisterShape(name, shape) => {
                 match shape {
@@ -251,6 +475,18 @@ impl TurtleTask {
                 self.turtle_list[turtle].state.speed = s;
                 let _ = resp.send(Response::Done);
             }
+            ScreenCmd::Duration(d) => {
+                self.turtle_list[turtle].state.duration = d;
+                let _ = resp.send(Response::Done);
+            }
+            ScreenCmd::Easing(e) => {
+                self.turtle_list[turtle].state.easing = e;
+                let _ = resp.send(Response::Done);
+            }
+            ScreenCmd::UndoBuffer(size) => {
+                self.turtle_list[turtle].state.set_undo_size(size);
+                let _ = resp.send(Response::Done);
+            }
             ScreenCmd::Background(TurtleColor::CurrentColor) => {
                 let _ = resp.send(Response::Done);
             }
@@ -262,12 +498,17 @@ impl TurtleTask {
                 gui.bgcolor("white".into());
                 gui.clearscreen();
                 self.turtle_list.truncate(1);
+                self.turtles_at.truncate(1);
                 self.turtle_list[0].reset();
+                self.reset_turtle(TurtleID::new(0), gui);
                 let _ = resp.send(Response::Done);
             }
             ScreenCmd::ClearStamp(id) => {
-                gui.clear_stamp(turtle, id);
-                let _ = resp.send(Response::Done);
+                let _ = resp.send(if gui.clear_stamp(turtle, id) {
+                    Response::Done
+                } else {
+                    Response::Error(format!("there's no stamp {id}"))
+                });
             }
             ScreenCmd::ClearStamps(count) => {
                 #[allow(clippy::comparison_chain)]
@@ -290,31 +531,57 @@ impl TurtleTask {
             .unwrap()
             .clone();
         match cmd {
-            InputCmd::Timer(f, d) => {
-                let _ = self.turtle_list[turtle]
+            InputCmd::Timer(f, d, repeat) => {
+                let id = self.next_timer.get();
+                self.turtle_list[turtle]
                     .event
-                    .ontimer
-                    .insert(TurtleTimer::new(f, d));
+                    .timers
+                    .insert(id, TurtleTimer::new(f, d, repeat, self.now));
+                let _ = resp.send(Response::TimerID(id));
+            }
+            InputCmd::CancelTimer(id) => {
+                // timer ids are unique across all turtles, so any turtle can cancel any timer
+                for td in &mut self.turtle_list {
+                    td.event.timers.remove(&id);
+                }
                 let _ = resp.send(Response::Done);
             }
             InputCmd::KeyRelease(f, k) => {
-                self.turtle_list[turtle].event.onkeyrelease.insert(k, f);
+                let handlers = &mut self.turtle_list[turtle].event.onkeyrelease;
+                if let Some(f) = f {
+                    handlers.insert(k, f);
+                } else {
+                    handlers.remove(&k);
+                }
                 let _ = resp.send(Response::Done);
             }
             InputCmd::KeyPress(f, k) => {
-                self.turtle_list[turtle].event.onkeypress.insert(k, f);
+                let handlers = &mut self.turtle_list[turtle].event.onkeypress;
+                if let Some(f) = f {
+                    handlers.insert(k, f);
+                } else {
+                    handlers.remove(&k);
+                }
                 let _ = resp.send(Response::Done);
             }
             InputCmd::MouseDrag(f) => {
-                self.turtle_list[turtle].event.onmousedrag = Some(f);
+                self.turtle_list[turtle].event.onmousedrag = f;
                 let _ = resp.send(Response::Done);
             }
             InputCmd::MousePress(f) => {
-                self.turtle_list[turtle].event.onmousepress = Some(f);
+                self.turtle_list[turtle].event.onmousepress = f;
                 let _ = resp.send(Response::Done);
             }
             InputCmd::MouseRelease(f) => {
-                self.turtle_list[turtle].event.onmouserelease = Some(f);
+                self.turtle_list[turtle].event.onmouserelease = f;
+                let _ = resp.send(Response::Done);
+            }
+            InputCmd::Close(f) => {
+                self.turtle_list[turtle].event.onclose = f;
+                let _ = resp.send(Response::Done);
+            }
+            InputCmd::Listen(listen) => {
+                self.turtle_list[turtle].event.ignore_keys = !listen;
                 let _ = resp.send(Response::Done);
             }
         }
@@ -327,13 +594,7 @@ impl TurtleTask {
         thread: TurtleThread,
         gui: &mut G,
     ) {
-        let resp = self.turtle_list[turtle]
-            .responder
-            .get(&thread)
-            .unwrap()
-            .clone();
-
-        let _ = match cmd {
+        let response = match cmd {
             DataCmd::GetTurtles => {
                 let mut turtles = Vec::new();
                 for turtle in &mut self.turtle_list {
@@ -341,71 +602,112 @@ impl TurtleTask {
                     let thing = turtle.spawn(thread, self.issue_command.as_ref().unwrap().clone());
                     turtles.push(thing);
                 }
-                resp.send(Response::Turtles(turtles))
-            }
-            DataCmd::GetShapes => {
-                resp.send(Response::ShapeList(self.shapes.keys().cloned().collect()))
-            }
-            DataCmd::GetFillingState => resp.send(Response::IsFilling(
-                self.turtle_list[turtle].state.insert_fill.is_some(),
-            )),
-            DataCmd::GetPenState => resp.send(Response::IsPenDown(
-                self.turtle_list[turtle].state.turtle.get_pen_state(),
-            )),
-            DataCmd::GetScreenSize => resp.send(Response::ScreenSize(self.winsize)),
-            DataCmd::Visibility => resp.send(Response::Visibility(gui.is_visible(turtle))),
-            DataCmd::GetPoly => resp.send(Response::Polygon(
-                self.turtle_list[turtle].state.shape_poly.verticies.clone(),
-            )),
+                Response::Turtles(turtles)
+            }
+            DataCmd::GetShapes => Response::ShapeList(self.shapes.keys().cloned().collect()),
+            DataCmd::GetFillingState => {
+                Response::IsFilling(self.turtle_list[turtle].state.insert_fill.is_some())
+            }
+            DataCmd::GetPenState => {
+                Response::IsPenDown(self.turtle_list[turtle].state.turtle.get_pen_state())
+            }
+            DataCmd::GetScreenSize => Response::ScreenSize(self.winsize),
+            DataCmd::Visibility => Response::Visibility(gui.is_visible(turtle)),
+            DataCmd::GetPoly => {
+                Response::Polygon(self.turtle_list[turtle].state.shape_poly.verticies.clone())
+            }
             DataCmd::TurtleShape(shape) => {
-                if let TurtleShapeName::Shape(name) = shape {
-                    if let Some(shape) = self.shapes.get(name) {
-                        gui.set_shape(turtle, shape.clone());
-                    }
+                if let TurtleShapeName::Shape(name) = shape
+                    && let Some(shape) = self.shapes.get(name)
+                {
+                    gui.set_shape(turtle, shape.clone());
                 }
-                resp.send(Response::Name(gui.get_turtle_shape_name(turtle)))
+                Response::Name(gui.get_turtle_shape_name(turtle))
+            }
+            DataCmd::UndoBufferEntries => {
+                Response::Count(self.turtle_list[turtle].state.undo_buffer.len())
             }
-            DataCmd::UndoBufferEntries => resp.send(Response::Count(gui.undo_count(turtle))),
             DataCmd::Towards(xpos, ypos) => {
-                let curpos: ScreenPosition<f32> = self.turtle_list[turtle].state.turtle.pos();
-                let x = xpos - curpos.x;
-                let y = ypos + curpos.y;
-
-                let heading = self.turtle_list[turtle]
-                    .state
-                    .turtle
-                    .radians_to_turtle(y.atan2(x));
+                let state = &self.turtle_list[turtle].state.turtle;
+                let heading = state.degrees_to_turtle(state.towards(*xpos, *ypos));
+                Response::Heading(heading)
+            }
+            DataCmd::Overlaps(other) if other.value() >= self.turtle_list.len() => {
+                Response::Error(format!("there's no turtle {}", other.value()))
+            }
+            DataCmd::Overlaps(other) => {
+                let outline = |tid: TurtleID| {
+                    let state = &self.turtle_list[tid].state.turtle;
+                    let pos: ScreenPosition<f32> = state.pos();
+                    collision::shape_outline(&gui.get_shape(tid), pos, state.screen_angle())
+                };
+                Response::Touching(collision::outlines_overlap(
+                    &outline(turtle),
+                    &outline(*other),
+                ))
+            }
+            DataCmd::TurtlesNear(x, y, radius) => {
+                // only the turtles in the cells around the circle are looked at
+                let corners = [
+                    self.world.to_screen(x - radius, y - radius),
+                    self.world.to_screen(x + radius, y + radius),
+                ];
+                let mut near: Vec<(f32, usize)> = self
+                    .turtles_at
+                    .within(corners[0], corners[1])
+                    .into_iter()
+                    .filter_map(|index| {
+                        let pos = self.turtle_list[index].state.turtle.world_pos();
+                        let distance = (pos.x - x).hypot(pos.y - y);
+                        (distance <= *radius).then_some((distance, index))
+                    })
+                    .collect();
+                near.sort_by(|a, b| a.0.total_cmp(&b.0));
 
-                resp.send(Response::Heading(heading))
+                let issue_command = self.issue_command.as_ref().unwrap();
+                let turtles = near
+                    .into_iter()
+                    .map(|(_, index)| {
+                        let td = &mut self.turtle_list[index];
+                        let thread = td.next_thread.get();
+                        td.spawn(thread, issue_command.clone())
+                    })
+                    .collect();
+                Response::Turtles(turtles)
+            }
+            DataCmd::CrossesDrawing => {
+                let drawn = &mut self.turtle_list[turtle].state.drawn;
+                if !drawn.is_built() {
+                    drawn.rebuild(&gui.save_drawing(turtle));
+                }
+                Response::Touching(drawn.last_move_crosses())
+            }
+            DataCmd::Position => {
+                Response::Position(self.turtle_list[turtle].state.turtle.world_pos())
             }
-            DataCmd::Position => resp.send(Response::Position(
-                self.turtle_list[turtle].state.turtle.pos(),
-            )),
             DataCmd::Heading => {
-                let angle = self.turtle_list[turtle].state.turtle.angle();
-                let angle = self.turtle_list[turtle]
-                    .state
-                    .turtle
-                    .degrees_to_turtle(angle);
-                resp.send(Response::Heading(angle))
+                let state = &self.turtle_list[turtle].state.turtle;
+                Response::Heading(state.degrees_to_turtle(state.heading()))
             }
+            DataCmd::GetMode => Response::Mode(self.mode),
             DataCmd::Stamp => {
                 self.turtle_list[turtle].queue.push_back(TurtleCommand {
                     cmd: DrawRequest::InstantaneousDraw(InstantaneousDrawCmd::Stamp),
                     turtle,
                     thread,
                 });
-                Ok(())
+                return;
             }
             DataCmd::NumInput(title, prompt) => {
                 gui.numinput(turtle, thread, title, prompt);
-                Ok(())
+                return;
             }
             DataCmd::TextInput(title, prompt) => {
                 gui.textinput(turtle, thread, title, prompt);
-                Ok(())
+                return;
             }
         };
+        self.respond(turtle, thread, response);
     }
 
     fn draw_cmd(&mut self, turtle: TurtleID, cmd: DrawRequest, thread: TurtleThread) {
@@ -424,18 +726,33 @@ impl TurtleTask {
     }
 
     fn handle_command<G: TurtleGui>(&mut self, req: Request, gui: &mut G) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.request(&req);
+        }
+
         let turtle = req.turtle;
         let thread = req.thread;
 
+        // clearscreen() removed this turtle, and with it the channel that
+        // answered it, so its thread has already been told
+        if turtle.value() >= self.turtle_list.len() {
+            return;
+        }
+
         match req.cmd {
             Command::ShutDown => {
-                if self.turtle_list[turtle].responder.len() == 1 {
-                    if let Some(handle) = self.turtle_list[turtle].join_handle.take() {
-                        let _ = handle.join();
-                    }
+                if self.turtle_list[turtle].responder.len() == 1
+                    && let Some(handle) = self.turtle_list[turtle].join_handle.take()
+                {
+                    self.finished(handle);
                 }
                 let tid = self.turtle_list[turtle].responder.remove(&thread);
                 self.turtle_list[turtle].event.pending_keys = false;
+                for timer in self.turtle_list[turtle].event.timers.values_mut() {
+                    if timer.running == Some(thread) {
+                        timer.running = None;
+                    }
+                }
                 assert!(tid.is_some());
             }
             Command::Screen(cmd) => self.screen_cmd(turtle, cmd, thread, gui),
@@ -444,9 +761,380 @@ impl TurtleTask {
             Command::Data(cmd) => self.data_cmd(turtle, &cmd, thread, gui),
             Command::Hatch => {
                 let new_turtle = self.hatch_turtle(gui);
-                let resp = &self.turtle_list[turtle].responder[&thread];
-                let _ = resp.send(Response::Turtle(new_turtle));
+                self.respond(turtle, thread, Response::Turtle(new_turtle));
+            }
+            Command::CloneTurtle => {
+                let new_turtle = self.clone_turtle(turtle, gui);
+                self.respond(turtle, thread, Response::Turtle(new_turtle));
             }
         }
     }
+
+    // Nobody is waiting for the answers to recorded commands, so they go to
+    // threads which don't exist. Commands which need a handler or a person
+    // to answer them are left out; their effects were recorded separately.
+    fn replay_command<G: TurtleGui>(&mut self, req: Request, gui: &mut G) {
+        if matches!(
+            req.cmd,
+            Command::Input(_) | Command::Data(DataCmd::NumInput(..) | DataCmd::TextInput(..))
+        ) {
+            return;
+        }
+        self.turtle_list[req.turtle]
+            .responder
+            .entry(req.thread)
+            .or_insert_with(|| mpsc::channel().0);
+        self.handle_command(req, gui);
+    }
+
+    // Record a failure exit status if the main turtle's thread panicked
+    fn finished(&self, handle: JoinHandle<()>) {
+        if let Err(payload) = handle.join()
+            && !Disconnected::is_disconnect(payload.as_ref())
+        {
+            self.exit_status.store(EXIT_PANIC, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Drop for TurtleTask {
+    fn drop(&mut self) {
+        const GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+        // Dropping the response channels wakes up any turtle thread that's
+        // waiting on us, and it unwinds the next time it uses its turtle
+        let handle = self
+            .turtle_list
+            .iter_mut()
+            .find_map(|td| td.join_handle.take());
+        self.turtle_list.clear();
+        self.receive_command = None;
+
+        // Give the main turtle a chance to clean up, but don't hang forever
+        // on a thread that never talks to its turtle again
+        if let (Some(handle), Some(main_finished)) = (handle, &self.main_finished)
+            && main_finished.recv_timeout(GRACE_PERIOD) != Err(RecvTimeoutError::Timeout)
+        {
+            self.finished(handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        process::ExitCode,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            mpsc, Arc, Mutex,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::{TurtleTask, EXIT_PANIC};
+    use crate::{
+        headless::{self, NoScreen, Slowpoke},
+        speed::SPEED_UNIT,
+        Handler, Turtle, TurtleEvent, TurtleFlags, TurtleMode, TurtleThread,
+    };
+
+    // A window whose clock only moves when the test says so
+    struct Clock {
+        task: TurtleTask,
+        gui: Handler<(), NoScreen>,
+        now: Instant,
+    }
+
+    impl Clock {
+        fn start<F: FnOnce(&mut Turtle) + Send + 'static>(func: F) -> Self {
+            let (issue_command, receive_command) = mpsc::channel();
+            let mut flags = TurtleFlags {
+                start_func: None,
+                issue_command: Some(issue_command),
+                receive_command: Some(receive_command),
+                title: String::new(),
+                size: [800., 600.],
+                exit_status: Arc::default(),
+                recorder: None,
+                replay: None,
+            };
+            let mut task = TurtleTask::new(&mut flags);
+            task.run_turtle(func);
+            Self {
+                now: task.now,
+                task,
+                gui: headless::handler(),
+            }
+        }
+
+        fn advance(&mut self, millis: u64) {
+            self.now += Duration::from_millis(millis);
+            self.task.tick_at(&mut self.gui, self.now);
+        }
+
+        // answer the turtles, without moving the clock, until `done`
+        fn settle(&mut self, mut done: impl FnMut(&TurtleTask) -> bool) {
+            while !done(&self.task) {
+                self.task.tick_at(&mut self.gui, self.now);
+                thread::yield_now();
+            }
+        }
+
+        // wait for every timer callback to return
+        fn idle(&mut self) {
+            let main = TurtleThread::new(0);
+            self.settle(|task| task.turtle_list[0].responder.keys().all(|t| *t == main));
+        }
+
+        // an event from the user, answered once every handler has returned
+        fn event(&mut self, event: &TurtleEvent) {
+            self.task.handle_event(None, None, event);
+            self.idle();
+        }
+
+        fn timers(&self) -> usize {
+            self.task.turtle_list[0].event.timers.len()
+        }
+    }
+
+    static FIRED: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+    fn first(_: &mut Turtle, _: Duration) {
+        FIRED.lock().unwrap().push("first");
+    }
+
+    fn second(_: &mut Turtle, _: Duration) {
+        FIRED.lock().unwrap().push("second");
+    }
+
+    fn third(_: &mut Turtle, _: Duration) {
+        FIRED.lock().unwrap().push("third");
+    }
+
+    #[test]
+    fn timers_fire_once_each_in_turn() {
+        let (send, receive) = mpsc::channel();
+        let mut clock = Clock::start(move |turtle| {
+            let _ = send.send([
+                turtle.ontimer(first, 50),
+                turtle.ontimer(third, 150),
+                turtle.ontimer(second, 100),
+            ]);
+        });
+        // the main turtle is done once its thread is gone
+        clock.settle(|task| task.turtle_list[0].responder.is_empty());
+        let [a, b, c] = receive.recv().expect("no timers were set");
+        assert!(a != b && b != c && a != c);
+
+        clock.advance(49);
+        assert_eq!(clock.timers(), 3);
+        for left in (0..3).rev() {
+            clock.advance(if left == 2 { 1 } else { 50 });
+            assert_eq!(clock.timers(), left);
+            clock.idle();
+        }
+        clock.advance(1000);
+        clock.idle();
+        assert_eq!(*FIRED.lock().unwrap(), ["first", "second", "third"]);
+    }
+
+    static TICKS: AtomicUsize = AtomicUsize::new(0);
+    static NEVER: AtomicUsize = AtomicUsize::new(0);
+
+    fn tick(_: &mut Turtle, _: Duration) {
+        TICKS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn never(_: &mut Turtle, _: Duration) {
+        NEVER.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn repeating_timers_stop_when_cancelled() {
+        let (go, wait) = mpsc::channel();
+        let (ready, set) = mpsc::channel();
+        let mut clock = Clock::start(move |turtle| {
+            let repeating = turtle.ontimer_repeat(tick, 20);
+            let once = turtle.ontimer(never, 100);
+            turtle.cancel_timer(once);
+            let _ = ready.send(());
+            let _ = wait.recv();
+            turtle.cancel_timer(repeating);
+        });
+        clock.settle(|_| set.try_recv().is_ok());
+        assert_eq!(clock.timers(), 1);
+
+        for _ in 0..5 {
+            clock.advance(20);
+            clock.idle();
+        }
+        assert_eq!(TICKS.load(Ordering::SeqCst), 5);
+
+        let _ = go.send(());
+        clock.settle(|task| task.turtle_list[0].event.timers.is_empty());
+        for _ in 0..5 {
+            clock.advance(20);
+            clock.idle();
+        }
+        assert_eq!(TICKS.load(Ordering::SeqCst), 5);
+        assert_eq!(NEVER.load(Ordering::SeqCst), 0);
+    }
+
+    static KEYS: AtomicUsize = AtomicUsize::new(0);
+    static CLICKS: AtomicUsize = AtomicUsize::new(0);
+
+    fn key(_: &mut Turtle, _: char) {
+        KEYS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn click(_: &mut Turtle, _: f32, _: f32) {
+        CLICKS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn unlistening_and_unbinding_stop_handlers() {
+        let (go, wait) = mpsc::channel();
+        let (ready, set) = mpsc::channel();
+        let mut clock = Clock::start(move |turtle| {
+            turtle.onkeypress(key, 'a');
+            turtle.onclick(click);
+            let _ = ready.send(());
+            let _ = wait.recv();
+            turtle.unlisten();
+            let _ = ready.send(());
+            let _ = wait.recv();
+            turtle.listen();
+            let _ = ready.send(());
+            let _ = wait.recv();
+            turtle.unbind_keypress('a');
+            turtle.unbind_click();
+            let _ = ready.send(());
+        });
+        let press_and_click = |clock: &mut Clock| {
+            clock.settle(|_| set.try_recv().is_ok());
+            clock.event(&TurtleEvent::KeyPress('a'));
+            clock.event(&TurtleEvent::MousePress(0., 0.));
+            let _ = go.send(());
+            [KEYS.load(Ordering::SeqCst), CLICKS.load(Ordering::SeqCst)]
+        };
+
+        assert_eq!(press_and_click(&mut clock), [1, 1]);
+        // clicks still get through to a turtle that isn't listening
+        assert_eq!(press_and_click(&mut clock), [1, 2]);
+        assert_eq!(press_and_click(&mut clock), [2, 3]);
+        assert_eq!(press_and_click(&mut clock), [2, 3]);
+    }
+
+    #[test]
+    fn animation_goes_by_the_clock() {
+        let mut clock = Clock::start(|turtle| {
+            turtle.speed(1);
+            turtle.forward(SPEED_UNIT);
+        });
+        clock.settle(|task| task.turtle_list[0].current_command.is_some());
+        let drawn = |clock: &Clock| clock.task.turtle_list[0].state.percent;
+        assert!(drawn(&clock).abs() < 1e-4);
+
+        // a second at speed 1 draws the whole line, but a long wait between
+        // ticks only counts as a tenth of one
+        clock.advance(50);
+        assert!((drawn(&clock) - 0.05).abs() < 1e-4);
+        clock.advance(2000);
+        assert!((drawn(&clock) - 0.15).abs() < 1e-4);
+    }
+
+    // the window stays open after the main turtle is done, so close it from
+    // another turtle
+    fn close_soon(turtle: &mut Turtle) {
+        let mut other = turtle.hatch();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            other.bye();
+        });
+    }
+
+    #[test]
+    fn a_panic_fails_the_exit_code() {
+        let code = Slowpoke::default().run(|turtle| {
+            close_soon(turtle);
+            turtle.forward(10.);
+            panic!("the turtle fell over");
+        });
+        assert_eq!(code, ExitCode::from(EXIT_PANIC));
+        assert_eq!(
+            Slowpoke::default().run(|turtle| turtle.bye()),
+            ExitCode::SUCCESS
+        );
+    }
+
+    #[test]
+    fn many_turtles_are_found_near_a_point() {
+        let (send, receive) = mpsc::channel();
+        Slowpoke::default().run(move |turtle| {
+            turtle.speed(0);
+            turtle.teleport(-5000, 0);
+            let mut turtles: Vec<Turtle> = (0..200)
+                .map(|i| {
+                    let mut other = turtle.hatch();
+                    other.speed(0);
+                    other.teleport(i * 10 - 1000, 0);
+                    other
+                })
+                .collect();
+            let before = turtle.turtles_near(3, 0, 15);
+            // turtles are found where they've moved to
+            turtles[150].teleport(5, 10);
+            let after = turtle.turtles_near(3, 0, 15);
+
+            let positions =
+                |near: Vec<Turtle>| near.iter().map(|t| t.pos().x.round()).collect::<Vec<_>>();
+            let _ = send.send((positions(before), positions(after)));
+            turtle.bye();
+        });
+        let (before, after) = receive.recv().expect("the turtles weren't found");
+        assert_eq!(before, [0., 10., -10.]);
+        assert_eq!(after, [0., 10., 5., -10.]);
+    }
+
+    #[test]
+    fn world_coordinates_that_dont_fit_are_refused() {
+        let (send, receive) = mpsc::channel();
+        Slowpoke::default().run(move |turtle| {
+            let fits = turtle.setworldcoordinates(0, 0, 10, 10);
+            turtle.screensize([0, 0]);
+            // the answer is waited for even when nothing else is
+            turtle.tracer(false);
+            let too_big = turtle.setworldcoordinates(-1, -1, 1, 1);
+            let _ = send.send((fits, too_big, turtle.getmode()));
+            turtle.bye();
+        });
+        let (fits, too_big, mode) = receive.recv().expect("no answer");
+        assert_eq!(fits, Ok(()));
+        assert!(too_big.is_err());
+        assert_eq!(mode, TurtleMode::World);
+    }
+
+    static CLEANED_UP: AtomicBool = AtomicBool::new(false);
+
+    struct CleanUp;
+
+    impl Drop for CleanUp {
+        fn drop(&mut self) {
+            CLEANED_UP.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn closing_unwinds_the_turtles() {
+        let code = Slowpoke::default().run(|turtle| {
+            let _clean_up = CleanUp;
+            close_soon(turtle);
+            loop {
+                turtle.forward(1.);
+            }
+        });
+        assert_eq!(code, ExitCode::SUCCESS);
+        assert!(CLEANED_UP.load(Ordering::SeqCst));
+    }
 }
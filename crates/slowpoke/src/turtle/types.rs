@@ -18,6 +18,11 @@ macro_rules! gen_generator {
             pub fn get(&mut self) -> Self {
                 Self(self.0.get())
             }
+
+            #[allow(dead_code)] // not every kind of id gets recorded
+            pub(crate) const fn value(&self) -> usize {
+                self.0.id
+            }
         }
 
         impl Display for $name {
@@ -47,6 +52,7 @@ impl IDGenerator {
 gen_generator!(TurtleID);
 gen_generator!(TurtleThread);
 gen_generator!(PopupID);
+gen_generator!(TimerID);
 
 impl From<usize> for TurtleID {
     fn from(id: usize) -> Self {
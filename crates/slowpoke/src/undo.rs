@@ -0,0 +1,54 @@
+/// How many commands each turtle remembers for `undo()`. `None` turns undo off.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UndoBufferSize(Option<usize>);
+
+impl Default for UndoBufferSize {
+    fn default() -> Self {
+        UndoBufferSize(Some(1000))
+    }
+}
+
+impl UndoBufferSize {
+    #[must_use]
+    pub fn get(&self) -> Option<usize> {
+        self.0
+    }
+}
+
+impl From<usize> for UndoBufferSize {
+    fn from(value: usize) -> Self {
+        Self(Some(value))
+    }
+}
+
+/// # Panics
+/// Panics if the size is negative
+impl From<i32> for UndoBufferSize {
+    fn from(value: i32) -> Self {
+        let size = usize::try_from(value).expect("undo buffer size can't be negative");
+        Self(Some(size))
+    }
+}
+
+impl From<Option<usize>> for UndoBufferSize {
+    fn from(value: Option<usize>) -> Self {
+        Self(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::UndoBufferSize;
+
+    #[test]
+    fn sizes_from_ints() {
+        assert_eq!(UndoBufferSize::from(0).get(), Some(0));
+        assert_eq!(UndoBufferSize::from(25).get(), Some(25));
+    }
+
+    #[test]
+    #[should_panic(expected = "can't be negative")]
+    fn negative_sizes_are_refused() {
+        let _ = UndoBufferSize::from(-1);
+    }
+}
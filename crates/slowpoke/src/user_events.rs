@@ -1,6 +1,10 @@
 use std::time::Duration;
 
-use crate::{command::InputCmd, turtle::Turtle};
+use crate::{
+    command::InputCmd,
+    comms::Response,
+    turtle::{types::TimerID, Turtle},
+};
 
 impl Turtle {
     pub fn onkey(&self, func: fn(&mut Turtle, char), key: char) {
@@ -8,26 +12,100 @@ impl Turtle {
     }
 
     pub fn onkeyrelease(&self, func: fn(&mut Turtle, char), key: char) {
-        self.do_input(InputCmd::KeyRelease(func, key));
+        self.do_input(InputCmd::KeyRelease(Some(func), key));
     }
 
     pub fn onkeypress(&self, func: fn(&mut Turtle, char), key: char) {
-        self.do_input(InputCmd::KeyPress(func, key));
+        self.do_input(InputCmd::KeyPress(Some(func), key));
     }
 
     pub fn onclick(&self, func: fn(&mut Turtle, f32, f32)) {
-        self.do_input(InputCmd::MousePress(func));
+        self.do_input(InputCmd::MousePress(Some(func)));
     }
 
     pub fn onrelease(&self, func: fn(&mut Turtle, f32, f32)) {
-        self.do_input(InputCmd::MouseRelease(func));
+        self.do_input(InputCmd::MouseRelease(Some(func)));
     }
 
     pub fn ondrag(&self, func: fn(&mut Turtle, f32, f32)) {
-        self.do_input(InputCmd::MouseDrag(func));
+        self.do_input(InputCmd::MouseDrag(Some(func)));
     }
 
-    pub fn ontimer(&self, func: fn(&mut Turtle, Duration), duration: u64) {
-        self.do_input(InputCmd::Timer(func, Duration::from_millis(duration)));
+    /// Call `func` when the user closes the window. The window stays open
+    /// until every turtle's handler has returned, so this is the place to
+    /// save a high score or flush a log.
+    pub fn onclose(&self, func: fn(&mut Turtle)) {
+        self.do_input(InputCmd::Close(Some(func)));
+    }
+
+    /*
+     * Removing bindings: these are the equivalent of passing `None` as the
+     * handler in python.
+     */
+
+    pub fn unbind_key(&self, key: char) {
+        self.unbind_keyrelease(key);
+    }
+
+    pub fn unbind_keyrelease(&self, key: char) {
+        self.do_input(InputCmd::KeyRelease(None, key));
+    }
+
+    pub fn unbind_keypress(&self, key: char) {
+        self.do_input(InputCmd::KeyPress(None, key));
+    }
+
+    pub fn unbind_click(&self) {
+        self.do_input(InputCmd::MousePress(None));
+    }
+
+    pub fn unbind_release(&self) {
+        self.do_input(InputCmd::MouseRelease(None));
+    }
+
+    pub fn unbind_drag(&self) {
+        self.do_input(InputCmd::MouseDrag(None));
+    }
+
+    pub fn unbind_close(&self) {
+        self.do_input(InputCmd::Close(None));
+    }
+
+    /// Start delivering key events to this turtle's handlers again after a
+    /// call to `unlisten()`. Every turtle listens when it is created.
+    pub fn listen(&self) {
+        self.do_input(InputCmd::Listen(true));
+    }
+
+    /// Stop delivering key events to this turtle without removing its
+    /// bindings, e.g. to switch between a menu turtle and a gameplay turtle.
+    pub fn unlisten(&self) {
+        self.do_input(InputCmd::Listen(false));
+    }
+
+    /// Call `func` once, `duration` milliseconds from now. A turtle can have
+    /// any number of pending timers; the returned id can be passed to
+    /// `cancel_timer()` to stop one before it fires.
+    pub fn ontimer(&self, func: fn(&mut Turtle, Duration), duration: u64) -> TimerID {
+        self.add_timer(func, duration, false)
+    }
+
+    /// Call `func` every `interval` milliseconds until the timer is cancelled.
+    /// The next call is skipped if the previous one hasn't returned yet.
+    pub fn ontimer_repeat(&self, func: fn(&mut Turtle, Duration), interval: u64) -> TimerID {
+        self.add_timer(func, interval, true)
+    }
+
+    pub fn cancel_timer(&self, id: TimerID) {
+        self.do_input(InputCmd::CancelTimer(id));
+    }
+
+    fn add_timer(&self, func: fn(&mut Turtle, Duration), millis: u64, repeat: bool) -> TimerID {
+        let response = self.do_input(InputCmd::Timer(func, Duration::from_millis(millis), repeat));
+        if let Response::TimerID(id) = response {
+            id
+        } else {
+            panic!("invalid response from turtle: {response:?}");
+        }
     }
 }
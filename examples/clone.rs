@@ -0,0 +1,22 @@
+use slowpoke::Slowpoke;
+
+fn main() {
+    Slowpoke::default()
+        .with_size(400, 400)
+        .with_title("a swarm of clones")
+        .run(|turtle| {
+            turtle.shape("turtle");
+            turtle.pencolor("red");
+            turtle.pensize(3);
+            turtle.speed(8);
+
+            let mut swarm = Vec::new();
+            for _ in 0..6 {
+                turtle.left(60);
+                swarm.push(turtle.clone_turtle());
+            }
+            for t in &mut swarm {
+                t.forward(100);
+            }
+        });
+}
@@ -0,0 +1,36 @@
+use slowpoke::Slowpoke;
+
+fn main() {
+    Slowpoke::default()
+        .with_size(500, 500)
+        .with_title("collisions")
+        .run(|turtle| {
+            turtle.speed(8);
+
+            // draw a star until the turtle crosses its own trail
+            turtle.teleport(-100, 0);
+            let mut lines = 0;
+            loop {
+                turtle.forward(200);
+                lines += 1;
+                if turtle.intersects_drawn_path() {
+                    break;
+                }
+                turtle.left(144);
+            }
+            println!("crossed the trail after {lines} lines");
+
+            // chase another turtle until they bump into each other
+            let mut other = turtle.hatch();
+            other.penup();
+            other.goto(-200, 200);
+            turtle.penup();
+            while !turtle.overlaps(&other) {
+                let pos = other.position();
+                turtle.setheading(turtle.towards(pos.x, pos.y));
+                turtle.forward(10);
+            }
+            let near = turtle.turtles_near(-200, 200, 50);
+            println!("{} turtles near the corner", near.len());
+        });
+}
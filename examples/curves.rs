@@ -0,0 +1,28 @@
+use slowpoke::Slowpoke;
+
+fn main() {
+    Slowpoke::default()
+        .with_size(500, 400)
+        .with_title("curves")
+        .run(|turtle| {
+            // a filled leaf made of two Bezier curves
+            turtle.fillcolor("green");
+            turtle.begin_fill();
+            turtle.curve_to([60., 60.], [120., 0.]);
+            turtle.setheading(180);
+            turtle.curve_to([60., 60.], [120., 0.]);
+            turtle.end_fill();
+
+            turtle.teleport(-150, 100);
+            turtle.setheading(0);
+            turtle.cubic_curve_to([50., 80.], [100., -80.], [150., 0.]).with_steps(64_usize);
+
+            turtle.teleport(-150, -100);
+            turtle.setheading(0);
+            turtle.ellipse(60, 30);
+
+            // half-way around a point above the turtle
+            turtle.teleport(100, -150);
+            turtle.arc(100, -100, 180);
+        });
+}
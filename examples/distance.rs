@@ -5,7 +5,7 @@ fn main() {
         .with_size(400, 400)
         .with_title("a distance")
         .run(|turtle| {
-            println!("distance to 0, 100: {}", turtle.distance((0, 100)));
+            println!("distance to 0, 100: {}", turtle.distance((0., 100.)));
 
             let mut other_turtle = turtle.hatch();
             other_turtle.goto(50, 50);
@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+use slowpoke::Slowpoke;
+
+fn main() {
+    Slowpoke::default()
+        .with_size(500, 400)
+        .with_title("easing")
+        .run(|turtle| {
+            turtle.speed(2);
+
+            // short and long lines are drawn at the same speed
+            turtle.teleport(-200, 100);
+            for length in [20, 40, 80, 160] {
+                turtle.forward(length);
+                turtle.right(90);
+                turtle.penup();
+                turtle.forward(10);
+                turtle.left(90);
+                turtle.pendown();
+            }
+
+            // every line takes a second, speeding up and slowing down
+            turtle.teleport(-200, -100);
+            turtle.duration(Duration::from_secs(1));
+            turtle.easing("easeinout");
+            for length in [20, 40, 80, 160] {
+                turtle.forward(length);
+                turtle.left(90);
+                turtle.forward(10);
+                turtle.right(90);
+            }
+        });
+}
@@ -0,0 +1,33 @@
+use slowpoke::{FillStyle, Slowpoke, Turtle};
+
+fn square(turtle: &mut Turtle, x: i32, y: i32) {
+    turtle.teleport(x, y);
+    turtle.begin_fill();
+    for _ in 0..4 {
+        turtle.forward(120);
+        turtle.left(90);
+    }
+    turtle.end_fill();
+}
+
+fn main() {
+    Slowpoke::default()
+        .with_size(400, 400)
+        .with_title("fill styles")
+        .run(|turtle| {
+            turtle.speed(0);
+            turtle.fillcolor("lightblue");
+
+            turtle.fillstyle(FillStyle::linear("red", "yellow", 45));
+            square(turtle, -150, 20);
+
+            turtle.fillstyle(FillStyle::radial("white", "blue"));
+            square(turtle, 30, 20);
+
+            turtle.fillstyle(FillStyle::hatch("black", 8, 30));
+            square(turtle, -150, -150);
+
+            turtle.fillstyle("solid");
+            square(turtle, 30, -150);
+        });
+}
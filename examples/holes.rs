@@ -0,0 +1,30 @@
+use slowpoke::Slowpoke;
+
+fn main() {
+    Slowpoke::default()
+        .with_size(400, 400)
+        .with_title("an \"O\" with a hole, and a star with the non-zero rule")
+        .run(|turtle| {
+            turtle.fillcolor("orange");
+
+            // the letter O: two contours, the inner one is a hole
+            turtle.teleport(-100, -60);
+            turtle.begin_fill();
+            turtle.ellipse(50, 60);
+            turtle.next_contour();
+            turtle.teleport(-100, -35);
+            turtle.ellipse(25, 35);
+            turtle.end_fill();
+
+            // with "nonzero", the middle of the star is filled too
+            turtle.fillrule("nonzero");
+            turtle.teleport(20, 20);
+            turtle.setheading(0);
+            turtle.begin_fill();
+            for _ in 0..5 {
+                turtle.forward(150);
+                turtle.right(144);
+            }
+            turtle.end_fill();
+        });
+}
@@ -0,0 +1,27 @@
+use slowpoke::Slowpoke;
+
+fn main() {
+    Slowpoke::default()
+        .with_size(400, 400)
+        .with_title("line styles")
+        .run(|turtle| {
+            turtle.pensize(8);
+            turtle.linecap("round");
+
+            // construction lines
+            turtle.linestyle("dashed");
+            turtle.teleport(-150, 0);
+            turtle.goto(150, 0);
+            turtle.linestyle("dotted");
+            turtle.teleport(0, -150);
+            turtle.goto(0, 150);
+
+            turtle.linestyle([20., 6., 4., 6.]);
+            turtle.linejoin("miter");
+            turtle.teleport(-100, -100);
+            for _ in 0..4 {
+                turtle.forward(200);
+                turtle.left(90);
+            }
+        });
+}
@@ -0,0 +1,38 @@
+use slowpoke::Slowpoke;
+
+// Run with the name of a Logo file to draw it, or without to draw a tree
+const TREE: &str = "
+to tree :size
+  if :size < 5 [stop]
+  setpensize :size / 10
+  fd :size
+  lt 30 tree :size * 0.7
+  rt 60 tree :size * 0.7
+  lt 30
+  bk :size
+end
+
+setpc \"brown
+pu bk 150 pd
+tree 100
+ht
+";
+
+fn main() {
+    let source = match std::env::args().nth(1) {
+        Some(path) => std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            eprintln!("couldn't read {path}: {e}");
+            std::process::exit(1);
+        }),
+        None => TREE.to_string(),
+    };
+
+    Slowpoke::default()
+        .with_size(500, 500)
+        .with_title("logo")
+        .run(move |turtle| {
+            if let Err(e) = slowpoke_logo::run(turtle, &source) {
+                eprintln!("{e}");
+            }
+        });
+}
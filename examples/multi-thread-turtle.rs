@@ -35,28 +35,26 @@ fn main() {
             }
 
             for t in &mut tlist {
-                t.ontimer(turtle_thread, 250);
+                t.ontimer_repeat(turtle_thread, 150);
             }
         });
 }
 
 fn turtle_thread(turtle: &mut Turtle, _duration: Duration) {
-    let mut rng = rand::thread_rng();
+    let mut rng = rand::rng();
 
-    if rng.gen::<f64>() > 0.95 {
+    if rng.random::<f64>() > 0.95 {
         turtle.clear();
     } else {
-        let dist: f64 = 10. + rng.gen::<f64>() * 20.;
+        let dist: f64 = 10. + rng.random::<f64>() * 20.;
         let pos = turtle.pos();
-        if pos.x > 200 || pos.x < -200 || pos.y > 200 || pos.y < -200 {
+        if pos.x > 200. || pos.x < -200. || pos.y > 200. || pos.y < -200. {
             let h = turtle.towards(0, 0);
             turtle.setheading(h);
         } else {
-            let angle: f64 = rng.gen::<f64>() * 40. - 20.;
+            let angle: f64 = rng.random::<f64>() * 40. - 20.;
             turtle.right(angle);
         }
         turtle.forward(dist);
     }
-
-    turtle.ontimer(turtle_thread, 150);
 }
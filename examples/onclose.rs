@@ -0,0 +1,21 @@
+use slowpoke::{Slowpoke, Turtle};
+
+fn save_drawing(turtle: &mut Turtle) {
+    let pos = turtle.pos();
+    println!("the turtle finished at {pos:?}");
+}
+
+fn main() {
+    Slowpoke::default()
+        .with_size(400, 400)
+        .with_title("close the window to see where the turtle ended up")
+        .run(|turtle| {
+            turtle.onclose(save_drawing);
+            for _ in 0..5 {
+                turtle.forward(100);
+                turtle.right(144);
+            }
+            turtle.done();
+            println!("goodbye");
+        });
+}
@@ -0,0 +1,34 @@
+use slowpoke::Slowpoke;
+
+// Run with the name of a Python turtle script, such as one from
+// python-examples/, or without to draw a spiral
+const SPIRAL: &str = "
+import turtle
+
+colors = ['red', 'orange', 'gold', 'green', 'blue', 'purple']
+turtle.speed(0)
+for i in range(120):
+    turtle.pencolor(colors[i % len(colors)])
+    turtle.forward(i * 2)
+    turtle.left(59)
+turtle.hideturtle()
+";
+
+fn main() {
+    let source = match std::env::args().nth(1) {
+        Some(path) => std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            eprintln!("couldn't read {path}: {e}");
+            std::process::exit(1);
+        }),
+        None => SPIRAL.to_string(),
+    };
+
+    Slowpoke::default()
+        .with_size(500, 500)
+        .with_title("python")
+        .run(move |turtle| {
+            if let Err(e) = slowpoke_python::run(turtle, &source) {
+                eprintln!("{e}");
+            }
+        });
+}
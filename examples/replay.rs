@@ -0,0 +1,35 @@
+use std::process::ExitCode;
+
+use slowpoke::Slowpoke;
+
+// Run without arguments to draw a star and save it to star.recording, then
+// run with that file's name to play it back.
+fn main() -> ExitCode {
+    let lib = Slowpoke::default().with_size(400, 400).with_title("replay");
+
+    if let Some(path) = std::env::args().nth(1) {
+        return lib.replay(&path).unwrap_or_else(|e| {
+            eprintln!("couldn't replay {path}: {e}");
+            ExitCode::FAILURE
+        });
+    }
+
+    let lib = lib.with_recording("star.recording");
+    let exit_code = lib.run(|turtle| {
+        turtle.pencolor("red");
+        turtle.fillcolor("yellow");
+        turtle.teleport(-100, 30);
+        turtle.begin_fill();
+        for _ in 0..5 {
+            turtle.forward(200);
+            turtle.right(144);
+        }
+        turtle.end_fill();
+        turtle.hideturtle();
+    });
+    if let Some(e) = lib.recording_error() {
+        eprintln!("the recording stopped early: {e}");
+        return ExitCode::FAILURE;
+    }
+    exit_code
+}
@@ -0,0 +1,25 @@
+use slowpoke::Slowpoke;
+
+// Run with an address to listen on (127.0.0.1:7007 if not given), then drive
+// turtles from anywhere, e.g.
+//   echo '{"draw": ["forward", 100]}' | nc -q 1 127.0.0.1 7007
+fn main() {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:7007".to_string());
+    let server = slowpoke::server::Server::tcp(&address).unwrap_or_else(|e| {
+        eprintln!("couldn't listen on {address}: {e}");
+        std::process::exit(1);
+    });
+    println!("listening on {address}");
+
+    Slowpoke::default()
+        .with_size(500, 500)
+        .with_title("server")
+        .run(move |turtle| {
+            // each client gets a turtle of its own
+            turtle.hideturtle();
+            let e = server.run(turtle);
+            eprintln!("the server stopped: {e}");
+        });
+}
@@ -15,7 +15,7 @@ fn main() {
             loop {
                 let dist: f64 = 10. + rng.random::<f64>() * 20.;
                 let pos = turtle.pos();
-                if pos.x > 200 || pos.x < -200 || pos.y > 200 || pos.y < -200 {
+                if pos.x > 200. || pos.x < -200. || pos.y > 200. || pos.y < -200. {
                     let h = turtle.towards(0, 0);
                     turtle.setheading(h);
                 } else {
@@ -0,0 +1,29 @@
+use slowpoke::Slowpoke;
+
+fn main() {
+    Slowpoke::default()
+        .with_size(400, 400)
+        .with_title("teleporting while filling")
+        .run(|turtle| {
+            turtle.fillcolor("purple");
+
+            // two separate triangles, each filled on its own
+            turtle.begin_fill();
+            turtle.teleport(-150, 50);
+            turtle.goto(-50, 50);
+            turtle.goto(-100, 130);
+            turtle.teleport(-150, -100);
+            turtle.goto(-50, -100);
+            turtle.goto(-100, -20);
+            turtle.end_fill();
+
+            // the gap is filled: one shape that includes the jump
+            turtle.begin_fill();
+            turtle.teleport(50, 50).with_fill_gap(true);
+            turtle.goto(150, 50);
+            turtle.goto(100, 130);
+            turtle.teleport(50, -100).with_fill_gap(true);
+            turtle.goto(150, -100);
+            turtle.end_fill();
+        });
+}
@@ -50,7 +50,7 @@ fn turtle_thread(turtle: &mut Turtle, _duration: Duration) {
     } else {
         let dist: f64 = 10. + rng.gen::<f64>() * 20.;
         let pos = turtle.pos();
-        if pos.x > 200 || pos.x < -200 || pos.y > 200 || pos.y < -200 {
+        if pos.x > 200. || pos.x < -200. || pos.y > 200. || pos.y < -200. {
             let h = turtle.towards(0, 0);
             turtle.setheading(h);
         } else {
@@ -10,7 +10,12 @@ fn main() {
             turtle.forward(50); // draw down
             turtle.undo(); // back up 50 pixels
             turtle.undo(); // turn to the left by 90
-            turtle.backward(100); // return to origin
+            turtle.redo(); // point down again
+            turtle.redo(); // draw down again
+            turtle.undo(); // and back up
+            turtle.undo(); // turn to the left again
+            turtle.backward(100); // return to origin, forgetting the redo
+            turtle.redo(); // does nothing
             turtle.bye();
         });
 }
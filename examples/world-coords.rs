@@ -0,0 +1,27 @@
+use slowpoke::Slowpoke;
+
+fn main() {
+    Slowpoke::default()
+        .with_size(600, 400)
+        .with_title("plotting sin(x) in world coordinates")
+        .run(|turtle| {
+            turtle
+                .setworldcoordinates(-7., -1.5, 7., 1.5)
+                .expect("the corners make a rectangle");
+            turtle.speed(0);
+
+            // axes
+            turtle.teleport(-7, 0);
+            turtle.goto(7, 0);
+            turtle.teleport(0, -1.5);
+            turtle.goto(0, 1.5);
+
+            turtle.pencolor("blue");
+            turtle.teleport(-7., (-7f64).sin());
+            for step in -70..=70 {
+                let x = f64::from(step) / 10.;
+                turtle.goto(x, x.sin());
+            }
+            println!("ended up at {:?}", turtle.pos());
+        });
+}